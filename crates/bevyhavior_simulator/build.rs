@@ -0,0 +1,56 @@
+use std::{env, fs, io, path::Path};
+
+/// Generates one test per scenario file, so that adding a scenario does not require touching Rust.
+fn main() -> io::Result<()> {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("cargo should set CARGO_MANIFEST_DIR");
+    let scenarios = Path::new(&manifest_dir).join("scenarios");
+    let out_dir = env::var("OUT_DIR").expect("cargo should set OUT_DIR");
+
+    fs::write(
+        Path::new(&out_dir).join("scenario_file_tests.rs"),
+        scenario_tests(&scenarios)?,
+    )?;
+    // These need the real robot stacks, the software_in_the_loop binary runs them
+    fs::write(
+        Path::new(&out_dir).join("software_in_the_loop_scenario_file_tests.rs"),
+        scenario_tests(&scenarios.join("software_in_the_loop"))?,
+    )
+}
+
+fn scenario_tests(directory: &Path) -> io::Result<String> {
+    println!("cargo:rerun-if-changed={}", directory.display());
+
+    let mut paths: Vec<_> = fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "json5" || extension == "toml")
+    });
+    paths.sort();
+
+    let mut tests = String::new();
+    for path in paths {
+        // The extension is part of the name, so x.json5 and x.toml do not collide
+        let mut name: String = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .chars()
+            .map(|character| {
+                if character.is_ascii_alphanumeric() {
+                    character.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.starts_with(|character: char| character.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        tests.push_str(&format!(
+            "#[test]\nfn {name}() -> color_eyre::Result<()> {{\n    super::run(std::path::Path::new({path:?}), false)\n}}\n\n"
+        ));
+    }
+    Ok(tests)
+}
@@ -1,21 +1,37 @@
-use std::{collections::BTreeMap, time::Duration, time::SystemTime};
+use std::{
+    collections::BTreeMap,
+    f32::consts::FRAC_PI_2,
+    fmt::{self, Display, Formatter},
+    time::Duration,
+    time::SystemTime,
+};
 
 use bevy::prelude::*;
-use coordinate_systems::{Ground, World};
-use hsl_network_messages::{GamePhase, GameState, Team};
-use linear_algebra::{Isometry2, Point2, Vector2, distance};
+use coordinate_systems::{Field, Ground, World};
+use eframe::egui::Color32;
+use hsl_network_messages::{GamePhase, GameState, Penalty, SubState, Team};
+use linear_algebra::{Isometry2, Point2, Vector2, distance, point};
 use types::{
     field_dimensions::{FieldDimensions, GlobalFieldSide, Side},
     game_controller_state::GameControllerState,
 };
 
-use crate::behavior_tree_simulator::{
-    SimulatedBall, SimulatorBall, SimulatorFieldDimensions, SimulatorGameState,
-    SimulatorGroundToWorld, SimulatorRobot, SimulatorRobotId, point_world_to_field,
+use crate::{
+    behavior_tree_simulator::{
+        Pushing, SimulatedBall, SimulatorBall, SimulatorFieldDimensions, SimulatorGameState,
+        SimulatorGroundToWorld, SimulatorRobot, SimulatorRobotCollisions, SimulatorRobotId,
+        SimulatorTimelineMarkers, point_world_to_field,
+    },
+    coordinates::world_to_field_transform,
+    game_controller::global_field_side_for_team,
 };
 
 const READY_STATIONARY_TRANSLATION_EPSILON: f32 = 0.01;
 const READY_STATIONARY_ROTATION_EPSILON: f32 = 0.01;
+/// Robots closer than this to the ball are assumed to touch it
+const BALL_TOUCH_DISTANCE: f32 = 0.35;
+/// Set plays end once the ball moved this far from where it was placed
+const SET_PLAY_BALL_MOVED_DISTANCE: f32 = 0.05;
 
 #[derive(Resource, Clone, Debug)]
 pub struct AutoRefereeConfig {
@@ -25,6 +41,14 @@ pub struct AutoRefereeConfig {
     pub halftime_duration: Duration,
     pub auto_whistle_in_set: bool,
     pub finish_on_halftime_timeout: bool,
+    pub penalty_duration: Duration,
+    /// Time the kicking team has to execute a set play before the ball is free
+    pub set_play_duration: Duration,
+    /// Field players that do not move for this long while playing are penalized as incapable,
+    /// disabled by default since standing still is a legitimate tactic
+    pub inactive_player_duration: Option<Duration>,
+    /// Distance robots may walk beyond the field lines before they left the field
+    pub leaving_the_field_distance: f32,
 }
 
 impl Default for AutoRefereeConfig {
@@ -36,6 +60,10 @@ impl Default for AutoRefereeConfig {
             halftime_duration: Duration::from_secs(10 * 60),
             auto_whistle_in_set: true,
             finish_on_halftime_timeout: true,
+            penalty_duration: Duration::from_secs(45),
+            set_play_duration: Duration::from_secs(30),
+            inactive_player_duration: None,
+            leaving_the_field_distance: 0.5,
         }
     }
 }
@@ -54,6 +82,13 @@ pub struct AutoRefereeState {
     pub restart_reason: Option<SimulatorRestartReason>,
     pub ready_stationary_since: Option<SystemTime>,
     pub ready_robot_poses: BTreeMap<SimulatorRobotId, Isometry2<Ground, World>>,
+    pub last_ball_touch: Option<SimulatorRobotId>,
+    /// Start of the running set play and where the ball was placed for it
+    pub set_play: Option<(SystemTime, Point2<World>)>,
+    /// Pose of every field player and since when it stands there
+    pub inactive_robot_poses: BTreeMap<SimulatorRobotId, (Isometry2<Ground, World>, SystemTime)>,
+    /// Every call of the referee, scenarios use it to validate rule compliance
+    pub calls: Vec<(SystemTime, RefereeCall)>,
 }
 
 impl Default for AutoRefereeState {
@@ -65,6 +100,10 @@ impl Default for AutoRefereeState {
             restart_reason: None,
             ready_stationary_since: None,
             ready_robot_poses: BTreeMap::new(),
+            last_ball_touch: None,
+            set_play: None,
+            inactive_robot_poses: BTreeMap::new(),
+            calls: Vec::new(),
         }
     }
 }
@@ -82,6 +121,46 @@ pub enum SimulatorRestartReason {
     DroppedBall,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RefereeCall {
+    SetPlay {
+        set_play: SubState,
+        kicking_team: Team,
+    },
+    Penalized {
+        robot: SimulatorRobotId,
+        penalty: Penalty,
+    },
+    Unpenalized {
+        robot: SimulatorRobotId,
+    },
+}
+
+impl RefereeCall {
+    pub fn color(&self) -> Color32 {
+        match self {
+            Self::SetPlay { .. } => Color32::YELLOW,
+            Self::Penalized { .. } => Color32::ORANGE,
+            Self::Unpenalized { .. } => Color32::LIGHT_BLUE,
+        }
+    }
+}
+
+impl Display for RefereeCall {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SetPlay {
+                set_play,
+                kicking_team,
+            } => write!(formatter, "{set_play:?} for {kicking_team:?}"),
+            Self::Penalized { robot, penalty } => {
+                write!(formatter, "robot {robot} penalized: {penalty:?}")
+            }
+            Self::Unpenalized { robot } => write!(formatter, "robot {robot} unpenalized"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Message)]
 pub enum SimulatorRefereeCommand {
     SetGameState(GameState),
@@ -90,6 +169,17 @@ pub enum SimulatorRefereeCommand {
     Resume,
     DroppedBall,
     SetTimeout(bool),
+    Penalize {
+        robot: SimulatorRobotId,
+        penalty: Penalty,
+    },
+    Unpenalize(SimulatorRobotId),
+    /// Places the ball and starts the set play as if the referee called it
+    SetPlay {
+        set_play: SubState,
+        kicking_team: Team,
+        position: Point2<World>,
+    },
 }
 
 pub trait AutoRefereeRule: Send + Sync {
@@ -104,6 +194,10 @@ pub struct AutoRefereeContext<'a> {
     pub auto_referee: &'a mut AutoRefereeState,
     pub ball: &'a mut SimulatorBall,
     pub robot_poses: BTreeMap<SimulatorRobotId, Isometry2<Ground, World>>,
+    pub cycle_time: Duration,
+    pub pushings: Vec<Pushing>,
+    /// Poses the referee moved robots to, applied after all rules ran
+    pub robot_placements: BTreeMap<SimulatorRobotId, Isometry2<Ground, World>>,
 }
 
 impl AutoRefereeContext<'_> {
@@ -120,6 +214,102 @@ impl AutoRefereeContext<'_> {
     fn set_kicking_team(&mut self, kicking_team: Option<Team>) {
         self.game_state.set_kicking_team(kicking_team);
     }
+
+    fn call(&mut self, call: RefereeCall) {
+        self.auto_referee.calls.push((self.now, call));
+    }
+
+    fn field_to_world(&self, team: Team) -> Isometry2<Field, World> {
+        world_to_field_transform(global_field_side_for_team(
+            &self.game_state.game_controller_state,
+            team,
+        ))
+        .inverse()
+    }
+
+    fn place_robot(&mut self, robot_id: SimulatorRobotId, pose: Isometry2<Ground, World>) {
+        self.robot_poses.insert(robot_id, pose);
+        self.robot_placements.insert(robot_id, pose);
+    }
+
+    /// Pose on the side line of the own half, `distance_outside` behind the line, facing the
+    /// field. Robots keep the side they are closer to.
+    fn side_line_pose(
+        &self,
+        robot_id: SimulatorRobotId,
+        distance_outside: f32,
+    ) -> Isometry2<Ground, World> {
+        let field_to_world = self.field_to_world(robot_id.team);
+        let side = self.robot_poses.get(&robot_id).map_or(1.0, |pose| {
+            (field_to_world.inverse() * pose.translation()).y().signum()
+        });
+        let position = point![
+            -self.field_dimensions.length / 4.0,
+            side * (self.field_dimensions.width / 2.0 + distance_outside)
+        ];
+        field_to_world
+            * Isometry2::<Ground, Field>::from_parts(position.coords(), -side * FRAC_PI_2)
+    }
+
+    /// Penalizes the robot and removes it from the field, robots already penalized are left
+    /// alone.
+    fn penalize(&mut self, robot_id: SimulatorRobotId, penalty: Penalty) {
+        if self.game_state.penalty(robot_id).is_some() {
+            return;
+        }
+        self.game_state.set_penalty(robot_id, Some(penalty));
+        let pose = self.side_line_pose(robot_id, self.field_dimensions.border_strip_width / 2.0);
+        self.place_robot(robot_id, pose);
+        self.call(RefereeCall::Penalized {
+            robot: robot_id,
+            penalty,
+        });
+    }
+
+    /// Lifts the penalty and puts the robot back in at the side line.
+    fn unpenalize(&mut self, robot_id: SimulatorRobotId) {
+        if self.game_state.penalty(robot_id).is_none() {
+            return;
+        }
+        self.game_state.set_penalty(robot_id, None);
+        let pose = self.side_line_pose(robot_id, 0.0);
+        self.place_robot(robot_id, pose);
+        self.call(RefereeCall::Unpenalized { robot: robot_id });
+    }
+
+    fn start_set_play(&mut self, set_play: SubState, kicking_team: Team, position: Point2<World>) {
+        let ball_in_field = point_world_to_field(
+            position,
+            self.game_state.game_controller_state.global_field_side,
+        );
+        self.ball.state = Some(SimulatedBall {
+            position,
+            velocity: Vector2::zeros(),
+            field_side: if ball_in_field.y() >= 0.0 {
+                Side::Left
+            } else {
+                Side::Right
+            },
+        });
+        self.set_kicking_team(Some(kicking_team));
+        self.game_state
+            .set_secondary_time(self.config.set_play_duration);
+        self.game_state.set_sub_state(Some(set_play));
+        self.auto_referee.set_play = Some((self.now, position));
+        self.auto_referee.last_ball_touch = None;
+        self.call(RefereeCall::SetPlay {
+            set_play,
+            kicking_team,
+        });
+    }
+
+    fn is_penalized(&self, robot_id: SimulatorRobotId) -> bool {
+        self.game_state.penalty(robot_id).is_some()
+    }
+
+    fn is_playing(&self) -> bool {
+        self.game_state.game_controller_state.game_state == GameState::Playing
+    }
 }
 
 pub struct ScoredGoalRule;
@@ -313,12 +503,294 @@ impl Default for HalftimeTimeoutRule {
     }
 }
 
+/// Tracks the robot that touched the ball last and restarts with a kick-in, goal kick or corner
+/// kick once the ball left the field.
+pub struct BallOutRule;
+
+impl AutoRefereeRule for BallOutRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        if !context.is_playing() {
+            return;
+        }
+        let Some(ball) = context.ball.state else {
+            return;
+        };
+
+        if let Some((robot_id, _)) = context
+            .robot_poses
+            .iter()
+            .map(|(robot_id, pose)| (*robot_id, distance(pose.translation(), ball.position)))
+            .filter(|(_, distance)| *distance < BALL_TOUCH_DISTANCE)
+            .min_by(|(_, left), (_, right)| left.total_cmp(right))
+        {
+            context.auto_referee.last_ball_touch = Some(robot_id);
+        }
+
+        let field = context.field_dimensions;
+        let over_side_line = ball.position.y().abs() >= field.width / 2.0 + field.ball_radius;
+        let over_goal_line = ball.position.x().abs() >= field.length / 2.0 + field.ball_radius
+            && !field.is_inside_any_goal(ball.position);
+        if !over_side_line && !over_goal_line {
+            return;
+        }
+
+        let ball_in_field = point_world_to_field(
+            ball.position,
+            context.game_state.game_controller_state.global_field_side,
+        );
+        let defending_team = if ball_in_field.x() < 0.0 {
+            Team::Hulks
+        } else {
+            Team::Opponent
+        };
+        let last_touch_team = context
+            .auto_referee
+            .last_ball_touch
+            .map(|robot_id| robot_id.team);
+
+        if over_side_line {
+            let kicking_team = last_touch_team.map_or(defending_team, opponent_of);
+            let position = point![
+                ball.position
+                    .x()
+                    .clamp(-field.length / 2.0, field.length / 2.0),
+                ball.position.y().signum() * field.width / 2.0
+            ];
+            context.start_set_play(SubState::ThrowIn, kicking_team, position);
+            return;
+        }
+
+        let field_to_world = context.field_to_world(defending_team);
+        let side = (field_to_world.inverse() * ball.position).y().signum();
+        if last_touch_team == Some(defending_team) {
+            let corner = point![-field.length / 2.0, side * field.width / 2.0];
+            context.start_set_play(
+                SubState::CornerKick,
+                opponent_of(defending_team),
+                field_to_world * corner,
+            );
+        } else {
+            let goal_box_corner = point![
+                -field.length / 2.0 + field.goal_box_area_length,
+                side * field.goal_box_area_width / 2.0
+            ];
+            context.start_set_play(
+                SubState::GoalKick,
+                defending_team,
+                field_to_world * goal_box_corner,
+            );
+        }
+    }
+}
+
+/// Counts down the secondary time of a set play and frees the ball once the kicking team moved it
+/// or ran out of time.
+pub struct SetPlayRule;
+
+impl AutoRefereeRule for SetPlayRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        let Some((started_at, position)) = context.auto_referee.set_play else {
+            return;
+        };
+        let ball_moved = context
+            .ball
+            .state
+            .is_none_or(|ball| distance(ball.position, position) > SET_PLAY_BALL_MOVED_DISTANCE);
+        if !context.is_playing()
+            || ball_moved
+            || has_elapsed(context.now, started_at, context.config.set_play_duration)
+        {
+            context.game_state.set_secondary_time(Duration::ZERO);
+            context.game_state.set_sub_state(None);
+            context.auto_referee.set_play = None;
+            return;
+        }
+        let elapsed = context
+            .now
+            .duration_since(started_at)
+            .unwrap_or(Duration::ZERO);
+        context
+            .game_state
+            .set_secondary_time(context.config.set_play_duration.saturating_sub(elapsed));
+    }
+}
+
+/// Penalizes robots that push opponents and awards the pushed team a free kick, contacts between
+/// teammates are not fouls.
+pub struct PushingRule;
+
+impl AutoRefereeRule for PushingRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        if !context.is_playing() {
+            return;
+        }
+        for pushing in std::mem::take(&mut context.pushings) {
+            if pushing.pusher.team == pushing.pushed.team
+                || context.is_penalized(pushing.pusher)
+                || context.is_penalized(pushing.pushed)
+            {
+                continue;
+            }
+            context.penalize(
+                pushing.pusher,
+                Penalty::Pushing {
+                    remaining: context.config.penalty_duration,
+                },
+            );
+            if context.auto_referee.set_play.is_some() {
+                continue;
+            }
+            if let Some(ball) = context.ball.state {
+                context.start_set_play(
+                    SubState::DirectFreeKick,
+                    pushing.pushed.team,
+                    ball.position,
+                );
+            }
+        }
+    }
+}
+
+/// Penalizes robots standing in the opponent half, or in the center circle when the opponent
+/// kicks off, once the game enters Set.
+pub struct IllegalPositioningRule;
+
+impl AutoRefereeRule for IllegalPositioningRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        if context.game_state.game_controller_state.game_state != GameState::Set
+            || context.auto_referee.last_game_state_change != context.now
+        {
+            return;
+        }
+        let kicking_team = context.game_state.game_controller_state.kicking_team;
+        for (robot_id, pose) in context.robot_poses.clone() {
+            let position = context.field_to_world(robot_id.team).inverse() * pose.translation();
+            let is_in_opponent_half = position.x() > 0.0;
+            let is_in_center_circle = kicking_team.is_some_and(|team| team != robot_id.team)
+                && position.coords().norm() < context.field_dimensions.center_circle_diameter / 2.0;
+            if is_in_opponent_half || is_in_center_circle {
+                context.penalize(
+                    robot_id,
+                    Penalty::IllegalPosition {
+                        remaining: context.config.penalty_duration,
+                    },
+                );
+            }
+        }
+    }
+}
+
+pub struct LeavingTheFieldRule;
+
+impl AutoRefereeRule for LeavingTheFieldRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        if !context.is_playing() {
+            return;
+        }
+        let maximum_x =
+            context.field_dimensions.length / 2.0 + context.config.leaving_the_field_distance;
+        let maximum_y =
+            context.field_dimensions.width / 2.0 + context.config.leaving_the_field_distance;
+        for (robot_id, pose) in context.robot_poses.clone() {
+            let position = pose.translation();
+            if context.is_penalized(robot_id)
+                || (position.x().abs() <= maximum_x && position.y().abs() <= maximum_y)
+            {
+                continue;
+            }
+            context.penalize(
+                robot_id,
+                Penalty::LeavingTheField {
+                    remaining: context.config.penalty_duration,
+                },
+            );
+        }
+    }
+}
+
+/// Penalizes field players that stand still for too long while playing.
+pub struct InactivePlayerRule;
+
+impl AutoRefereeRule for InactivePlayerRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        let Some(duration) = context.config.inactive_player_duration else {
+            return;
+        };
+        if !context.is_playing() {
+            context.auto_referee.inactive_robot_poses.clear();
+            return;
+        }
+
+        let mut inactive_robots = Vec::new();
+        for (robot_id, pose) in context.robot_poses.clone() {
+            if context.is_penalized(robot_id) || is_goal_keeper(context.game_state, robot_id) {
+                context.auto_referee.inactive_robot_poses.remove(&robot_id);
+                continue;
+            }
+            let (since_pose, since) = context
+                .auto_referee
+                .inactive_robot_poses
+                .entry(robot_id)
+                .or_insert((pose, context.now));
+            if !robot_pose_is_stationary(*since_pose, pose) {
+                *since_pose = pose;
+                *since = context.now;
+            } else if has_elapsed(context.now, *since, duration) {
+                inactive_robots.push(robot_id);
+            }
+        }
+
+        for robot_id in inactive_robots {
+            context.auto_referee.inactive_robot_poses.remove(&robot_id);
+            context.penalize(
+                robot_id,
+                Penalty::IncapableRobot {
+                    remaining: context.config.penalty_duration,
+                },
+            );
+        }
+    }
+}
+
+/// Counts down penalties and returns robots on the side line of their own half afterwards.
+pub struct PenaltyRule;
+
+impl AutoRefereeRule for PenaltyRule {
+    fn apply(&mut self, context: &mut AutoRefereeContext<'_>) {
+        if matches!(
+            context.game_state.game_controller_state.game_state,
+            GameState::Initial | GameState::Finished
+        ) {
+            return;
+        }
+        for robot_id in context.robot_poses.keys().copied().collect::<Vec<_>>() {
+            let Some(mut penalty) = context.game_state.penalty(robot_id) else {
+                continue;
+            };
+            let remaining = remaining_penalty_time(&mut penalty);
+            *remaining = remaining.saturating_sub(context.cycle_time);
+            if !remaining.is_zero() {
+                context.game_state.set_penalty(robot_id, Some(penalty));
+                continue;
+            }
+            context.unpenalize(robot_id);
+        }
+    }
+}
+
 impl SimulatorAutoReferee {
     pub fn with_default_rules() -> Self {
         Self {
             rules: vec![
+                Box::new(PenaltyRule),
                 Box::new(ScoredGoalRule),
+                Box::new(BallOutRule),
+                Box::new(SetPlayRule),
+                Box::new(PushingRule),
+                Box::new(LeavingTheFieldRule),
+                Box::new(InactivePlayerRule),
                 Box::new(GameStateTransitionRule),
+                Box::new(IllegalPositioningRule),
                 Box::new(HalftimeTimeoutRule),
             ],
             state: AutoRefereeState::default(),
@@ -339,8 +811,10 @@ pub fn run_auto_referee(
     mut auto_referee: ResMut<SimulatorAutoReferee>,
     mut game_state: ResMut<SimulatorGameState>,
     mut ball: ResMut<SimulatorBall>,
+    mut timeline_markers: ResMut<SimulatorTimelineMarkers>,
+    collisions: Res<SimulatorRobotCollisions>,
     mut referee_commands: MessageReader<SimulatorRefereeCommand>,
-    robots: Query<(&SimulatorRobot, &SimulatorGroundToWorld)>,
+    mut robots: Query<(&SimulatorRobot, &mut SimulatorGroundToWorld)>,
 ) {
     let mut rules = std::mem::take(&mut auto_referee.rules);
     let robot_poses = robots
@@ -355,7 +829,11 @@ pub fn run_auto_referee(
         auto_referee: &mut auto_referee.state,
         ball: &mut ball,
         robot_poses,
+        cycle_time: clock.tick_duration,
+        pushings: collisions.pushings.clone(),
+        robot_placements: BTreeMap::new(),
     };
+    let number_of_calls = context.auto_referee.calls.len();
 
     for command in referee_commands.read() {
         apply_referee_command(*command, &mut context);
@@ -365,6 +843,16 @@ pub fn run_auto_referee(
         rule.apply(&mut context);
     }
 
+    let robot_placements = std::mem::take(&mut context.robot_placements);
+    for (time, call) in &auto_referee.state.calls[number_of_calls..] {
+        timeline_markers.add(*time, call.color(), call.to_string());
+    }
+    for (robot, mut ground_to_world) in &mut robots {
+        if let Some(pose) = robot_placements.get(&robot.id()) {
+            ground_to_world.ground_to_world = *pose;
+        }
+    }
+
     auto_referee.rules = rules;
 }
 
@@ -398,6 +886,13 @@ fn apply_referee_command(command: SimulatorRefereeCommand, context: &mut AutoRef
                 GamePhase::Normal
             });
         }
+        SimulatorRefereeCommand::Penalize { robot, penalty } => context.penalize(robot, penalty),
+        SimulatorRefereeCommand::Unpenalize(robot) => context.unpenalize(robot),
+        SimulatorRefereeCommand::SetPlay {
+            set_play,
+            kicking_team,
+            position,
+        } => context.start_set_play(set_play, kicking_team, position),
     }
 }
 
@@ -415,6 +910,30 @@ fn goal_difference(game_controller_state: &GameControllerState) -> u8 {
         .abs_diff(game_controller_state.opponent_team.score)
 }
 
+fn is_goal_keeper(game_state: &SimulatorGameState, robot_id: SimulatorRobotId) -> bool {
+    let team_state = match robot_id.team {
+        Team::Hulks => &game_state.game_controller_state.hulks_team,
+        Team::Opponent => &game_state.game_controller_state.opponent_team,
+    };
+    team_state.goal_keeper_player_number == Some(robot_id.player_number)
+}
+
+fn remaining_penalty_time(penalty: &mut Penalty) -> &mut Duration {
+    match penalty {
+        Penalty::IllegalPosition { remaining }
+        | Penalty::MotionInSet { remaining }
+        | Penalty::LocalGameStuck { remaining }
+        | Penalty::IncapableRobot { remaining }
+        | Penalty::PickUp { remaining }
+        | Penalty::BallHolding { remaining }
+        | Penalty::LeavingTheField { remaining }
+        | Penalty::PlayingWithArmsHands { remaining }
+        | Penalty::Pushing { remaining }
+        | Penalty::SentOff { remaining }
+        | Penalty::Substitute { remaining } => remaining,
+    }
+}
+
 fn has_elapsed(now: SystemTime, since: SystemTime, duration: Duration) -> bool {
     matches!(now.duration_since(since), Ok(elapsed) if elapsed >= duration)
 }
@@ -498,6 +1017,9 @@ mod tests {
             auto_referee,
             ball,
             robot_poses,
+            cycle_time: Duration::from_millis(10),
+            pushings: Vec::new(),
+            robot_placements: BTreeMap::new(),
         }
     }
 
@@ -509,6 +1031,7 @@ mod tests {
             halftime_duration: Duration::from_secs(600),
             auto_whistle_in_set: true,
             finish_on_halftime_timeout: true,
+            ..Default::default()
         }
     }
 
@@ -632,6 +1155,7 @@ mod tests {
         assert_eq!(config.halftime_duration, Duration::from_secs(10 * 60));
         assert!(config.auto_whistle_in_set);
         assert!(config.finish_on_halftime_timeout);
+        assert_eq!(config.inactive_player_duration, None);
     }
 
     #[test]
@@ -965,4 +1489,296 @@ mod tests {
             GamePhase::Timeout
         );
     }
+
+    fn playing_context_test(
+        robot_poses: BTreeMap<SimulatorRobotId, Isometry2<Ground, World>>,
+        ball_position: Point2<World>,
+        test: impl FnOnce(&mut AutoRefereeContext<'_>),
+    ) -> (SimulatorGameState, AutoRefereeState, SimulatorBall) {
+        let config = AutoRefereeConfig::default();
+        let mut game_state = SimulatorGameState::default();
+        let mut auto_referee = AutoRefereeState::default();
+        let mut ball = SimulatorBall {
+            state: Some(SimulatedBall {
+                position: ball_position,
+                velocity: vector![0.0, 0.0],
+                field_side: Side::Left,
+            }),
+        };
+        test(&mut auto_referee_context_with_robot_poses(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+            &config,
+            FieldDimensions::SPL_2025,
+            &mut game_state,
+            &mut auto_referee,
+            &mut ball,
+            robot_poses,
+        ));
+        (game_state, auto_referee, ball)
+    }
+
+    #[test]
+    fn ball_over_side_line_gives_kick_in_to_opponent_of_last_touch() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let robot_poses = BTreeMap::from([(
+            robot_id(PlayerNumber::Three),
+            Isometry2::from_parts(vector![1.0, field_dimensions.width / 2.0 - 0.1], 0.0),
+        )]);
+
+        let (game_state, auto_referee, ball) = playing_context_test(
+            robot_poses,
+            point![1.0, field_dimensions.width / 2.0 + 0.1],
+            |context| {
+                context.auto_referee.last_ball_touch = Some(robot_id(PlayerNumber::Three));
+                BallOutRule.apply(context);
+            },
+        );
+
+        assert_eq!(
+            game_state.game_controller_state.sub_state,
+            Some(SubState::ThrowIn)
+        );
+        assert_eq!(
+            game_state.game_controller_state.kicking_team,
+            Some(Team::Opponent)
+        );
+        assert_eq!(
+            ball.state.expect("ball should be placed").position,
+            point![1.0, field_dimensions.width / 2.0]
+        );
+        assert_eq!(
+            auto_referee.calls,
+            vec![(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+                RefereeCall::SetPlay {
+                    set_play: SubState::ThrowIn,
+                    kicking_team: Team::Opponent,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn ball_over_own_goal_line_gives_corner_or_goal_kick() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let ball_position = point![-field_dimensions.length / 2.0 - 0.1, 1.5];
+
+        let (game_state, _, ball) =
+            playing_context_test(BTreeMap::new(), ball_position, |context| {
+                context.auto_referee.last_ball_touch = Some(robot_id(PlayerNumber::Two));
+                BallOutRule.apply(context);
+            });
+        assert_eq!(
+            game_state.game_controller_state.sub_state,
+            Some(SubState::CornerKick)
+        );
+        assert_eq!(
+            game_state.game_controller_state.kicking_team,
+            Some(Team::Opponent)
+        );
+        assert_eq!(
+            ball.state.expect("ball should be placed").position,
+            point![-field_dimensions.length / 2.0, field_dimensions.width / 2.0]
+        );
+
+        let (game_state, _, _) = playing_context_test(BTreeMap::new(), ball_position, |context| {
+            context.auto_referee.last_ball_touch =
+                Some(SimulatorRobotId::new(Team::Opponent, PlayerNumber::Two));
+            BallOutRule.apply(context);
+        });
+        assert_eq!(
+            game_state.game_controller_state.sub_state,
+            Some(SubState::GoalKick)
+        );
+        assert_eq!(
+            game_state.game_controller_state.kicking_team,
+            Some(Team::Hulks)
+        );
+    }
+
+    #[test]
+    fn pushing_penalizes_pusher_and_awards_free_kick() {
+        let pusher = SimulatorRobotId::new(Team::Opponent, PlayerNumber::Four);
+        let pushed = robot_id(PlayerNumber::Three);
+        let robot_poses = BTreeMap::from([
+            (pusher, Isometry2::from_parts(vector![0.5, 0.0], 0.0)),
+            (pushed, Isometry2::identity()),
+        ]);
+
+        let (game_state, auto_referee, _) =
+            playing_context_test(robot_poses, point![1.0, 0.0], |context| {
+                context.pushings.push(Pushing {
+                    time: context.now,
+                    pusher,
+                    pushed,
+                });
+                PushingRule.apply(context);
+                assert!(context.robot_placements.contains_key(&pusher));
+            });
+
+        assert!(matches!(
+            game_state.penalty(pusher),
+            Some(Penalty::Pushing { .. })
+        ));
+        assert_eq!(game_state.penalty(pushed), None);
+        assert_eq!(
+            game_state.game_controller_state.sub_state,
+            Some(SubState::DirectFreeKick)
+        );
+        assert_eq!(
+            game_state.game_controller_state.kicking_team,
+            Some(Team::Hulks)
+        );
+        assert_eq!(auto_referee.calls.len(), 2);
+    }
+
+    #[test]
+    fn pushing_a_teammate_is_not_penalized() {
+        let pusher = robot_id(PlayerNumber::Four);
+        let pushed = robot_id(PlayerNumber::Three);
+        let robot_poses = BTreeMap::from([
+            (pusher, Isometry2::from_parts(vector![0.5, 0.0], 0.0)),
+            (pushed, Isometry2::identity()),
+        ]);
+
+        let (game_state, auto_referee, _) =
+            playing_context_test(robot_poses, point![1.0, 0.0], |context| {
+                context.pushings.push(Pushing {
+                    time: context.now,
+                    pusher,
+                    pushed,
+                });
+                PushingRule.apply(context);
+            });
+
+        assert_eq!(game_state.penalty(pusher), None);
+        assert_eq!(game_state.game_controller_state.sub_state, None);
+        assert!(auto_referee.calls.is_empty());
+    }
+
+    #[test]
+    fn set_play_counts_down_secondary_time() {
+        let config = AutoRefereeConfig::default();
+        let mut game_state = SimulatorGameState::default();
+        let mut auto_referee = AutoRefereeState::default();
+        let mut ball = SimulatorBall::default();
+        let started_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+
+        let mut context = auto_referee_context(
+            started_at,
+            &config,
+            FieldDimensions::SPL_2025,
+            &mut game_state,
+            &mut auto_referee,
+            &mut ball,
+        );
+        context.start_set_play(SubState::GoalKick, Team::Hulks, point![-3.5, 1.0]);
+        assert_eq!(
+            context.game_state.game_controller_state.secondary_time,
+            config.set_play_duration
+        );
+
+        context.now = started_at + Duration::from_secs(10);
+        SetPlayRule.apply(&mut context);
+        assert_eq!(
+            context.game_state.game_controller_state.secondary_time,
+            config.set_play_duration - Duration::from_secs(10)
+        );
+        assert_eq!(
+            context
+                .game_state
+                .filtered_game_controller_state
+                .as_ref()
+                .expect("filtered game state should exist")
+                .secondary_time,
+            config.set_play_duration - Duration::from_secs(10)
+        );
+
+        context.now = started_at + config.set_play_duration;
+        SetPlayRule.apply(&mut context);
+        assert_eq!(
+            context.game_state.game_controller_state.secondary_time,
+            Duration::ZERO
+        );
+        assert_eq!(context.game_state.game_controller_state.sub_state, None);
+    }
+
+    #[test]
+    fn expired_penalty_returns_robot_to_side_line() {
+        let robot = robot_id(PlayerNumber::Three);
+        let robot_poses = BTreeMap::from([(robot, Isometry2::identity())]);
+
+        let (game_state, auto_referee, _) =
+            playing_context_test(robot_poses, point![0.0, 0.0], |context| {
+                context.game_state.set_penalty(
+                    robot,
+                    Some(Penalty::Pushing {
+                        remaining: Duration::from_millis(15),
+                    }),
+                );
+                PenaltyRule.apply(context);
+                assert!(context.is_penalized(robot));
+                PenaltyRule.apply(context);
+                assert!(!context.is_penalized(robot));
+
+                let pose = context.robot_placements[&robot];
+                assert_eq!(
+                    pose.translation().y().abs(),
+                    context.field_dimensions.width / 2.0
+                );
+                assert!(pose.translation().x() < 0.0);
+            });
+
+        assert_eq!(game_state.penalty(robot), None);
+        assert!(matches!(
+            auto_referee.calls.as_slice(),
+            [(_, RefereeCall::Unpenalized { .. })]
+        ));
+    }
+
+    #[test]
+    fn robots_in_opponent_half_are_penalized_when_entering_set() {
+        let config = AutoRefereeConfig::default();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let mut game_state = SimulatorGameState::default();
+        game_state.set_game_state(GameState::Set, now);
+        let mut auto_referee = AutoRefereeState {
+            last_game_state_change: now,
+            ..Default::default()
+        };
+        let mut ball = SimulatorBall::default();
+        let legal = robot_id(PlayerNumber::Two);
+        let in_opponent_half = robot_id(PlayerNumber::Three);
+        let in_center_circle = SimulatorRobotId::new(Team::Opponent, PlayerNumber::Four);
+
+        IllegalPositioningRule.apply(&mut auto_referee_context_with_robot_poses(
+            now,
+            &config,
+            FieldDimensions::SPL_2025,
+            &mut game_state,
+            &mut auto_referee,
+            &mut ball,
+            BTreeMap::from([
+                (legal, Isometry2::from_parts(vector![-1.0, 0.0], 0.0)),
+                (
+                    in_opponent_half,
+                    Isometry2::from_parts(vector![1.0, 0.0], 0.0),
+                ),
+                (
+                    in_center_circle,
+                    Isometry2::from_parts(vector![0.3, 0.0], 0.0),
+                ),
+            ]),
+        ));
+
+        assert_eq!(game_state.penalty(legal), None);
+        assert!(matches!(
+            game_state.penalty(in_opponent_half),
+            Some(Penalty::IllegalPosition { .. })
+        ));
+        assert!(matches!(
+            game_state.penalty(in_center_circle),
+            Some(Penalty::IllegalPosition { .. })
+        ));
+    }
 }
@@ -10,7 +10,9 @@ use types::{
 };
 
 use crate::{
-    behavior_tree_simulator::SimulatorClock, config::SimulationConfig,
+    behavior_tree_simulator::{SimulatorClock, SimulatorFieldDimensions, SimulatorGroundToWorld},
+    collisions::collide_ball,
+    config::SimulationConfig,
     coordinates::point_world_to_field,
 };
 
@@ -38,6 +40,7 @@ impl SimulatedBall {
             ball_in_ground: ground_to_world.inverse() * self.position,
             ball_in_field,
             ball_in_ground_velocity: ground_to_world.inverse() * self.velocity,
+            ball_in_ground_covariance: Default::default(),
             last_seen_ball: now,
             field_side: self.field_side,
         }
@@ -47,7 +50,9 @@ impl SimulatedBall {
 pub fn move_ball(
     clock: Res<SimulatorClock>,
     config: Res<SimulationConfig>,
+    field_dimensions: Res<SimulatorFieldDimensions>,
     mut ball: ResMut<SimulatorBall>,
+    robots: Query<&SimulatorGroundToWorld>,
 ) {
     let Some(ball) = &mut ball.state else {
         return;
@@ -55,4 +60,10 @@ pub fn move_ball(
     let dt = clock.tick_duration.as_secs_f32();
     ball.position += ball.velocity * dt;
     ball.velocity *= (1.0 - config.ball_friction_per_second * dt).clamp(0.0, 1.0);
+
+    let robot_positions: Vec<_> = robots
+        .iter()
+        .map(|ground_to_world| ground_to_world.ground_to_world.translation())
+        .collect();
+    collide_ball(ball, &robot_positions, &field_dimensions.0, &config);
 }
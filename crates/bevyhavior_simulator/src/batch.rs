@@ -0,0 +1,549 @@
+use std::{
+    f32::consts::PI,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use coordinate_systems::{Ground, World};
+use hsl_network_messages::{GameState, PlayerNumber, Team};
+use linear_algebra::{Isometry2, distance, point, vector};
+use serde::Serialize;
+use types::{field_dimensions::Side, parameters::BehaviorParameters, primary_state::PrimaryState};
+
+use crate::behavior_tree_simulator::{
+    AppExt, AutoRefereeConfig, BehaviorTreeSimulatorPlugin, BehaviorTreeSimulatorSet,
+    SimulatedBall, SimulationConfig, SimulatorBall, SimulatorClock,
+    SimulatorCurrentInvariantViolations, SimulatorGameState, SimulatorGroundToWorld,
+    SimulatorRobot, SimulatorRobotBundle,
+};
+use crate::invariant_checks::BEHAVIOR_TICK_ERROR_CHECK_NAME;
+
+/// Standard normal quantile of a two-sided 95 % confidence interval
+const NORMAL_QUANTILE: f32 = 1.959964;
+/// Student t quantiles of a two-sided 95 % confidence interval for 1 to 30 degrees of freedom
+const STUDENT_T_QUANTILES: [f32; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+/// The team of the closest robot possesses the ball if it is at most this far away
+const POSSESSION_DISTANCE: f32 = 1.0;
+
+#[derive(Clone, Debug)]
+pub struct BatchVariant {
+    pub name: String,
+    pub parameters: BehaviorParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchConfig {
+    pub number_of_matches: usize,
+    /// Match `i` is seeded with `seed + i`, so every match of a batch is reproducible on its own
+    pub seed: u64,
+    pub number_of_threads: usize,
+    pub match_duration: Duration,
+    /// Variant A plays as HULKs in even matches and as opponent in odd matches
+    pub variant_a: BatchVariant,
+    pub variant_b: BatchVariant,
+    /// Perception and network seeds are replaced by the match seed
+    pub simulation: SimulationConfig,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MatchResult {
+    pub seed: u64,
+    pub variant_a_team: Team,
+    pub goals_a: u8,
+    pub goals_b: u8,
+    /// Seconds until the first goal of the variant, if it scored
+    pub time_to_first_goal_a: Option<f32>,
+    pub time_to_first_goal_b: Option<f32>,
+    /// Fraction of the match the variant was closest to the ball
+    pub possession_a: f32,
+    pub possession_b: f32,
+    /// Invariant violations summed over all ticks, including those below the failure thresholds
+    pub invariant_violations: usize,
+    pub messages_used_a: u16,
+    pub messages_used_b: u16,
+    /// Why the match failed, this includes invariant violations
+    pub error: Option<String>,
+}
+
+/// Sample mean with a 95 % confidence interval of the mean.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Estimate {
+    pub samples: usize,
+    pub mean: f32,
+    pub standard_deviation: f32,
+    pub confidence_interval: [f32; 2],
+}
+
+impl Estimate {
+    pub fn from_samples(samples: &[f32]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let number_of_samples = samples.len() as f32;
+        let mean = samples.iter().sum::<f32>() / number_of_samples;
+        let standard_deviation = if samples.len() > 1 {
+            (samples
+                .iter()
+                .map(|sample| (sample - mean).powi(2))
+                .sum::<f32>()
+                / (number_of_samples - 1.0))
+                .sqrt()
+        } else {
+            0.0
+        };
+        let half_width =
+            student_t_quantile(samples.len() - 1) * standard_deviation / number_of_samples.sqrt();
+        Self {
+            samples: samples.len(),
+            mean,
+            standard_deviation,
+            confidence_interval: [mean - half_width, mean + half_width],
+        }
+    }
+}
+
+/// Quantile of a two-sided 95 % confidence interval of the Student t distribution.
+///
+/// Beyond the table the Cornish-Fisher expansion around the normal quantile is exact to three
+/// decimals.
+fn student_t_quantile(degrees_of_freedom: usize) -> f32 {
+    if let Some(quantile) = degrees_of_freedom
+        .checked_sub(1)
+        .and_then(|index| STUDENT_T_QUANTILES.get(index))
+    {
+        return *quantile;
+    }
+    let z = NORMAL_QUANTILE;
+    let degrees_of_freedom = degrees_of_freedom.max(1) as f32;
+    z + (z.powi(3) + z) / (4.0 * degrees_of_freedom)
+        + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * degrees_of_freedom.powi(2))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VariantStatistics {
+    pub name: String,
+    pub goals: Estimate,
+    /// Only matches in which the variant scored
+    pub time_to_first_goal: Estimate,
+    pub possession: Estimate,
+    pub messages_used: Estimate,
+    pub wins: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchReport {
+    pub number_of_matches: usize,
+    pub seed: u64,
+    pub match_duration: f32,
+    /// Statistics of the variants only cover matches without error, failed matches end early and
+    /// would bias goals and possession
+    pub variant_a: VariantStatistics,
+    pub variant_b: VariantStatistics,
+    pub draws: usize,
+    /// Goals of variant A minus goals of variant B per match, an interval excluding zero
+    /// indicates a significant difference
+    pub goal_difference: Estimate,
+    pub invariant_violations: Estimate,
+    pub failed_matches: usize,
+    pub matches: Vec<MatchResult>,
+}
+
+impl BatchReport {
+    pub fn from_matches(config: &BatchConfig, matches: Vec<MatchResult>) -> Self {
+        let completed_matches: Vec<_> = matches
+            .iter()
+            .filter(|result| result.error.is_none())
+            .collect();
+        let estimate = |sample: fn(&MatchResult) -> Option<f32>| {
+            Estimate::from_samples(
+                &completed_matches
+                    .iter()
+                    .filter_map(|result| sample(result))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        Self {
+            number_of_matches: matches.len(),
+            seed: config.seed,
+            match_duration: config.match_duration.as_secs_f32(),
+            variant_a: VariantStatistics {
+                name: config.variant_a.name.clone(),
+                goals: estimate(|result| Some(result.goals_a.into())),
+                time_to_first_goal: estimate(|result| result.time_to_first_goal_a),
+                possession: estimate(|result| Some(result.possession_a)),
+                messages_used: estimate(|result| Some(result.messages_used_a.into())),
+                wins: completed_matches
+                    .iter()
+                    .filter(|result| result.goals_a > result.goals_b)
+                    .count(),
+            },
+            variant_b: VariantStatistics {
+                name: config.variant_b.name.clone(),
+                goals: estimate(|result| Some(result.goals_b.into())),
+                time_to_first_goal: estimate(|result| result.time_to_first_goal_b),
+                possession: estimate(|result| Some(result.possession_b)),
+                messages_used: estimate(|result| Some(result.messages_used_b.into())),
+                wins: completed_matches
+                    .iter()
+                    .filter(|result| result.goals_b > result.goals_a)
+                    .count(),
+            },
+            draws: completed_matches
+                .iter()
+                .filter(|result| result.goals_a == result.goals_b)
+                .count(),
+            goal_difference: estimate(|result| {
+                Some(f32::from(result.goals_a) - f32::from(result.goals_b))
+            }),
+            invariant_violations: Estimate::from_samples(
+                &matches
+                    .iter()
+                    .map(|result| result.invariant_violations as f32)
+                    .collect::<Vec<_>>(),
+            ),
+            failed_matches: matches
+                .iter()
+                .filter(|result| result.error.is_some())
+                .count(),
+            matches,
+        }
+    }
+}
+
+/// Runs all matches of the batch headless on `number_of_threads` threads.
+pub fn run_batch(config: &BatchConfig) -> BatchReport {
+    let next_match = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(config.number_of_matches));
+
+    thread::scope(|scope| {
+        for _ in 0..config.number_of_threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let index = next_match.fetch_add(1, Ordering::Relaxed);
+                    if index >= config.number_of_matches {
+                        break;
+                    }
+                    let result = run_match(config, index);
+                    results
+                        .lock()
+                        .expect("a match thread panicked")
+                        .push((index, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().expect("a match thread panicked");
+    results.sort_by_key(|(index, _)| *index);
+    BatchReport::from_matches(
+        config,
+        results.into_iter().map(|(_, result)| result).collect(),
+    )
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+struct MatchStatistics {
+    ticks: u32,
+    possession_ticks_hulks: u32,
+    possession_ticks_opponent: u32,
+    first_goal_hulks: Option<Duration>,
+    first_goal_opponent: Option<Duration>,
+    invariant_violations: usize,
+}
+
+fn run_match(config: &BatchConfig, index: usize) -> MatchResult {
+    let seed = config.seed.wrapping_add(index as u64);
+    let (hulks, opponent, variant_a_team) = if index % 2 == 0 {
+        (&config.variant_a, &config.variant_b, Team::Hulks)
+    } else {
+        (&config.variant_b, &config.variant_a, Team::Opponent)
+    };
+
+    let mut simulation = config.simulation.clone();
+    simulation.perception.seed = seed;
+    simulation.network.seed = seed;
+
+    let mut app = App::new();
+    app.add_plugins(BehaviorTreeSimulatorPlugin {
+        config: simulation,
+        auto_referee_config: AutoRefereeConfig {
+            halftime_duration: config.match_duration,
+            ..Default::default()
+        },
+        enable_timeline_recording: false,
+        ..Default::default()
+    })
+    .insert_resource(MatchStatistics::default())
+    .add_systems(
+        Update,
+        record_match_statistics.in_set(BehaviorTreeSimulatorSet::Scenario),
+    );
+
+    let mut error = None;
+    for (team, variant) in [(Team::Hulks, hulks), (Team::Opponent, opponent)] {
+        for (player_number, pose) in lineup(team) {
+            match SimulatorRobotBundle::new(team, player_number, pose, variant.parameters.clone()) {
+                Ok(bundle) => {
+                    app.world_mut()
+                        .spawn(bundle.with_primary_state(PrimaryState::Playing));
+                }
+                Err(spawn_error) => error = Some(format!("{spawn_error:#}")),
+            }
+        }
+    }
+    app.world_mut().resource_mut::<SimulatorBall>().state = Some(SimulatedBall {
+        position: point![0.0, 0.0],
+        velocity: vector![0.0, 0.0],
+        field_side: Side::Left,
+    });
+
+    let remaining_messages = |app: &App| {
+        let game_state = &app
+            .world()
+            .resource::<SimulatorGameState>()
+            .game_controller_state;
+        (
+            game_state.hulks_team.remaining_amount_of_messages,
+            game_state.opponent_team.remaining_amount_of_messages,
+        )
+    };
+    let initial_messages = remaining_messages(&app);
+
+    if error.is_none()
+        && let Err(run_error) = app.run_to_completion()
+    {
+        error = Some(format!("{run_error:#}"));
+    }
+
+    let final_messages = remaining_messages(&app);
+    let messages_used = (
+        initial_messages.0.saturating_sub(final_messages.0),
+        initial_messages.1.saturating_sub(final_messages.1),
+    );
+    let game_state = &app
+        .world()
+        .resource::<SimulatorGameState>()
+        .game_controller_state;
+    let goals = (game_state.hulks_team.score, game_state.opponent_team.score);
+    let statistics = app.world().resource::<MatchStatistics>();
+    let ticks = statistics.ticks.max(1) as f32;
+    let possession = (
+        statistics.possession_ticks_hulks as f32 / ticks,
+        statistics.possession_ticks_opponent as f32 / ticks,
+    );
+    let first_goal = (
+        statistics.first_goal_hulks.map(|time| time.as_secs_f32()),
+        statistics
+            .first_goal_opponent
+            .map(|time| time.as_secs_f32()),
+    );
+    let invariant_violations = statistics.invariant_violations;
+
+    let (goals_a, goals_b) = by_variant(goals, variant_a_team);
+    let (possession_a, possession_b) = by_variant(possession, variant_a_team);
+    let (time_to_first_goal_a, time_to_first_goal_b) = by_variant(first_goal, variant_a_team);
+    let (messages_used_a, messages_used_b) = by_variant(messages_used, variant_a_team);
+
+    MatchResult {
+        seed,
+        variant_a_team,
+        goals_a,
+        goals_b,
+        time_to_first_goal_a,
+        time_to_first_goal_b,
+        possession_a,
+        possession_b,
+        invariant_violations,
+        messages_used_a,
+        messages_used_b,
+        error,
+    }
+}
+
+/// Reorders values of (HULKs, opponent) to (variant A, variant B).
+fn by_variant<T>((hulks, opponent): (T, T), variant_a_team: Team) -> (T, T) {
+    match variant_a_team {
+        Team::Hulks => (hulks, opponent),
+        Team::Opponent => (opponent, hulks),
+    }
+}
+
+fn lineup(team: Team) -> [(PlayerNumber, Isometry2<Ground, World>); 3] {
+    let (sign, yaw) = match team {
+        Team::Hulks => (1.0, 0.0),
+        Team::Opponent => (-1.0, PI),
+    };
+    let pose = |x: f32, y: f32| Isometry2::from_parts(vector![sign * x, sign * y], yaw);
+    [
+        (PlayerNumber::Three, pose(-0.8, 0.0)),
+        (PlayerNumber::Four, pose(-1.5, 1.0)),
+        (PlayerNumber::Five, pose(-1.5, -1.0)),
+    ]
+}
+
+fn record_match_statistics(
+    clock: Res<SimulatorClock>,
+    game_state: Res<SimulatorGameState>,
+    ball: Res<SimulatorBall>,
+    current_violations: Res<SimulatorCurrentInvariantViolations>,
+    mut statistics: ResMut<MatchStatistics>,
+    mut exit: MessageWriter<AppExit>,
+    robots: Query<(&SimulatorRobot, &SimulatorGroundToWorld)>,
+) {
+    let elapsed = clock
+        .now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let game_controller_state = &game_state.game_controller_state;
+    if game_controller_state.hulks_team.score > 0 && statistics.first_goal_hulks.is_none() {
+        statistics.first_goal_hulks = Some(elapsed);
+    }
+    if game_controller_state.opponent_team.score > 0 && statistics.first_goal_opponent.is_none() {
+        statistics.first_goal_opponent = Some(elapsed);
+    }
+
+    statistics.ticks += 1;
+    // behavior tick errors stay in the current violations and already fail the match
+    statistics.invariant_violations += current_violations
+        .0
+        .iter()
+        .filter(|violation| violation.check_name != BEHAVIOR_TICK_ERROR_CHECK_NAME)
+        .count();
+    let possessing_team = ball.state.and_then(|ball| {
+        robots
+            .iter()
+            .map(|(robot, ground_to_world)| {
+                (
+                    robot.team,
+                    distance(ground_to_world.ground_to_world.translation(), ball.position),
+                )
+            })
+            .filter(|(_, distance)| *distance <= POSSESSION_DISTANCE)
+            .min_by(|(_, left), (_, right)| left.total_cmp(right))
+            .map(|(team, _)| team)
+    });
+    match possessing_team {
+        Some(Team::Hulks) => statistics.possession_ticks_hulks += 1,
+        Some(Team::Opponent) => statistics.possession_ticks_opponent += 1,
+        None => {}
+    }
+
+    if game_controller_state.game_state == GameState::Finished {
+        exit.write(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn estimate_contains_mean_of_samples() {
+        let estimate = Estimate::from_samples(&[1.0, 2.0, 3.0, 4.0]);
+
+        assert_eq!(estimate.samples, 4);
+        assert_relative_eq!(estimate.mean, 2.5);
+        assert_relative_eq!(estimate.standard_deviation, 1.2909944);
+        assert_relative_eq!(
+            estimate.confidence_interval[1] - estimate.mean,
+            3.182 * 1.2909944 / 2.0,
+            epsilon = 1e-5
+        );
+        assert_eq!(Estimate::from_samples(&[]), Estimate::default());
+    }
+
+    #[test]
+    fn student_t_quantile_approaches_normal_quantile() {
+        assert_relative_eq!(student_t_quantile(1), 12.706);
+        assert_relative_eq!(student_t_quantile(30), 2.042);
+        assert_relative_eq!(student_t_quantile(40), 2.021, epsilon = 1e-3);
+        assert_relative_eq!(student_t_quantile(120), 1.980, epsilon = 1e-3);
+        assert!(student_t_quantile(10_000) - NORMAL_QUANTILE < 1e-3);
+    }
+
+    fn match_result(index: usize, hulks_score: u8) -> MatchResult {
+        MatchResult {
+            seed: index as u64,
+            variant_a_team: if index % 2 == 0 {
+                Team::Hulks
+            } else {
+                Team::Opponent
+            },
+            goals_a: hulks_score,
+            goals_b: 0,
+            time_to_first_goal_a: None,
+            time_to_first_goal_b: None,
+            possession_a: 0.5,
+            possession_b: 0.5,
+            invariant_violations: 0,
+            messages_used_a: 0,
+            messages_used_b: 0,
+            error: None,
+        }
+    }
+
+    fn batch_config(number_of_matches: usize) -> BatchConfig {
+        let parameters = BehaviorParameters::default();
+        BatchConfig {
+            number_of_matches,
+            seed: 0,
+            number_of_threads: 1,
+            match_duration: Duration::from_secs(1),
+            variant_a: BatchVariant {
+                name: "variant".to_string(),
+                parameters: parameters.clone(),
+            },
+            variant_b: BatchVariant {
+                name: "baseline".to_string(),
+                parameters,
+            },
+            simulation: SimulationConfig::default(),
+        }
+    }
+
+    #[test]
+    fn report_counts_wins_and_draws_of_variants() {
+        let report = BatchReport::from_matches(
+            &batch_config(2),
+            vec![match_result(0, 2), match_result(1, 0)],
+        );
+
+        assert_eq!(report.variant_a.wins, 1);
+        assert_eq!(report.draws, 1);
+        assert_relative_eq!(report.goal_difference.mean, 1.0);
+        assert_eq!(lineup(Team::Opponent)[0].1.translation(), point![0.8, 0.0]);
+    }
+
+    #[test]
+    fn report_excludes_failed_matches_from_variant_statistics() {
+        let failed_match = MatchResult {
+            possession_a: 0.0,
+            invariant_violations: 3,
+            error: Some("robot fell".to_string()),
+            ..match_result(1, 0)
+        };
+
+        let report =
+            BatchReport::from_matches(&batch_config(2), vec![match_result(0, 2), failed_match]);
+
+        assert_eq!(report.failed_matches, 1);
+        assert_eq!(report.variant_a.goals.samples, 1);
+        assert_relative_eq!(report.variant_a.goals.mean, 2.0);
+        assert_relative_eq!(report.variant_a.possession.mean, 0.5);
+        assert_eq!(report.draws, 0);
+        assert_eq!(report.invariant_violations.samples, 2);
+        assert_relative_eq!(report.invariant_violations.mean, 1.5);
+    }
+}
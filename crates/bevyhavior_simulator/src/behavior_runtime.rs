@@ -2,11 +2,13 @@ use std::{net::SocketAddr, time::Duration};
 
 use behavior_node::{
     behavior_tree::Node as BehaviorNodeTree, motion_assembler::assemble_motion_command,
-    node::Blackboard as BehaviorBlackboard, tree::create_tree as create_behavior_tree,
+    node::Blackboard as BehaviorBlackboard, registry::default_registry, send_message::HslSender,
+    tree::create_tree_from_description,
 };
 use bevy::{app::AppExit, prelude::*};
 use color_eyre::Result;
 use coordinate_systems::{Field, Ground};
+use hsl_network_messages::PlayerNumber;
 use linear_algebra::{Point2, Pose2};
 use types::{
     behavior_tree::NodeTrace,
@@ -21,8 +23,9 @@ use voronoi::VoronoiGrid;
 
 use crate::behavior_tree_simulator::{
     InvariantSeverity, InvariantViolation, RobotFrame, SimulatorClock,
-    SimulatorCurrentInvariantViolations, SimulatorFieldDimensions, SimulatorRobot,
-    SimulatorRobotFrames, SimulatorRobotParameters, SimulatorScenarioResult, SimulatorWorldStates,
+    SimulatorCurrentInvariantViolations, SimulatorFieldDimensions, SimulatorOpponentPolicies,
+    SimulatorRobot, SimulatorRobotFrames, SimulatorRobotParameters, SimulatorScenarioResult,
+    SimulatorWorldStates,
 };
 use crate::invariant_checks::BEHAVIOR_TICK_ERROR_CHECK_NAME;
 
@@ -31,17 +34,19 @@ pub struct SimulatorRobotBehavior {
     pub tree: BehaviorNodeTree<BehaviorBlackboard>,
     pub blackboard: BehaviorBlackboard,
     pub static_layout: NodeTrace,
+    pub hsl_sender: HslSender,
 }
 
 impl SimulatorRobotBehavior {
-    pub fn new(parameters: BehaviorParameters) -> Self {
-        let tree = create_behavior_tree();
+    pub fn new(parameters: BehaviorParameters) -> Result<Self> {
+        let tree = create_tree_from_description(parameters.tree.as_ref(), &default_registry())?;
         let static_layout = tree.static_layout_trace();
-        Self {
+        Ok(Self {
             tree,
-            blackboard: create_behavior_blackboard(parameters),
+            blackboard: BehaviorBlackboard::new(FieldDimensions::default(), parameters),
             static_layout,
-        }
+            hsl_sender: HslSender::default(),
+        })
     }
 
     pub fn tick_behavior_tree(
@@ -50,6 +55,7 @@ impl SimulatorRobotBehavior {
     ) -> Result<SimulatorBehaviorTickOutput> {
         self.blackboard.field_dimensions = input.field_dimensions;
         self.blackboard.parameters = input.parameters;
+        self.blackboard.goal_keeper_number = Some(input.goal_keeper_number);
         self.blackboard.world_state = input.world_state.clone();
 
         self.blackboard.path_obstacles_output.clear();
@@ -61,6 +67,8 @@ impl SimulatorRobotBehavior {
         self.blackboard.body_motion = None;
         self.blackboard.head_motion = None;
         self.blackboard.voronoi_map = None;
+        self.blackboard.kick_decision = None;
+        self.blackboard.utility_table = None;
 
         if let Some(ball) = self.blackboard.world_state.ball {
             self.blackboard.ball = Some(behavior_node::node::LastBall {
@@ -129,7 +137,10 @@ impl SimulatorRobotBehavior {
         {
             outgoing_messages.push(message);
         }
-        if let Some(message) = self.blackboard.state_message() {
+        if let Some(message) = self.hsl_sender.message(
+            &self.blackboard.world_state,
+            &self.blackboard.parameters.hsl_network,
+        ) {
             outgoing_messages.push(message);
         }
         outgoing_messages
@@ -140,6 +151,7 @@ pub struct SimulatorBehaviorTickInput {
     pub world_state: WorldState,
     pub field_dimensions: FieldDimensions,
     pub parameters: BehaviorParameters,
+    pub goal_keeper_number: PlayerNumber,
 }
 
 pub struct SimulatorBehaviorTickOutput {
@@ -154,39 +166,11 @@ pub struct SimulatorBehaviorTickOutput {
     pub voronoi_inputs: Vec<Pose2<Field>>,
 }
 
-fn create_behavior_blackboard(parameters: BehaviorParameters) -> BehaviorBlackboard {
-    BehaviorBlackboard {
-        field_dimensions: FieldDimensions::default(),
-        parameters,
-        world_state: WorldState::default(),
-        path_obstacles_output: Vec::new(),
-        time_since_last_switch: Duration::ZERO,
-        direction_difference: 0.0,
-        voronoi_inputs: Vec::new(),
-        ball: None,
-        last_ball: None,
-        last_close_enough_to_kick: false,
-        last_kick_target: None,
-        last_motion_command: MotionCommand::default(),
-        last_motion_switch_time: ros_z::time::Time::zero(),
-        last_motion_type: None,
-        last_sent_game_controller_return_message_time: None,
-        last_sent_hsl_message_time: None,
-        last_closest_to_ball: false,
-        closest_to_ball_entered_area_since: None,
-        closest_to_ball_left_area_since: None,
-        is_injected_motion_command: false,
-        walk_position: None,
-        body_motion: None,
-        head_motion: None,
-        voronoi_map: None,
-    }
-}
-
 pub fn tick_behavior_trees(
     clock: Res<SimulatorClock>,
     field_dimensions: Res<SimulatorFieldDimensions>,
     world_states: Res<SimulatorWorldStates>,
+    opponent_policies: Res<SimulatorOpponentPolicies>,
     mut robot_frames: ResMut<SimulatorRobotFrames>,
     mut current_violations: ResMut<SimulatorCurrentInvariantViolations>,
     mut scenario_result: ResMut<SimulatorScenarioResult>,
@@ -200,6 +184,9 @@ pub fn tick_behavior_trees(
     robot_frames.0.clear();
 
     for (robot, parameters, mut behavior) in &mut robots {
+        if opponent_policies.controls(robot.team) {
+            continue;
+        }
         let robot_id = robot.id();
         let Some(world_state) = world_states.0.get(&robot_id).cloned() else {
             continue;
@@ -209,12 +196,13 @@ pub fn tick_behavior_trees(
             world_state: world_state.clone(),
             field_dimensions: field_dimensions.0,
             parameters: parameters.behavior.clone(),
+            goal_keeper_number: parameters.goal_keeper_number,
         }) {
             Ok(tick_output) => tick_output,
             Err(error) => {
                 scenario_result.failed = true;
                 current_violations.0.push(InvariantViolation {
-                    check_name: BEHAVIOR_TICK_ERROR_CHECK_NAME,
+                    check_name: BEHAVIOR_TICK_ERROR_CHECK_NAME.into(),
                     player_number: Some(robot.player_number),
                     message: behavior_tick_failure_message(robot_id, &clock, &error),
                     severity: InvariantSeverity::Error,
@@ -260,7 +248,7 @@ mod tests {
     use std::{collections::BTreeMap, time::Duration, time::SystemTime};
 
     use bevy::{app::App, ecs::message::Messages};
-    use hsl_network_messages::{PlayerNumber, Team};
+    use hsl_network_messages::Team;
     use types::{behavior_tree::Status, world_state::WorldState};
 
     use super::*;
@@ -288,13 +276,15 @@ mod tests {
             .insert_resource(SimulatorRobotFrames::default())
             .insert_resource(SimulatorCurrentInvariantViolations::default())
             .insert_resource(SimulatorScenarioResult::default())
+            .insert_resource(SimulatorOpponentPolicies::default())
             .add_systems(Update, tick_behavior_trees);
 
         let mut behavior = SimulatorRobotBehavior::new(
             default_behavior_parameters().expect("failed to load behavior parameters"),
-        );
+        )
+        .expect("failed to create behavior");
         behavior.tree = BehaviorNodeTree::Action {
-            name: "return_idle",
+            name: "return_idle".into(),
             action: Box::new(|_| Status::Idle),
         };
         app.world_mut().spawn((
@@ -306,6 +296,7 @@ mod tests {
                 behavior: default_behavior_parameters()
                     .expect("failed to load behavior parameters"),
                 walking: default_walking_parameters().expect("failed to load walking parameters"),
+                goal_keeper_number: PlayerNumber::One,
             },
             behavior,
         ));
@@ -8,7 +8,7 @@ use bevy::{
 use color_eyre::{Result, eyre::bail};
 use coordinate_systems::{Ground, World};
 use linear_algebra::{Isometry2, Point2};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use types::{
     field_dimensions::FieldDimensions,
     obstacles::{Obstacle, ObstacleKind},
@@ -19,42 +19,76 @@ use types::{
 use crate::timeline_viewer::{TimelineViewerData, show_timeline_viewer};
 
 pub use crate::auto_referee::{
-    AutoRefereeConfig, AutoRefereeContext, AutoRefereeRule, AutoRefereeState,
-    GameStateTransitionRule, HalftimeTimeoutRule, ScoredGoalRule, SimulatorAutoReferee,
-    SimulatorRefereeCommand, SimulatorRestartReason,
+    AutoRefereeConfig, AutoRefereeContext, AutoRefereeRule, AutoRefereeState, BallOutRule,
+    GameStateTransitionRule, HalftimeTimeoutRule, IllegalPositioningRule, InactivePlayerRule,
+    LeavingTheFieldRule, PenaltyRule, PushingRule, RefereeCall, ScoredGoalRule, SetPlayRule,
+    SimulatorAutoReferee, SimulatorRefereeCommand, SimulatorRestartReason,
 };
 pub use crate::ball::{SimulatedBall, SimulatorBall};
+pub use crate::batch::{
+    BatchConfig, BatchReport, BatchVariant, Estimate, MatchResult, VariantStatistics, run_batch,
+};
 pub use crate::behavior_runtime::{
     SimulatorBehaviorTickInput, SimulatorBehaviorTickOutput, SimulatorRobotBehavior,
 };
+pub use crate::collisions::{Pushing, SimulatorRobotCollisions, goal_post_positions};
 pub use crate::communication::{
     SimulatorHslNetworkParameters, SimulatorIncomingMessage, SimulatorIncomingMessages,
     SimulatorMessage, SimulatorOutgoingMessages, SimulatorReceivedHslMessage,
     SimulatorReceivedHslMessages,
 };
 pub use crate::config::{
-    DEFAULT_TICK_DURATION, SimulationConfig, default_behavior_parameters,
-    default_walking_parameters,
+    BurstOutages, DEFAULT_TICK_DURATION, Jitter, LinkImpairment, LocalizationDrift, NetworkConfig,
+    PerceptionConfig, PositionNoise, SimulationConfig, default_behavior_parameters,
+    default_goal_keeper_number, default_walking_parameters,
 };
 pub use crate::game_controller::SimulatorGameState;
 pub use crate::invariant_checks::{
-    InvariantCheck, InvariantSeverity, InvariantViolation, RobotSnapshot, SimulationSnapshot,
-    SimulatorCurrentInvariantViolations, SimulatorInvariantChecks, default_invariant_checks,
+    FieldBoundaryWalkCheck, GoalKeeperLeavingPenaltyAreaCheck, HslMessageBudgetCheck,
+    InvariantCheck, InvariantChecksConfig, InvariantSeverity, InvariantViolation,
+    MissingDefenderCheck, OscillatingMotionCheck, RobotSnapshot, RuleObstacleWalkCheck,
+    SimulationSnapshot, SimulatorCurrentInvariantViolations, SimulatorInvariantChecks,
+    SimulatorInvariantThresholds, StandingStillCheck, TeammateClusteringCheck,
+    default_invariant_checks,
+};
+pub use crate::network::SimulatorNetwork;
+pub use crate::opponent_policies::{
+    BallChaserSwarmPolicy, GoalKeeperOnlyPolicy, LongShotPolicy, OpponentPoliciesConfig,
+    OpponentPolicy, OpponentPolicyConfig, OpponentPolicyInput, ReplayKeyframe, ReplayPolicy,
+    SimulatorOpponentPolicies, StationaryWallPolicy,
 };
+pub use crate::perception::SimulatorPerception;
 pub use crate::robot::{
     SimulatorFallDownState, SimulatorGroundToWorld, SimulatorHeadYaw, SimulatorLastKickTime,
     SimulatorPrimaryState, SimulatorRobot, SimulatorRobotBundle, SimulatorRobotId,
     SimulatorRobotParameters, SimulatorSuggestedSearchPosition,
 };
+pub use crate::scenario_file::{
+    ScenarioAction, ScenarioBall, ScenarioCondition, ScenarioEvent, ScenarioFile,
+    ScenarioFilePlugin, ScenarioNetwork, ScenarioRobot, ScenarioRobotReference,
+    SimulatorScenarioFile,
+};
+#[cfg(feature = "software_in_the_loop")]
+pub use crate::software_in_the_loop::{
+    NominalCamera, SimulatorSoftwareInTheLoop, SoftwareInTheLoopConfig, SoftwareInTheLoopPlugin,
+};
 pub use crate::timeline::{
     RobotFrame, SimulatorFailure, SimulatorRobotFrames, SimulatorScenarioResult, SimulatorTimeline,
-    SimulatorTimelineMarker, SimulatorTimelineMarkers, TimelineFrame,
+    SimulatorTimelineMarker, SimulatorTimelineMarkers, TimelineDivergence, TimelineFrame,
+    aligned_frame_index, first_divergence,
+};
+pub use crate::timeline_file::{
+    SimulatorTimelineRecording, TIMELINE_DIRECTORY_VARIABLE, TIMELINE_FILE_EXTENSION,
+    TIMELINE_FILE_FORMAT_VERSION, TimelineRecordingPlugin, TimelineWriter,
+    finish_timeline_recording, keep_timeline_of_failure, load_timeline, read_timeline,
+    record_timeline_to_directory, save_timeline,
 };
 pub use crate::world_states::SimulatorWorldStates;
 
 pub use crate::auto_referee::run_auto_referee;
 pub use crate::ball::move_ball;
 pub use crate::behavior_runtime::tick_behavior_trees;
+pub use crate::collisions::separate_robots;
 pub use crate::communication::{
     apply_incoming_hsl_messages, plan_communication, route_outgoing_communication,
 };
@@ -62,7 +96,10 @@ pub use crate::coordinates::point_world_to_field;
 pub use crate::game_controller::sync_primary_states_from_game_state;
 pub use crate::invariant_checks::run_invariant_checks;
 pub use crate::kinematics::move_robots;
+pub use crate::network::receive_game_controller_packets;
+pub use crate::opponent_policies::tick_opponent_policies;
 pub use crate::timeline::record_timeline_frame;
+pub use crate::timeline_file::stream_timeline_frame;
 pub use crate::world_states::build_world_states;
 #[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BehaviorTreeSimulatorSet {
@@ -100,9 +137,13 @@ pub struct BehaviorTreeSimulatorPlugin {
     pub hsl_network_parameters: HslNetworkParameters,
     pub tick_duration: Duration,
     pub enable_default_ball_physics: bool,
+    pub enable_default_behavior: bool,
     pub enable_default_kinematics: bool,
     pub enable_default_communication_routing: bool,
     pub enable_default_invariant_checks: bool,
+    /// Keeps every tick in `SimulatorTimeline`, headless runs that are never viewed can save the
+    /// memory
+    pub enable_timeline_recording: bool,
 }
 
 impl Default for BehaviorTreeSimulatorPlugin {
@@ -114,9 +155,11 @@ impl Default for BehaviorTreeSimulatorPlugin {
             hsl_network_parameters: HslNetworkParameters::default(),
             tick_duration: DEFAULT_TICK_DURATION,
             enable_default_ball_physics: true,
+            enable_default_behavior: true,
             enable_default_kinematics: true,
             enable_default_communication_routing: true,
             enable_default_invariant_checks: true,
+            enable_timeline_recording: true,
         }
     }
 }
@@ -153,9 +196,14 @@ impl Plugin for BehaviorTreeSimulatorPlugin {
             .insert_resource(SimulatorIncomingMessages::default())
             .insert_resource(SimulatorOutgoingMessages::default())
             .insert_resource(SimulatorReceivedHslMessages::default())
+            .insert_resource(SimulatorNetwork::default())
+            .insert_resource(SimulatorRobotCollisions::default())
+            .insert_resource(SimulatorPerception::default())
             .insert_resource(SimulatorWorldStates::default())
             .insert_resource(SimulatorRobotFrames::default())
-            .insert_resource(SimulatorCurrentInvariantViolations::default());
+            .insert_resource(SimulatorCurrentInvariantViolations::default())
+            .insert_resource(SimulatorInvariantThresholds::default())
+            .insert_resource(SimulatorOpponentPolicies::default());
 
         if self.enable_default_invariant_checks {
             app.insert_resource(SimulatorInvariantChecks(default_invariant_checks()));
@@ -223,25 +271,25 @@ impl Plugin for BehaviorTreeSimulatorPlugin {
         )
         .add_systems(
             Update,
-            apply_incoming_hsl_messages.in_set(BehaviorTreeSimulatorSet::BuildTeamContext),
-        )
-        .add_systems(
-            Update,
-            tick_behavior_trees.in_set(BehaviorTreeSimulatorSet::TickBehaviorTrees),
+            (receive_game_controller_packets, apply_incoming_hsl_messages)
+                .in_set(BehaviorTreeSimulatorSet::BuildTeamContext),
         )
         .add_systems(
             Update,
-            plan_communication.in_set(BehaviorTreeSimulatorSet::PlanCommunication),
+            tick_opponent_policies.in_set(BehaviorTreeSimulatorSet::AfterBehavior),
         )
         .add_systems(
             Update,
             run_invariant_checks.in_set(BehaviorTreeSimulatorSet::RunInvariantChecks),
-        )
-        .add_systems(
-            Update,
-            record_timeline_frame.in_set(BehaviorTreeSimulatorSet::RecordTimeline),
         );
 
+        if self.enable_timeline_recording {
+            app.add_systems(
+                Update,
+                record_timeline_frame.in_set(BehaviorTreeSimulatorSet::RecordTimeline),
+            );
+        }
+
         if self.enable_default_ball_physics {
             app.add_systems(
                 Update,
@@ -249,6 +297,17 @@ impl Plugin for BehaviorTreeSimulatorPlugin {
             );
         }
 
+        if self.enable_default_behavior {
+            app.add_systems(
+                Update,
+                tick_behavior_trees.in_set(BehaviorTreeSimulatorSet::TickBehaviorTrees),
+            )
+            .add_systems(
+                Update,
+                plan_communication.in_set(BehaviorTreeSimulatorSet::PlanCommunication),
+            );
+        }
+
         if self.enable_default_communication_routing {
             app.add_systems(
                 Update,
@@ -259,7 +318,9 @@ impl Plugin for BehaviorTreeSimulatorPlugin {
         if self.enable_default_kinematics {
             app.add_systems(
                 Update,
-                move_robots.in_set(BehaviorTreeSimulatorSet::ApplyKinematics),
+                (move_robots, separate_robots)
+                    .chain()
+                    .in_set(BehaviorTreeSimulatorSet::ApplyKinematics),
             );
         }
     }
@@ -273,11 +334,13 @@ pub trait AppExt {
 impl AppExt for App {
     fn run_to_completion(&mut self) -> Result<()> {
         let exit = run_until_exit(self);
-        check_scenario_result(self, exit)
+        let recording = finish_timeline_recording(self.world_mut());
+        check_scenario_result(self, exit).and(recording)
     }
 
     fn run_to_completion_with_viewer(&mut self) -> Result<()> {
         let exit = run_until_exit(self);
+        let recording = finish_timeline_recording(self.world_mut());
 
         if env::var_os("BEVYHAVIOR_SIMULATOR_NO_VIEWER").is_none() {
             let viewer_data = TimelineViewerData {
@@ -301,7 +364,7 @@ impl AppExt for App {
             show_timeline_viewer(viewer_data)?;
         }
 
-        check_scenario_result(self, exit)
+        check_scenario_result(self, exit).and(recording)
     }
 }
 
@@ -363,7 +426,7 @@ impl SimulatorScenarioObstacles {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SimulatorObstacle {
     pub kind: ObstacleKind,
     pub position: Point2<World>,
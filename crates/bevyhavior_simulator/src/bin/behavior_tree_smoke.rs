@@ -24,7 +24,6 @@ fn startup(
 ) {
     let mut parameters =
         default_behavior_parameters().expect("failed to load default behavior parameters");
-    parameters.goal_keeper_number = PlayerNumber::One;
     parameters.last_ball_timeout = Duration::from_secs(2);
 
     commands.spawn(
@@ -35,6 +34,7 @@ fn startup(
             parameters.clone(),
         )
         .expect("failed to create robot bundle")
+        .with_goal_keeper_number(PlayerNumber::One)
         .with_primary_state(PrimaryState::Playing),
     );
     commands.spawn(
@@ -45,6 +45,7 @@ fn startup(
             parameters,
         )
         .expect("failed to create robot bundle")
+        .with_goal_keeper_number(PlayerNumber::One)
         .with_primary_state(PrimaryState::Playing),
     );
     scenario_obstacles.add(SimulatorObstacle::robot(point![2.0, -0.1], 0.3, 0.5));
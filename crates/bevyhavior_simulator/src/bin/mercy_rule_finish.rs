@@ -21,7 +21,6 @@ fn mercy_rule_finish(app: &mut App) {
 fn startup(mut commands: Commands, mut ball: ResMut<SimulatorBall>) {
     let mut parameters =
         default_behavior_parameters().expect("failed to load default behavior parameters");
-    parameters.goal_keeper_number = PlayerNumber::One;
     parameters.last_ball_timeout = Duration::from_secs(2);
 
     for (player_number, pose) in [
@@ -41,6 +40,7 @@ fn startup(mut commands: Commands, mut ball: ResMut<SimulatorBall>) {
         commands.spawn(
             SimulatorRobotBundle::new(Team::Hulks, player_number, pose, parameters.clone())
                 .expect("failed to create robot bundle")
+                .with_goal_keeper_number(PlayerNumber::One)
                 .with_primary_state(PrimaryState::Playing),
         );
     }
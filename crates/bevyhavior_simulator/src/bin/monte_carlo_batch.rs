@@ -0,0 +1,88 @@
+use std::{
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    thread::available_parallelism,
+    time::Duration,
+};
+
+use bevyhavior_simulator::behavior_tree_simulator::{
+    BatchConfig, BatchVariant, SimulationConfig, default_behavior_parameters, run_batch,
+};
+use clap::Parser;
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+    install,
+};
+use types::parameters::BehaviorParameters;
+
+/// Plays seeded headless matches of two behavior parameter variants against each other and
+/// writes a JSON report with confidence intervals.
+#[derive(Parser, Debug)]
+#[clap(name = "monte_carlo_batch")]
+struct CommandlineArguments {
+    /// Behavior parameters of variant A, defaults to the base parameters
+    #[arg(long)]
+    variant: Option<PathBuf>,
+    /// Behavior parameters of variant B, defaults to the base parameters
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    #[arg(long, default_value_t = 20)]
+    matches: usize,
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Defaults to the number of available cores
+    #[arg(long)]
+    threads: Option<usize>,
+    #[arg(long, default_value_t = 120)]
+    match_duration_seconds: u64,
+    /// Prints the report if not given
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    install()?;
+    let arguments = CommandlineArguments::parse();
+
+    let config = BatchConfig {
+        number_of_matches: arguments.matches,
+        seed: arguments.seed,
+        number_of_threads: arguments
+            .threads
+            .unwrap_or_else(|| available_parallelism().map_or(1, |threads| threads.get())),
+        match_duration: Duration::from_secs(arguments.match_duration_seconds),
+        variant_a: variant("variant", arguments.variant.as_deref())?,
+        variant_b: variant("baseline", arguments.baseline.as_deref())?,
+        simulation: SimulationConfig::default(),
+    };
+    let report = run_batch(&config);
+    let json = serde_json::to_string_pretty(&report).wrap_err("failed to serialize report")?;
+
+    match arguments.output {
+        Some(output) => write(&output, json)
+            .wrap_err_with(|| format!("failed to write report to {}", output.display()))?,
+        None => println!("{json}"),
+    }
+
+    if report.failed_matches == report.number_of_matches && report.number_of_matches > 0 {
+        bail!("all matches failed");
+    }
+    Ok(())
+}
+
+fn variant(name: &str, path: Option<&Path>) -> Result<BatchVariant> {
+    let parameters = match path {
+        Some(path) => {
+            let file = read_to_string(path)
+                .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+            json5::from_str::<BehaviorParameters>(&file)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?
+        }
+        None => default_behavior_parameters()?,
+    };
+    Ok(BatchVariant {
+        name: path.map_or(name.to_string(), |path| path.display().to_string()),
+        parameters,
+    })
+}
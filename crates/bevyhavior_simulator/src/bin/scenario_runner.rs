@@ -0,0 +1,118 @@
+use std::{
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
+
+use bevy::app::App;
+use bevyhavior_simulator::behavior_tree_simulator::{
+    AppExt, BehaviorTreeSimulatorPlugin, ScenarioFile, ScenarioFilePlugin, SimulatorScenarioResult,
+    keep_timeline_of_failure, record_timeline_to_directory,
+};
+use clap::Parser;
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+    install,
+};
+
+/// Runs scenarios declared in JSON5 or TOML files.
+///
+/// If BEVYHAVIOR_SIMULATOR_TIMELINE_DIRECTORY is set, the timelines of failing scenarios are saved
+/// there and can be opened with the timeline_viewer.
+#[derive(Parser, Debug)]
+#[clap(name = "scenario_runner")]
+struct CommandlineArguments {
+    /// Scenario files or directories containing them, defaults to the scenarios of this crate
+    paths: Vec<PathBuf>,
+    /// Opens the timeline viewer after each scenario
+    #[arg(long)]
+    viewer: bool,
+}
+
+fn main() -> Result<()> {
+    install()?;
+    let arguments = CommandlineArguments::parse();
+
+    let paths = if arguments.paths.is_empty() {
+        vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios")]
+    } else {
+        arguments.paths
+    };
+    let mut scenarios = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            scenarios.extend(scenario_files(&path)?);
+        } else {
+            scenarios.push(path);
+        }
+    }
+
+    let mut number_of_failed_scenarios = 0;
+    for scenario in &scenarios {
+        match run(scenario, arguments.viewer) {
+            Ok(()) => println!("scenario={} result=ok", scenario.display()),
+            Err(error) => {
+                number_of_failed_scenarios += 1;
+                println!(
+                    "scenario={} result=fail reason={error:#}",
+                    scenario.display()
+                );
+            }
+        }
+    }
+
+    if number_of_failed_scenarios > 0 {
+        bail!(
+            "{number_of_failed_scenarios} of {} scenario(s) failed",
+            scenarios.len()
+        );
+    }
+    Ok(())
+}
+
+fn scenario_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in
+        read_dir(directory).wrap_err_with(|| format!("failed to read {}", directory.display()))?
+    {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json5" || extension == "toml")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn run(path: &Path, viewer: bool) -> Result<()> {
+    let plugin = ScenarioFilePlugin::new(ScenarioFile::load(path)?)
+        .wrap_err_with(|| format!("invalid scenario {}", path.display()))?;
+    let mut app = App::new();
+    app.add_plugins(BehaviorTreeSimulatorPlugin::default())
+        .add_plugins(plugin);
+
+    // The extension is part of the name, so x.json5 and x.toml do not share a timeline
+    let timeline_path = record_timeline_to_directory(
+        &mut app,
+        &path.file_name().unwrap_or_default().to_string_lossy(),
+    )?;
+
+    let result = if viewer {
+        app.run_to_completion_with_viewer()
+    } else {
+        app.run_to_completion()
+    };
+    for failure in &app.world().resource::<SimulatorScenarioResult>().failures {
+        eprintln!("{}: {failure}", path.display());
+    }
+    keep_timeline_of_failure(timeline_path, &result)?;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    include!(concat!(env!("OUT_DIR"), "/scenario_file_tests.rs"));
+}
@@ -0,0 +1,188 @@
+use std::time::{Duration, SystemTime};
+
+use behavior_node::set_play::wall_position;
+use bevy::prelude::*;
+use bevyhavior_simulator::behavior_tree_simulator::{
+    AutoRefereeConfig, BehaviorTreeSimulatorSet, SimulatedBall, SimulatorBall, SimulatorClock,
+    SimulatorFieldDimensions, SimulatorGameState, SimulatorGroundToWorld, SimulatorRefereeCommand,
+    SimulatorRobot, SimulatorRobotBundle, default_behavior_parameters, point_world_to_field,
+};
+use coordinate_systems::World;
+use hsl_network_messages::{PlayerNumber, SubState, Team};
+use linear_algebra::{Isometry2, Point2, distance, point, vector};
+use scenario::scenario;
+use types::{field_dimensions::Side, primary_state::PrimaryState};
+
+/// Time the robots get to walk into the wall before it is checked
+const WALL_CHECK_TIME: Duration = Duration::from_secs(15);
+const WALL_TOLERANCE: f32 = 0.3;
+const BALL_MOVED_DISTANCE: f32 = 0.05;
+
+#[scenario]
+fn set_play_wall_and_execution(app: &mut App) {
+    app.add_systems(Startup, startup)
+        .add_systems(Update, update.in_set(BehaviorTreeSimulatorSet::Scenario));
+}
+
+fn ball_position() -> Point2<World> {
+    point![0.5, 0.0]
+}
+
+fn startup(mut commands: Commands, mut ball: ResMut<SimulatorBall>) {
+    let parameters =
+        default_behavior_parameters().expect("failed to load default behavior parameters");
+
+    for (player_number, pose) in [
+        (
+            PlayerNumber::Three,
+            Isometry2::from_parts(vector![-1.5, 1.0], 0.0),
+        ),
+        (
+            PlayerNumber::Four,
+            Isometry2::from_parts(vector![-1.5, -1.0], 0.0),
+        ),
+        (
+            PlayerNumber::Five,
+            Isometry2::from_parts(vector![-3.0, 0.0], 0.0),
+        ),
+    ] {
+        commands.spawn(
+            SimulatorRobotBundle::new(Team::Hulks, player_number, pose, parameters.clone())
+                .expect("failed to create robot bundle")
+                .with_goal_keeper_number(PlayerNumber::One)
+                .with_primary_state(PrimaryState::Playing),
+        );
+    }
+
+    ball.state = Some(SimulatedBall {
+        position: ball_position(),
+        velocity: vector![0.0, 0.0],
+        field_side: Side::Left,
+    });
+}
+
+#[derive(Default)]
+enum Phase {
+    #[default]
+    Start,
+    /// The opponent executes a free kick, the closest robots have to form a wall
+    Wall,
+    /// The HULKs execute a free kick and may only kick once the execution time is reached
+    Execution {
+        started_at: Duration,
+        earliest_execution: Duration,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update(
+    clock: Res<SimulatorClock>,
+    auto_referee_config: Res<AutoRefereeConfig>,
+    field_dimensions: Res<SimulatorFieldDimensions>,
+    game_state: Res<SimulatorGameState>,
+    ball: Res<SimulatorBall>,
+    robots: Query<(&SimulatorRobot, &SimulatorGroundToWorld)>,
+    mut phase: Local<Phase>,
+    mut referee_commands: MessageWriter<SimulatorRefereeCommand>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let elapsed = clock
+        .now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("simulator time should not move backwards");
+
+    match *phase {
+        Phase::Start => {
+            referee_commands.write(SimulatorRefereeCommand::SetPlay {
+                set_play: SubState::DirectFreeKick,
+                kicking_team: Team::Opponent,
+                position: ball_position(),
+            });
+            *phase = Phase::Wall;
+        }
+        Phase::Wall if elapsed >= WALL_CHECK_TIME => {
+            let parameters =
+                default_behavior_parameters().expect("failed to load default behavior parameters");
+            let global_field_side = game_state.game_controller_state.global_field_side;
+            let positions: Vec<_> = robots
+                .iter()
+                .map(|(robot, ground_to_world)| {
+                    (
+                        robot.player_number,
+                        point_world_to_field(
+                            ground_to_world.ground_to_world.translation(),
+                            global_field_side,
+                        ),
+                    )
+                })
+                .collect();
+            let ball = point_world_to_field(ball_position(), global_field_side);
+            let wall_members: Vec<_> = positions
+                .iter()
+                .filter_map(|(player_number, position)| {
+                    let wall_position = wall_position(
+                        *player_number,
+                        &positions,
+                        ball,
+                        &field_dimensions.0,
+                        &parameters.set_play,
+                        parameters.path_planning.robot_radius,
+                    )?;
+                    Some((*player_number, distance(*position, wall_position)))
+                })
+                .collect();
+            if wall_members.len() != parameters.set_play.maximum_wall_size
+                || wall_members
+                    .iter()
+                    .any(|(_, distance)| *distance > WALL_TOLERANCE)
+            {
+                println!("failed: robots did not form a wall: {wall_members:?}");
+                exit.write(AppExit::from_code(1));
+                return;
+            }
+
+            referee_commands.write(SimulatorRefereeCommand::SetPlay {
+                set_play: SubState::DirectFreeKick,
+                kicking_team: Team::Hulks,
+                position: ball_position(),
+            });
+            *phase = Phase::Execution {
+                started_at: elapsed,
+                earliest_execution: auto_referee_config
+                    .set_play_duration
+                    .saturating_sub(parameters.set_play.execution_remaining_time),
+            };
+        }
+        Phase::Wall => {}
+        Phase::Execution {
+            started_at,
+            earliest_execution,
+        } => {
+            let since_set_play = elapsed.saturating_sub(started_at);
+            let ball_moved = ball
+                .state
+                .is_none_or(|ball| distance(ball.position, ball_position()) > BALL_MOVED_DISTANCE);
+            if !ball_moved {
+                if since_set_play > auto_referee_config.set_play_duration {
+                    println!("failed: set play was not executed in time");
+                    exit.write(AppExit::from_code(1));
+                }
+                return;
+            }
+
+            if since_set_play < earliest_execution {
+                println!(
+                    "failed: ball moved {:.2}s into the set play, before the execution time",
+                    since_set_play.as_secs_f32()
+                );
+                exit.write(AppExit::from_code(1));
+            } else {
+                println!(
+                    "ok: set play executed after {:.2}s",
+                    since_set_play.as_secs_f32()
+                );
+                exit.write(AppExit::Success);
+            }
+        }
+    }
+}
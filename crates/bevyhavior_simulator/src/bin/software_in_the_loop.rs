@@ -0,0 +1,119 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::app::App;
+use bevyhavior_simulator::behavior_tree_simulator::{
+    AppExt, BehaviorTreeSimulatorPlugin, ScenarioFile, ScenarioFilePlugin, SimulatorScenarioResult,
+    SoftwareInTheLoopConfig, SoftwareInTheLoopPlugin, TimelineRecordingPlugin,
+};
+use clap::Parser;
+use color_eyre::{Result, eyre::Context, install};
+
+/// Runs a scenario file against the real ros-z nodes instead of the simulated behavior.
+#[derive(Parser, Debug)]
+#[clap(name = "software_in_the_loop")]
+struct CommandlineArguments {
+    /// Scenario file to run
+    scenario: PathBuf,
+    /// Zenoh endpoint the robot stacks connect to
+    #[arg(long, default_value = "tcp/127.0.0.1:7448")]
+    listen_endpoint: String,
+    /// Directory containing the ros-z parameter layers
+    #[arg(long)]
+    parameter_root: Option<PathBuf>,
+    #[arg(long, default_value = "default_location")]
+    location: String,
+    /// Milliseconds a robot stack may take to publish the motion command of a tick before the run
+    /// fails
+    #[arg(long, default_value_t = 1000)]
+    motion_command_timeout: u64,
+    /// Opens the timeline viewer after the scenario
+    #[arg(long)]
+    viewer: bool,
+    /// Records the timeline to this file
+    #[arg(long)]
+    timeline: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    install()?;
+    let arguments = CommandlineArguments::parse();
+
+    let defaults = SoftwareInTheLoopConfig::default();
+    let config = SoftwareInTheLoopConfig {
+        listen_endpoint: arguments.listen_endpoint,
+        parameter_root: arguments.parameter_root.unwrap_or(defaults.parameter_root),
+        location: arguments.location,
+        motion_command_timeout: Duration::from_millis(arguments.motion_command_timeout),
+        ..defaults
+    };
+    run(
+        &arguments.scenario,
+        config,
+        arguments.viewer,
+        arguments.timeline,
+    )
+}
+
+fn run(
+    path: &Path,
+    config: SoftwareInTheLoopConfig,
+    viewer: bool,
+    timeline: Option<PathBuf>,
+) -> Result<()> {
+    let scenario = ScenarioFilePlugin::new(ScenarioFile::load(path)?)
+        .wrap_err_with(|| format!("invalid scenario {}", path.display()))?;
+    let software_in_the_loop = SoftwareInTheLoopPlugin::new(config)?;
+
+    let mut app = App::new();
+    app.add_plugins(BehaviorTreeSimulatorPlugin {
+        enable_default_behavior: false,
+        ..Default::default()
+    })
+    .add_plugins(software_in_the_loop)
+    .add_plugins(scenario);
+    if let Some(path) = timeline {
+        app.add_plugins(TimelineRecordingPlugin { path });
+    }
+
+    let result = if viewer {
+        app.run_to_completion_with_viewer()
+    } else {
+        app.run_to_completion()
+    };
+    for failure in &app.world().resource::<SimulatorScenarioResult>().failures {
+        eprintln!("{}: {failure}", path.display());
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::Path,
+        sync::atomic::{AtomicU16, Ordering},
+    };
+
+    use bevyhavior_simulator::behavior_tree_simulator::SoftwareInTheLoopConfig;
+
+    /// Tests run in parallel, every scenario gets a router port of its own
+    static NEXT_PORT: AtomicU16 = AtomicU16::new(7449);
+
+    fn run(path: &Path, viewer: bool) -> color_eyre::Result<()> {
+        let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+        let config = SoftwareInTheLoopConfig {
+            listen_endpoint: format!("tcp/127.0.0.1:{port}"),
+            ..Default::default()
+        };
+        super::run(path, config, viewer, None)
+    }
+
+    mod scenarios {
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/software_in_the_loop_scenario_file_tests.rs"
+        ));
+    }
+}
@@ -23,7 +23,6 @@ fn three_vs_three_until_goal_or_half(app: &mut App) {
 fn startup(mut commands: Commands, mut ball: ResMut<SimulatorBall>) {
     let mut parameters =
         default_behavior_parameters().expect("failed to load default behavior parameters");
-    parameters.goal_keeper_number = PlayerNumber::One;
     parameters.last_ball_timeout = Duration::from_secs(2);
 
     for (team, player_number, pose) in [
@@ -37,6 +36,7 @@ fn startup(mut commands: Commands, mut ball: ResMut<SimulatorBall>) {
         commands.spawn(
             SimulatorRobotBundle::new(team, player_number, pose, parameters.clone())
                 .expect("failed to create robot bundle")
+                .with_goal_keeper_number(PlayerNumber::One)
                 .with_primary_state(PrimaryState::Playing),
         );
     }
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use bevyhavior_simulator::{
+    behavior_tree_simulator::load_timeline,
+    timeline_viewer::{show_timeline_comparison, show_timeline_viewer},
+};
+use clap::Parser;
+use color_eyre::{Result, install};
+
+/// Scrubs through timelines saved by the simulator and compares two of them side by side.
+#[derive(Parser, Debug)]
+#[clap(name = "timeline_viewer")]
+struct CommandlineArguments {
+    /// Timeline file to open
+    timeline: PathBuf,
+    /// Timeline file to show next to the first one, e.g. of a run with changed behavior
+    #[arg(long)]
+    compare: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    install()?;
+    let arguments = CommandlineArguments::parse();
+
+    let data = load_timeline(&arguments.timeline)?;
+    match arguments.compare {
+        Some(comparison) => show_timeline_comparison(data, load_timeline(&comparison)?),
+        None => show_timeline_viewer(data),
+    }
+}
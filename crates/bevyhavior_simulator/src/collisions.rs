@@ -0,0 +1,344 @@
+use std::{collections::BTreeMap, time::SystemTime};
+
+use bevy::prelude::*;
+use coordinate_systems::World;
+use linear_algebra::{Isometry2, Point2, Vector2, point};
+use types::field_dimensions::FieldDimensions;
+
+use crate::behavior_tree_simulator::{
+    SimulatedBall, SimulationConfig, SimulatorClock, SimulatorGroundToWorld, SimulatorRobot,
+    SimulatorRobotId,
+};
+
+/// A robot that walked into another one faster than `SimulationConfig::minimum_pushing_speed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pushing {
+    pub time: SystemTime,
+    pub pusher: SimulatorRobotId,
+    pub pushed: SimulatorRobotId,
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SimulatorRobotCollisions {
+    previous_positions: BTreeMap<SimulatorRobotId, Point2<World>>,
+    /// Pushings of the current tick
+    pub pushings: Vec<Pushing>,
+}
+
+/// Bounces the ball off robots and goal posts and stops it once it left the field, where the
+/// referee takes over.
+pub fn collide_ball(
+    ball: &mut SimulatedBall,
+    robot_positions: &[Point2<World>],
+    field_dimensions: &FieldDimensions,
+    config: &SimulationConfig,
+) {
+    for &robot_position in robot_positions {
+        deflect_ball_off_robot(ball, robot_position, config);
+    }
+    let goal_post_radius = field_dimensions.goal_post_diameter / 2.0;
+    for goal_post in goal_post_positions(field_dimensions) {
+        bounce_ball_off_circle(
+            ball,
+            goal_post,
+            goal_post_radius + config.ball_radius,
+            config.ball_goal_post_restitution,
+        );
+    }
+    stop_ball_at_boundary(ball, field_dimensions, config.ball_radius);
+}
+
+/// Balls rolling into a robot bounce off its body, but the ball is only moved out of the feet, so
+/// that robots can still walk up to the ball and kick it.
+fn deflect_ball_off_robot(
+    ball: &mut SimulatedBall,
+    robot_position: Point2<World>,
+    config: &SimulationConfig,
+) {
+    let to_ball = ball.position - robot_position;
+    if to_ball.norm() >= config.robot_radius + config.ball_radius {
+        return;
+    }
+    let Some(normal) = to_ball.try_normalize(f32::EPSILON) else {
+        return;
+    };
+    reflect_velocity(ball, normal, config.ball_robot_restitution);
+    move_ball_out_of_feet(ball, robot_position, config);
+}
+
+/// Moves a ball touching the feet of the robot to the contact distance. Returns the direction from
+/// the robot to the ball if they touched.
+pub(crate) fn move_ball_out_of_feet(
+    ball: &mut SimulatedBall,
+    robot_position: Point2<World>,
+    config: &SimulationConfig,
+) -> Option<Vector2<World>> {
+    let contact_distance = config.ball_contact_radius + config.ball_radius;
+    let to_ball = ball.position - robot_position;
+    if to_ball.norm() >= contact_distance {
+        return None;
+    }
+    let normal = to_ball.try_normalize(f32::EPSILON)?;
+    ball.position = robot_position + normal * contact_distance;
+    Some(normal)
+}
+
+fn bounce_ball_off_circle(
+    ball: &mut SimulatedBall,
+    center: Point2<World>,
+    contact_distance: f32,
+    restitution: f32,
+) {
+    let to_ball = ball.position - center;
+    if to_ball.norm() >= contact_distance {
+        return;
+    }
+    let Some(normal) = to_ball.try_normalize(f32::EPSILON) else {
+        return;
+    };
+    ball.position = center + normal * contact_distance;
+    reflect_velocity(ball, normal, restitution);
+}
+
+/// Reverses the velocity component towards the obstacle, if the ball is approaching it.
+fn reflect_velocity(ball: &mut SimulatedBall, normal: Vector2<World>, restitution: f32) {
+    let speed_along_normal = ball.velocity.dot(&normal);
+    if speed_along_normal < 0.0 {
+        ball.velocity -= normal * (speed_along_normal * (1.0 + restitution));
+    }
+}
+
+/// The ball is out once it completely crossed a field line. Balls in a goal stop at the back of
+/// the goal.
+fn stop_ball_at_boundary(
+    ball: &mut SimulatedBall,
+    field_dimensions: &FieldDimensions,
+    ball_radius: f32,
+) {
+    let maximum_x = if field_dimensions.is_inside_any_goal(ball.position) {
+        field_dimensions.length / 2.0 + field_dimensions.goal_depth - ball_radius
+    } else {
+        field_dimensions.length / 2.0 + ball_radius
+    };
+    let maximum_y = field_dimensions.width / 2.0 + ball_radius;
+    if ball.position.x().abs() <= maximum_x && ball.position.y().abs() <= maximum_y {
+        return;
+    }
+    ball.position = point![
+        ball.position.x().clamp(-maximum_x, maximum_x),
+        ball.position.y().clamp(-maximum_y, maximum_y)
+    ];
+    ball.velocity = Vector2::zeros();
+}
+
+pub fn goal_post_positions(field_dimensions: &FieldDimensions) -> [Point2<World>; 4] {
+    let x = field_dimensions.length / 2.0 + field_dimensions.goal_post_diameter / 2.0
+        - field_dimensions.line_width / 2.0;
+    let y = field_dimensions.goal_inner_width / 2.0 + field_dimensions.goal_post_diameter / 2.0;
+    [point![x, y], point![x, -y], point![-x, y], point![-x, -y]]
+}
+
+/// Moves overlapping robots apart and records which robot walked into the other.
+pub fn separate_robots(
+    clock: Res<SimulatorClock>,
+    config: Res<SimulationConfig>,
+    mut collisions: ResMut<SimulatorRobotCollisions>,
+    mut robots: Query<(&SimulatorRobot, &mut SimulatorGroundToWorld)>,
+) {
+    let mut positions: Vec<_> = robots
+        .iter()
+        .map(|(robot, ground_to_world)| (robot.id(), ground_to_world.ground_to_world.translation()))
+        .collect();
+    positions.sort_by_key(|(robot_id, _)| *robot_id);
+
+    let velocities: Vec<_> = positions
+        .iter()
+        .map(|(robot_id, position)| {
+            collisions
+                .previous_positions
+                .get(robot_id)
+                .map_or(Vector2::zeros(), |previous| {
+                    (*position - *previous) / clock.tick_duration.as_secs_f32().max(f32::EPSILON)
+                })
+        })
+        .collect();
+
+    collisions.pushings.clear();
+    let minimum_distance = 2.0 * config.robot_radius;
+    for first in 0..positions.len() {
+        for second in first + 1..positions.len() {
+            let to_second = positions[second].1 - positions[first].1;
+            let distance = to_second.norm();
+            if distance >= minimum_distance {
+                continue;
+            }
+            let normal = to_second
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(Vector2::x_axis());
+            let correction = normal * ((minimum_distance - distance) / 2.0);
+            positions[first].1 -= correction;
+            positions[second].1 += correction;
+
+            let first_approach = velocities[first].dot(&normal);
+            let second_approach = -velocities[second].dot(&normal);
+            let (pusher, pushed, approach) = if first_approach >= second_approach {
+                (positions[first].0, positions[second].0, first_approach)
+            } else {
+                (positions[second].0, positions[first].0, second_approach)
+            };
+            if approach > config.minimum_pushing_speed {
+                collisions.pushings.push(Pushing {
+                    time: clock.now,
+                    pusher,
+                    pushed,
+                });
+            }
+        }
+    }
+
+    let positions: BTreeMap<_, _> = positions.into_iter().collect();
+    for (robot, mut ground_to_world) in &mut robots {
+        ground_to_world.ground_to_world = Isometry2::from_parts(
+            positions[&robot.id()].coords(),
+            ground_to_world.ground_to_world.orientation().angle(),
+        );
+    }
+    collisions.previous_positions = positions;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hsl_network_messages::{PlayerNumber, Team};
+    use linear_algebra::vector;
+    use types::field_dimensions::Side;
+
+    use super::*;
+
+    fn ball(position: Point2<World>, velocity: Vector2<World>) -> SimulatedBall {
+        SimulatedBall {
+            position,
+            velocity,
+            field_side: Side::Left,
+        }
+    }
+
+    #[test]
+    fn ball_bounces_off_robot_body() {
+        let config = SimulationConfig::default();
+        let mut ball = ball(point![0.28, 0.0], vector![-2.0, 0.0]);
+
+        collide_ball(
+            &mut ball,
+            &[Point2::origin()],
+            &FieldDimensions::SPL_2025,
+            &config,
+        );
+
+        assert!(ball.velocity.x() > 0.0);
+        assert!(ball.velocity.x() < 2.0);
+        assert_eq!(ball.position, point![0.28, 0.0]);
+    }
+
+    #[test]
+    fn ball_bounces_off_goal_post() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let goal_post = goal_post_positions(&field_dimensions)[0];
+        let mut ball = ball(goal_post - vector![0.05, 0.0], vector![2.0, 0.0]);
+
+        collide_ball(
+            &mut ball,
+            &[],
+            &field_dimensions,
+            &SimulationConfig::default(),
+        );
+
+        assert!(ball.velocity.x() < 0.0);
+        assert!(ball.position.x() < goal_post.x() - field_dimensions.goal_post_diameter / 2.0);
+    }
+
+    #[test]
+    fn ball_stops_outside_field_and_at_back_of_goal() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let config = SimulationConfig::default();
+
+        let mut out = ball(
+            point![0.0, field_dimensions.width / 2.0 + 0.5],
+            vector![0.0, 1.0],
+        );
+        collide_ball(&mut out, &[], &field_dimensions, &config);
+        assert_eq!(out.velocity, Vector2::zeros());
+        assert_eq!(
+            out.position.y(),
+            field_dimensions.width / 2.0 + config.ball_radius
+        );
+
+        let mut goal = ball(
+            point![field_dimensions.length / 2.0 + 1.0, 0.0],
+            vector![1.0, 0.0],
+        );
+        collide_ball(&mut goal, &[], &field_dimensions, &config);
+        assert_eq!(goal.velocity, Vector2::zeros());
+        assert!(field_dimensions.is_inside_any_goal(goal.position));
+    }
+
+    #[test]
+    fn walking_into_robot_separates_and_detects_pushing() {
+        let mut app = App::new();
+        app.insert_resource(SimulatorClock {
+            now: SystemTime::UNIX_EPOCH,
+            tick_duration: Duration::from_millis(10),
+        })
+        .insert_resource(SimulationConfig::default())
+        .insert_resource(SimulatorRobotCollisions::default())
+        .add_systems(Update, separate_robots);
+
+        let pusher = app
+            .world_mut()
+            .spawn((
+                SimulatorRobot {
+                    team: Team::Hulks,
+                    player_number: PlayerNumber::Three,
+                },
+                SimulatorGroundToWorld {
+                    ground_to_world: Isometry2::identity(),
+                },
+            ))
+            .id();
+        app.world_mut().spawn((
+            SimulatorRobot {
+                team: Team::Opponent,
+                player_number: PlayerNumber::Three,
+            },
+            SimulatorGroundToWorld {
+                ground_to_world: Isometry2::from_parts(vector![1.0, 0.0], 0.0),
+            },
+        ));
+        app.update();
+
+        app.world_mut()
+            .get_mut::<SimulatorGroundToWorld>(pusher)
+            .unwrap()
+            .ground_to_world = Isometry2::from_parts(vector![0.6, 0.0], 0.0);
+        app.update();
+
+        let mut query = app.world_mut().query::<&SimulatorGroundToWorld>();
+        let positions: Vec<_> = query
+            .iter(app.world())
+            .map(|ground_to_world| ground_to_world.ground_to_world.translation())
+            .collect();
+        let distance = (positions[0] - positions[1]).norm();
+        assert!(distance >= 2.0 * SimulationConfig::default().robot_radius - 1e-4);
+
+        let collisions = app.world().resource::<SimulatorRobotCollisions>();
+        assert_eq!(
+            collisions.pushings,
+            vec![Pushing {
+                time: SystemTime::UNIX_EPOCH,
+                pusher: SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three),
+                pushed: SimulatorRobotId::new(Team::Opponent, PlayerNumber::Three),
+            }]
+        );
+    }
+}
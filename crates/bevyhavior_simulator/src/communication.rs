@@ -11,9 +11,13 @@ use types::{
     world_state::PlayerState,
 };
 
-use crate::behavior_tree_simulator::{
-    SimulationConfig, SimulatorClock, SimulatorGameState, SimulatorRobot, SimulatorRobotBehavior,
-    SimulatorRobotFrames, SimulatorRobotId, SimulatorWorldStates,
+use crate::{
+    behavior_tree_simulator::{
+        SimulationConfig, SimulatorClock, SimulatorGameState, SimulatorOpponentPolicies,
+        SimulatorRobot, SimulatorRobotBehavior, SimulatorRobotFrames, SimulatorRobotId,
+        SimulatorWorldStates,
+    },
+    network::SimulatorNetwork,
 };
 
 #[derive(Resource, Clone, Debug, Default)]
@@ -60,6 +64,7 @@ pub fn plan_communication(
     config: Res<SimulationConfig>,
     hsl_network_parameters: Res<SimulatorHslNetworkParameters>,
     world_states: Res<SimulatorWorldStates>,
+    opponent_policies: Res<SimulatorOpponentPolicies>,
     mut robot_frames: ResMut<SimulatorRobotFrames>,
     mut outgoing_messages: ResMut<SimulatorOutgoingMessages>,
     mut robots: Query<(&SimulatorRobot, &mut SimulatorRobotBehavior)>,
@@ -67,6 +72,9 @@ pub fn plan_communication(
     outgoing_messages.messages.clear();
 
     for (robot, mut behavior) in &mut robots {
+        if opponent_policies.controls(robot.team) {
+            continue;
+        }
         let robot_id = robot.id();
         let Some(world_state) = world_states.0.get(&robot_id) else {
             continue;
@@ -104,20 +112,21 @@ pub fn apply_incoming_hsl_messages(
             continue;
         };
 
-        let HulkMessage::State(state_message) = message;
-        let player_state = PlayerState {
-            pose: state_message.pose,
-            ball_position: state_message.ball_position.map(|ball| {
-                BallPosition::from_network_ball(
-                    ball,
-                    ros_z::time::Time::from_wallclock(incoming_message.received_at),
-                )
-            }),
-        };
-        received_hsl_messages
-            .player_states_by_receiver
-            .entry(incoming_message.receiver)
-            .or_default()[state_message.player_number] = Some(player_state);
+        if let HulkMessage::State(state_message) = message {
+            let player_state = PlayerState {
+                pose: state_message.pose,
+                ball_position: state_message.ball_position.map(|ball| {
+                    BallPosition::from_network_ball(
+                        ball,
+                        ros_z::time::Time::from_wallclock(incoming_message.received_at),
+                    )
+                }),
+            };
+            received_hsl_messages
+                .player_states_by_receiver
+                .entry(incoming_message.receiver)
+                .or_default()[state_message.player_number] = Some(player_state);
+        }
 
         received_hsl_messages
             .messages_by_receiver
@@ -135,12 +144,16 @@ pub fn apply_incoming_hsl_messages(
 
 pub fn route_outgoing_communication(
     clock: Res<SimulatorClock>,
+    config: Res<SimulationConfig>,
     outgoing_messages: Res<SimulatorOutgoingMessages>,
     mut incoming_messages: ResMut<SimulatorIncomingMessages>,
     mut game_state: ResMut<SimulatorGameState>,
+    mut network: ResMut<SimulatorNetwork>,
     robots: Query<&SimulatorRobot>,
 ) {
     incoming_messages.messages.clear();
+    let mut receivers: Vec<_> = robots.iter().map(|robot| robot.id()).collect();
+    receivers.sort();
 
     for outgoing_message in &outgoing_messages.messages {
         let OutgoingMessage::Hsl(message) = outgoing_message.message.clone() else {
@@ -166,19 +179,24 @@ pub fn route_outgoing_communication(
         }
         *remaining_amount_of_messages = remaining_amount_of_messages.saturating_sub(1);
 
-        for robot in &robots {
-            if robot.team != outgoing_message.sender.team || robot.id() == outgoing_message.sender {
+        for &receiver in &receivers {
+            if receiver.team != outgoing_message.sender.team || receiver == outgoing_message.sender
+            {
                 continue;
             }
 
-            incoming_messages.messages.push(SimulatorIncomingMessage {
-                receiver: robot.id(),
-                sender: outgoing_message.sender,
-                message: IncomingMessage::Hsl(message),
-                received_at: clock.now,
-            });
+            network.send(
+                SimulatorIncomingMessage {
+                    receiver,
+                    sender: outgoing_message.sender,
+                    message: IncomingMessage::Hsl(message),
+                    received_at: clock.now,
+                },
+                &config.network,
+            );
         }
     }
+    incoming_messages.messages = network.deliver(clock.now);
 
     game_state.sync_filtered_game_controller_state();
 }
@@ -198,9 +216,12 @@ pub fn player_states_from_received_hsl_messages(
 mod tests {
     use std::time::{Duration, SystemTime};
 
-    use hsl_network_messages::{HulkMessage, PlayerNumber, Team};
+    use hsl_network_messages::{Half, HulkMessage, PlayerNumber, Team};
     use linear_algebra::{Pose2, point};
-    use types::messages::{IncomingMessage, OutgoingMessage};
+    use types::{
+        message_scheduler::{MessageBudget, MessagePriority, MessageScheduler},
+        messages::{IncomingMessage, OutgoingMessage},
+    };
 
     use super::*;
     use crate::behavior_tree_simulator::{
@@ -215,6 +236,7 @@ mod tests {
                 age: Duration::from_millis(500),
                 position: point![x + 1.0, y],
             }),
+            searched_area: Default::default(),
         })
     }
 
@@ -245,6 +267,8 @@ mod tests {
             })
             .insert_resource(SimulatorOutgoingMessages::default())
             .insert_resource(SimulatorIncomingMessages::default())
+            .insert_resource(SimulationConfig::default())
+            .insert_resource(SimulatorNetwork::default())
             .insert_resource(game_state_with_message_budget(remaining_amount_of_messages))
             .add_systems(Update, route_outgoing_communication);
 
@@ -458,4 +482,98 @@ mod tests {
                 .is_some()
         );
     }
+
+    #[test]
+    fn scheduled_hsl_messages_stay_within_game_budget() {
+        let half_duration = Duration::from_secs(600);
+        let parameters = HslNetworkParameters {
+            remaining_amount_of_messages_to_stop_sending: 20,
+            remaining_amount_of_messages_to_stop_sending_urgent: 5,
+            silence_interval_between_messages: Duration::from_millis(500),
+            hsl_state_message_send_interval: Duration::from_secs(1),
+            half_duration,
+            ..Default::default()
+        };
+        let player_numbers = [
+            PlayerNumber::One,
+            PlayerNumber::Two,
+            PlayerNumber::Three,
+            PlayerNumber::Four,
+            PlayerNumber::Five,
+        ];
+        let mut app = route_test_app(1200);
+        for player_number in [PlayerNumber::One, PlayerNumber::Two] {
+            app.world_mut().spawn(SimulatorRobot {
+                team: Team::Hulks,
+                player_number,
+            });
+        }
+        {
+            let mut game_state = app.world_mut().resource_mut::<SimulatorGameState>();
+            game_state.game_controller_state.half = Half::First;
+            game_state.game_controller_state.remaining_time_in_half = half_duration;
+            game_state.sync_filtered_game_controller_state();
+        }
+        let mut schedulers = [MessageScheduler::default(); 5];
+        let mut number_of_sent_messages = 0;
+
+        for second in 0..2 * half_duration.as_secs() {
+            let now = app.world().resource::<SimulatorClock>().now;
+            let filtered_game_controller_state = app
+                .world()
+                .resource::<SimulatorGameState>()
+                .filtered_game_controller_state
+                .clone()
+                .expect("filtered game state should exist");
+            let budget = MessageBudget::new(
+                &filtered_game_controller_state,
+                player_numbers.len(),
+                half_duration,
+            );
+
+            let mut outgoing_messages = Vec::new();
+            for (player_number, scheduler) in player_numbers.into_iter().zip(&mut schedulers) {
+                let now = ros_z::time::Time::from_wallclock(now);
+                if scheduler.is_due(now, MessagePriority::Regular, &budget, &parameters) {
+                    scheduler.mark_sent(now);
+                    outgoing_messages.push(SimulatorMessage {
+                        sender: robot_id(player_number),
+                        message: OutgoingMessage::Hsl(hsl_state_message(player_number, 0.0, 0.0)),
+                    });
+                }
+            }
+            number_of_sent_messages += outgoing_messages.len();
+            app.world_mut()
+                .resource_mut::<SimulatorOutgoingMessages>()
+                .messages = outgoing_messages;
+
+            app.update();
+
+            app.world_mut().resource_mut::<SimulatorClock>().now += Duration::from_secs(1);
+            let mut game_state = app.world_mut().resource_mut::<SimulatorGameState>();
+            let game_controller_state = &mut game_state.game_controller_state;
+            if second + 1 == half_duration.as_secs() {
+                game_controller_state.half = Half::Second;
+                game_controller_state.remaining_time_in_half = half_duration;
+            } else {
+                game_controller_state.remaining_time_in_half = game_controller_state
+                    .remaining_time_in_half
+                    .saturating_sub(Duration::from_secs(1));
+            }
+            game_state.sync_filtered_game_controller_state();
+        }
+
+        let remaining_amount_of_messages = app
+            .world()
+            .resource::<SimulatorGameState>()
+            .game_controller_state
+            .hulks_team
+            .remaining_amount_of_messages;
+        assert!(remaining_amount_of_messages > 0);
+        assert_eq!(
+            usize::from(1200 - remaining_amount_of_messages),
+            number_of_sent_messages
+        );
+        assert!(number_of_sent_messages > 900);
+    }
 }
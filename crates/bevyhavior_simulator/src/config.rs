@@ -1,12 +1,15 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{collections::BTreeMap, net::SocketAddr, time::Duration};
 
 use color_eyre::{Result, eyre::Context};
-use serde::Deserialize;
+use hsl_network_messages::PlayerNumber;
+use serde::{Deserialize, Serialize};
 use types::parameters::{BehaviorParameters, RLWalkingParameters};
 
+use crate::robot::SimulatorRobotId;
+
 pub const DEFAULT_TICK_DURATION: Duration = Duration::from_millis(10);
 
-#[derive(bevy::prelude::Resource, Clone, Debug)]
+#[derive(bevy::prelude::Resource, Clone, Debug, Serialize, Deserialize)]
 pub struct SimulationConfig {
     pub walk_translation_speed: f32,
     pub walk_rotation_speed: f32,
@@ -26,8 +29,68 @@ pub struct SimulationConfig {
     pub head_glance_angle: f32,
     pub robot_radius: f32,
     pub kick_radius: f32,
+    pub ball_radius: f32,
+    /// Distance from the robot center to the front of its feet, the ball is pushed when it gets
+    /// closer than this plus `ball_radius`
+    pub ball_contact_radius: f32,
+    /// Fraction of the approach speed the ball bounces off with when it is pushed
+    pub ball_push_restitution: f32,
+    /// Fraction of its speed a ball keeps when rolling into a robot
+    pub ball_robot_restitution: f32,
+    pub ball_goal_post_restitution: f32,
+    /// Robots walking into another robot faster than this are pushing
+    pub minimum_pushing_speed: f32,
     pub remaining_amount_of_messages: Option<u16>,
     pub game_controller_address: Option<SocketAddr>,
+    pub perception: PerceptionConfig,
+    pub network: NetworkConfig,
+}
+
+/// Errors of the simulated perception. The default perceives the ground truth.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PerceptionConfig {
+    /// Seed of all perception errors, runs with equal seeds perceive equally
+    pub seed: u64,
+    pub ball_position_noise: PositionNoise,
+    pub robot_position_noise: PositionNoise,
+    /// Probability per tick to miss a visible ball
+    pub ball_false_negative_probability: f32,
+    /// Probability per tick to detect a ball at a random position in view if none is visible
+    pub ball_false_positive_probability: f32,
+    /// Probability per tick to miss a visible robot
+    pub robot_false_negative_probability: f32,
+    /// Probability per tick to detect an additional robot at a random position in view
+    pub robot_false_positive_probability: f32,
+    /// Time between seeing an object and the behavior receiving the detection
+    pub detection_latency: Duration,
+    pub localization_drift: LocalizationDrift,
+    /// Whether robots hide balls and robots behind them
+    pub occlusion: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct PositionNoise {
+    /// Standard deviation of the position error of close objects
+    pub standard_deviation: f32,
+    /// Increase of the standard deviation per meter distance to the object
+    pub standard_deviation_per_meter: f32,
+}
+
+impl PositionNoise {
+    pub fn standard_deviation_at(&self, distance: f32) -> f32 {
+        self.standard_deviation + self.standard_deviation_per_meter * distance
+    }
+}
+
+/// Random walk of the `ground_to_field` handed to the behavior.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct LocalizationDrift {
+    /// Standard deviation of the translation error added within one second
+    pub translation_per_second: f32,
+    /// Standard deviation of the rotation error added within one second
+    pub rotation_per_second: f32,
+    /// Fraction of the error removed per second, as the localization corrects itself
+    pub correction_per_second: f32,
 }
 
 impl Default for SimulationConfig {
@@ -49,12 +112,128 @@ impl Default for SimulationConfig {
             head_glance_angle: 0.25,
             robot_radius: 0.25,
             kick_radius: 0.25,
+            ball_radius: 0.05,
+            ball_contact_radius: 0.15,
+            ball_push_restitution: 0.2,
+            ball_robot_restitution: 0.3,
+            ball_goal_post_restitution: 0.5,
+            minimum_pushing_speed: 0.1,
             remaining_amount_of_messages: Some(u16::MAX),
             game_controller_address: None,
+            perception: PerceptionConfig::default(),
+            network: NetworkConfig::default(),
         }
     }
 }
 
+/// Impairments of the Wi-Fi carrying HSL and GameController packets. The default delivers every
+/// packet instantly.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Seed of all network impairments, runs with equal seeds lose and delay equal packets
+    pub seed: u64,
+    /// Impairment of HSL links without an entry in `links`
+    pub hsl: LinkImpairment,
+    /// Impairment of HSL links from a sender to a receiver
+    #[serde(with = "link_impairments")]
+    pub links: BTreeMap<(SimulatorRobotId, SimulatorRobotId), LinkImpairment>,
+    /// Periods in which no packet gets through at all
+    pub outages: Option<BurstOutages>,
+    /// Time between two GameController packets, every tick carries one if not given
+    pub game_controller_packet_interval: Option<Duration>,
+    /// Probability of a robot to miss a GameController packet, it keeps the last received state
+    pub game_controller_packet_loss: f32,
+}
+
+impl NetworkConfig {
+    /// Wi-Fi as experienced on crowded competition venues.
+    pub fn competition(seed: u64) -> Self {
+        Self {
+            seed,
+            hsl: LinkImpairment {
+                packet_loss: 0.3,
+                latency: Duration::from_millis(50),
+                jitter: Jitter::Exponential {
+                    mean: Duration::from_millis(150),
+                },
+            },
+            links: BTreeMap::new(),
+            outages: Some(BurstOutages {
+                mean_time_between: Duration::from_secs(60),
+                mean_duration: Duration::from_secs(4),
+            }),
+            game_controller_packet_interval: Some(Duration::from_millis(500)),
+            game_controller_packet_loss: 0.2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct LinkImpairment {
+    pub packet_loss: f32,
+    pub latency: Duration,
+    /// Additional random latency of every packet
+    pub jitter: Jitter,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum Jitter {
+    #[default]
+    None,
+    Uniform {
+        maximum: Duration,
+    },
+    Exponential {
+        mean: Duration,
+    },
+}
+
+/// Outages start and end at exponentially distributed times.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BurstOutages {
+    pub mean_time_between: Duration,
+    pub mean_duration: Duration,
+}
+
+/// Links are keyed by sender and receiver, which formats without tuple map keys (e.g. JSON) cannot
+/// represent, so they are stored as a list of entries.
+mod link_impairments {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::LinkImpairment;
+    use crate::robot::SimulatorRobotId;
+
+    type Links = BTreeMap<(SimulatorRobotId, SimulatorRobotId), LinkImpairment>;
+
+    #[derive(Serialize, Deserialize)]
+    struct LinkEntry {
+        sender: SimulatorRobotId,
+        receiver: SimulatorRobotId,
+        impairment: LinkImpairment,
+    }
+
+    pub fn serialize<S: Serializer>(links: &Links, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            links
+                .iter()
+                .map(|(&(sender, receiver), &impairment)| LinkEntry {
+                    sender,
+                    receiver,
+                    impairment,
+                }),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Links, D::Error> {
+        Ok(Vec::<LinkEntry>::deserialize(deserializer)?
+            .into_iter()
+            .map(|entry| ((entry.sender, entry.receiver), entry.impairment))
+            .collect())
+    }
+}
+
 pub fn default_behavior_parameters() -> Result<BehaviorParameters> {
     json5::from_str(include_str!(
         "../../../etc/parameters/ros_z/base/behavior_node.json5"
@@ -62,6 +241,19 @@ pub fn default_behavior_parameters() -> Result<BehaviorParameters> {
     .wrap_err("failed to parse behavior parameters")
 }
 
+#[derive(Deserialize)]
+struct GlobalParametersFile {
+    goal_keeper_number: PlayerNumber,
+}
+
+pub fn default_goal_keeper_number() -> Result<PlayerNumber> {
+    let file: GlobalParametersFile = json5::from_str(include_str!(
+        "../../../etc/parameters/ros_z/base/global.json5"
+    ))
+    .wrap_err("failed to parse global parameters")?;
+    Ok(file.goal_keeper_number)
+}
+
 #[derive(Deserialize)]
 struct BoosterInterfaceParametersFile {
     walking: BoosterWalkingParameters,
@@ -1,14 +1,19 @@
 use std::time::{Duration, SystemTime};
 
 use bevy::prelude::*;
-use hsl_network_messages::{GamePhase, GameState, PlayerNumber, Team, TeamColor, TeamState};
+use hsl_network_messages::{
+    GamePhase, GameState, Half, Penalty, PlayerNumber, SubState, Team, TeamColor, TeamState,
+};
 use types::{
     field_dimensions::GlobalFieldSide, filtered_game_controller_state::FilteredGameControllerState,
     filtered_game_state::FilteredGameState, game_controller_state::GameControllerState,
     players::Players, primary_state::PrimaryState,
 };
 
-use crate::behavior_tree_simulator::SimulatorPrimaryState;
+use crate::{
+    behavior_tree_simulator::{SimulatorPrimaryState, SimulatorRobot, SimulatorRobotId},
+    network::SimulatorNetwork,
+};
 
 const HULKS_TEAM_NUMBER: u8 = 24;
 const OPPONENT_TEAM_NUMBER: u8 = 1;
@@ -53,6 +58,29 @@ impl SimulatorGameState {
         self.sync_filtered_game_controller_state();
     }
 
+    pub fn set_secondary_time(&mut self, secondary_time: Duration) {
+        self.game_controller_state.secondary_time = secondary_time;
+        self.sync_filtered_game_controller_state();
+    }
+
+    pub fn set_sub_state(&mut self, sub_state: Option<SubState>) {
+        self.game_controller_state.sub_state = sub_state;
+        self.sync_filtered_game_controller_state();
+    }
+
+    pub fn penalty(&self, robot_id: SimulatorRobotId) -> Option<Penalty> {
+        penalty_of(&self.game_controller_state, robot_id)
+    }
+
+    pub fn set_penalty(&mut self, robot_id: SimulatorRobotId, penalty: Option<Penalty>) {
+        let penalties = match robot_id.team {
+            Team::Hulks => &mut self.game_controller_state.penalties,
+            Team::Opponent => &mut self.game_controller_state.opponent_penalties,
+        };
+        penalties[robot_id.player_number] = penalty;
+        self.sync_filtered_game_controller_state();
+    }
+
     pub fn sync_filtered_game_controller_state(&mut self) {
         self.filtered_game_controller_state = Some(filtered_game_controller_state_from(
             &self.game_controller_state,
@@ -60,6 +88,16 @@ impl SimulatorGameState {
     }
 }
 
+pub fn penalty_of(
+    game_controller_state: &GameControllerState,
+    robot_id: SimulatorRobotId,
+) -> Option<Penalty> {
+    match robot_id.team {
+        Team::Hulks => game_controller_state.penalties[robot_id.player_number],
+        Team::Opponent => game_controller_state.opponent_penalties[robot_id.player_number],
+    }
+}
+
 pub fn global_field_side_for_team(
     game_controller_state: &GameControllerState,
     team: Team,
@@ -75,7 +113,9 @@ pub fn default_game_controller_state() -> GameControllerState {
         game_state: GameState::Playing,
         stopped: false,
         game_phase: GamePhase::Normal,
+        half: Half::First,
         remaining_time_in_half: Duration::ZERO,
+        secondary_time: Duration::ZERO,
         kicking_team: Some(Team::Hulks),
         last_game_state_change: SystemTime::UNIX_EPOCH,
         penalties: Players::new(None),
@@ -135,7 +175,9 @@ pub fn filtered_game_controller_state_for_team(
     FilteredGameControllerState {
         game_state: filtered_game_state_from(game_controller_state),
         opponent_game_state: filtered_game_state_from(game_controller_state),
+        half: game_controller_state.half,
         remaining_time_in_half: game_controller_state.remaining_time_in_half,
+        secondary_time: game_controller_state.secondary_time,
         game_phase: game_controller_state.game_phase,
         kicking_team: game_controller_state
             .kicking_team
@@ -187,13 +229,24 @@ pub fn primary_state_from_game_controller_state(
     }
 }
 
+/// Robots follow the last GameController state they received. Penalized robots stay penalized
+/// until the GameController releases them.
 pub fn sync_primary_states_from_game_state(
     game_state: Res<SimulatorGameState>,
-    mut robots: Query<&mut SimulatorPrimaryState>,
+    network: Res<SimulatorNetwork>,
+    mut robots: Query<(&SimulatorRobot, &mut SimulatorPrimaryState)>,
 ) {
-    let primary_state = primary_state_from_game_controller_state(&game_state.game_controller_state);
-    for mut robot_primary_state in &mut robots {
-        robot_primary_state.primary_state = primary_state;
+    for (robot, mut robot_primary_state) in &mut robots {
+        let game_controller_state = network
+            .game_controller_states
+            .get(&robot.id())
+            .unwrap_or(&game_state.game_controller_state);
+        robot_primary_state.primary_state =
+            if penalty_of(game_controller_state, robot.id()).is_some() {
+                PrimaryState::Penalized
+            } else {
+                primary_state_from_game_controller_state(game_controller_state)
+            };
     }
 }
 
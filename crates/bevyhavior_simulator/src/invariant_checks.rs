@@ -1,24 +1,32 @@
 use std::fmt::{Display, Formatter, Result};
-use std::{collections::BTreeMap, time::SystemTime};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    mem::{Discriminant, discriminant},
+    time::{Duration, SystemTime},
+};
 
 use bevy::prelude::*;
 use booster::FallDownState;
 use coordinate_systems::{Field, Ground, World};
-use hsl_network_messages::PlayerNumber;
+use hsl_network_messages::{GameState, PlayerNumber, Team};
 use linear_algebra::{Isometry2, Orientation2, Point2};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use types::path::traits::EndPoints;
 use types::{
-    field_dimensions::FieldDimensions, motion_command::MotionCommand, primary_state::PrimaryState,
-    rule_obstacles::RuleObstacle,
+    field_dimensions::FieldDimensions, game_controller_state::GameControllerState,
+    messages::OutgoingMessage, motion_command::MotionCommand, primary_state::PrimaryState,
+    roles::Role, rule_obstacles::RuleObstacle,
 };
 
 use crate::behavior_tree_simulator::{
     RobotFrame, SimulatedBall, SimulationConfig, SimulatorBall, SimulatorFailure,
-    SimulatorFallDownState, SimulatorFieldDimensions, SimulatorGroundToWorld,
+    SimulatorFallDownState, SimulatorFieldDimensions, SimulatorGameState, SimulatorGroundToWorld,
     SimulatorPrimaryState, SimulatorRobot, SimulatorRobotFrames, SimulatorRobotId,
-    SimulatorRuleObstacles, SimulatorScenarioResult,
+    SimulatorRobotParameters, SimulatorRuleObstacles, SimulatorScenarioResult,
+    point_world_to_field,
 };
+use crate::game_controller::global_field_side_for_team;
 use crate::timeline::robot_snapshots_from_query;
 
 #[derive(Resource, Default)]
@@ -29,7 +37,7 @@ pub struct SimulatorCurrentInvariantViolations(pub Vec<InvariantViolation>);
 
 pub const BEHAVIOR_TICK_ERROR_CHECK_NAME: &str = "behavior_tick_error";
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct RobotSnapshot {
     pub id: SimulatorRobotId,
     pub player_number: PlayerNumber,
@@ -45,14 +53,54 @@ pub struct SimulationSnapshot {
     pub ball: Option<SimulatedBall>,
     pub robots: BTreeMap<SimulatorRobotId, RobotSnapshot>,
     pub robot_frames: BTreeMap<SimulatorRobotId, RobotFrame>,
+    /// Global `goal_keeper_number` parameter of each robot
+    pub goal_keeper_numbers: BTreeMap<SimulatorRobotId, PlayerNumber>,
     pub field_dimensions: FieldDimensions,
     pub rule_obstacles: Vec<RuleObstacle>,
     pub config: SimulationConfig,
+    pub game_controller_state: GameControllerState,
+}
+
+impl SimulationSnapshot {
+    fn is_playing(&self) -> bool {
+        self.game_controller_state.game_state == GameState::Playing
+    }
+
+    fn position_in_field(&self, team: Team, position: Point2<World>) -> Point2<Field> {
+        point_world_to_field(
+            position,
+            global_field_side_for_team(&self.game_controller_state, team),
+        )
+    }
+
+    fn teams(&self) -> impl Iterator<Item = Team> + '_ {
+        [Team::Hulks, Team::Opponent]
+            .into_iter()
+            .filter(|team| self.robots.keys().any(|robot_id| robot_id.team == *team))
+    }
+
+    fn teammates(&self, team: Team) -> impl Iterator<Item = &RobotSnapshot> {
+        self.robots
+            .values()
+            .filter(move |robot| robot.id.team == team)
+    }
+
+    /// The role assigned by the robot stack wins, robots without role go by their parameters.
+    fn is_goal_keeper(&self, robot: &RobotSnapshot) -> bool {
+        let role = self
+            .robot_frames
+            .get(&robot.id)
+            .and_then(|frame| frame.world_state.robot.role);
+        match role {
+            Some(role) => matches!(role, Role::Keeper | Role::ReplacementKeeper),
+            None => self.goal_keeper_numbers.get(&robot.id) == Some(&robot.player_number),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InvariantViolation {
-    pub check_name: &'static str,
+    pub check_name: Cow<'static, str>,
     pub player_number: Option<PlayerNumber>,
     pub message: String,
     pub severity: InvariantSeverity,
@@ -68,24 +116,113 @@ impl Display for InvariantViolation {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InvariantSeverity {
     Warning,
     Error,
 }
 
+/// Number of violations per severity a scenario may produce and still pass.
+#[derive(Resource, Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulatorInvariantThresholds {
+    /// Unlimited if not given
+    pub maximum_warnings: Option<usize>,
+    /// Unlimited if not given
+    pub maximum_errors: Option<usize>,
+}
+
+impl Default for SimulatorInvariantThresholds {
+    fn default() -> Self {
+        Self {
+            maximum_warnings: None,
+            maximum_errors: Some(0),
+        }
+    }
+}
+
+impl SimulatorInvariantThresholds {
+    pub fn maximum(&self, severity: InvariantSeverity) -> Option<usize> {
+        match severity {
+            InvariantSeverity::Warning => self.maximum_warnings,
+            InvariantSeverity::Error => self.maximum_errors,
+        }
+    }
+}
+
 pub trait InvariantCheck: Send + Sync {
     fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation>;
 }
 
 pub fn default_invariant_checks() -> Vec<Box<dyn InvariantCheck>> {
-    vec![
-        Box::new(RuleObstacleWalkCheck),
-        Box::new(FieldBoundaryWalkCheck),
-    ]
+    InvariantChecksConfig::default().checks()
+}
+
+/// Selects and parametrizes the invariant checks of a scenario, `null` disables a check.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InvariantChecksConfig {
+    pub rule_obstacle_walk: Option<RuleObstacleWalkCheck>,
+    pub field_boundary_walk: Option<FieldBoundaryWalkCheck>,
+    pub teammate_clustering: Option<TeammateClusteringCheck>,
+    pub missing_defender: Option<MissingDefenderCheck>,
+    pub goal_keeper_leaving_penalty_area: Option<GoalKeeperLeavingPenaltyAreaCheck>,
+    pub oscillating_motion: Option<OscillatingMotionCheck>,
+    pub hsl_message_budget: Option<HslMessageBudgetCheck>,
+    pub standing_still: Option<StandingStillCheck>,
+}
+
+impl Default for InvariantChecksConfig {
+    fn default() -> Self {
+        Self {
+            rule_obstacle_walk: Some(Default::default()),
+            field_boundary_walk: Some(Default::default()),
+            teammate_clustering: Some(Default::default()),
+            missing_defender: Some(Default::default()),
+            goal_keeper_leaving_penalty_area: Some(Default::default()),
+            oscillating_motion: Some(Default::default()),
+            hsl_message_budget: Some(Default::default()),
+            standing_still: Some(Default::default()),
+        }
+    }
+}
+
+impl InvariantChecksConfig {
+    pub fn checks(self) -> Vec<Box<dyn InvariantCheck>> {
+        fn boxed(check: impl InvariantCheck + 'static) -> Box<dyn InvariantCheck> {
+            Box::new(check)
+        }
+
+        [
+            self.rule_obstacle_walk.map(boxed),
+            self.field_boundary_walk.map(boxed),
+            self.teammate_clustering.map(boxed),
+            self.missing_defender.map(boxed),
+            self.goal_keeper_leaving_penalty_area.map(boxed),
+            self.oscillating_motion.map(boxed),
+            self.hsl_message_budget.map(boxed),
+            self.standing_still.map(boxed),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
 }
 
-pub struct RuleObstacleWalkCheck;
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleObstacleWalkCheck {
+    pub severity: InvariantSeverity,
+}
+
+impl Default for RuleObstacleWalkCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Error,
+        }
+    }
+}
 
 impl InvariantCheck for RuleObstacleWalkCheck {
     fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
@@ -98,12 +235,12 @@ impl InvariantCheck for RuleObstacleWalkCheck {
             for obstacle in &frame.world_state.rule_obstacles {
                 if obstacle.contains(target) {
                     violations.push(InvariantViolation {
-                        check_name: "rule_obstacle_walk",
+                        check_name: "rule_obstacle_walk".into(),
                         player_number: Some(robot_id.player_number),
                         message: format!(
                             "robot {robot_id} plans to walk into a known rule obstacle"
                         ),
-                        severity: InvariantSeverity::Error,
+                        severity: self.severity,
                     });
                     break;
                 }
@@ -113,7 +250,19 @@ impl InvariantCheck for RuleObstacleWalkCheck {
     }
 }
 
-pub struct FieldBoundaryWalkCheck;
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldBoundaryWalkCheck {
+    pub severity: InvariantSeverity,
+}
+
+impl Default for FieldBoundaryWalkCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Error,
+        }
+    }
+}
 
 impl InvariantCheck for FieldBoundaryWalkCheck {
     fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
@@ -125,10 +274,412 @@ impl InvariantCheck for FieldBoundaryWalkCheck {
 
             if !is_inside_field_with_border_margin(target, snapshot.field_dimensions) {
                 violations.push(InvariantViolation {
-                    check_name: "field_boundary_walk",
+                    check_name: "field_boundary_walk".into(),
                     player_number: Some(robot_id.player_number),
                     message: format!("robot {robot_id} plans to walk outside the known field"),
-                    severity: InvariantSeverity::Error,
+                    severity: self.severity,
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// Teammates bumping into each other or crowding around the ball.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TeammateClusteringCheck {
+    pub severity: InvariantSeverity,
+    /// Teammates closer to each other than this are colliding
+    pub minimum_distance: f32,
+    /// Teammates within this distance of the ball count as being on the ball
+    pub ball_distance: f32,
+    pub maximum_players_on_ball: usize,
+    pub duration_seconds: f32,
+    #[serde(skip)]
+    collisions: Persistence<(SimulatorRobotId, SimulatorRobotId)>,
+    #[serde(skip)]
+    clusters: Persistence<usize>,
+}
+
+impl Default for TeammateClusteringCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Warning,
+            minimum_distance: 0.3,
+            ball_distance: 0.75,
+            maximum_players_on_ball: 2,
+            duration_seconds: 1.0,
+            collisions: Persistence::default(),
+            clusters: Persistence::default(),
+        }
+    }
+}
+
+impl InvariantCheck for TeammateClusteringCheck {
+    fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+        let duration = Duration::from_secs_f32(self.duration_seconds);
+        let mut colliding = Vec::new();
+        let mut clustering = Vec::new();
+        if snapshot.is_playing() {
+            for team in snapshot.teams() {
+                let teammates = snapshot
+                    .teammates(team)
+                    .filter(|robot| robot.primary_state == PrimaryState::Playing)
+                    .collect::<Vec<_>>();
+                for (index, robot) in teammates.iter().enumerate() {
+                    for other in &teammates[index + 1..] {
+                        if (position(robot) - position(other)).norm() < self.minimum_distance {
+                            colliding.push((robot.id, other.id));
+                        }
+                    }
+                }
+
+                if let Some(ball) = snapshot.ball {
+                    let players_on_ball = teammates
+                        .iter()
+                        .filter(|robot| {
+                            (position(robot) - ball.position).norm() < self.ball_distance
+                        })
+                        .count();
+                    if players_on_ball > self.maximum_players_on_ball {
+                        clustering.push(team_index(team));
+                    }
+                }
+            }
+        }
+
+        let collisions = self.collisions.update(colliding, snapshot.now, duration);
+        let clusters = self.clusters.update(clustering, snapshot.now, duration);
+
+        let collisions = collisions
+            .into_iter()
+            .map(|(robot, other)| InvariantViolation {
+                check_name: "teammate_collision".into(),
+                player_number: Some(robot.player_number),
+                message: format!("robots {robot} and {other} are colliding"),
+                severity: self.severity,
+            });
+        let clusters = clusters.into_iter().map(|team| InvariantViolation {
+            check_name: "teammate_clustering".into(),
+            player_number: None,
+            message: format!(
+                "more than {} robots of team {:?} are on the ball",
+                self.maximum_players_on_ball, TEAMS[team]
+            ),
+            severity: self.severity,
+        });
+        collisions.chain(clusters).collect()
+    }
+}
+
+/// The ball lies in the own half but no robot of the team is between the ball and the own goal.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MissingDefenderCheck {
+    pub severity: InvariantSeverity,
+    pub duration_seconds: f32,
+    #[serde(skip)]
+    undefended: Persistence<usize>,
+}
+
+impl Default for MissingDefenderCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Warning,
+            duration_seconds: 3.0,
+            undefended: Persistence::default(),
+        }
+    }
+}
+
+impl InvariantCheck for MissingDefenderCheck {
+    fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+        let mut undefended = Vec::new();
+        if let Some(ball) = snapshot.ball
+            && snapshot.is_playing()
+        {
+            for team in snapshot.teams() {
+                let ball_in_field = snapshot.position_in_field(team, ball.position);
+                if ball_in_field.x() >= 0.0 {
+                    continue;
+                }
+                let is_defended = snapshot
+                    .teammates(team)
+                    .filter(|robot| robot.primary_state == PrimaryState::Playing)
+                    .any(|robot| {
+                        snapshot.position_in_field(team, position(robot)).x() < ball_in_field.x()
+                    });
+                if !is_defended {
+                    undefended.push(team_index(team));
+                }
+            }
+        }
+
+        self.undefended
+            .update(
+                undefended,
+                snapshot.now,
+                Duration::from_secs_f32(self.duration_seconds),
+            )
+            .into_iter()
+            .map(|team| InvariantViolation {
+                check_name: "missing_defender".into(),
+                player_number: None,
+                message: format!(
+                    "no robot of team {:?} defends while the ball is in its own half",
+                    TEAMS[team]
+                ),
+                severity: self.severity,
+            })
+            .collect()
+    }
+}
+
+/// The goal keeper of a team staying outside of its penalty area.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GoalKeeperLeavingPenaltyAreaCheck {
+    pub severity: InvariantSeverity,
+    /// Distance outside of the penalty area the goal keeper may still walk to
+    pub margin: f32,
+    pub duration_seconds: f32,
+    #[serde(skip)]
+    outside: Persistence<SimulatorRobotId>,
+}
+
+impl Default for GoalKeeperLeavingPenaltyAreaCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Warning,
+            margin: 0.3,
+            duration_seconds: 2.0,
+            outside: Persistence::default(),
+        }
+    }
+}
+
+impl InvariantCheck for GoalKeeperLeavingPenaltyAreaCheck {
+    fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+        let field_dimensions = snapshot.field_dimensions;
+        let maximum_x =
+            -field_dimensions.length / 2.0 + field_dimensions.penalty_area_length + self.margin;
+        let maximum_y = field_dimensions.penalty_area_width / 2.0 + self.margin;
+        let outside = snapshot
+            .robots
+            .values()
+            .filter(|robot| {
+                snapshot.is_playing()
+                    && robot.primary_state == PrimaryState::Playing
+                    && snapshot.is_goal_keeper(robot)
+            })
+            .filter(|robot| {
+                let position = snapshot.position_in_field(robot.id.team, position(robot));
+                position.x() > maximum_x || position.y().abs() > maximum_y
+            })
+            .map(|robot| robot.id)
+            .collect::<Vec<_>>();
+
+        self.outside
+            .update(
+                outside,
+                snapshot.now,
+                Duration::from_secs_f32(self.duration_seconds),
+            )
+            .into_iter()
+            .map(|robot_id| InvariantViolation {
+                check_name: "goal_keeper_leaving_penalty_area".into(),
+                player_number: Some(robot_id.player_number),
+                message: format!("goal keeper {robot_id} left its penalty area"),
+                severity: self.severity,
+            })
+            .collect()
+    }
+}
+
+/// Robots switching between motion types, e.g. walk and stand, many times within a short window.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OscillatingMotionCheck {
+    pub severity: InvariantSeverity,
+    pub maximum_switches: usize,
+    pub window_seconds: f32,
+    #[serde(skip)]
+    last_motion_types: BTreeMap<SimulatorRobotId, Discriminant<MotionCommand>>,
+    #[serde(skip)]
+    switches: BTreeMap<SimulatorRobotId, VecDeque<SystemTime>>,
+    #[serde(skip)]
+    reported: BTreeSet<SimulatorRobotId>,
+}
+
+impl Default for OscillatingMotionCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Warning,
+            maximum_switches: 4,
+            window_seconds: 1.0,
+            last_motion_types: BTreeMap::new(),
+            switches: BTreeMap::new(),
+            reported: BTreeSet::new(),
+        }
+    }
+}
+
+impl InvariantCheck for OscillatingMotionCheck {
+    fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+        let window = Duration::from_secs_f32(self.window_seconds);
+        let mut violations = Vec::new();
+        for (robot_id, frame) in &snapshot.robot_frames {
+            let motion_type = discriminant(&frame.motion_command);
+            let switches = self.switches.entry(*robot_id).or_default();
+            if self
+                .last_motion_types
+                .insert(*robot_id, motion_type)
+                .is_some_and(|last_motion_type| last_motion_type != motion_type)
+            {
+                switches.push_back(snapshot.now);
+            }
+            retain_window(switches, snapshot.now, window);
+
+            if switches.len() <= self.maximum_switches {
+                self.reported.remove(robot_id);
+            } else if self.reported.insert(*robot_id) {
+                violations.push(InvariantViolation {
+                    check_name: "oscillating_motion".into(),
+                    player_number: Some(robot_id.player_number),
+                    message: format!(
+                        "robot {robot_id} switched its motion type {} times within {:.1}s",
+                        switches.len(),
+                        self.window_seconds
+                    ),
+                    severity: self.severity,
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// A team sending more HSL messages than its budget allows on average.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HslMessageBudgetCheck {
+    pub severity: InvariantSeverity,
+    /// Sustainable rate of a team, 1200 messages per game spread over two ten minute halves
+    pub maximum_messages_per_second: f32,
+    pub window_seconds: f32,
+    #[serde(skip)]
+    sent: [VecDeque<SystemTime>; 2],
+    #[serde(skip)]
+    reported: [bool; 2],
+}
+
+impl Default for HslMessageBudgetCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Warning,
+            maximum_messages_per_second: 1.0,
+            window_seconds: 10.0,
+            sent: Default::default(),
+            reported: Default::default(),
+        }
+    }
+}
+
+impl InvariantCheck for HslMessageBudgetCheck {
+    fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+        let window = Duration::from_secs_f32(self.window_seconds);
+        let budget = self.maximum_messages_per_second * self.window_seconds;
+        for (robot_id, frame) in &snapshot.robot_frames {
+            let number_of_messages = frame
+                .outgoing_messages
+                .iter()
+                .filter(|message| matches!(message, OutgoingMessage::Hsl(_)))
+                .count();
+            self.sent[team_index(robot_id.team)]
+                .extend(std::iter::repeat_n(snapshot.now, number_of_messages));
+        }
+
+        let mut violations = Vec::new();
+        for (team, sent) in self.sent.iter_mut().enumerate() {
+            retain_window(sent, snapshot.now, window);
+            if sent.len() as f32 <= budget {
+                self.reported[team] = false;
+            } else if !self.reported[team] {
+                self.reported[team] = true;
+                violations.push(InvariantViolation {
+                    check_name: "hsl_message_budget".into(),
+                    player_number: None,
+                    message: format!(
+                        "team {:?} sent {} HSL messages within {:.1}s, budget is {budget:.0}",
+                        TEAMS[team],
+                        sent.len(),
+                        self.window_seconds
+                    ),
+                    severity: self.severity,
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// Robots not moving for a long time while the game is playing.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StandingStillCheck {
+    pub severity: InvariantSeverity,
+    /// Robots moving less than this count as standing still
+    pub minimum_distance: f32,
+    pub duration_seconds: f32,
+    #[serde(skip)]
+    anchors: BTreeMap<SimulatorRobotId, (Point2<World>, SystemTime)>,
+    #[serde(skip)]
+    reported: BTreeSet<SimulatorRobotId>,
+}
+
+impl Default for StandingStillCheck {
+    fn default() -> Self {
+        Self {
+            severity: InvariantSeverity::Warning,
+            minimum_distance: 0.1,
+            duration_seconds: 10.0,
+            anchors: BTreeMap::new(),
+            reported: BTreeSet::new(),
+        }
+    }
+}
+
+impl InvariantCheck for StandingStillCheck {
+    fn check(&mut self, snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+        let duration = Duration::from_secs_f32(self.duration_seconds);
+        let mut violations = Vec::new();
+        for robot in snapshot.robots.values() {
+            if !snapshot.is_playing() || robot.primary_state != PrimaryState::Playing {
+                self.anchors.remove(&robot.id);
+                self.reported.remove(&robot.id);
+                continue;
+            }
+
+            let position = position(robot);
+            let (anchor, since) = *self
+                .anchors
+                .entry(robot.id)
+                .or_insert((position, snapshot.now));
+            if (position - anchor).norm() >= self.minimum_distance {
+                self.anchors.insert(robot.id, (position, snapshot.now));
+                self.reported.remove(&robot.id);
+            } else if elapsed_since(since, snapshot.now) >= duration
+                && self.reported.insert(robot.id)
+            {
+                violations.push(InvariantViolation {
+                    check_name: "standing_still".into(),
+                    player_number: Some(robot.player_number),
+                    message: format!(
+                        "robot {} stood still for {:.1}s while playing",
+                        robot.id, self.duration_seconds
+                    ),
+                    severity: self.severity,
                 });
             }
         }
@@ -142,7 +693,10 @@ pub fn run_invariant_checks(
     field_dimensions: Res<SimulatorFieldDimensions>,
     rule_obstacles: Res<SimulatorRuleObstacles>,
     config: Res<SimulationConfig>,
+    game_state: Res<SimulatorGameState>,
     robot_frames: Res<SimulatorRobotFrames>,
+    thresholds: Res<SimulatorInvariantThresholds>,
+    mut violation_counts: Local<BTreeMap<InvariantSeverity, usize>>,
     mut invariant_checks: ResMut<SimulatorInvariantChecks>,
     mut current_violations: ResMut<SimulatorCurrentInvariantViolations>,
     mut scenario_result: ResMut<SimulatorScenarioResult>,
@@ -153,6 +707,7 @@ pub fn run_invariant_checks(
         &SimulatorPrimaryState,
         &SimulatorFallDownState,
     )>,
+    robot_parameters: Query<(&SimulatorRobot, &SimulatorRobotParameters)>,
 ) {
     current_violations
         .0
@@ -163,22 +718,36 @@ pub fn run_invariant_checks(
         ball: ball.state,
         robots: robot_snapshots_from_query(&robots),
         robot_frames: robot_frames.0.clone(),
+        goal_keeper_numbers: robot_parameters
+            .iter()
+            .map(|(robot, parameters)| (robot.id(), parameters.goal_keeper_number))
+            .collect(),
         field_dimensions: field_dimensions.0,
         rule_obstacles: rule_obstacles.obstacles.clone(),
         config: config.clone(),
+        game_controller_state: game_state.game_controller_state.clone(),
     };
 
+    let mut failures = current_violations.0.clone();
     for check in &mut invariant_checks.0 {
-        current_violations.0.extend(check.check(&snapshot));
+        for violation in check.check(&snapshot) {
+            let count = violation_counts.entry(violation.severity).or_default();
+            *count += 1;
+            if thresholds
+                .maximum(violation.severity)
+                .is_some_and(|maximum| *count > maximum)
+            {
+                failures.push(violation.clone());
+            }
+            current_violations.0.push(violation);
+        }
     }
 
-    if !current_violations.0.is_empty() {
+    if !failures.is_empty() {
         scenario_result.failed = true;
         scenario_result.failures.extend(
-            current_violations
-                .0
-                .iter()
-                .cloned()
+            failures
+                .into_iter()
                 .map(SimulatorFailure::InvariantViolation),
         );
     }
@@ -200,3 +769,407 @@ fn motion_target_in_field(frame: &RobotFrame) -> Option<Point2<Field>> {
     let ground_to_field = frame.world_state.robot.ground_to_field?;
     Some(ground_to_field * path.end_point())
 }
+
+const TEAMS: [Team; 2] = [Team::Hulks, Team::Opponent];
+
+fn team_index(team: Team) -> usize {
+    match team {
+        Team::Hulks => 0,
+        Team::Opponent => 1,
+    }
+}
+
+fn position(robot: &RobotSnapshot) -> Point2<World> {
+    robot.ground_to_world.translation()
+}
+
+fn elapsed_since(since: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(since).unwrap_or_default()
+}
+
+fn retain_window(times: &mut VecDeque<SystemTime>, now: SystemTime, window: Duration) {
+    while times
+        .front()
+        .is_some_and(|time| elapsed_since(*time, now) > window)
+    {
+        times.pop_front();
+    }
+}
+
+/// Reports a condition once it held continuously for a while and again only after it cleared.
+#[derive(Clone, Debug)]
+struct Persistence<Key> {
+    active_since: BTreeMap<Key, (SystemTime, bool)>,
+}
+
+impl<Key> Default for Persistence<Key> {
+    fn default() -> Self {
+        Self {
+            active_since: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Key: Ord + Copy> Persistence<Key> {
+    fn update(
+        &mut self,
+        active: impl IntoIterator<Item = Key>,
+        now: SystemTime,
+        duration: Duration,
+    ) -> Vec<Key> {
+        let active = active.into_iter().collect::<BTreeSet<_>>();
+        self.active_since.retain(|key, _| active.contains(key));
+
+        let mut due = Vec::new();
+        for key in active {
+            let (since, reported) = self.active_since.entry(key).or_insert((now, false));
+            if !*reported && elapsed_since(*since, now) >= duration {
+                *reported = true;
+                due.push(key);
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::{point, vector};
+    use types::{
+        behavior_tree::{NodeTrace, Status},
+        field_dimensions::Side,
+        motion_command::HeadMotion,
+        world_state::WorldState,
+    };
+
+    use super::*;
+    use crate::behavior_tree_simulator::{DEFAULT_TICK_DURATION, SimulatorClock, SimulatorHeadYaw};
+    use crate::game_controller::default_game_controller_state;
+
+    fn snapshot(
+        now: SystemTime,
+        robots: &[(SimulatorRobotId, Point2<World>)],
+    ) -> SimulationSnapshot {
+        SimulationSnapshot {
+            now,
+            ball: None,
+            robots: robots
+                .iter()
+                .map(|(robot_id, position)| {
+                    (
+                        *robot_id,
+                        RobotSnapshot {
+                            id: *robot_id,
+                            player_number: robot_id.player_number,
+                            ground_to_world: Isometry2::from_parts(position.coords(), 0.0),
+                            head_yaw: Orientation2::identity(),
+                            primary_state: PrimaryState::Playing,
+                            fall_down_state: None,
+                        },
+                    )
+                })
+                .collect(),
+            robot_frames: BTreeMap::new(),
+            goal_keeper_numbers: BTreeMap::new(),
+            field_dimensions: FieldDimensions::SPL_2025,
+            rule_obstacles: Vec::new(),
+            config: SimulationConfig::default(),
+            game_controller_state: default_game_controller_state(),
+        }
+    }
+
+    fn frame(motion_command: MotionCommand) -> RobotFrame {
+        let trace = NodeTrace {
+            name: String::new(),
+            status: Status::Success,
+            child_index: 0,
+            duration: Duration::ZERO,
+            children: Vec::new(),
+        };
+        RobotFrame {
+            world_state: WorldState::default(),
+            motion_command,
+            trace: trace.clone(),
+            static_layout: trace,
+            path_obstacles: Vec::new(),
+            time_since_last_switch: Duration::ZERO,
+            direction_difference: 0.0,
+            walk_position: None,
+            voronoi_map: None,
+            voronoi_inputs: Vec::new(),
+            outgoing_messages: Vec::new(),
+        }
+    }
+
+    fn at(seconds: f32) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs_f32(seconds)
+    }
+
+    #[test]
+    fn standing_still_is_reported_once_until_the_robot_moves() {
+        let robot_id = SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three);
+        let mut check = StandingStillCheck {
+            duration_seconds: 2.0,
+            ..Default::default()
+        };
+
+        assert!(
+            check
+                .check(&snapshot(at(0.0), &[(robot_id, point![0.0, 0.0])]))
+                .is_empty()
+        );
+        assert!(
+            check
+                .check(&snapshot(at(1.0), &[(robot_id, point![0.05, 0.0])]))
+                .is_empty()
+        );
+        assert_eq!(
+            check
+                .check(&snapshot(at(2.0), &[(robot_id, point![0.05, 0.0])]))
+                .len(),
+            1
+        );
+        assert!(
+            check
+                .check(&snapshot(at(3.0), &[(robot_id, point![0.05, 0.0])]))
+                .is_empty()
+        );
+        assert!(
+            check
+                .check(&snapshot(at(4.0), &[(robot_id, point![1.0, 0.0])]))
+                .is_empty()
+        );
+        assert!(
+            check
+                .check(&snapshot(at(5.0), &[(robot_id, point![1.0, 0.0])]))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn missing_defender_requires_a_robot_between_ball_and_own_goal() {
+        let robot_id = SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three);
+        let mut check = MissingDefenderCheck {
+            duration_seconds: 0.0,
+            ..Default::default()
+        };
+        let ball = SimulatedBall {
+            position: point![-2.0, 0.0],
+            velocity: vector![0.0, 0.0],
+            field_side: Side::Left,
+        };
+
+        let mut defended = snapshot(at(0.0), &[(robot_id, point![-3.0, 0.0])]);
+        defended.ball = Some(ball);
+        assert!(check.check(&defended).is_empty());
+
+        let mut undefended = snapshot(at(1.0), &[(robot_id, point![-1.0, 0.0])]);
+        undefended.ball = Some(ball);
+        let violations = check.check(&undefended);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].check_name, "missing_defender");
+    }
+
+    #[test]
+    fn oscillating_motion_is_reported_when_switching_too_often() {
+        let robot_id = SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three);
+        let mut check = OscillatingMotionCheck {
+            maximum_switches: 2,
+            ..Default::default()
+        };
+        let stand = MotionCommand::Stand {
+            head: HeadMotion::ZeroAngles,
+        };
+
+        let violations = (0..4)
+            .flat_map(|tick| {
+                let mut snapshot = snapshot(at(tick as f32 * 0.1), &[]);
+                let motion_command = if tick % 2 == 0 {
+                    stand.clone()
+                } else {
+                    MotionCommand::Prepare
+                };
+                snapshot
+                    .robot_frames
+                    .insert(robot_id, frame(motion_command));
+                check.check(&snapshot)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].player_number, Some(PlayerNumber::Three));
+    }
+
+    #[test]
+    fn null_disables_a_check_and_missing_entries_keep_their_default() {
+        let config: InvariantChecksConfig = json5::from_str(
+            r#"{ standing_still: null, teammate_clustering: { severity: "error" } }"#,
+        )
+        .expect("config should parse");
+
+        assert!(config.standing_still.is_none());
+        assert!(config.missing_defender.is_some());
+        assert_eq!(
+            config
+                .teammate_clustering
+                .expect("check should be enabled")
+                .severity,
+            InvariantSeverity::Error
+        );
+    }
+
+    #[test]
+    fn goal_keeper_is_taken_from_the_role_or_the_parameters() {
+        let one = SimulatorRobotId::new(Team::Hulks, PlayerNumber::One);
+        let two = SimulatorRobotId::new(Team::Hulks, PlayerNumber::Two);
+        let mut check = GoalKeeperLeavingPenaltyAreaCheck {
+            duration_seconds: 0.0,
+            ..Default::default()
+        };
+
+        let mut by_parameters = snapshot(
+            at(0.0),
+            &[(one, point![0.0, 1.0]), (two, point![0.0, -1.0])],
+        );
+        by_parameters.goal_keeper_numbers =
+            BTreeMap::from([(one, PlayerNumber::Two), (two, PlayerNumber::Two)]);
+        let violations = check.check(&by_parameters);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].player_number, Some(PlayerNumber::Two));
+
+        let mut by_role = by_parameters.clone();
+        by_role.now = at(1.0);
+        let mut keeper = frame(MotionCommand::Prepare);
+        keeper.world_state.robot.role = Some(Role::ReplacementKeeper);
+        let mut striker = frame(MotionCommand::Prepare);
+        striker.world_state.robot.role = Some(Role::Striker);
+        by_role.robot_frames = BTreeMap::from([(one, keeper), (two, striker)]);
+        let violations = check.check(&by_role);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].player_number, Some(PlayerNumber::One));
+    }
+
+    #[test]
+    fn hsl_message_budget_is_reported_once_per_exceeded_window() {
+        let robot_id = SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three);
+        let mut check = HslMessageBudgetCheck {
+            maximum_messages_per_second: 0.2,
+            window_seconds: 10.0,
+            ..Default::default()
+        };
+        let mut sending = frame(MotionCommand::Prepare);
+        sending.outgoing_messages = vec![OutgoingMessage::default(); 3];
+
+        let mut violations = Vec::new();
+        for tick in 0..3 {
+            let mut snapshot = snapshot(at(tick as f32), &[]);
+            snapshot.robot_frames.insert(robot_id, sending.clone());
+            violations.extend(check.check(&snapshot));
+        }
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].check_name, "hsl_message_budget");
+
+        assert!(check.check(&snapshot(at(20.0), &[])).is_empty());
+        let mut snapshot = snapshot(at(21.0), &[]);
+        snapshot.robot_frames.insert(robot_id, sending);
+        assert_eq!(check.check(&snapshot).len(), 1);
+    }
+
+    #[test]
+    fn teammate_clustering_reports_collisions_and_crowding_around_the_ball() {
+        let robot = |player_number| SimulatorRobotId::new(Team::Hulks, player_number);
+        let mut check = TeammateClusteringCheck {
+            duration_seconds: 0.0,
+            ..Default::default()
+        };
+
+        let mut crowded = snapshot(
+            at(0.0),
+            &[
+                (robot(PlayerNumber::Two), point![0.5, 0.0]),
+                (robot(PlayerNumber::Three), point![-0.5, 0.0]),
+                (robot(PlayerNumber::Four), point![0.0, 0.5]),
+                (robot(PlayerNumber::Five), point![3.0, 0.0]),
+                (robot(PlayerNumber::Six), point![3.1, 0.0]),
+            ],
+        );
+        crowded.ball = Some(SimulatedBall {
+            position: point![0.0, 0.0],
+            velocity: vector![0.0, 0.0],
+            field_side: Side::Left,
+        });
+        let mut check_names = check
+            .check(&crowded)
+            .into_iter()
+            .map(|violation| violation.check_name)
+            .collect::<Vec<_>>();
+        check_names.sort();
+
+        assert_eq!(check_names, ["teammate_clustering", "teammate_collision"]);
+    }
+
+    struct AlwaysViolated;
+
+    impl InvariantCheck for AlwaysViolated {
+        fn check(&mut self, _snapshot: &SimulationSnapshot) -> Vec<InvariantViolation> {
+            vec![InvariantViolation {
+                check_name: "always_violated".into(),
+                player_number: None,
+                message: String::new(),
+                severity: InvariantSeverity::Warning,
+            }]
+        }
+    }
+
+    #[test]
+    fn violations_fail_the_scenario_only_above_the_threshold() {
+        let mut app = App::new();
+        app.insert_resource(SimulatorClock {
+            now: SystemTime::UNIX_EPOCH,
+            tick_duration: DEFAULT_TICK_DURATION,
+        })
+        .insert_resource(SimulatorBall::default())
+        .insert_resource(SimulatorFieldDimensions(FieldDimensions::SPL_2025))
+        .insert_resource(SimulatorRuleObstacles::default())
+        .insert_resource(SimulationConfig::default())
+        .insert_resource(SimulatorGameState::default())
+        .insert_resource(SimulatorRobotFrames::default())
+        .insert_resource(SimulatorInvariantThresholds {
+            maximum_warnings: Some(1),
+            maximum_errors: Some(0),
+        })
+        .insert_resource(SimulatorInvariantChecks(vec![Box::new(AlwaysViolated)]))
+        .insert_resource(SimulatorCurrentInvariantViolations::default())
+        .insert_resource(SimulatorScenarioResult::default())
+        .add_systems(Update, run_invariant_checks);
+        app.world_mut().spawn((
+            SimulatorRobot {
+                team: Team::Hulks,
+                player_number: PlayerNumber::Three,
+            },
+            SimulatorGroundToWorld {
+                ground_to_world: Isometry2::identity(),
+            },
+            SimulatorHeadYaw::default(),
+            SimulatorPrimaryState {
+                primary_state: PrimaryState::Playing,
+            },
+            SimulatorFallDownState::default(),
+        ));
+
+        app.update();
+        assert!(!app.world().resource::<SimulatorScenarioResult>().failed);
+        assert_eq!(
+            app.world()
+                .resource::<SimulatorCurrentInvariantViolations>()
+                .0
+                .len(),
+            1
+        );
+
+        app.update();
+        let scenario_result = app.world().resource::<SimulatorScenarioResult>();
+        assert!(scenario_result.failed);
+        assert_eq!(scenario_result.failures.len(), 1);
+    }
+}
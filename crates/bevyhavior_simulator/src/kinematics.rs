@@ -9,10 +9,13 @@ use types::{
     step::Step,
 };
 
-use crate::behavior_tree_simulator::{
-    SimulatedBall, SimulationConfig, SimulatorBall, SimulatorClock, SimulatorFallDownState,
-    SimulatorGroundToWorld, SimulatorHeadYaw, SimulatorLastKickTime, SimulatorRobot,
-    SimulatorRobotFrames, SimulatorRobotParameters,
+use crate::{
+    behavior_tree_simulator::{
+        SimulatedBall, SimulationConfig, SimulatorBall, SimulatorClock, SimulatorFallDownState,
+        SimulatorGroundToWorld, SimulatorHeadYaw, SimulatorLastKickTime, SimulatorRobot,
+        SimulatorRobotFrames, SimulatorRobotParameters,
+    },
+    collisions::move_ball_out_of_feet,
 };
 
 pub fn move_robots(
@@ -41,6 +44,7 @@ pub fn move_robots(
         let Some(frame) = robot_frames.0.get(&robot.id()) else {
             continue;
         };
+        let previous_ground_to_world = ground_to_world.ground_to_world;
 
         match &frame.motion_command {
             MotionCommand::Walk { .. } => {
@@ -85,6 +89,14 @@ pub fn move_robots(
             MotionCommand::Damping | MotionCommand::Prepare | MotionCommand::Stand { .. } => {}
         }
 
+        push_ball_on_contact(
+            &mut ball.state,
+            previous_ground_to_world,
+            ground_to_world.ground_to_world,
+            clock.tick_duration,
+            &config,
+        );
+
         head_yaw.yaw = apply_head_motion(
             head_yaw.yaw,
             frame.motion_command.head_motion(),
@@ -192,6 +204,34 @@ fn apply_walk_with_velocity_to_pose<Frame>(
     ground_to_frame * delta
 }
 
+/// Moves the ball out of the robot's feet and lets it bounce off with the robot's velocity
+/// towards it, which allows dribbling by walking into the ball.
+fn push_ball_on_contact(
+    ball: &mut Option<SimulatedBall>,
+    previous_ground_to_world: Isometry2<Ground, World>,
+    ground_to_world: Isometry2<Ground, World>,
+    tick_duration: Duration,
+    config: &SimulationConfig,
+) {
+    let Some(ball) = ball else { return };
+    let Some(normal) = move_ball_out_of_feet(ball, ground_to_world.translation(), config) else {
+        return;
+    };
+
+    let dt = tick_duration.as_secs_f32();
+    if dt <= f32::EPSILON {
+        return;
+    }
+    let robot_velocity =
+        (ground_to_world.translation() - previous_ground_to_world.translation()) / dt;
+    let approach_speed = robot_velocity.dot(&normal);
+    let ball_speed_along_normal = ball.velocity.dot(&normal);
+    let pushed_speed = approach_speed * (1.0 + config.ball_push_restitution);
+    if pushed_speed > ball_speed_along_normal {
+        ball.velocity += normal * (pushed_speed - ball_speed_along_normal);
+    }
+}
+
 fn apply_kick_to_ball(
     now: SystemTime,
     ball: &mut Option<SimulatedBall>,
@@ -357,6 +397,7 @@ mod tests {
                     deceleration_distance: 0.5,
                     ..Default::default()
                 },
+                goal_keeper_number: PlayerNumber::One,
             },
             SimulatorGroundToWorld {
                 ground_to_world: Isometry2::identity(),
@@ -460,6 +501,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn walking_into_ball_pushes_it_forward() {
+        let config = SimulationConfig::default();
+        let mut ball = Some(SimulatedBall {
+            position: point![0.19, 0.0],
+            velocity: vector![0.0, 0.0],
+            field_side: Side::Left,
+        });
+
+        push_ball_on_contact(
+            &mut ball,
+            Isometry2::identity(),
+            Isometry2::from_parts(vector![0.01, 0.0], 0.0),
+            DEFAULT_TICK_DURATION,
+            &config,
+        );
+
+        let ball = ball.expect("ball should still exist");
+        assert!(ball.position.x() >= 0.01 + config.ball_contact_radius + config.ball_radius - 1e-4);
+        assert!(ball.velocity.x() > 1.0);
+        assert_eq!(ball.velocity.y(), 0.0);
+    }
+
+    #[test]
+    fn ball_out_of_reach_is_not_pushed() {
+        let mut ball = Some(SimulatedBall {
+            position: point![0.5, 0.0],
+            velocity: vector![0.0, 0.0],
+            field_side: Side::Left,
+        });
+
+        push_ball_on_contact(
+            &mut ball,
+            Isometry2::identity(),
+            Isometry2::from_parts(vector![0.01, 0.0], 0.0),
+            DEFAULT_TICK_DURATION,
+            &SimulationConfig::default(),
+        );
+
+        let ball = ball.expect("ball should still exist");
+        assert_eq!(ball.position, point![0.5, 0.0]);
+        assert_eq!(ball.velocity, vector![0.0, 0.0]);
+    }
+
     #[test]
     fn head_motion_is_rate_limited() {
         let config = SimulationConfig {
@@ -486,6 +571,8 @@ mod tests {
         NodeTrace {
             name: String::new(),
             status: Status::Success,
+            child_index: 0,
+            duration: Duration::ZERO,
             children: Vec::new(),
         }
     }
@@ -2,15 +2,24 @@
 
 mod auto_referee;
 mod ball;
+mod batch;
 mod behavior_runtime;
 pub mod behavior_tree_simulator;
+mod collisions;
 mod communication;
 mod config;
 mod coordinates;
 mod game_controller;
 mod invariant_checks;
 mod kinematics;
+mod network;
+mod opponent_policies;
+mod perception;
 mod robot;
+mod scenario_file;
+#[cfg(feature = "software_in_the_loop")]
+mod software_in_the_loop;
 mod timeline;
+mod timeline_file;
 pub mod timeline_viewer;
 mod world_states;
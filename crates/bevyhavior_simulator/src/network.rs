@@ -0,0 +1,258 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use rand_distr::Exp1;
+use types::game_controller_state::GameControllerState;
+
+use crate::{
+    behavior_tree_simulator::{
+        SimulationConfig, SimulatorClock, SimulatorGameState, SimulatorIncomingMessage,
+        SimulatorRobot, SimulatorRobotId,
+    },
+    config::{Jitter, LinkImpairment, NetworkConfig},
+};
+
+/// Packets on their way and the state of the Wi-Fi.
+#[derive(Resource, Default)]
+pub struct SimulatorNetwork {
+    random_number_generator: Option<ChaChaRng>,
+    outage_end: Option<SystemTime>,
+    in_flight: Vec<SimulatorIncomingMessage>,
+    last_game_controller_packet: Option<SystemTime>,
+    /// Last GameController state every robot received
+    pub game_controller_states: BTreeMap<SimulatorRobotId, GameControllerState>,
+}
+
+impl SimulatorNetwork {
+    pub fn is_down(&self, now: SystemTime) -> bool {
+        self.outage_end.is_some_and(|outage_end| now < outage_end)
+    }
+
+    /// Starts a new outage at random if none is ongoing.
+    pub fn update_outage(&mut self, config: &NetworkConfig, now: SystemTime, cycle_time: Duration) {
+        let Some(outages) = config.outages else {
+            return;
+        };
+        if self.is_down(now) {
+            return;
+        }
+        let probability = cycle_time.as_secs_f32() / outages.mean_time_between.as_secs_f32();
+        if self.chance(config.seed, probability) {
+            let duration = outages
+                .mean_duration
+                .mul_f32(self.random_number_generator(config.seed).sample(Exp1));
+            self.outage_end = Some(now + duration);
+        }
+    }
+
+    /// Loses or schedules a message. `received_at` of the message is its send time.
+    pub fn send(&mut self, mut message: SimulatorIncomingMessage, config: &NetworkConfig) {
+        let link = config
+            .links
+            .get(&(message.sender, message.receiver))
+            .copied()
+            .unwrap_or(config.hsl);
+        if self.is_down(message.received_at) || self.chance(config.seed, link.packet_loss) {
+            return;
+        }
+        message.received_at += link.latency + self.jitter(&link, config.seed);
+        self.in_flight.push(message);
+    }
+
+    /// Removes all messages that arrived until `now` in order of arrival.
+    pub fn deliver(&mut self, now: SystemTime) -> Vec<SimulatorIncomingMessage> {
+        let (mut arrived, in_flight): (Vec<_>, Vec<_>) = self
+            .in_flight
+            .drain(..)
+            .partition(|message| message.received_at <= now);
+        self.in_flight = in_flight;
+        arrived.sort_by_key(|message| message.received_at);
+        arrived
+    }
+
+    /// Whether the GameController sends a packet at `now`.
+    pub fn is_game_controller_packet_sent(
+        &mut self,
+        config: &NetworkConfig,
+        now: SystemTime,
+    ) -> bool {
+        let Some(interval) = config.game_controller_packet_interval else {
+            return true;
+        };
+        if self
+            .last_game_controller_packet
+            .is_some_and(|sent_at| now < sent_at + interval)
+        {
+            return false;
+        }
+        self.last_game_controller_packet = Some(now);
+        true
+    }
+
+    /// Whether a sent GameController packet gets lost on its way to a robot.
+    pub fn is_game_controller_packet_lost(
+        &mut self,
+        config: &NetworkConfig,
+        now: SystemTime,
+    ) -> bool {
+        self.is_down(now) || self.chance(config.seed, config.game_controller_packet_loss)
+    }
+
+    fn jitter(&mut self, link: &LinkImpairment, seed: u64) -> Duration {
+        match link.jitter {
+            Jitter::None => Duration::ZERO,
+            Jitter::Uniform { maximum } => {
+                maximum.mul_f32(self.random_number_generator(seed).random::<f32>())
+            }
+            Jitter::Exponential { mean } => {
+                mean.mul_f32(self.random_number_generator(seed).sample(Exp1))
+            }
+        }
+    }
+
+    fn chance(&mut self, seed: u64, probability: f32) -> bool {
+        self.random_number_generator(seed)
+            .random_bool(f64::from(probability.clamp(0.0, 1.0)))
+    }
+
+    fn random_number_generator(&mut self, seed: u64) -> &mut ChaChaRng {
+        self.random_number_generator
+            .get_or_insert_with(|| ChaChaRng::seed_from_u64(seed))
+    }
+}
+
+/// Hands the GameController state to every robot that receives the packet sent in this tick, if
+/// any. Robots start with the initial state.
+pub fn receive_game_controller_packets(
+    clock: Res<SimulatorClock>,
+    config: Res<SimulationConfig>,
+    game_state: Res<SimulatorGameState>,
+    robots: Query<&SimulatorRobot>,
+    mut network: ResMut<SimulatorNetwork>,
+) {
+    network.update_outage(&config.network, clock.now, clock.tick_duration);
+    let is_packet_sent = network.is_game_controller_packet_sent(&config.network, clock.now);
+
+    let mut robot_ids: Vec<_> = robots.iter().map(|robot| robot.id()).collect();
+    robot_ids.sort();
+    for robot_id in robot_ids {
+        let has_state = network.game_controller_states.contains_key(&robot_id);
+        if !has_state
+            || (is_packet_sent
+                && !network.is_game_controller_packet_lost(&config.network, clock.now))
+        {
+            network
+                .game_controller_states
+                .insert(robot_id, game_state.game_controller_state.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hsl_network_messages::{HulkMessage, PlayerNumber, Team};
+    use types::messages::IncomingMessage;
+
+    use super::*;
+
+    fn message(sent_at: SystemTime) -> SimulatorIncomingMessage {
+        SimulatorIncomingMessage {
+            receiver: SimulatorRobotId::new(Team::Hulks, PlayerNumber::Four),
+            sender: SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three),
+            message: IncomingMessage::Hsl(HulkMessage::State(Default::default())),
+            received_at: sent_at,
+        }
+    }
+
+    #[test]
+    fn messages_arrive_after_latency() {
+        let mut network = SimulatorNetwork::default();
+        let config = NetworkConfig {
+            hsl: LinkImpairment {
+                latency: Duration::from_millis(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let sent_at = SystemTime::UNIX_EPOCH;
+
+        network.send(message(sent_at), &config);
+
+        assert!(
+            network
+                .deliver(sent_at + Duration::from_millis(50))
+                .is_empty()
+        );
+        let arrived = network.deliver(sent_at + Duration::from_millis(100));
+        assert_eq!(arrived.len(), 1);
+        assert_eq!(arrived[0].received_at, sent_at + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn packet_loss_is_reproducible_from_seed() {
+        let arrivals = |seed| {
+            let mut network = SimulatorNetwork::default();
+            let config = NetworkConfig::competition(seed);
+            (0..100)
+                .map(|tick| {
+                    let now = SystemTime::UNIX_EPOCH + Duration::from_millis(10 * tick);
+                    network.send(message(now), &config);
+                    network.deliver(now).len()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(arrivals(3), arrivals(3));
+        assert_ne!(arrivals(3), arrivals(4));
+    }
+
+    #[test]
+    fn game_controller_packets_are_sent_at_interval() {
+        let mut network = SimulatorNetwork::default();
+        let config = NetworkConfig::competition(0);
+
+        let sent_at: Vec<_> = (0..200)
+            .map(|tick| SystemTime::UNIX_EPOCH + Duration::from_millis(10 * tick))
+            .filter(|now| network.is_game_controller_packet_sent(&config, *now))
+            .collect();
+
+        assert_eq!(
+            sent_at,
+            [0, 500, 1000, 1500]
+                .map(|milliseconds| SystemTime::UNIX_EPOCH + Duration::from_millis(milliseconds))
+        );
+
+        let mut network = SimulatorNetwork::default();
+        let config = NetworkConfig::default();
+        assert!((0..10).all(|tick| {
+            network.is_game_controller_packet_sent(
+                &config,
+                SystemTime::UNIX_EPOCH + Duration::from_millis(10 * tick),
+            )
+        }));
+    }
+
+    #[test]
+    fn outages_drop_all_packets() {
+        let mut network = SimulatorNetwork {
+            outage_end: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let config = NetworkConfig::default();
+
+        network.send(message(SystemTime::UNIX_EPOCH), &config);
+        assert!(network.is_game_controller_packet_lost(&config, SystemTime::UNIX_EPOCH));
+        network.send(
+            message(SystemTime::UNIX_EPOCH + Duration::from_secs(1)),
+            &config,
+        );
+
+        let arrived = network.deliver(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        assert_eq!(arrived.len(), 1);
+    }
+}
@@ -0,0 +1,728 @@
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail},
+};
+use coordinate_systems::{Field, Ground};
+use hsl_network_messages::{PlayerNumber, Team};
+use linear_algebra::{Isometry2, Orientation2, Point2, Vector2, point};
+use serde::Deserialize;
+use types::{
+    behavior_tree::{NodeTrace, Status},
+    field_dimensions::FieldDimensions,
+    motion_command::{HeadMotion, KickPower, MotionCommand},
+    primary_state::PrimaryState,
+    world_state::WorldState,
+};
+
+use crate::{
+    behavior_tree_simulator::{
+        RobotFrame, SimulationConfig, SimulatorBall, SimulatorClock, SimulatorFieldDimensions,
+        SimulatorGameState, SimulatorGroundToWorld, SimulatorPrimaryState, SimulatorRobot,
+        SimulatorRobotFrames, SimulatorWorldStates,
+    },
+    coordinates::{ground_to_field_from_world, point_world_to_field},
+    game_controller::global_field_side_for_team,
+};
+
+const DEFAULT_SUPPORT_DISTANCE: f32 = 1.5;
+const GOAL_KEEPER_DISTANCE_TO_GOAL_LINE: f32 = 0.25;
+
+/// Replaces the behavior tree for all robots of a team, e.g. to play against something other
+/// than a mirror of our own behavior.
+pub trait OpponentPolicy: Send + Sync {
+    /// Shown as root of the behavior trace in the timeline viewer
+    fn name(&self) -> &'static str;
+
+    /// Robots without a motion command stand
+    fn motion_commands(
+        &mut self,
+        input: &OpponentPolicyInput,
+    ) -> BTreeMap<PlayerNumber, MotionCommand>;
+}
+
+/// Ground truth of the match in field coordinates of the controlled team, i.e. its own goal is
+/// at negative x.
+pub struct OpponentPolicyInput<'a> {
+    /// Time since the start of the simulation
+    pub elapsed: Duration,
+    pub tick_duration: Duration,
+    pub config: &'a SimulationConfig,
+    pub field_dimensions: &'a FieldDimensions,
+    pub ball: Option<Point2<Field>>,
+    /// Robots of the team which are playing, robots in any other primary state stand
+    pub robots: BTreeMap<PlayerNumber, Isometry2<Ground, Field>>,
+}
+
+impl OpponentPolicyInput<'_> {
+    pub fn opponent_goal_center(&self) -> Point2<Field> {
+        point![self.field_dimensions.length / 2.0, 0.0]
+    }
+
+    /// Walks towards the pose as fast as the simulated walk allows, reaching it within one tick if
+    /// it is close enough.
+    pub fn walk_to(
+        &self,
+        ground_to_field: Isometry2<Ground, Field>,
+        position: Point2<Field>,
+        orientation: Orientation2<Field>,
+    ) -> MotionCommand {
+        let dt = self.tick_duration.as_secs_f32();
+        let scale = self.config.walk_with_velocity_scale;
+        if dt <= f32::EPSILON || scale <= f32::EPSILON {
+            return stand();
+        }
+
+        let target = (ground_to_field.inverse() * position).coords();
+        let distance = target.norm();
+        let velocity = if distance > f32::EPSILON {
+            target * ((distance / dt).min(self.config.walk_translation_speed) / distance)
+        } else {
+            Vector2::zeros()
+        };
+        let turn = ground_to_field
+            .orientation()
+            .rotation_to(orientation)
+            .angle();
+        let maximum_turn_speed = self.config.walk_rotation_speed;
+        let angular_velocity = (turn / dt).clamp(-maximum_turn_speed, maximum_turn_speed);
+
+        MotionCommand::WalkWithVelocity {
+            head: HeadMotion::ZeroAngles,
+            velocity: velocity / scale,
+            angular_velocity: angular_velocity / scale,
+        }
+    }
+}
+
+/// Robots stand wherever they were placed.
+pub struct StationaryWallPolicy;
+
+impl OpponentPolicy for StationaryWallPolicy {
+    fn name(&self) -> &'static str {
+        "stationary_wall"
+    }
+
+    fn motion_commands(
+        &mut self,
+        _: &OpponentPolicyInput,
+    ) -> BTreeMap<PlayerNumber, MotionCommand> {
+        BTreeMap::new()
+    }
+}
+
+/// Every robot runs for the ball and pokes it towards the opponent goal.
+pub struct BallChaserSwarmPolicy;
+
+impl OpponentPolicy for BallChaserSwarmPolicy {
+    fn name(&self) -> &'static str {
+        "ball_chaser_swarm"
+    }
+
+    fn motion_commands(
+        &mut self,
+        input: &OpponentPolicyInput,
+    ) -> BTreeMap<PlayerNumber, MotionCommand> {
+        let Some(ball) = input.ball else {
+            return BTreeMap::new();
+        };
+        input
+            .robots
+            .iter()
+            .map(|(&player_number, &ground_to_field)| {
+                (
+                    player_number,
+                    input.kick(
+                        ground_to_field,
+                        ball,
+                        input.opponent_goal_center(),
+                        KickPower::Rumpelstilzchen,
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The robot closest to the ball shoots at the opponent goal with full power from wherever it
+/// gets the ball, the others push up behind the ball in their lanes.
+pub struct LongShotPolicy {
+    /// Distance in x the supporting robots keep behind the ball
+    pub support_distance: f32,
+}
+
+impl OpponentPolicy for LongShotPolicy {
+    fn name(&self) -> &'static str {
+        "long_shot"
+    }
+
+    fn motion_commands(
+        &mut self,
+        input: &OpponentPolicyInput,
+    ) -> BTreeMap<PlayerNumber, MotionCommand> {
+        let Some(ball) = input.ball else {
+            return BTreeMap::new();
+        };
+        let shooter = input
+            .robots
+            .iter()
+            .min_by(|(_, left), (_, right)| {
+                let left = (left.translation() - ball).norm();
+                let right = (right.translation() - ball).norm();
+                left.total_cmp(&right)
+            })
+            .map(|(&player_number, _)| player_number);
+
+        input
+            .robots
+            .iter()
+            .map(|(&player_number, &ground_to_field)| {
+                let motion_command = if Some(player_number) == shooter {
+                    input.kick(
+                        ground_to_field,
+                        ball,
+                        input.opponent_goal_center(),
+                        KickPower::Schlong,
+                    )
+                } else {
+                    input.walk_to(
+                        ground_to_field,
+                        point![
+                            ball.x() - self.support_distance,
+                            ground_to_field.translation().y()
+                        ],
+                        Orientation2::identity(),
+                    )
+                };
+                (player_number, motion_command)
+            })
+            .collect()
+    }
+}
+
+/// Only the goal keeper plays: it stays on its goal line between ball and goal and clears balls
+/// entering the penalty area. Field players stand.
+pub struct GoalKeeperOnlyPolicy {
+    pub goal_keeper: PlayerNumber,
+}
+
+impl OpponentPolicy for GoalKeeperOnlyPolicy {
+    fn name(&self) -> &'static str {
+        "goal_keeper_only"
+    }
+
+    fn motion_commands(
+        &mut self,
+        input: &OpponentPolicyInput,
+    ) -> BTreeMap<PlayerNumber, MotionCommand> {
+        let Some(&ground_to_field) = input.robots.get(&self.goal_keeper) else {
+            return BTreeMap::new();
+        };
+        let field_dimensions = input.field_dimensions;
+        let own_goal_line = -field_dimensions.length / 2.0;
+        let goal_line_position = |y: f32| {
+            point![
+                own_goal_line + GOAL_KEEPER_DISTANCE_TO_GOAL_LINE,
+                y.clamp(
+                    -field_dimensions.goal_inner_width / 2.0,
+                    field_dimensions.goal_inner_width / 2.0
+                )
+            ]
+        };
+
+        let motion_command = match input.ball {
+            Some(ball)
+                if ball.x() < own_goal_line + field_dimensions.penalty_area_length
+                    && ball.y().abs() < field_dimensions.penalty_area_width / 2.0 =>
+            {
+                input.kick(
+                    ground_to_field,
+                    ball,
+                    input.opponent_goal_center(),
+                    KickPower::Schlong,
+                )
+            }
+            Some(ball) => input.walk_to(
+                ground_to_field,
+                goal_line_position(ball.y()),
+                Orientation2::identity(),
+            ),
+            None => input.walk_to(
+                ground_to_field,
+                goal_line_position(0.0),
+                Orientation2::identity(),
+            ),
+        };
+        BTreeMap::from([(self.goal_keeper, motion_command)])
+    }
+}
+
+/// A recorded pose of a replayed robot, in field coordinates of its team.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayKeyframe {
+    pub at_seconds: f32,
+    pub player_number: PlayerNumber,
+    pub position: Point2<Field>,
+    #[serde(default)]
+    pub orientation: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReplayFile {
+    keyframes: Vec<ReplayKeyframe>,
+}
+
+/// Robots follow recorded poses, e.g. of a real opponent extracted from a game log. Poses
+/// between keyframes are interpolated linearly, before the first and after the last keyframe a
+/// robot holds that pose. Robots walk as fast as the simulated walk allows, so they lag behind
+/// recordings that are faster. Robots without keyframes stand.
+pub struct ReplayPolicy {
+    keyframes: BTreeMap<PlayerNumber, Vec<ReplayKeyframe>>,
+}
+
+impl ReplayPolicy {
+    pub fn new(keyframes: impl IntoIterator<Item = ReplayKeyframe>) -> Self {
+        let mut keyframes_by_robot: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for keyframe in keyframes {
+            keyframes_by_robot
+                .entry(keyframe.player_number)
+                .or_default()
+                .push(keyframe);
+        }
+        for keyframes in keyframes_by_robot.values_mut() {
+            keyframes.sort_by(|left, right| left.at_seconds.total_cmp(&right.at_seconds));
+        }
+        Self {
+            keyframes: keyframes_by_robot,
+        }
+    }
+
+    /// Reads a recording with a `keyframes` list, TOML files are detected by their extension,
+    /// everything else is read as JSON5.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let recording: ReplayFile = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&file).wrap_err_with(|| format!("failed to parse {}", path.display()))?
+        } else {
+            json5::from_str(&file)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?
+        };
+        if recording.keyframes.is_empty() {
+            bail!("{} contains no keyframes", path.display());
+        }
+        Ok(Self::new(recording.keyframes))
+    }
+
+    pub fn pose_at(
+        &self,
+        player_number: PlayerNumber,
+        elapsed: Duration,
+    ) -> Option<(Point2<Field>, Orientation2<Field>)> {
+        let keyframes = self.keyframes.get(&player_number)?;
+        let time = elapsed.as_secs_f32();
+        let next = keyframes.partition_point(|keyframe| keyframe.at_seconds <= time);
+        let (from, to) = match next {
+            0 => (keyframes.first()?, keyframes.first()?),
+            next if next == keyframes.len() => (keyframes.last()?, keyframes.last()?),
+            next => (&keyframes[next - 1], &keyframes[next]),
+        };
+
+        let duration = to.at_seconds - from.at_seconds;
+        let fraction = if duration > f32::EPSILON {
+            (time - from.at_seconds) / duration
+        } else {
+            0.0
+        };
+        Some((
+            from.position.lerp(to.position, fraction),
+            Orientation2::new(from.orientation).slerp(Orientation2::new(to.orientation), fraction),
+        ))
+    }
+}
+
+impl OpponentPolicy for ReplayPolicy {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn motion_commands(
+        &mut self,
+        input: &OpponentPolicyInput,
+    ) -> BTreeMap<PlayerNumber, MotionCommand> {
+        input
+            .robots
+            .iter()
+            .filter_map(|(&player_number, &ground_to_field)| {
+                let (position, orientation) = self.pose_at(player_number, input.elapsed)?;
+                Some((
+                    player_number,
+                    input.walk_to(ground_to_field, position, orientation),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Selects a built-in policy in scenario files.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum OpponentPolicyConfig {
+    StationaryWall,
+    BallChaserSwarm,
+    LongShot {
+        #[serde(default = "default_support_distance")]
+        support_distance: f32,
+    },
+    GoalKeeperOnly {
+        #[serde(default = "default_goal_keeper")]
+        goal_keeper: PlayerNumber,
+    },
+    Replay {
+        path: PathBuf,
+    },
+}
+
+fn default_support_distance() -> f32 {
+    DEFAULT_SUPPORT_DISTANCE
+}
+
+fn default_goal_keeper() -> PlayerNumber {
+    PlayerNumber::One
+}
+
+impl OpponentPolicyConfig {
+    /// Fails if the recording of a replay cannot be loaded.
+    pub fn policy(&self) -> Result<Box<dyn OpponentPolicy>> {
+        Ok(match self {
+            OpponentPolicyConfig::StationaryWall => Box::new(StationaryWallPolicy),
+            OpponentPolicyConfig::BallChaserSwarm => Box::new(BallChaserSwarmPolicy),
+            OpponentPolicyConfig::LongShot { support_distance } => Box::new(LongShotPolicy {
+                support_distance: *support_distance,
+            }),
+            OpponentPolicyConfig::GoalKeeperOnly { goal_keeper } => {
+                Box::new(GoalKeeperOnlyPolicy {
+                    goal_keeper: *goal_keeper,
+                })
+            }
+            OpponentPolicyConfig::Replay { path } => Box::new(ReplayPolicy::load(path)?),
+        })
+    }
+}
+
+/// Policies per team, teams without one run the behavior tree.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpponentPoliciesConfig {
+    pub hulks: Option<OpponentPolicyConfig>,
+    pub opponent: Option<OpponentPolicyConfig>,
+}
+
+impl OpponentPoliciesConfig {
+    /// Makes relative replay paths relative to the given directory instead of the working
+    /// directory.
+    pub fn resolve_paths(&mut self, directory: &Path) {
+        for config in [&mut self.hulks, &mut self.opponent].into_iter().flatten() {
+            if let OpponentPolicyConfig::Replay { path } = config
+                && path.is_relative()
+            {
+                *path = directory.join(&*path);
+            }
+        }
+    }
+
+    pub fn policies(&self) -> Result<SimulatorOpponentPolicies> {
+        Ok(SimulatorOpponentPolicies {
+            hulks: self
+                .hulks
+                .as_ref()
+                .map(OpponentPolicyConfig::policy)
+                .transpose()?,
+            opponent: self
+                .opponent
+                .as_ref()
+                .map(OpponentPolicyConfig::policy)
+                .transpose()?,
+        })
+    }
+}
+
+/// Robots of a team with a policy are controlled by it instead of their behavior tree and do not
+/// communicate.
+#[derive(Resource, Default)]
+pub struct SimulatorOpponentPolicies {
+    pub hulks: Option<Box<dyn OpponentPolicy>>,
+    pub opponent: Option<Box<dyn OpponentPolicy>>,
+}
+
+impl SimulatorOpponentPolicies {
+    pub fn controls(&self, team: Team) -> bool {
+        match team {
+            Team::Hulks => self.hulks.is_some(),
+            Team::Opponent => self.opponent.is_some(),
+        }
+    }
+
+    fn policy_mut(&mut self, team: Team) -> Option<&mut dyn OpponentPolicy> {
+        match team {
+            Team::Hulks => self.hulks.as_deref_mut(),
+            Team::Opponent => self.opponent.as_deref_mut(),
+        }
+    }
+}
+
+fn stand() -> MotionCommand {
+    MotionCommand::Stand {
+        head: HeadMotion::ZeroAngles,
+    }
+}
+
+fn policy_frame(name: &str, world_state: WorldState, motion_command: MotionCommand) -> RobotFrame {
+    let trace = NodeTrace {
+        name: name.to_string(),
+        status: Status::Running,
+        child_index: 0,
+        duration: Duration::ZERO,
+        children: Vec::new(),
+    };
+    RobotFrame {
+        world_state,
+        motion_command,
+        static_layout: trace.clone(),
+        trace,
+        path_obstacles: Vec::new(),
+        time_since_last_switch: Duration::ZERO,
+        direction_difference: 0.0,
+        walk_position: None,
+        voronoi_map: None,
+        voronoi_inputs: Vec::new(),
+        outgoing_messages: Vec::new(),
+    }
+}
+
+pub fn tick_opponent_policies(
+    clock: Res<SimulatorClock>,
+    config: Res<SimulationConfig>,
+    field_dimensions: Res<SimulatorFieldDimensions>,
+    game_state: Res<SimulatorGameState>,
+    ball: Res<SimulatorBall>,
+    world_states: Res<SimulatorWorldStates>,
+    mut policies: ResMut<SimulatorOpponentPolicies>,
+    mut robot_frames: ResMut<SimulatorRobotFrames>,
+    robots: Query<(
+        &SimulatorRobot,
+        &SimulatorGroundToWorld,
+        &SimulatorPrimaryState,
+    )>,
+) {
+    let elapsed = clock
+        .now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("simulator time should not move backwards");
+
+    for team in [Team::Hulks, Team::Opponent] {
+        let Some(policy) = policies.policy_mut(team) else {
+            continue;
+        };
+        let global_field_side = global_field_side_for_team(&game_state.game_controller_state, team);
+        let input = OpponentPolicyInput {
+            elapsed,
+            tick_duration: clock.tick_duration,
+            config: &config,
+            field_dimensions: &field_dimensions.0,
+            ball: ball
+                .state
+                .map(|ball| point_world_to_field(ball.position, global_field_side)),
+            robots: robots
+                .iter()
+                .filter(|(robot, _, primary_state)| {
+                    robot.team == team && primary_state.primary_state == PrimaryState::Playing
+                })
+                .map(|(robot, ground_to_world, _)| {
+                    (
+                        robot.player_number,
+                        ground_to_field_from_world(
+                            ground_to_world.ground_to_world,
+                            global_field_side,
+                        ),
+                    )
+                })
+                .collect(),
+        };
+
+        let mut motion_commands = policy.motion_commands(&input);
+        for (robot, _, _) in robots.iter().filter(|(robot, ..)| robot.team == team) {
+            let robot_id = robot.id();
+            let Some(world_state) = world_states.0.get(&robot_id) else {
+                continue;
+            };
+            let motion_command = motion_commands
+                .remove(&robot.player_number)
+                .unwrap_or_else(stand);
+            robot_frames.0.insert(
+                robot_id,
+                policy_frame(policy.name(), world_state.clone(), motion_command),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use linear_algebra::{Isometry2, vector};
+
+    use crate::behavior_tree_simulator::DEFAULT_TICK_DURATION;
+
+    use super::*;
+
+    fn input<'a>(
+        config: &'a SimulationConfig,
+        ball: Option<Point2<Field>>,
+        robots: impl IntoIterator<Item = (PlayerNumber, Isometry2<Ground, Field>)>,
+    ) -> OpponentPolicyInput<'a> {
+        OpponentPolicyInput {
+            elapsed: Duration::from_secs(1),
+            tick_duration: DEFAULT_TICK_DURATION,
+            config,
+            field_dimensions: &FieldDimensions::SPL_2025,
+            ball,
+            robots: robots.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn ball_chasers_all_go_for_the_ball() {
+        let config = SimulationConfig::default();
+        let input = input(
+            &config,
+            Some(point![1.0, 0.0]),
+            [
+                (
+                    PlayerNumber::One,
+                    Isometry2::from_parts(vector![-2.0, 1.0], 0.0),
+                ),
+                (
+                    PlayerNumber::Two,
+                    Isometry2::from_parts(vector![-2.0, -1.0], 0.0),
+                ),
+            ],
+        );
+
+        let motion_commands = BallChaserSwarmPolicy.motion_commands(&input);
+
+        assert_eq!(motion_commands.len(), 2);
+        assert!(motion_commands.values().all(|motion_command| matches!(
+            motion_command,
+            MotionCommand::VisualKick {
+                kick_power: KickPower::Rumpelstilzchen,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn goal_keeper_stays_inside_goal_width() {
+        let config = SimulationConfig::default();
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let goal_line = -field_dimensions.length / 2.0 + GOAL_KEEPER_DISTANCE_TO_GOAL_LINE;
+        let input = input(
+            &config,
+            Some(point![0.0, field_dimensions.width / 2.0]),
+            [
+                (
+                    PlayerNumber::One,
+                    Isometry2::from_parts(vector![goal_line, 0.0], 0.0),
+                ),
+                (
+                    PlayerNumber::Two,
+                    Isometry2::from_parts(vector![-1.0, 0.0], 0.0),
+                ),
+            ],
+        );
+
+        let motion_commands = GoalKeeperOnlyPolicy {
+            goal_keeper: PlayerNumber::One,
+        }
+        .motion_commands(&input);
+
+        assert_eq!(motion_commands.len(), 1);
+        let MotionCommand::WalkWithVelocity { velocity, .. } = motion_commands[&PlayerNumber::One]
+        else {
+            panic!("goal keeper should walk");
+        };
+        assert_relative_eq!(velocity.x(), 0.0, epsilon = 1e-4);
+        assert!(velocity.y() > 0.0);
+    }
+
+    #[test]
+    fn replay_interpolates_between_keyframes_and_holds_the_ends() {
+        let policy = ReplayPolicy::new([
+            ReplayKeyframe {
+                at_seconds: 2.0,
+                player_number: PlayerNumber::Two,
+                position: point![2.0, 1.0],
+                orientation: 0.0,
+            },
+            ReplayKeyframe {
+                at_seconds: 1.0,
+                player_number: PlayerNumber::Two,
+                position: point![0.0, 1.0],
+                orientation: 0.0,
+            },
+        ]);
+
+        let (position, _) = policy
+            .pose_at(PlayerNumber::Two, Duration::from_millis(1500))
+            .unwrap();
+        assert_relative_eq!(position, point![1.0, 1.0]);
+        let (position, _) = policy.pose_at(PlayerNumber::Two, Duration::ZERO).unwrap();
+        assert_relative_eq!(position, point![0.0, 1.0]);
+        let (position, _) = policy
+            .pose_at(PlayerNumber::Two, Duration::from_secs(5))
+            .unwrap();
+        assert_relative_eq!(position, point![2.0, 1.0]);
+        assert!(policy.pose_at(PlayerNumber::One, Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn replay_walks_towards_the_recorded_pose_at_walking_speed() {
+        let config = SimulationConfig::default();
+        let mut policy = ReplayPolicy::new([ReplayKeyframe {
+            at_seconds: 0.0,
+            player_number: PlayerNumber::Three,
+            position: point![1.0, 0.0],
+            orientation: 0.0,
+        }]);
+        let input = input(
+            &config,
+            None,
+            [(PlayerNumber::Three, Isometry2::identity())],
+        );
+
+        let motion_commands = policy.motion_commands(&input);
+
+        let MotionCommand::WalkWithVelocity { velocity, .. } =
+            motion_commands[&PlayerNumber::Three]
+        else {
+            panic!("replayed robot should walk");
+        };
+        assert_relative_eq!(
+            velocity * config.walk_with_velocity_scale,
+            vector![config.walk_translation_speed, 0.0],
+            epsilon = 1e-4
+        );
+    }
+}
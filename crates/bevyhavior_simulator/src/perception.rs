@@ -0,0 +1,328 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use coordinate_systems::{Field, Ground, World};
+use hsl_network_messages::Team;
+use linear_algebra::{Isometry2, Orientation2, Point2, Vector2, point, vector};
+use nalgebra::Matrix2;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use rand_distr::StandardNormal;
+use types::{field_dimensions::Side, world_state::BallState};
+
+use crate::{
+    behavior_tree_simulator::{SimulatorObstacle, SimulatorRobotId},
+    config::{LocalizationDrift, PositionNoise, SimulationConfig},
+};
+
+/// Per robot state of the perception errors. Every robot draws from its own random stream, so the
+/// perception of a robot does not depend on the order robots are processed in.
+#[derive(Resource, Default)]
+pub struct SimulatorPerception {
+    robots: BTreeMap<SimulatorRobotId, RobotPerception>,
+}
+
+impl SimulatorPerception {
+    pub fn robot(&mut self, robot_id: SimulatorRobotId, seed: u64) -> &mut RobotPerception {
+        self.robots
+            .entry(robot_id)
+            .or_insert_with(|| RobotPerception::new(robot_id, seed))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Detections {
+    pub time: SystemTime,
+    pub ball: Option<BallState>,
+    pub obstacles: Vec<SimulatorObstacle>,
+}
+
+pub struct RobotPerception {
+    random_number_generator: ChaChaRng,
+    translation_error: Vector2<Field>,
+    rotation_error: f32,
+    pending: VecDeque<Detections>,
+}
+
+impl RobotPerception {
+    fn new(robot_id: SimulatorRobotId, seed: u64) -> Self {
+        let mut random_number_generator = ChaChaRng::seed_from_u64(seed);
+        let team = match robot_id.team {
+            Team::Hulks => 0,
+            Team::Opponent => 1,
+        };
+        random_number_generator.set_stream((team << 8) | robot_id.player_number as u64);
+        Self {
+            random_number_generator,
+            translation_error: Vector2::zeros(),
+            rotation_error: 0.0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Advances the localization error and applies it to the true `ground_to_field`.
+    pub fn localize(
+        &mut self,
+        ground_to_field: Isometry2<Ground, Field>,
+        drift: &LocalizationDrift,
+        cycle_time: Duration,
+    ) -> Isometry2<Ground, Field> {
+        let cycle_time = cycle_time.as_secs_f32();
+        let correction = (1.0 - drift.correction_per_second * cycle_time).clamp(0.0, 1.0);
+        let translation_step = drift.translation_per_second * cycle_time.sqrt();
+        let rotation_step = drift.rotation_per_second * cycle_time.sqrt();
+
+        self.translation_error =
+            self.translation_error * correction + self.normal_vector() * translation_step;
+        self.rotation_error = self.rotation_error * correction + self.normal() * rotation_step;
+
+        Isometry2::<Field, Field>::from_parts(self.translation_error, self.rotation_error)
+            * ground_to_field
+    }
+
+    /// Turns the objects visible to the robot into noisy detections, including misses and
+    /// phantoms.
+    pub fn detect(
+        &mut self,
+        visible_ball: Option<BallState>,
+        visible_robots: Vec<SimulatorObstacle>,
+        ground_to_world: Isometry2<Ground, World>,
+        ground_to_field: Isometry2<Ground, Field>,
+        head_yaw: Orientation2<Ground>,
+        now: SystemTime,
+        config: &SimulationConfig,
+    ) -> Detections {
+        let perception = &config.perception;
+
+        let ball = match visible_ball {
+            Some(_) if self.chance(perception.ball_false_negative_probability) => None,
+            Some(ball) => Some(ball.ball_in_ground),
+            None if self.chance(perception.ball_false_positive_probability) => {
+                Some(self.position_in_view(head_yaw, config))
+            }
+            None => None,
+        }
+        .map(|ball_in_ground| {
+            let standard_deviation = perception
+                .ball_position_noise
+                .standard_deviation_at(ball_in_ground.coords().norm());
+            let ball_in_ground = ball_in_ground + self.normal_vector() * standard_deviation;
+            let ball_in_field = ground_to_field * ball_in_ground;
+            BallState {
+                ball_in_ground,
+                ball_in_field,
+                ball_in_ground_velocity: visible_ball
+                    .map_or(Vector2::zeros(), |ball| ball.ball_in_ground_velocity),
+                ball_in_ground_covariance: Matrix2::identity() * standard_deviation.powi(2),
+                last_seen_ball: now,
+                field_side: visible_ball.map_or(
+                    if ball_in_field.y() >= 0.0 {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    },
+                    |ball| ball.field_side,
+                ),
+            }
+        });
+
+        let mut obstacles = Vec::new();
+        for obstacle in visible_robots {
+            if self.chance(perception.robot_false_negative_probability) {
+                continue;
+            }
+            let distance = (ground_to_world.inverse() * obstacle.position)
+                .coords()
+                .norm();
+            obstacles.push(self.displace(obstacle, &perception.robot_position_noise, distance));
+        }
+        if self.chance(perception.robot_false_positive_probability) {
+            let position = ground_to_world * self.position_in_view(head_yaw, config);
+            obstacles.push(SimulatorObstacle::robot(
+                position,
+                config.robot_radius,
+                config.robot_radius,
+            ));
+        }
+
+        Detections {
+            time: now,
+            ball,
+            obstacles,
+        }
+    }
+
+    /// Holds back detections until they are older than the latency. Returns the latest detections
+    /// the behavior may know about.
+    pub fn delay(&mut self, detections: Detections, latency: Duration) -> Detections {
+        let now = detections.time;
+        let delivery_time = now.checked_sub(latency).unwrap_or(SystemTime::UNIX_EPOCH);
+        self.pending.push_back(detections);
+        while self
+            .pending
+            .get(1)
+            .is_some_and(|next| next.time <= delivery_time)
+        {
+            self.pending.pop_front();
+        }
+
+        match self.pending.front() {
+            Some(oldest) if oldest.time <= delivery_time => oldest.clone(),
+            _ => Detections {
+                time: now,
+                ball: None,
+                obstacles: Vec::new(),
+            },
+        }
+    }
+
+    fn displace(
+        &mut self,
+        obstacle: SimulatorObstacle,
+        noise: &PositionNoise,
+        distance: f32,
+    ) -> SimulatorObstacle {
+        let offset: Vector2<World> = self.normal_vector() * noise.standard_deviation_at(distance);
+        SimulatorObstacle {
+            position: obstacle.position + offset,
+            ..obstacle
+        }
+    }
+
+    fn position_in_view(
+        &mut self,
+        head_yaw: Orientation2<Ground>,
+        config: &SimulationConfig,
+    ) -> Point2<Ground> {
+        let distance = self.random_number_generator.random_range(
+            config.robot_radius..=config.ball_visibility_range.max(config.robot_radius),
+        );
+        let angle = head_yaw.angle()
+            + self.random_number_generator.random_range(-0.5..0.5)
+                * config.visibility_field_of_view;
+        point![distance * angle.cos(), distance * angle.sin()]
+    }
+
+    fn chance(&mut self, probability: f32) -> bool {
+        self.random_number_generator
+            .random_bool(f64::from(probability.clamp(0.0, 1.0)))
+    }
+
+    fn normal(&mut self) -> f32 {
+        self.random_number_generator.sample(StandardNormal)
+    }
+
+    fn normal_vector<Frame>(&mut self) -> Vector2<Frame> {
+        vector![self.normal(), self.normal()]
+    }
+}
+
+/// Whether any of the occluders lies between the viewer at the origin and the position.
+pub fn is_occluded(
+    position: Point2<Ground>,
+    occluders: &[Point2<Ground>],
+    occluder_radius: f32,
+) -> bool {
+    let distance = position.coords().norm();
+    let Some(direction) = position.coords().try_normalize(f32::EPSILON) else {
+        return false;
+    };
+    occluders.iter().any(|occluder| {
+        let along = occluder.coords().dot(&direction);
+        let across = (occluder.coords() - direction * along).norm();
+        along > 0.0 && along < distance - occluder_radius && across < occluder_radius
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use hsl_network_messages::PlayerNumber;
+
+    use super::*;
+    use crate::config::PerceptionConfig;
+
+    fn noisy_config(seed: u64) -> SimulationConfig {
+        SimulationConfig {
+            perception: PerceptionConfig {
+                seed,
+                ball_position_noise: PositionNoise {
+                    standard_deviation: 0.05,
+                    standard_deviation_per_meter: 0.05,
+                },
+                ball_false_positive_probability: 0.5,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn perceived_balls(config: &SimulationConfig) -> Vec<Option<Point2<Ground>>> {
+        let mut perception = SimulatorPerception::default();
+        let robot_id = SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three);
+        (0..20)
+            .map(|_| {
+                perception
+                    .robot(robot_id, config.perception.seed)
+                    .detect(
+                        None,
+                        Vec::new(),
+                        Isometry2::identity(),
+                        Isometry2::identity(),
+                        Orientation2::identity(),
+                        SystemTime::UNIX_EPOCH,
+                        config,
+                    )
+                    .ball
+                    .map(|ball| ball.ball_in_ground)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn perception_is_reproducible_from_seed() {
+        assert_eq!(
+            perceived_balls(&noisy_config(7)),
+            perceived_balls(&noisy_config(7))
+        );
+        assert_ne!(
+            perceived_balls(&noisy_config(7)),
+            perceived_balls(&noisy_config(8))
+        );
+    }
+
+    #[test]
+    fn detections_arrive_after_latency() {
+        let mut perception = SimulatorPerception::default();
+        let robot = perception.robot(SimulatorRobotId::new(Team::Hulks, PlayerNumber::Three), 0);
+        let detections = |milliseconds, ball: Option<BallState>| Detections {
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(milliseconds),
+            ball,
+            obstacles: Vec::new(),
+        };
+        let latency = Duration::from_millis(100);
+
+        let first = robot.delay(detections(1000, Some(BallState::default())), latency);
+        let second = robot.delay(detections(1050, None), latency);
+        let third = robot.delay(detections(1100, None), latency);
+
+        assert!(first.ball.is_none());
+        assert!(second.ball.is_none());
+        assert!(third.ball.is_some());
+        assert_eq!(
+            third.time,
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1000)
+        );
+    }
+
+    #[test]
+    fn robots_occlude_objects_behind_them() {
+        let occluders = [point![1.0, 0.0]];
+
+        assert!(is_occluded(point![2.0, 0.1], &occluders, 0.25));
+        assert!(!is_occluded(point![0.5, 0.0], &occluders, 0.25));
+        assert!(!is_occluded(point![2.0, 1.0], &occluders, 0.25));
+    }
+}
@@ -2,17 +2,19 @@ use std::time::SystemTime;
 
 use bevy::prelude::*;
 use booster::FallDownState;
-use color_eyre::Result;
+use color_eyre::{Result, eyre::bail};
 use coordinate_systems::{Field, Ground, World};
 use hsl_network_messages::{PlayerNumber, Team};
 use linear_algebra::{Isometry2, Orientation2, Point2};
-use serde::Serializer;
+use serde::{Deserialize, Deserializer, Serializer};
 use types::{
     parameters::{BehaviorParameters, RLWalkingParameters},
     primary_state::PrimaryState,
 };
 
-use crate::behavior_tree_simulator::{SimulatorRobotBehavior, default_walking_parameters};
+use crate::behavior_tree_simulator::{
+    SimulatorRobotBehavior, default_goal_keeper_number, default_walking_parameters,
+};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct SimulatorRobotId {
@@ -76,6 +78,39 @@ impl serde::Serialize for SimulatorRobotId {
     }
 }
 
+impl std::str::FromStr for SimulatorRobotId {
+    type Err = color_eyre::Report;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut characters = text.chars();
+        let team = match characters.next() {
+            Some('H') => Team::Hulks,
+            Some('O') => Team::Opponent,
+            _ => bail!("robot id {text:?} does not start with H or O"),
+        };
+        let player_number = match characters.as_str() {
+            "1" => PlayerNumber::One,
+            "2" => PlayerNumber::Two,
+            "3" => PlayerNumber::Three,
+            "4" => PlayerNumber::Four,
+            "5" => PlayerNumber::Five,
+            _ => bail!("robot id {text:?} does not end with a player number from 1 to 5"),
+        };
+        Ok(Self::new(team, player_number))
+    }
+}
+
+impl<'de> Deserialize<'de> for SimulatorRobotId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Component, Clone, Copy, Debug)]
 pub struct SimulatorRobot {
     pub team: Team,
@@ -107,6 +142,8 @@ pub struct SimulatorPrimaryState {
 pub struct SimulatorRobotParameters {
     pub behavior: BehaviorParameters,
     pub walking: RLWalkingParameters,
+    /// Global parameter the nodes of the robot receive on the `goal_keeper_number` topic
+    pub goal_keeper_number: PlayerNumber,
 }
 
 #[derive(Component, Clone, Copy, Debug, Default)]
@@ -154,10 +191,11 @@ impl SimulatorRobotBundle {
             primary_state: SimulatorPrimaryState {
                 primary_state: PrimaryState::Damping,
             },
-            behavior: SimulatorRobotBehavior::new(parameters.clone()),
+            behavior: SimulatorRobotBehavior::new(parameters.clone())?,
             parameters: SimulatorRobotParameters {
                 behavior: parameters,
                 walking: default_walking_parameters()?,
+                goal_keeper_number: default_goal_keeper_number()?,
             },
             fall_down_state: SimulatorFallDownState::default(),
             suggested_search_position: SimulatorSuggestedSearchPosition::default(),
@@ -171,4 +209,9 @@ impl SimulatorRobotBundle {
         self.primary_state.primary_state = primary_state;
         self
     }
+
+    pub fn with_goal_keeper_number(mut self, goal_keeper_number: PlayerNumber) -> Self {
+        self.parameters.goal_keeper_number = goal_keeper_number;
+        self
+    }
 }
@@ -0,0 +1,723 @@
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+};
+use coordinate_systems::World;
+use eframe::egui::Color32;
+use hsl_network_messages::{GameState, Penalty, PlayerNumber, Team};
+use linear_algebra::{Isometry2, Point2, Vector2};
+use parameters::json::merge_json;
+use serde::Deserialize;
+use serde_json::Value;
+use types::{
+    field_dimensions::{GlobalFieldSide, Side},
+    game_controller_state::GameControllerState,
+    parameters::BehaviorParameters,
+    primary_state::PrimaryState,
+};
+
+use crate::{
+    behavior_tree_simulator::{
+        BehaviorTreeSimulatorSet, InvariantChecksConfig, NetworkConfig, OpponentPoliciesConfig,
+        RobotSnapshot, SimulatedBall, SimulationConfig, SimulatorBall, SimulatorClock,
+        SimulatorFailure, SimulatorFallDownState, SimulatorGameState, SimulatorGroundToWorld,
+        SimulatorHeadYaw, SimulatorInvariantChecks, SimulatorInvariantThresholds,
+        SimulatorOpponentPolicies, SimulatorPrimaryState, SimulatorRefereeCommand, SimulatorRobot,
+        SimulatorRobotBundle, SimulatorRobotId, SimulatorScenarioResult, SimulatorTimelineMarkers,
+        default_behavior_parameters, point_world_to_field,
+    },
+    game_controller::penalty_of,
+    timeline::robot_snapshots_from_query,
+};
+
+/// A scenario declared in a JSON5 or TOML file instead of a Rust binary.
+///
+/// Positions are given in world coordinates, times in seconds since the start of the scenario.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioFile {
+    /// Merged into the base behavior parameters of all robots
+    #[serde(default)]
+    pub parameter_overrides: Value,
+    /// Global parameter of all robots, keeps the base parameter if not given
+    #[serde(default)]
+    pub goal_keeper_number: Option<PlayerNumber>,
+    pub robots: Vec<ScenarioRobot>,
+    #[serde(default)]
+    pub ball: Option<ScenarioBall>,
+    /// Keeps the simulator default if not given
+    #[serde(default)]
+    pub game_state: Option<GameState>,
+    #[serde(default)]
+    pub events: Vec<ScenarioEvent>,
+    /// The scenario passes once all of these hold at the same time
+    #[serde(default)]
+    pub success: Vec<ScenarioCondition>,
+    /// The scenario fails as soon as any of these holds
+    #[serde(default)]
+    pub failure: Vec<ScenarioCondition>,
+    /// Scenarios with success conditions fail once the timeout is reached, all others pass
+    pub timeout_seconds: f32,
+    /// Keeps the default checks if not given
+    #[serde(default)]
+    pub invariant_checks: InvariantChecksConfig,
+    #[serde(default)]
+    pub invariant_thresholds: SimulatorInvariantThresholds,
+    /// Replaces the behavior tree per team, relative replay paths are resolved against the
+    /// directory of the scenario file
+    #[serde(default)]
+    pub policies: OpponentPoliciesConfig,
+    /// Keeps the simulator default if not given
+    #[serde(default)]
+    pub network: Option<ScenarioNetwork>,
+}
+
+/// Wi-Fi the HSL and GameController packets of a scenario travel through.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioNetwork {
+    /// Delivers every packet instantly
+    Ideal,
+    /// See [`NetworkConfig::competition`]
+    Competition { seed: u64 },
+}
+
+impl ScenarioNetwork {
+    pub fn config(self) -> NetworkConfig {
+        match self {
+            Self::Ideal => NetworkConfig::default(),
+            Self::Competition { seed } => NetworkConfig::competition(seed),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioRobot {
+    pub team: Team,
+    pub player_number: PlayerNumber,
+    pub position: Point2<World>,
+    #[serde(default)]
+    pub orientation: f32,
+    #[serde(default = "playing")]
+    pub primary_state: PrimaryState,
+    /// Merged into the behavior parameters of this robot after the scenario wide overrides
+    #[serde(default)]
+    pub parameter_overrides: Value,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioBall {
+    pub position: Point2<World>,
+    #[serde(default = "Vector2::zeros")]
+    pub velocity: Vector2<World>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioRobotReference {
+    pub team: Team,
+    pub player_number: PlayerNumber,
+}
+
+impl From<ScenarioRobotReference> for SimulatorRobotId {
+    fn from(robot: ScenarioRobotReference) -> Self {
+        SimulatorRobotId::new(robot.team, robot.player_number)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScenarioEvent {
+    pub at_seconds: f32,
+    #[serde(flatten)]
+    pub action: ScenarioAction,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ScenarioAction {
+    SetGameState {
+        game_state: GameState,
+    },
+    Penalize {
+        robot: ScenarioRobotReference,
+        penalty: Penalty,
+    },
+    Unpenalize {
+        robot: ScenarioRobotReference,
+    },
+    MoveBall {
+        position: Point2<World>,
+        #[serde(default = "Vector2::zeros")]
+        velocity: Vector2<World>,
+    },
+    RemoveBall,
+    MoveRobot {
+        robot: ScenarioRobotReference,
+        position: Point2<World>,
+        #[serde(default)]
+        orientation: f32,
+    },
+}
+
+impl ScenarioAction {
+    fn robot(&self) -> Option<SimulatorRobotId> {
+        match self {
+            Self::Penalize { robot, .. }
+            | Self::Unpenalize { robot }
+            | Self::MoveRobot { robot, .. } => Some((*robot).into()),
+            Self::SetGameState { .. } | Self::MoveBall { .. } | Self::RemoveBall => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "condition", rename_all = "snake_case")]
+pub enum ScenarioCondition {
+    /// The team scored at least this many goals
+    Score {
+        team: Team,
+        goals: u8,
+    },
+    GameState {
+        game_state: GameState,
+    },
+    /// The ball lies within the axis aligned rectangle
+    BallInArea {
+        min: Point2<World>,
+        max: Point2<World>,
+    },
+    RobotInArea {
+        robot: ScenarioRobotReference,
+        min: Point2<World>,
+        max: Point2<World>,
+    },
+    PrimaryState {
+        robot: ScenarioRobotReference,
+        primary_state: PrimaryState,
+    },
+    Penalized {
+        robot: ScenarioRobotReference,
+    },
+    /// At least this many seconds passed since the start of the scenario
+    After {
+        seconds: f32,
+    },
+}
+
+impl ScenarioCondition {
+    fn robot(&self) -> Option<SimulatorRobotId> {
+        match self {
+            Self::RobotInArea { robot, .. }
+            | Self::PrimaryState { robot, .. }
+            | Self::Penalized { robot } => Some((*robot).into()),
+            Self::Score { .. }
+            | Self::GameState { .. }
+            | Self::BallInArea { .. }
+            | Self::After { .. } => None,
+        }
+    }
+
+    fn holds(&self, snapshot: &ScenarioSnapshot) -> bool {
+        match self {
+            Self::Score { team, goals } => {
+                let team_state = match team {
+                    Team::Hulks => &snapshot.game_controller_state.hulks_team,
+                    Team::Opponent => &snapshot.game_controller_state.opponent_team,
+                };
+                team_state.score >= *goals
+            }
+            Self::GameState { game_state } => {
+                snapshot.game_controller_state.game_state == *game_state
+            }
+            Self::BallInArea { min, max } => snapshot
+                .ball
+                .is_some_and(|ball| is_inside(ball.position, *min, *max)),
+            Self::RobotInArea { robot, min, max } => snapshot
+                .robots
+                .get(&(*robot).into())
+                .is_some_and(|robot| is_inside(robot.ground_to_world.translation(), *min, *max)),
+            Self::PrimaryState {
+                robot,
+                primary_state,
+            } => snapshot
+                .robots
+                .get(&(*robot).into())
+                .is_some_and(|robot| robot.primary_state == *primary_state),
+            Self::Penalized { robot } => {
+                penalty_of(snapshot.game_controller_state, (*robot).into()).is_some()
+            }
+            Self::After { seconds } => snapshot.elapsed.as_secs_f32() >= *seconds,
+        }
+    }
+}
+
+struct ScenarioSnapshot<'a> {
+    elapsed: Duration,
+    game_controller_state: &'a GameControllerState,
+    ball: Option<SimulatedBall>,
+    robots: BTreeMap<SimulatorRobotId, RobotSnapshot>,
+}
+
+impl ScenarioFile {
+    /// Parses a scenario, TOML files are detected by their extension, everything else is read as
+    /// JSON5.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let mut scenario: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&file).wrap_err_with(|| format!("failed to parse {}", path.display()))?
+        } else {
+            json5::from_str(&file)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?
+        };
+        if let Some(directory) = path.parent() {
+            scenario.policies.resolve_paths(directory);
+        }
+        Ok(scenario)
+    }
+}
+
+/// Spawns the robots and ball of a scenario file, plays its events and decides the result from
+/// its conditions.
+pub struct ScenarioFilePlugin {
+    scenario: ScenarioFile,
+    robot_parameters: Vec<BehaviorParameters>,
+    /// Policies are not `Clone`, `build` takes them out
+    policies: Mutex<Option<SimulatorOpponentPolicies>>,
+}
+
+impl ScenarioFilePlugin {
+    pub fn new(mut scenario: ScenarioFile) -> Result<Self> {
+        let robot_ids: Vec<_> = scenario
+            .robots
+            .iter()
+            .map(|robot| SimulatorRobotId::new(robot.team, robot.player_number))
+            .collect();
+        for (index, robot_id) in robot_ids.iter().enumerate() {
+            if robot_ids[..index].contains(robot_id) {
+                bail!("robot {robot_id} is declared twice");
+            }
+        }
+        let referenced_robots = scenario
+            .events
+            .iter()
+            .filter_map(|event| event.action.robot())
+            .chain(
+                scenario
+                    .success
+                    .iter()
+                    .chain(&scenario.failure)
+                    .filter_map(ScenarioCondition::robot),
+            );
+        for robot_id in referenced_robots {
+            if !robot_ids.contains(&robot_id) {
+                bail!("robot {robot_id} is referenced but not declared");
+            }
+        }
+
+        let robot_parameters = scenario
+            .robots
+            .iter()
+            .map(|robot| {
+                robot_parameters(&scenario.parameter_overrides, &robot.parameter_overrides)
+                    .wrap_err_with(|| {
+                        format!(
+                            "invalid parameter overrides for robot {}",
+                            SimulatorRobotId::new(robot.team, robot.player_number)
+                        )
+                    })
+            })
+            .collect::<Result<_>>()?;
+        let policies = scenario
+            .policies
+            .policies()
+            .wrap_err("failed to load opponent policies")?;
+
+        scenario
+            .events
+            .sort_by(|left, right| left.at_seconds.total_cmp(&right.at_seconds));
+        Ok(Self {
+            scenario,
+            robot_parameters,
+            policies: Mutex::new(Some(policies)),
+        })
+    }
+}
+
+impl Plugin for ScenarioFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulatorScenarioFile {
+            scenario: self.scenario.clone(),
+            robot_parameters: self.robot_parameters.clone(),
+            next_event: 0,
+        })
+        .insert_resource(SimulatorInvariantChecks(
+            self.scenario.invariant_checks.clone().checks(),
+        ))
+        .insert_resource(self.scenario.invariant_thresholds)
+        .insert_resource(
+            self.policies
+                .lock()
+                .expect("policies lock should not be poisoned")
+                .take()
+                .expect("ScenarioFilePlugin should only be built once"),
+        )
+        .add_systems(Startup, setup_scenario_file)
+        .add_systems(
+            Update,
+            apply_scenario_events.in_set(BehaviorTreeSimulatorSet::BeforeBallPhysics),
+        )
+        .add_systems(
+            Update,
+            check_scenario_conditions.in_set(BehaviorTreeSimulatorSet::Scenario),
+        );
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct SimulatorScenarioFile {
+    pub scenario: ScenarioFile,
+    pub robot_parameters: Vec<BehaviorParameters>,
+    /// Index of the first event which was not applied yet
+    pub next_event: usize,
+}
+
+fn robot_parameters(
+    scenario_overrides: &Value,
+    robot_overrides: &Value,
+) -> Result<BehaviorParameters> {
+    let mut parameters = serde_json::to_value(default_behavior_parameters()?)
+        .wrap_err("failed to serialize behavior parameters")?;
+    for overrides in [scenario_overrides, robot_overrides] {
+        if !overrides.is_null() {
+            merge_json(&mut parameters, overrides);
+        }
+    }
+    serde_json::from_value(parameters).wrap_err("failed to deserialize behavior parameters")
+}
+
+fn setup_scenario_file(
+    mut commands: Commands,
+    clock: Res<SimulatorClock>,
+    scenario_file: Res<SimulatorScenarioFile>,
+    mut ball: ResMut<SimulatorBall>,
+    mut game_state: ResMut<SimulatorGameState>,
+    mut config: ResMut<SimulationConfig>,
+) {
+    let scenario = &scenario_file.scenario;
+    if let Some(network) = scenario.network {
+        config.network = network.config();
+    }
+    for (robot, parameters) in scenario.robots.iter().zip(&scenario_file.robot_parameters) {
+        let mut bundle = SimulatorRobotBundle::new(
+            robot.team,
+            robot.player_number,
+            Isometry2::from_parts(robot.position.coords(), robot.orientation),
+            parameters.clone(),
+        )
+        .expect("failed to create robot bundle")
+        .with_primary_state(robot.primary_state);
+        if let Some(goal_keeper_number) = scenario.goal_keeper_number {
+            bundle = bundle.with_goal_keeper_number(goal_keeper_number);
+        }
+        commands.spawn(bundle);
+    }
+
+    let global_field_side = game_state.game_controller_state.global_field_side;
+    ball.state = scenario.ball.map(|scenario_ball| {
+        simulated_ball(
+            scenario_ball.position,
+            scenario_ball.velocity,
+            global_field_side,
+        )
+    });
+    if let Some(scenario_game_state) = scenario.game_state {
+        game_state.set_game_state(scenario_game_state, clock.now);
+    }
+}
+
+fn apply_scenario_events(
+    clock: Res<SimulatorClock>,
+    game_state: Res<SimulatorGameState>,
+    mut scenario_file: ResMut<SimulatorScenarioFile>,
+    mut ball: ResMut<SimulatorBall>,
+    mut timeline_markers: ResMut<SimulatorTimelineMarkers>,
+    mut referee_commands: MessageWriter<SimulatorRefereeCommand>,
+    mut robots: Query<(&SimulatorRobot, &mut SimulatorGroundToWorld)>,
+) {
+    let elapsed = elapsed(&clock);
+    while let Some(event) = scenario_file
+        .scenario
+        .events
+        .get(scenario_file.next_event)
+        .cloned()
+        && event.at_seconds <= elapsed.as_secs_f32()
+    {
+        scenario_file.next_event += 1;
+        timeline_markers.add(
+            clock.now,
+            Color32::LIGHT_GRAY,
+            format!("scenario: {:?}", event.action),
+        );
+        match event.action {
+            ScenarioAction::SetGameState { game_state } => {
+                referee_commands.write(SimulatorRefereeCommand::SetGameState(game_state));
+            }
+            ScenarioAction::Penalize { robot, penalty } => {
+                referee_commands.write(SimulatorRefereeCommand::Penalize {
+                    robot: robot.into(),
+                    penalty,
+                });
+            }
+            ScenarioAction::Unpenalize { robot } => {
+                referee_commands.write(SimulatorRefereeCommand::Unpenalize(robot.into()));
+            }
+            ScenarioAction::MoveBall { position, velocity } => {
+                ball.state = Some(simulated_ball(
+                    position,
+                    velocity,
+                    game_state.game_controller_state.global_field_side,
+                ));
+            }
+            ScenarioAction::RemoveBall => ball.state = None,
+            ScenarioAction::MoveRobot {
+                robot,
+                position,
+                orientation,
+            } => {
+                let robot_id = SimulatorRobotId::from(robot);
+                for (robot, mut ground_to_world) in &mut robots {
+                    if robot.id() == robot_id {
+                        ground_to_world.ground_to_world =
+                            Isometry2::from_parts(position.coords(), orientation);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_scenario_conditions(
+    clock: Res<SimulatorClock>,
+    scenario_file: Res<SimulatorScenarioFile>,
+    game_state: Res<SimulatorGameState>,
+    ball: Res<SimulatorBall>,
+    mut scenario_result: ResMut<SimulatorScenarioResult>,
+    mut exit: MessageWriter<AppExit>,
+    robots: Query<(
+        &SimulatorRobot,
+        &SimulatorGroundToWorld,
+        &SimulatorHeadYaw,
+        &SimulatorPrimaryState,
+        &SimulatorFallDownState,
+    )>,
+) {
+    let scenario = &scenario_file.scenario;
+    let snapshot = ScenarioSnapshot {
+        elapsed: elapsed(&clock),
+        game_controller_state: &game_state.game_controller_state,
+        ball: ball.state,
+        robots: robot_snapshots_from_query(&robots),
+    };
+
+    let timed_out = snapshot.elapsed.as_secs_f32() >= scenario.timeout_seconds;
+    let succeeded = !scenario.success.is_empty()
+        && scenario
+            .success
+            .iter()
+            .all(|condition| condition.holds(&snapshot));
+
+    let failure = scenario
+        .failure
+        .iter()
+        .find(|condition| condition.holds(&snapshot))
+        .map(|condition| format!("failure condition {condition:?} holds"));
+    let failure = match failure {
+        None if timed_out && !succeeded && !scenario.success.is_empty() => Some(format!(
+            "timed out after {}s before all success conditions held",
+            scenario.timeout_seconds
+        )),
+        failure => failure,
+    };
+
+    if let Some(message) = failure {
+        scenario_result.failed = true;
+        scenario_result.failures.push(SimulatorFailure::Scenario {
+            time: clock.now,
+            message,
+        });
+        exit.write(AppExit::from_code(1));
+    } else if succeeded || timed_out {
+        exit.write(AppExit::Success);
+    }
+}
+
+fn elapsed(clock: &SimulatorClock) -> Duration {
+    clock
+        .now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("simulator time should not move backwards")
+}
+
+fn simulated_ball(
+    position: Point2<World>,
+    velocity: Vector2<World>,
+    global_field_side: GlobalFieldSide,
+) -> SimulatedBall {
+    SimulatedBall {
+        position,
+        velocity,
+        field_side: if point_world_to_field(position, global_field_side).y() >= 0.0 {
+            Side::Left
+        } else {
+            Side::Right
+        },
+    }
+}
+
+fn is_inside(position: Point2<World>, min: Point2<World>, max: Point2<World>) -> bool {
+    (min.x()..=max.x()).contains(&position.x()) && (min.y()..=max.y()).contains(&position.y())
+}
+
+fn playing() -> PrimaryState {
+    PrimaryState::Playing
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::point;
+
+    use crate::{
+        behavior_tree_simulator::OpponentPolicyConfig,
+        game_controller::default_game_controller_state,
+    };
+
+    use super::*;
+
+    const SCENARIO: &str = r#"{
+        goal_keeper_number: "Three",
+        robots: [
+            {
+                team: "Hulks",
+                player_number: "Three",
+                position: [-1.0, 0.0],
+                parameter_overrides: { last_ball_timeout: { secs: 2, nanos: 0 } },
+            },
+        ],
+        ball: { position: [0.0, 0.0] },
+        events: [
+            {
+                at_seconds: 2.0,
+                action: "penalize",
+                robot: { team: "Hulks", player_number: "Three" },
+                penalty: { PickUp: { remaining: { secs: 5, nanos: 0 } } },
+            },
+            { at_seconds: 1.0, action: "move_ball", position: [1.0, 0.5] },
+        ],
+        success: [{ condition: "score", team: "Hulks", goals: 1 }],
+        timeout_seconds: 10.0,
+    }"#;
+
+    #[test]
+    fn scenario_file_resolves_parameters_and_orders_events() {
+        let scenario: ScenarioFile = json5::from_str(SCENARIO).expect("scenario should parse");
+        let plugin = ScenarioFilePlugin::new(scenario).expect("scenario should be valid");
+
+        assert_eq!(
+            plugin.scenario.goal_keeper_number,
+            Some(PlayerNumber::Three)
+        );
+        assert_eq!(
+            plugin.robot_parameters[0].last_ball_timeout,
+            Duration::from_secs(2)
+        );
+        assert!(matches!(
+            plugin.scenario.events[0].action,
+            ScenarioAction::MoveBall { .. }
+        ));
+        assert!(matches!(
+            plugin.scenario.events[1].action,
+            ScenarioAction::Penalize { .. }
+        ));
+    }
+
+    #[test]
+    fn scenario_file_rejects_undeclared_robots() {
+        let mut scenario: ScenarioFile = json5::from_str(SCENARIO).expect("scenario should parse");
+        scenario.robots.clear();
+
+        assert!(ScenarioFilePlugin::new(scenario).is_err());
+    }
+
+    #[test]
+    fn scenario_file_selects_policies_per_team() {
+        let mut scenario: ScenarioFile = json5::from_str(SCENARIO).expect("scenario should parse");
+        scenario.policies =
+            json5::from_str(r#"{ opponent: { policy: "long_shot", support_distance: 2.0 } }"#)
+                .expect("policies should parse");
+        let plugin = ScenarioFilePlugin::new(scenario).expect("scenario should be valid");
+        let policies = plugin.policies.into_inner().unwrap().unwrap();
+
+        assert!(!policies.controls(Team::Hulks));
+        assert!(policies.controls(Team::Opponent));
+
+        let mut scenario: ScenarioFile = json5::from_str(SCENARIO).expect("scenario should parse");
+        scenario.policies.hulks = Some(OpponentPolicyConfig::Replay {
+            path: "does_not_exist.json5".into(),
+        });
+        assert!(ScenarioFilePlugin::new(scenario).is_err());
+    }
+
+    #[test]
+    fn conditions_hold_on_snapshot() {
+        let mut game_controller_state = default_game_controller_state();
+        game_controller_state.hulks_team.score = 1;
+        let snapshot = ScenarioSnapshot {
+            elapsed: Duration::from_secs(3),
+            game_controller_state: &game_controller_state,
+            ball: Some(simulated_ball(
+                Point2::origin(),
+                Vector2::zeros(),
+                GlobalFieldSide::Home,
+            )),
+            robots: BTreeMap::new(),
+        };
+
+        assert!(
+            ScenarioCondition::Score {
+                team: Team::Hulks,
+                goals: 1
+            }
+            .holds(&snapshot)
+        );
+        assert!(
+            !ScenarioCondition::Score {
+                team: Team::Opponent,
+                goals: 1
+            }
+            .holds(&snapshot)
+        );
+        assert!(
+            ScenarioCondition::BallInArea {
+                min: point![-0.5, -0.5],
+                max: point![0.5, 0.5]
+            }
+            .holds(&snapshot)
+        );
+        assert!(!ScenarioCondition::After { seconds: 5.0 }.holds(&snapshot));
+    }
+}
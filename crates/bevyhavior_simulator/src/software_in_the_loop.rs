@@ -0,0 +1,925 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    f32::consts::FRAC_PI_2,
+    fs::{create_dir_all, remove_dir_all, write},
+    net::SocketAddr,
+    path::PathBuf,
+    process,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use booster::{FallDownState, FallDownStateType, Odometer};
+use color_eyre::{
+    Result,
+    eyre::{Context as _, bail, eyre},
+};
+use coordinate_systems::{Camera, Field, Ground, Head, Pixel, Robot, World};
+use geometry::rectangle::Rectangle;
+use hsl_network_messages::Team;
+use hulk_ros_z_nodes::{NodeEntryPoint, decision_nodes};
+use linear_algebra::{
+    IntoTransform, Isometry2, Isometry3, Orientation2, Point2, Vector2, point, vector,
+};
+use projection::{Projection, camera_matrix::CameraMatrix};
+use ros_z::{
+    cache::Cache,
+    prelude::*,
+    qos::{QosDurability, QosProfile},
+    time::{Clock, Time},
+};
+use ros_z_streams::{AnnouncingPublisher, CreateAnnouncingPublisher};
+use serde_json::json;
+use tokio::{runtime::Runtime, task::JoinSet, time::timeout};
+use types::{
+    behavior_tree::NodeTrace,
+    bounding_box::BoundingBox,
+    field_dimensions::FieldDimensions,
+    filtered_game_controller_state::FilteredGameControllerState,
+    messages::{IncomingMessage, OutgoingMessage},
+    motion_command::MotionCommand,
+    object_detection::{Object, RobocupObjectLabel, YOLOObjectLabel},
+    pose_detection::Pose,
+    primary_state::PrimaryState,
+    time_wrapper::TimeWrapper,
+    world_state::WorldState,
+};
+
+use crate::{
+    behavior_tree_simulator::{
+        BehaviorTreeSimulatorSet, RobotFrame, SimulationConfig, SimulatorBall, SimulatorClock,
+        SimulatorFailure, SimulatorFallDownState, SimulatorFieldDimensions, SimulatorGameState,
+        SimulatorGroundToWorld, SimulatorHeadYaw, SimulatorIncomingMessages, SimulatorMessage,
+        SimulatorOpponentPolicies, SimulatorOutgoingMessages, SimulatorPrimaryState,
+        SimulatorRobot, SimulatorRobotBehavior, SimulatorRobotFrames, SimulatorRobotId,
+        SimulatorRobotParameters, SimulatorScenarioResult, SimulatorWorldStates,
+        apply_incoming_hsl_messages, receive_game_controller_packets,
+    },
+    coordinates::ground_to_field_from_world,
+    game_controller::{filtered_game_controller_state_for_team, global_field_side_for_team},
+    network::SimulatorNetwork,
+    perception::is_occluded,
+};
+
+const ROBOT_HEIGHT: f32 = 1.2;
+const ROBOT_RADIUS: f32 = 0.2;
+
+#[derive(Clone, Debug)]
+pub struct SoftwareInTheLoopConfig {
+    /// Zenoh endpoint of the router session the robot stacks connect to
+    pub listen_endpoint: String,
+    /// Directory containing the `base` and `location` parameter layers
+    pub parameter_root: PathBuf,
+    pub location: String,
+    /// How long to wait for a freshly started robot stack to publish its first motion command
+    pub startup_timeout: Duration,
+    /// Wall clock time a robot stack may take to publish the motion command of a tick before the
+    /// run fails
+    pub motion_command_timeout: Duration,
+    pub camera: NominalCamera,
+    /// The nodes between the inputs published by the simulator and `behavior/motion_command`,
+    /// perception nodes working on images and the localization are replaced by ground truth
+    pub nodes: Vec<NodeEntryPoint>,
+}
+
+impl Default for SoftwareInTheLoopConfig {
+    fn default() -> Self {
+        Self {
+            listen_endpoint: "tcp/127.0.0.1:7448".to_string(),
+            parameter_root: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("../../etc/parameters/ros_z"),
+            location: "default_location".to_string(),
+            startup_timeout: Duration::from_secs(10),
+            motion_command_timeout: Duration::from_secs(1),
+            camera: NominalCamera::default(),
+            nodes: decision_nodes(),
+        }
+    }
+}
+
+/// Camera mounted in the head at a fixed height and pitch, used to render ground truth into
+/// detections.
+#[derive(Clone, Copy, Debug)]
+pub struct NominalCamera {
+    pub image_size: Vector2<Pixel>,
+    pub focal_length: nalgebra::Vector2<f32>,
+    pub optical_center: nalgebra::Point2<f32>,
+    pub height: f32,
+    /// Downward tilt of the optical axis in radians
+    pub pitch: f32,
+}
+
+impl Default for NominalCamera {
+    fn default() -> Self {
+        Self {
+            image_size: vector![544.0, 448.0],
+            focal_length: nalgebra::vector![0.6, 0.73],
+            optical_center: nalgebra::point![0.5, 0.5],
+            height: 1.1,
+            pitch: 0.45,
+        }
+    }
+}
+
+impl NominalCamera {
+    pub fn camera_matrix(&self, head_yaw: Orientation2<Ground>) -> CameraMatrix {
+        let ground_to_robot = Isometry3::<Ground, Robot>::from_translation(0.0, 0.0, -self.height);
+        let robot_to_head =
+            Isometry3::<Robot, Head>::from_rotation(vector![0.0, 0.0, -head_yaw.angle()]);
+        let head_to_camera: Isometry3<Head, Camera> =
+            (nalgebra::Isometry3::rotation(nalgebra::Vector3::x() * self.pitch)
+                * nalgebra::Isometry3::rotation(nalgebra::Vector3::y() * -FRAC_PI_2)
+                * nalgebra::Isometry3::rotation(nalgebra::Vector3::x() * FRAC_PI_2))
+            .framed_transform();
+
+        CameraMatrix::from_normalized_focal_and_center(
+            self.focal_length,
+            self.optical_center,
+            self.image_size,
+            ground_to_robot,
+            robot_to_head,
+            head_to_camera,
+        )
+    }
+
+    fn contains(&self, pixel: Point2<Pixel>) -> bool {
+        (0.0..self.image_size.x()).contains(&pixel.x())
+            && (0.0..self.image_size.y()).contains(&pixel.y())
+    }
+
+    fn clamp(&self, pixel: Point2<Pixel>) -> Point2<Pixel> {
+        point![
+            pixel.x().clamp(0.0, self.image_size.x()),
+            pixel.y().clamp(0.0, self.image_size.y())
+        ]
+    }
+}
+
+/// Replaces the simulated behavior with real ros-z node stacks, one per robot.
+///
+/// Each robot gets its own namespace (e.g. `/hulks3`) and context connected to a local router
+/// session. Every tick the simulator publishes the node inputs from ground truth, advances the
+/// shared logical clock to the simulated time and waits for the `behavior/motion_command` published
+/// at that time.
+/// Use together with `BehaviorTreeSimulatorPlugin { enable_default_behavior: false, .. }`.
+pub struct SoftwareInTheLoopPlugin {
+    config: SoftwareInTheLoopConfig,
+    runtime: Arc<Runtime>,
+    clock: Clock,
+    router: Context,
+}
+
+impl SoftwareInTheLoopPlugin {
+    pub fn new(config: SoftwareInTheLoopConfig) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .wrap_err("failed to build Tokio runtime")?;
+        let clock = Clock::logical(Time::from_wallclock(SystemTime::UNIX_EPOCH));
+        let router = runtime
+            .block_on(
+                ContextBuilder::default()
+                    .with_clock(clock.clone())
+                    .with_mode("router")
+                    .disable_multicast_scouting()
+                    .with_connect_endpoints(std::iter::empty::<&str>())
+                    .with_listen_endpoints([config.listen_endpoint.as_str()])
+                    .build(),
+            )
+            .wrap_err_with(|| format!("failed to start router on {}", config.listen_endpoint))?;
+
+        Ok(Self {
+            config,
+            runtime: Arc::new(runtime),
+            clock,
+            router,
+        })
+    }
+}
+
+impl Plugin for SoftwareInTheLoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulatorSoftwareInTheLoop {
+            config: self.config.clone(),
+            runtime: self.runtime.clone(),
+            clock: self.clock.clone(),
+            router: self.router.clone(),
+            stacks: BTreeMap::new(),
+        })
+        .add_systems(
+            Update,
+            start_robot_stacks.in_set(BehaviorTreeSimulatorSet::AfterAutoReferee),
+        )
+        .add_systems(
+            Update,
+            forward_incoming_messages
+                .after(receive_game_controller_packets)
+                .before(apply_incoming_hsl_messages)
+                .in_set(BehaviorTreeSimulatorSet::BuildTeamContext),
+        )
+        .add_systems(
+            Update,
+            publish_robot_inputs.in_set(BehaviorTreeSimulatorSet::BeforeBehavior),
+        )
+        .add_systems(
+            Update,
+            receive_motion_commands.in_set(BehaviorTreeSimulatorSet::TickBehaviorTrees),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct SimulatorSoftwareInTheLoop {
+    pub config: SoftwareInTheLoopConfig,
+    runtime: Arc<Runtime>,
+    clock: Clock,
+    router: Context,
+    stacks: BTreeMap<SimulatorRobotId, RobotStack>,
+}
+
+impl SimulatorSoftwareInTheLoop {
+    pub fn namespaces(&self) -> impl Iterator<Item = (SimulatorRobotId, &str)> {
+        self.stacks
+            .iter()
+            .map(|(robot_id, stack)| (*robot_id, stack.namespace.as_str()))
+    }
+}
+
+struct RobotStack {
+    namespace: String,
+    context: Arc<Context>,
+    nodes: JoinSet<Result<()>>,
+    parameter_layer: PathBuf,
+    initial_ground_to_world: Isometry2<Ground, World>,
+    io: RobotStackIo,
+}
+
+impl Drop for RobotStack {
+    fn drop(&mut self) {
+        self.nodes.abort_all();
+        let _ = self.context.shutdown();
+        let _ = remove_dir_all(&self.parameter_layer);
+    }
+}
+
+struct RobotStackIo {
+    odometer: AnnouncingPublisher<Odometer>,
+    fall_down_state: Publisher<FallDownState>,
+    filtered_game_controller_state: Publisher<FilteredGameControllerState>,
+    game_controller_address: Publisher<Option<SocketAddr>>,
+    camera_matrix: Publisher<TimeWrapper<CameraMatrix>>,
+    detected_objects: AnnouncingPublisher<Vec<Object<RobocupObjectLabel>>>,
+    detected_poses: AnnouncingPublisher<Vec<Pose<YOLOObjectLabel>>>,
+    message: Publisher<TimeWrapper<IncomingMessage>>,
+    ground_to_field: Publisher<Isometry2<Ground, Field>>,
+    primary_state: Publisher<PrimaryState>,
+    motion_command: Subscriber<MotionCommand>,
+    outgoing_message: Subscriber<OutgoingMessage>,
+    world_state: Cache<WorldState>,
+    trace: Cache<NodeTrace>,
+}
+
+impl RobotStackIo {
+    async fn new(node: &Node) -> Result<Self> {
+        Ok(Self {
+            odometer: node
+                .announcing_publisher::<Odometer>("inputs/odometer")
+                .await?,
+            fall_down_state: node
+                .publisher::<FallDownState>("inputs/fall_down_state")
+                .build()
+                .await?,
+            filtered_game_controller_state: node
+                .publisher::<FilteredGameControllerState>("filtered_game_controller_state")
+                .build()
+                .await?,
+            game_controller_address: node
+                .publisher::<Option<SocketAddr>>("game_controller_address")
+                .build()
+                .await?,
+            camera_matrix: node
+                .publisher::<TimeWrapper<CameraMatrix>>("camera_matrix")
+                .build()
+                .await?,
+            detected_objects: node
+                .announcing_publisher::<Vec<Object<RobocupObjectLabel>>>("detected_objects")
+                .await?,
+            detected_poses: node
+                .announcing_publisher::<Vec<Pose<YOLOObjectLabel>>>("detected_poses")
+                .await?,
+            message: node
+                .publisher::<TimeWrapper<IncomingMessage>>("inputs/message")
+                .build()
+                .await?,
+            ground_to_field: node
+                .publisher::<Isometry2<Ground, Field>>("ground_to_field")
+                .build()
+                .await?,
+            primary_state: node
+                .publisher::<PrimaryState>("primary_state")
+                .qos(QosProfile {
+                    durability: QosDurability::TransientLocal,
+                    ..Default::default()
+                })
+                .build()
+                .await?,
+            motion_command: node
+                .subscriber::<MotionCommand>("behavior/motion_command")
+                .build()
+                .await?,
+            outgoing_message: node
+                .subscriber::<OutgoingMessage>("outputs/message")
+                .build()
+                .await?,
+            world_state: node
+                .subscriber::<WorldState>("world_state")
+                .cache(1)
+                .build()
+                .await?,
+            trace: node
+                .subscriber::<NodeTrace>("behavior/trace")
+                .cache(1)
+                .build()
+                .await?,
+        })
+    }
+}
+
+struct RobotInputs {
+    now: Time,
+    odometer: Odometer,
+    fall_down_state: FallDownState,
+    filtered_game_controller_state: FilteredGameControllerState,
+    game_controller_address: Option<SocketAddr>,
+    camera_matrix: CameraMatrix,
+    detected_objects: Vec<Object<RobocupObjectLabel>>,
+    ground_to_field: Isometry2<Ground, Field>,
+    primary_state: PrimaryState,
+}
+
+fn start_robot_stacks(
+    clock: Res<SimulatorClock>,
+    field_dimensions: Res<SimulatorFieldDimensions>,
+    opponent_policies: Res<SimulatorOpponentPolicies>,
+    mut software_in_the_loop: ResMut<SimulatorSoftwareInTheLoop>,
+    mut scenario_result: ResMut<SimulatorScenarioResult>,
+    mut exit: MessageWriter<AppExit>,
+    robots: Query<(
+        &SimulatorRobot,
+        &SimulatorGroundToWorld,
+        &SimulatorRobotParameters,
+    )>,
+) {
+    let software_in_the_loop = &mut *software_in_the_loop;
+    software_in_the_loop
+        .stacks
+        .retain(|robot_id, _| robots.iter().any(|(robot, _, _)| robot.id() == *robot_id));
+
+    for (robot, ground_to_world, parameters) in &robots {
+        let robot_id = robot.id();
+        if software_in_the_loop.stacks.contains_key(&robot_id)
+            || opponent_policies.controls(robot.team)
+        {
+            continue;
+        }
+
+        let stack = software_in_the_loop
+            .runtime
+            .block_on(start_robot_stack(
+                &software_in_the_loop.router,
+                &software_in_the_loop.clock,
+                &software_in_the_loop.config,
+                robot_id,
+                parameters,
+                field_dimensions.0,
+                ground_to_world.ground_to_world,
+            ))
+            .wrap_err_with(|| format!("failed to start node stack of robot {robot_id}"));
+        match stack {
+            Ok(stack) => {
+                software_in_the_loop.stacks.insert(robot_id, stack);
+            }
+            Err(error) => {
+                fail(&clock, &mut scenario_result, &mut exit, error);
+                return;
+            }
+        }
+    }
+}
+
+async fn start_robot_stack(
+    router: &Context,
+    clock: &Clock,
+    config: &SoftwareInTheLoopConfig,
+    robot_id: SimulatorRobotId,
+    parameters: &SimulatorRobotParameters,
+    field_dimensions: FieldDimensions,
+    initial_ground_to_world: Isometry2<Ground, World>,
+) -> Result<RobotStack> {
+    let namespace = namespace(robot_id);
+    let parameter_layer =
+        write_parameter_layer(&namespace, robot_id, parameters, field_dimensions)?;
+
+    let context = Arc::new(
+        ContextBuilder::default()
+            .with_namespace(&namespace)
+            .with_clock(clock.clone())
+            .with_parameter_layers([
+                config.parameter_root.join("base"),
+                config
+                    .parameter_root
+                    .join("location")
+                    .join(&config.location),
+                parameter_layer.clone(),
+            ])
+            .with_mode("client")
+            .disable_multicast_scouting()
+            .with_router_endpoint(config.listen_endpoint.clone())?
+            .build()
+            .await?,
+    );
+    let node = router
+        .create_node("software_in_the_loop")
+        .with_namespace(&namespace)
+        .build()
+        .await?;
+    let io = RobotStackIo::new(&node).await?;
+
+    let mut nodes = JoinSet::new();
+    for run in &config.nodes {
+        nodes.spawn(run(context.clone()));
+    }
+
+    if !io
+        .motion_command
+        .wait_for_publishers(1, config.startup_timeout)
+        .await
+    {
+        bail!(
+            "no motion command publisher in {namespace} after {:?}",
+            config.startup_timeout
+        );
+    }
+
+    Ok(RobotStack {
+        namespace,
+        context,
+        nodes,
+        parameter_layer,
+        initial_ground_to_world,
+        io,
+    })
+}
+
+fn namespace(robot_id: SimulatorRobotId) -> String {
+    let team = match robot_id.team {
+        Team::Hulks => "hulks",
+        Team::Opponent => "opponent",
+    };
+    format!("/{team}{}", robot_id.player_number)
+}
+
+/// Writes the robot specific parameters into a layer on top of the base and location layers.
+fn write_parameter_layer(
+    namespace: &str,
+    robot_id: SimulatorRobotId,
+    parameters: &SimulatorRobotParameters,
+    field_dimensions: FieldDimensions,
+) -> Result<PathBuf> {
+    let parameter_layer = env::temp_dir()
+        .join("bevyhavior_simulator")
+        .join(process::id().to_string())
+        .join(namespace.trim_start_matches('/'));
+    create_dir_all(&parameter_layer)
+        .wrap_err_with(|| format!("failed to create {}", parameter_layer.display()))?;
+    write(
+        parameter_layer.join("global.json5"),
+        serde_json::to_string_pretty(&json!({
+            "player_number": robot_id.player_number,
+            "goal_keeper_number": parameters.goal_keeper_number,
+            "field_dimensions": field_dimensions,
+        }))?,
+    )?;
+    write(
+        parameter_layer.join("behavior_node.json5"),
+        serde_json::to_string_pretty(&parameters.behavior)?,
+    )?;
+
+    Ok(parameter_layer)
+}
+
+fn forward_incoming_messages(
+    clock: Res<SimulatorClock>,
+    incoming_messages: Res<SimulatorIncomingMessages>,
+    software_in_the_loop: Res<SimulatorSoftwareInTheLoop>,
+    mut scenario_result: ResMut<SimulatorScenarioResult>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let result = software_in_the_loop.runtime.block_on(async {
+        for incoming_message in &incoming_messages.messages {
+            let Some(stack) = software_in_the_loop.stacks.get(&incoming_message.receiver) else {
+                continue;
+            };
+            stack
+                .io
+                .message
+                .publish(&TimeWrapper {
+                    time: Time::from_wallclock(incoming_message.received_at),
+                    inner: incoming_message.message.clone(),
+                })
+                .await?;
+        }
+        Ok(())
+    });
+
+    if let Err(error) = result {
+        fail(&clock, &mut scenario_result, &mut exit, error);
+    }
+}
+
+fn publish_robot_inputs(
+    clock: Res<SimulatorClock>,
+    ball: Res<SimulatorBall>,
+    game_state: Res<SimulatorGameState>,
+    network: Res<SimulatorNetwork>,
+    field_dimensions: Res<SimulatorFieldDimensions>,
+    config: Res<SimulationConfig>,
+    software_in_the_loop: Res<SimulatorSoftwareInTheLoop>,
+    mut scenario_result: ResMut<SimulatorScenarioResult>,
+    mut exit: MessageWriter<AppExit>,
+    robots: Query<(
+        &SimulatorRobot,
+        &SimulatorGroundToWorld,
+        &SimulatorHeadYaw,
+        &SimulatorPrimaryState,
+        &SimulatorFallDownState,
+    )>,
+) {
+    let robot_poses = robots
+        .iter()
+        .map(|(robot, ground_to_world, ..)| (robot.id(), ground_to_world.ground_to_world))
+        .collect::<Vec<_>>();
+
+    for (robot, ground_to_world, head_yaw, primary_state, fall_down_state) in &robots {
+        let robot_id = robot.id();
+        let Some(stack) = software_in_the_loop.stacks.get(&robot_id) else {
+            continue;
+        };
+        let ground_to_world = ground_to_world.ground_to_world;
+        let game_controller_state = network
+            .game_controller_states
+            .get(&robot_id)
+            .unwrap_or(&game_state.game_controller_state);
+        let camera_matrix = software_in_the_loop
+            .config
+            .camera
+            .camera_matrix(head_yaw.yaw);
+        let others = robot_poses
+            .iter()
+            .filter(|(other_id, _)| *other_id != robot_id)
+            .map(|(_, other_ground_to_world)| {
+                ground_to_world.inverse() * other_ground_to_world.translation()
+            })
+            .collect::<Vec<_>>();
+
+        let mut detected_objects = others
+            .iter()
+            .filter_map(|position| {
+                detected_robot(
+                    &software_in_the_loop.config.camera,
+                    &camera_matrix,
+                    *position,
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(ball) = ball.state {
+            let position = ground_to_world.inverse() * ball.position;
+            if !is_occluded(position, &others, ROBOT_RADIUS) {
+                detected_objects.extend(detected_ball(
+                    &software_in_the_loop.config.camera,
+                    &camera_matrix,
+                    position,
+                    field_dimensions.0.ball_radius,
+                ));
+            }
+        }
+
+        let inputs = RobotInputs {
+            now: Time::from_wallclock(clock.now),
+            odometer: odometer(stack.initial_ground_to_world, ground_to_world),
+            fall_down_state: fall_down_state.fall_down_state.unwrap_or(FallDownState {
+                fall_down_state: FallDownStateType::IsReady,
+                is_recovery_available: true,
+            }),
+            filtered_game_controller_state: filtered_game_controller_state_for_team(
+                game_controller_state,
+                robot.team,
+            ),
+            game_controller_address: config.game_controller_address,
+            camera_matrix,
+            detected_objects,
+            ground_to_field: ground_to_field_from_world(
+                ground_to_world,
+                global_field_side_for_team(&game_state.game_controller_state, robot.team),
+            ),
+            primary_state: primary_state.primary_state,
+        };
+
+        let result = software_in_the_loop
+            .runtime
+            .block_on(publish(&stack.io, inputs))
+            .wrap_err_with(|| format!("failed to publish inputs of robot {robot_id}"));
+        if let Err(error) = result {
+            fail(&clock, &mut scenario_result, &mut exit, error);
+            return;
+        }
+    }
+}
+
+async fn publish(io: &RobotStackIo, inputs: RobotInputs) -> Result<()> {
+    io.fall_down_state.publish(&inputs.fall_down_state).await?;
+    io.filtered_game_controller_state
+        .publish(&inputs.filtered_game_controller_state)
+        .await?;
+    io.game_controller_address
+        .publish(&inputs.game_controller_address)
+        .await?;
+    io.ground_to_field.publish(&inputs.ground_to_field).await?;
+    io.primary_state.publish(&inputs.primary_state).await?;
+    io.camera_matrix
+        .publish(&TimeWrapper {
+            time: inputs.now,
+            inner: inputs.camera_matrix,
+        })
+        .await?;
+    io.odometer
+        .announce(inputs.now)
+        .await?
+        .publish(&inputs.odometer)
+        .await?;
+    io.detected_objects
+        .announce(inputs.now)
+        .await?
+        .publish(&inputs.detected_objects)
+        .await?;
+    io.detected_poses
+        .announce(inputs.now)
+        .await?
+        .publish(&Vec::new())
+        .await?;
+    Ok(())
+}
+
+fn receive_motion_commands(
+    clock: Res<SimulatorClock>,
+    world_states: Res<SimulatorWorldStates>,
+    mut software_in_the_loop: ResMut<SimulatorSoftwareInTheLoop>,
+    mut robot_frames: ResMut<SimulatorRobotFrames>,
+    mut outgoing_messages: ResMut<SimulatorOutgoingMessages>,
+    mut scenario_result: ResMut<SimulatorScenarioResult>,
+    mut exit: MessageWriter<AppExit>,
+    robots: Query<(&SimulatorRobot, &SimulatorRobotBehavior)>,
+) {
+    robot_frames.0.clear();
+    outgoing_messages.messages.clear();
+
+    let software_in_the_loop = &mut *software_in_the_loop;
+    let now = Time::from_wallclock(clock.now);
+    if let Err(error) = software_in_the_loop.clock.set_time(now) {
+        fail(&clock, &mut scenario_result, &mut exit, eyre!("{error:?}"));
+        return;
+    }
+
+    for (robot, behavior) in &robots {
+        let robot_id = robot.id();
+        let Some(stack) = software_in_the_loop.stacks.get_mut(&robot_id) else {
+            continue;
+        };
+        if let Some(joined) = stack.nodes.try_join_next() {
+            let error = match joined {
+                Ok(Ok(())) => eyre!("a node in {} stopped", stack.namespace),
+                Ok(Err(error)) => error.wrap_err(format!("a node in {} failed", stack.namespace)),
+                Err(error) => eyre!("a node in {} panicked: {error}", stack.namespace),
+            };
+            fail(&clock, &mut scenario_result, &mut exit, error);
+            return;
+        }
+
+        let timeout_duration = software_in_the_loop.config.motion_command_timeout;
+        let received = software_in_the_loop.runtime.block_on(async {
+            // commands published before the clock advanced belong to the previous tick
+            let motion_command = timeout(timeout_duration, async {
+                loop {
+                    let received = stack.io.motion_command.recv_with_metadata().await?;
+                    if received.source_time >= now {
+                        return Ok::<_, color_eyre::Report>(received.message);
+                    }
+                }
+            })
+            .await
+            .map_err(|_| {
+                eyre!(
+                    "{} published no motion command for {now:?} within {timeout_duration:?}",
+                    stack.namespace
+                )
+            })??;
+            let mut outgoing_messages = Vec::new();
+            while let Ok(Ok(message)) =
+                timeout(Duration::ZERO, stack.io.outgoing_message.recv()).await
+            {
+                outgoing_messages.push(message);
+            }
+            Ok::<_, color_eyre::Report>((motion_command, outgoing_messages))
+        });
+        let (motion_command, robot_outgoing_messages) = match received {
+            Ok(received) => received,
+            Err(error) => {
+                fail(&clock, &mut scenario_result, &mut exit, error);
+                return;
+            }
+        };
+
+        let Some(world_state) = stack
+            .io
+            .world_state
+            .get_latest()
+            .map(|world_state| world_state.as_ref().clone())
+            .or_else(|| world_states.0.get(&robot_id).cloned())
+        else {
+            continue;
+        };
+        let trace = stack
+            .io
+            .trace
+            .get_latest()
+            .map(|trace| trace.as_ref().clone())
+            .unwrap_or_else(|| behavior.static_layout.clone());
+
+        outgoing_messages
+            .messages
+            .extend(
+                robot_outgoing_messages
+                    .iter()
+                    .cloned()
+                    .map(|message| SimulatorMessage {
+                        sender: robot_id,
+                        message,
+                    }),
+            );
+        robot_frames.0.insert(
+            robot_id,
+            RobotFrame {
+                world_state,
+                motion_command,
+                trace,
+                static_layout: behavior.static_layout.clone(),
+                path_obstacles: Vec::new(),
+                time_since_last_switch: Duration::ZERO,
+                direction_difference: 0.0,
+                walk_position: None,
+                voronoi_map: None,
+                voronoi_inputs: Vec::new(),
+                outgoing_messages: robot_outgoing_messages,
+            },
+        );
+    }
+}
+
+/// Odometer reading of a robot which started at `initial_ground_to_world`.
+pub fn odometer(
+    initial_ground_to_world: Isometry2<Ground, World>,
+    ground_to_world: Isometry2<Ground, World>,
+) -> Odometer {
+    let current_to_initial = initial_ground_to_world.inverse() * ground_to_world;
+    let translation = current_to_initial.translation();
+    Odometer {
+        x: translation.x(),
+        y: translation.y(),
+        theta: current_to_initial.orientation().angle(),
+    }
+}
+
+fn detected_ball(
+    camera: &NominalCamera,
+    camera_matrix: &CameraMatrix,
+    position: Point2<Ground>,
+    radius: f32,
+) -> Option<Object<RobocupObjectLabel>> {
+    let center = camera_matrix
+        .ground_with_z_to_pixel(position, radius)
+        .ok()?;
+    if !camera.contains(center) {
+        return None;
+    }
+    let pixel_radius = camera_matrix.get_pixel_radius(radius, center).ok()?;
+
+    Some(Object {
+        label: RobocupObjectLabel::Ball,
+        bounding_box: BoundingBox {
+            area: Rectangle {
+                min: camera.clamp(center - vector![pixel_radius, pixel_radius]),
+                max: camera.clamp(center + vector![pixel_radius, pixel_radius]),
+            },
+            confidence: 1.0,
+        },
+    })
+}
+
+fn detected_robot(
+    camera: &NominalCamera,
+    camera_matrix: &CameraMatrix,
+    position: Point2<Ground>,
+) -> Option<Object<RobocupObjectLabel>> {
+    let side = position
+        .coords()
+        .try_normalize(f32::EPSILON)
+        .map(|direction| vector![-direction.y(), direction.x()] * ROBOT_RADIUS)?;
+    let corners = [
+        camera_matrix.ground_to_pixel(position + side).ok()?,
+        camera_matrix.ground_to_pixel(position - side).ok()?,
+        camera_matrix
+            .ground_with_z_to_pixel(position + side, ROBOT_HEIGHT)
+            .ok()?,
+        camera_matrix
+            .ground_with_z_to_pixel(position - side, ROBOT_HEIGHT)
+            .ok()?,
+    ];
+    let foot = camera_matrix.ground_to_pixel(position).ok()?;
+    if !camera.contains(foot) {
+        return None;
+    }
+    let min = corners.iter().fold(corners[0], |min, corner| {
+        point![min.x().min(corner.x()), min.y().min(corner.y())]
+    });
+    let max = corners.iter().fold(corners[0], |max, corner| {
+        point![max.x().max(corner.x()), max.y().max(corner.y())]
+    });
+
+    Some(Object {
+        label: RobocupObjectLabel::Robot,
+        bounding_box: BoundingBox {
+            area: Rectangle {
+                min: camera.clamp(min),
+                max: camera.clamp(max),
+            },
+            confidence: 1.0,
+        },
+    })
+}
+
+fn fail(
+    clock: &SimulatorClock,
+    scenario_result: &mut SimulatorScenarioResult,
+    exit: &mut MessageWriter<AppExit>,
+    error: color_eyre::Report,
+) {
+    scenario_result.failed = true;
+    scenario_result
+        .failures
+        .push(SimulatorFailure::SoftwareInTheLoop {
+            time: clock.now,
+            message: format!("{error:#}"),
+        });
+    exit.write(AppExit::from_code(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn odometer_starts_at_zero_and_follows_the_robot() {
+        let initial = Isometry2::<Ground, World>::from_parts(vector![1.0, -2.0], FRAC_PI_2);
+
+        let start = odometer(initial, initial);
+        assert_relative_eq!(start.x, 0.0);
+        assert_relative_eq!(start.y, 0.0);
+        assert_relative_eq!(start.theta, 0.0);
+
+        let walked = Isometry2::<Ground, World>::from_parts(vector![1.0, -1.0], FRAC_PI_2);
+        let moved = odometer(initial, walked);
+        assert_relative_eq!(moved.x, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(moved.y, 0.0, epsilon = 1e-6);
+        assert_relative_eq!(moved.theta, 0.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn nominal_camera_sees_the_ball_in_front_but_not_behind() {
+        let camera = NominalCamera::default();
+        let camera_matrix = camera.camera_matrix(Orientation2::identity());
+
+        let in_front = detected_ball(&camera, &camera_matrix, point![2.0, 0.0], 0.05);
+        let behind = detected_ball(&camera, &camera_matrix, point![-2.0, 0.0], 0.05);
+
+        let bounding_box = in_front
+            .expect("ball in front should be detected")
+            .bounding_box;
+        let center = (bounding_box.area.min.x() + bounding_box.area.max.x()) / 2.0;
+        assert_relative_eq!(center, camera.image_size.x() / 2.0, epsilon = 1.0);
+        assert!(behind.is_none());
+    }
+}
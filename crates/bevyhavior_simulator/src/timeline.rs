@@ -1,6 +1,7 @@
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
+    mem::discriminant,
     time::Duration,
     time::SystemTime,
 };
@@ -8,8 +9,8 @@ use std::{
 use bevy::prelude::*;
 use coordinate_systems::{Field, Ground};
 use eframe::egui::Color32;
-use linear_algebra::{Point2, Pose2};
-use serde::Serialize;
+use linear_algebra::{Point2, Pose2, distance};
+use serde::{Deserialize, Serialize};
 use types::{
     behavior_tree::NodeTrace, filtered_game_state::FilteredGameState, messages::OutgoingMessage,
     motion_command::MotionCommand, path_obstacles::PathObstacle, world_state::WorldState,
@@ -24,6 +25,11 @@ use crate::behavior_tree_simulator::{
 };
 use crate::game_controller::filtered_game_state_from;
 
+/// Robots or balls closer than this to their counterpart have not diverged
+const DIVERGENCE_DISTANCE_TOLERANCE: f32 = 0.01;
+/// Robots turned less than this against their counterpart have not diverged
+const DIVERGENCE_ANGLE_TOLERANCE: f32 = 0.01;
+
 #[derive(Resource, Clone, Debug, Default)]
 pub struct SimulatorTimeline {
     pub frames: Vec<TimelineFrame>,
@@ -44,7 +50,7 @@ impl SimulatorTimelineMarkers {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SimulatorTimelineMarker {
     pub frame_time: SystemTime,
     pub color: Color32,
@@ -57,15 +63,27 @@ pub struct SimulatorScenarioResult {
     pub failures: Vec<SimulatorFailure>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SimulatorFailure {
     InvariantViolation(InvariantViolation),
+    /// A scenario file failure condition held or its success conditions were not met in time
+    Scenario {
+        time: SystemTime,
+        message: String,
+    },
+    /// A robot's software-in-the-loop node stack could not be started or stopped running
+    SoftwareInTheLoop {
+        time: SystemTime,
+        message: String,
+    },
 }
 
 impl Display for SimulatorFailure {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Self::InvariantViolation(violation) => write!(formatter, "{}", violation),
+            Self::Scenario { message, .. } => write!(formatter, "{message}"),
+            Self::SoftwareInTheLoop { message, .. } => write!(formatter, "{message}"),
         }
     }
 }
@@ -73,7 +91,7 @@ impl Display for SimulatorFailure {
 #[derive(Resource, Clone, Debug, Default)]
 pub struct SimulatorRobotFrames(pub BTreeMap<SimulatorRobotId, RobotFrame>);
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TimelineFrame {
     pub now: SystemTime,
     pub game_state: FilteredGameState,
@@ -84,7 +102,7 @@ pub struct TimelineFrame {
     pub invariant_violations: Vec<InvariantViolation>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RobotFrame {
     pub world_state: WorldState,
     pub motion_command: MotionCommand,
@@ -178,3 +196,218 @@ pub fn robot_snapshots_from_query(
     }
     snapshots
 }
+
+/// First frame at which two timelines of the same scenario disagree.
+#[derive(Clone, Debug)]
+pub struct TimelineDivergence {
+    pub frame_index: usize,
+    pub comparison_frame_index: usize,
+    pub time: SystemTime,
+    pub reason: String,
+}
+
+/// Compares frames at equal time since the start of their timeline. Differences within a
+/// centimeter or a hundredth of a radian are not considered diverged.
+pub fn first_divergence(
+    frames: &[TimelineFrame],
+    comparison: &[TimelineFrame],
+) -> Option<TimelineDivergence> {
+    let divergence = |frame_index: usize, comparison_frame_index: usize, reason: String| {
+        Some(TimelineDivergence {
+            frame_index,
+            comparison_frame_index,
+            time: frames[frame_index].now,
+            reason,
+        })
+    };
+
+    let last_frame_index = frames.len().checked_sub(1)?;
+    let Some(last_comparison_frame_index) = comparison.len().checked_sub(1) else {
+        return divergence(0, 0, "comparison timeline is empty".to_string());
+    };
+
+    for (frame_index, frame) in frames.iter().enumerate() {
+        let comparison_frame_index = aligned_frame_index(frames, frame_index, comparison);
+        if elapsed(frames, frame_index) > elapsed(comparison, last_comparison_frame_index) {
+            return divergence(
+                frame_index,
+                comparison_frame_index,
+                "comparison timeline ended".to_string(),
+            );
+        }
+        if let Some(reason) = frame_difference(frame, &comparison[comparison_frame_index]) {
+            return divergence(frame_index, comparison_frame_index, reason);
+        }
+    }
+
+    if elapsed(comparison, last_comparison_frame_index) > elapsed(frames, last_frame_index) {
+        return divergence(
+            last_frame_index,
+            last_comparison_frame_index,
+            "timeline ended before the comparison".to_string(),
+        );
+    }
+    None
+}
+
+/// Index of the comparison frame at the time since start of the given frame.
+pub fn aligned_frame_index(
+    frames: &[TimelineFrame],
+    frame_index: usize,
+    comparison: &[TimelineFrame],
+) -> usize {
+    let Some(first_comparison_frame) = comparison.first() else {
+        return 0;
+    };
+    let target = first_comparison_frame.now + elapsed(frames, frame_index);
+    comparison
+        .partition_point(|frame| frame.now < target)
+        .min(comparison.len() - 1)
+}
+
+fn elapsed(frames: &[TimelineFrame], frame_index: usize) -> Duration {
+    frames[frame_index]
+        .now
+        .duration_since(frames[0].now)
+        .unwrap_or_default()
+}
+
+fn frame_difference(frame: &TimelineFrame, comparison: &TimelineFrame) -> Option<String> {
+    if frame.game_state != comparison.game_state {
+        return Some(format!(
+            "game state {:?} instead of {:?}",
+            comparison.game_state, frame.game_state
+        ));
+    }
+
+    for robot_id in frame.robots.keys() {
+        if !comparison.robots.contains_key(robot_id) {
+            return Some(format!("robot {robot_id} is missing"));
+        }
+    }
+    for (robot_id, comparison_robot) in &comparison.robots {
+        let Some(robot) = frame.robots.get(robot_id) else {
+            return Some(format!("robot {robot_id} was added"));
+        };
+        if robot.primary_state != comparison_robot.primary_state {
+            return Some(format!(
+                "robot {robot_id} is {:?} instead of {:?}",
+                comparison_robot.primary_state, robot.primary_state
+            ));
+        }
+        if let (Some(robot_frame), Some(comparison_robot_frame)) = (
+            frame.robot_frames.get(robot_id),
+            comparison.robot_frames.get(robot_id),
+        ) && discriminant(&robot_frame.motion_command)
+            != discriminant(&comparison_robot_frame.motion_command)
+        {
+            return Some(format!(
+                "robot {robot_id} executes a different motion command"
+            ));
+        }
+        let position_difference = distance(
+            robot.ground_to_world.translation(),
+            comparison_robot.ground_to_world.translation(),
+        );
+        if position_difference > DIVERGENCE_DISTANCE_TOLERANCE {
+            return Some(format!(
+                "robot {robot_id} is {position_difference:.2}m apart"
+            ));
+        }
+        let angle_difference = robot
+            .ground_to_world
+            .orientation()
+            .rotation_to(comparison_robot.ground_to_world.orientation())
+            .angle()
+            .abs();
+        if angle_difference > DIVERGENCE_ANGLE_TOLERANCE {
+            return Some(format!(
+                "robot {robot_id} is turned by {angle_difference:.2}rad"
+            ));
+        }
+    }
+
+    match (frame.ball, comparison.ball) {
+        (Some(_), None) => Some("ball is missing".to_string()),
+        (None, Some(_)) => Some("ball was added".to_string()),
+        (Some(ball), Some(comparison_ball)) => {
+            let position_difference = distance(ball.position, comparison_ball.position);
+            (position_difference > DIVERGENCE_DISTANCE_TOLERANCE)
+                .then(|| format!("ball is {position_difference:.2}m apart"))
+        }
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::{point, vector};
+    use types::field_dimensions::Side;
+
+    use super::*;
+
+    fn frame(milliseconds: u64, ball_x: f32) -> TimelineFrame {
+        TimelineFrame {
+            now: SystemTime::UNIX_EPOCH + Duration::from_millis(milliseconds),
+            game_state: FilteredGameState::Initial,
+            ball: Some(SimulatedBall {
+                position: point![ball_x, 0.0],
+                velocity: vector![0.0, 0.0],
+                field_side: Side::Left,
+            }),
+            scenario_obstacles: Vec::new(),
+            robots: BTreeMap::new(),
+            robot_frames: BTreeMap::new(),
+            invariant_violations: Vec::new(),
+        }
+    }
+
+    fn timeline(ball_positions: &[f32]) -> Vec<TimelineFrame> {
+        ball_positions
+            .iter()
+            .enumerate()
+            .map(|(index, ball_x)| frame(index as u64 * 10, *ball_x))
+            .collect()
+    }
+
+    #[test]
+    fn equal_timelines_do_not_diverge() {
+        let frames = timeline(&[0.0, 0.1, 0.2, 0.3]);
+
+        assert!(first_divergence(&frames, &frames).is_none());
+    }
+
+    #[test]
+    fn divergence_is_found_at_first_differing_frame() {
+        let frames = timeline(&[0.0, 0.1, 0.2, 0.3]);
+        let comparison = timeline(&[0.0, 0.1, 0.205, 0.4]);
+
+        let divergence = first_divergence(&frames, &comparison).expect("timelines diverge");
+
+        assert_eq!(divergence.frame_index, 3);
+        assert_eq!(divergence.comparison_frame_index, 3);
+    }
+
+    #[test]
+    fn frames_are_aligned_by_time_since_start() {
+        let frames = timeline(&[0.0, 0.1, 0.2]);
+        let mut comparison = timeline(&[0.0, 0.1, 0.2]);
+        for frame in &mut comparison {
+            frame.now += Duration::from_secs(5);
+        }
+
+        assert!(first_divergence(&frames, &comparison).is_none());
+        assert_eq!(aligned_frame_index(&frames, 2, &comparison), 2);
+    }
+
+    #[test]
+    fn shorter_comparison_diverges_where_it_ends() {
+        let frames = timeline(&[0.0, 0.1, 0.2, 0.3]);
+        let comparison = timeline(&[0.0, 0.1]);
+
+        let divergence = first_divergence(&frames, &comparison).expect("timelines diverge");
+
+        assert_eq!(divergence.frame_index, 2);
+        assert_eq!(divergence.reason, "comparison timeline ended");
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    env,
+    fs::{File, create_dir_all, remove_file},
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use color_eyre::{
+    Report, Result,
+    eyre::{WrapErr, bail},
+};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use types::field_dimensions::FieldDimensions;
+
+use crate::{
+    behavior_tree_simulator::{
+        BehaviorTreeSimulatorSet, SimulationConfig, SimulatorFailure, SimulatorFieldDimensions,
+        SimulatorScenarioResult, SimulatorTimeline, SimulatorTimelineMarker,
+        SimulatorTimelineMarkers, TimelineFrame, record_timeline_frame,
+    },
+    timeline_viewer::TimelineViewerData,
+};
+
+/// Incremented whenever a change of the recorded types makes older files unreadable
+pub const TIMELINE_FILE_FORMAT_VERSION: u32 = 1;
+pub const TIMELINE_FILE_EXTENSION: &str = "timeline";
+/// Failing scenarios keep their timeline in this directory, so CI can attach it for inspection
+pub const TIMELINE_DIRECTORY_VARIABLE: &str = "BEVYHAVIOR_SIMULATOR_TIMELINE_DIRECTORY";
+
+#[derive(Serialize)]
+enum RecordToWrite<'a> {
+    Header {
+        field_dimensions: &'a FieldDimensions,
+        config: &'a SimulationConfig,
+    },
+    Frame(&'a TimelineFrame),
+    Marker(&'a SimulatorTimelineMarker),
+    Failure(&'a SimulatorFailure),
+}
+
+#[derive(Deserialize)]
+enum Record {
+    Header {
+        field_dimensions: FieldDimensions,
+        config: SimulationConfig,
+    },
+    Frame(Box<TimelineFrame>),
+    Marker(SimulatorTimelineMarker),
+    Failure(SimulatorFailure),
+}
+
+/// Streams a timeline as gzip compressed MessagePack records. Frames are written as they are
+/// recorded, so the file of an interrupted simulation is readable up to its last complete frame.
+pub struct TimelineWriter<W: Write> {
+    encoder: GzEncoder<W>,
+}
+
+impl TimelineWriter<BufWriter<File>> {
+    pub fn create(
+        path: &Path,
+        field_dimensions: &FieldDimensions,
+        config: &SimulationConfig,
+    ) -> Result<Self> {
+        let file =
+            File::create(path).wrap_err_with(|| format!("failed to create {}", path.display()))?;
+        Self::new(BufWriter::new(file), field_dimensions, config)
+    }
+}
+
+impl<W: Write> TimelineWriter<W> {
+    pub fn new(
+        writer: W,
+        field_dimensions: &FieldDimensions,
+        config: &SimulationConfig,
+    ) -> Result<Self> {
+        let mut timeline_writer = Self {
+            encoder: GzEncoder::new(writer, Compression::default()),
+        };
+        timeline_writer.write(&TIMELINE_FILE_FORMAT_VERSION)?;
+        timeline_writer.write(&RecordToWrite::Header {
+            field_dimensions,
+            config,
+        })?;
+        Ok(timeline_writer)
+    }
+
+    pub fn write_frame(&mut self, frame: &TimelineFrame) -> Result<()> {
+        self.write(&RecordToWrite::Frame(frame))
+    }
+
+    pub fn write_marker(&mut self, marker: &SimulatorTimelineMarker) -> Result<()> {
+        self.write(&RecordToWrite::Marker(marker))
+    }
+
+    pub fn write_failure(&mut self, failure: &SimulatorFailure) -> Result<()> {
+        self.write(&RecordToWrite::Failure(failure))
+    }
+
+    pub fn finish(self) -> Result<W> {
+        let mut writer = self
+            .encoder
+            .finish()
+            .wrap_err("failed to finish timeline compression")?;
+        writer.flush().wrap_err("failed to flush timeline")?;
+        Ok(writer)
+    }
+
+    fn write(&mut self, value: &impl Serialize) -> Result<()> {
+        rmp_serde::encode::write_named(&mut self.encoder, value)
+            .wrap_err("failed to write timeline record")
+    }
+}
+
+pub fn save_timeline(path: &Path, data: &TimelineViewerData) -> Result<()> {
+    let mut writer = TimelineWriter::create(path, &data.field_dimensions, &data.config)?;
+    for frame in &data.frames {
+        writer.write_frame(frame)?;
+    }
+    for marker in &data.markers {
+        writer.write_marker(marker)?;
+    }
+    for failure in &data.failures {
+        writer.write_failure(failure)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn load_timeline(path: &Path) -> Result<TimelineViewerData> {
+    let file = File::open(path).wrap_err_with(|| format!("failed to open {}", path.display()))?;
+    read_timeline(file).wrap_err_with(|| format!("failed to read timeline {}", path.display()))
+}
+
+/// Reads a timeline written by [`TimelineWriter`]. A truncated file, e.g. of a simulation that
+/// was killed, yields all records before the truncation.
+pub fn read_timeline(reader: impl Read) -> Result<TimelineViewerData> {
+    let mut reader = BufReader::new(GzDecoder::new(reader));
+    let format_version: u32 =
+        rmp_serde::from_read(&mut reader).wrap_err("failed to read format version")?;
+    if format_version != TIMELINE_FILE_FORMAT_VERSION {
+        bail!(
+            "unsupported format version {format_version}, expected {TIMELINE_FILE_FORMAT_VERSION}"
+        );
+    }
+    let Record::Header {
+        field_dimensions,
+        config,
+    } = rmp_serde::from_read(&mut reader).wrap_err("failed to read header")?
+    else {
+        bail!("timeline does not start with a header");
+    };
+
+    let mut data = TimelineViewerData {
+        field_dimensions,
+        config,
+        frames: Vec::new(),
+        markers: Vec::new(),
+        failures: Vec::new(),
+    };
+    loop {
+        match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(_) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error).wrap_err("failed to decompress timeline"),
+        }
+        let record = match rmp_serde::from_read(&mut reader) {
+            Ok(record) => record,
+            Err(error) if is_truncated(&error) => break,
+            Err(error) => return Err(error).wrap_err("failed to read timeline record"),
+        };
+        match record {
+            Record::Header { .. } => bail!("timeline contains a second header"),
+            Record::Frame(frame) => data.frames.push(*frame),
+            Record::Marker(marker) => data.markers.push(marker),
+            Record::Failure(failure) => data.failures.push(failure),
+        }
+    }
+    Ok(data)
+}
+
+fn is_truncated(error: &rmp_serde::decode::Error) -> bool {
+    match error {
+        rmp_serde::decode::Error::InvalidMarkerRead(error)
+        | rmp_serde::decode::Error::InvalidDataRead(error) => {
+            error.kind() == ErrorKind::UnexpectedEof
+        }
+        _ => false,
+    }
+}
+
+/// Streams the timeline to a file while the simulation runs. Markers and failures are appended
+/// once the app ran to completion.
+pub struct TimelineRecordingPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for TimelineRecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulatorTimelineRecording {
+            path: self.path.clone(),
+            writer: None,
+            error: None,
+        })
+        .add_systems(
+            Update,
+            stream_timeline_frame
+                .after(record_timeline_frame)
+                .in_set(BehaviorTreeSimulatorSet::RecordTimeline),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct SimulatorTimelineRecording {
+    pub path: PathBuf,
+    writer: Option<TimelineWriter<BufWriter<File>>>,
+    error: Option<Report>,
+}
+
+impl SimulatorTimelineRecording {
+    fn open_writer(
+        &mut self,
+        field_dimensions: &FieldDimensions,
+        config: &SimulationConfig,
+    ) -> Result<&mut TimelineWriter<BufWriter<File>>> {
+        if self.writer.is_none() {
+            self.writer = Some(TimelineWriter::create(
+                &self.path,
+                field_dimensions,
+                config,
+            )?);
+        }
+        Ok(self.writer.as_mut().expect("writer was created above"))
+    }
+
+    fn finish(mut self, world: &World) -> Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let writer = self.open_writer(
+            &world.resource::<SimulatorFieldDimensions>().0,
+            world.resource::<SimulationConfig>(),
+        )?;
+        for marker in &world.resource::<SimulatorTimelineMarkers>().markers {
+            writer.write_marker(marker)?;
+        }
+        for failure in &world.resource::<SimulatorScenarioResult>().failures {
+            writer.write_failure(failure)?;
+        }
+        self.writer
+            .take()
+            .expect("writer was created above")
+            .finish()?;
+        Ok(())
+    }
+}
+
+pub fn stream_timeline_frame(
+    field_dimensions: Res<SimulatorFieldDimensions>,
+    config: Res<SimulationConfig>,
+    timeline: Res<SimulatorTimeline>,
+    mut recording: ResMut<SimulatorTimelineRecording>,
+) {
+    if recording.error.is_some() {
+        return;
+    }
+    let Some(frame) = timeline.frames.last() else {
+        return;
+    };
+    if let Err(error) = recording
+        .open_writer(&field_dimensions.0, &config)
+        .and_then(|writer| writer.write_frame(frame))
+    {
+        recording.writer = None;
+        recording.error = Some(error);
+    }
+}
+
+/// Appends markers and failures to the recording of [`TimelineRecordingPlugin`] and completes the
+/// file. Does nothing if the timeline is not recorded.
+pub fn finish_timeline_recording(world: &mut World) -> Result<()> {
+    let Some(recording) = world.remove_resource::<SimulatorTimelineRecording>() else {
+        return Ok(());
+    };
+    let path = recording.path.clone();
+    recording
+        .finish(world)
+        .wrap_err_with(|| format!("failed to record timeline to {}", path.display()))
+}
+
+/// Records the timeline of the scenario `name` into BEVYHAVIOR_SIMULATOR_TIMELINE_DIRECTORY if the
+/// variable is set. Hand the returned path to [`keep_timeline_of_failure`] after the run.
+pub fn record_timeline_to_directory(app: &mut App, name: &str) -> Result<Option<PathBuf>> {
+    let Some(directory) = env::var_os(TIMELINE_DIRECTORY_VARIABLE) else {
+        return Ok(None);
+    };
+    create_dir_all(&directory)
+        .wrap_err_with(|| format!("failed to create {}", directory.display()))?;
+    let path = Path::new(&directory).join(format!("{name}.{TIMELINE_FILE_EXTENSION}"));
+    app.add_plugins(TimelineRecordingPlugin { path: path.clone() });
+    Ok(Some(path))
+}
+
+/// Removes the timeline recorded by [`record_timeline_to_directory`] if the scenario passed.
+pub fn keep_timeline_of_failure(timeline_path: Option<PathBuf>, result: &Result<()>) -> Result<()> {
+    let Some(timeline_path) = timeline_path else {
+        return Ok(());
+    };
+    if result.is_ok() {
+        remove_file(&timeline_path)
+            .wrap_err_with(|| format!("failed to remove {}", timeline_path.display()))?;
+    } else if timeline_path.exists() {
+        eprintln!("timeline saved to {}", timeline_path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use linear_algebra::{point, vector};
+    use types::{field_dimensions::Side, filtered_game_state::FilteredGameState};
+
+    use crate::behavior_tree_simulator::SimulatedBall;
+
+    use super::*;
+
+    fn frames(count: u64) -> Vec<TimelineFrame> {
+        (0..count)
+            .map(|index| TimelineFrame {
+                now: SystemTime::UNIX_EPOCH + Duration::from_millis(index * 10),
+                game_state: FilteredGameState::Ready,
+                ball: Some(SimulatedBall {
+                    position: point![(index as f32 * 0.37).sin(), (index as f32 * 0.11).cos()],
+                    velocity: vector![index as f32, -(index as f32)],
+                    field_side: Side::Right,
+                }),
+                scenario_obstacles: Vec::new(),
+                robots: Default::default(),
+                robot_frames: Default::default(),
+                invariant_violations: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn write_timeline(frames: &[TimelineFrame], failure: &SimulatorFailure) -> Vec<u8> {
+        let mut writer = TimelineWriter::new(
+            Vec::new(),
+            &FieldDimensions::SPL_2025,
+            &SimulationConfig::default(),
+        )
+        .unwrap();
+        for frame in frames {
+            writer.write_frame(frame).unwrap();
+        }
+        writer.write_failure(failure).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn timeline_round_trips_through_file_format() {
+        let frames = frames(100);
+        let failure = SimulatorFailure::Scenario {
+            time: frames[42].now,
+            message: "ball was not scored".to_string(),
+        };
+
+        let data = read_timeline(write_timeline(&frames, &failure).as_slice()).unwrap();
+
+        assert_eq!(data.frames.len(), frames.len());
+        assert_eq!(data.frames[42].now, frames[42].now);
+        assert_eq!(
+            data.frames[42].ball.unwrap().position,
+            frames[42].ball.unwrap().position
+        );
+        assert_eq!(
+            data.field_dimensions.length,
+            FieldDimensions::SPL_2025.length
+        );
+        assert_eq!(data.failures.len(), 1);
+        assert_eq!(data.failures[0].to_string(), failure.to_string());
+    }
+
+    #[test]
+    fn truncated_timeline_keeps_complete_frames() {
+        let frames = frames(2000);
+        let failure = SimulatorFailure::Scenario {
+            time: frames[0].now,
+            message: "interrupted".to_string(),
+        };
+        let bytes = write_timeline(&frames, &failure);
+
+        let data = read_timeline(&bytes[..bytes.len() / 2]).unwrap();
+
+        assert!(!data.frames.is_empty());
+        assert!(data.frames.len() < frames.len());
+        assert!(data.failures.is_empty());
+        let last_frame = data.frames.last().unwrap();
+        assert_eq!(last_frame.now, frames[data.frames.len() - 1].now);
+    }
+}
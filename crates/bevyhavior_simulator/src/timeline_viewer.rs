@@ -17,7 +17,7 @@ use eframe::{
 };
 use egui_dock::{DockArea, DockState, Node, Split, TabViewer};
 use hsl_network_messages::{PlayerNumber, Team};
-use linear_algebra::{Orientation2, Pose2, point, vector};
+use linear_algebra::{Orientation2, Pose2, distance, point, vector};
 use serde_json::{Value, json};
 use twix_legacy::{
     behavior_tree::BehaviorTreeVisualizer,
@@ -31,7 +31,8 @@ use types::{
 
 use crate::behavior_tree_simulator::{
     SimulationConfig, SimulatorFailure, SimulatorObstacle, SimulatorRobotId,
-    SimulatorTimelineMarker, TimelineFrame,
+    SimulatorTimelineMarker, TimelineDivergence, TimelineFrame, aligned_frame_index,
+    first_divergence,
 };
 
 const SCRUBBER_MARGIN: f32 = 8.0;
@@ -41,6 +42,7 @@ const MIN_PLAYBACK_SPEED: f32 = 1.0 / 8.0;
 const MAX_PLAYBACK_SPEED: f32 = 256.0;
 const WALK_PATH_LINE_COLOR: Color32 = Color32::from_rgba_premultiplied(0, 0, 202, 150);
 const WALK_PATH_ARC_COLOR: Color32 = Color32::from_rgba_premultiplied(136, 170, 182, 150);
+const DIVERGENCE_COLOR: Color32 = Color32::from_rgb(255, 0, 255);
 
 #[derive(Debug)]
 pub struct TimelineViewerData {
@@ -52,10 +54,23 @@ pub struct TimelineViewerData {
 }
 
 pub fn show_timeline_viewer(data: TimelineViewerData) -> Result<()> {
+    run_viewer(TimelineViewerApp::new(data, None))
+}
+
+/// Shows two timelines of the same scenario side by side, e.g. before and after a behavior change,
+/// and marks the frame at which they diverged.
+pub fn show_timeline_comparison(
+    data: TimelineViewerData,
+    comparison: TimelineViewerData,
+) -> Result<()> {
+    run_viewer(TimelineViewerApp::new(data, Some(comparison)))
+}
+
+fn run_viewer(app: TimelineViewerApp) -> Result<()> {
     run_native(
         "Behavior Tree Simulator",
         NativeOptions::default(),
-        Box::new(move |_creation_context| Ok(Box::new(TimelineViewerApp::new(data)))),
+        Box::new(move |_creation_context| Ok(Box::new(app))),
     )
     .map_err(|error| Report::msg(error.to_string()))
     .wrap_err("failed to run behavior tree simulator viewer")
@@ -76,24 +91,62 @@ struct TimelineViewerApp {
     selected_trace_robot: Option<SimulatorRobotId>,
     behavior_tree_visualizer: BehaviorTreeVisualizer,
     dock_state: DockState<TimelineViewerTab>,
+    comparison: Option<TimelineComparison>,
+}
+
+struct TimelineComparison {
+    data: TimelineViewerData,
+    divergence: Option<TimelineDivergence>,
+    behavior_tree_visualizer: BehaviorTreeVisualizer,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum TimelineViewerTab {
     Field,
     BehaviorTree,
+    ComparisonField,
+    ComparisonBehaviorTree,
 }
 
 impl TimelineViewerApp {
-    fn new(data: TimelineViewerData) -> Self {
+    fn new(mut data: TimelineViewerData, comparison: Option<TimelineViewerData>) -> Self {
         let mut dock_state = DockState::new(vec![TimelineViewerTab::Field]);
-        dock_state.split(
+        let [field, behavior_tree] = dock_state.split(
             (0.into(), 0.into()),
             Split::Below,
             2.0 / 3.0,
             Node::leaf(TimelineViewerTab::BehaviorTree),
         );
 
+        let comparison = comparison.map(|comparison| {
+            dock_state.split(
+                (0.into(), field),
+                Split::Right,
+                0.5,
+                Node::leaf(TimelineViewerTab::ComparisonField),
+            );
+            dock_state.split(
+                (0.into(), behavior_tree),
+                Split::Right,
+                0.5,
+                Node::leaf(TimelineViewerTab::ComparisonBehaviorTree),
+            );
+
+            let divergence = first_divergence(&data.frames, &comparison.frames);
+            if let Some(divergence) = &divergence {
+                data.markers.push(SimulatorTimelineMarker {
+                    frame_time: divergence.time,
+                    color: DIVERGENCE_COLOR,
+                    label: format!("diverged: {}", divergence.reason),
+                });
+            }
+            TimelineComparison {
+                data: comparison,
+                divergence,
+                behavior_tree_visualizer: BehaviorTreeVisualizer::default(),
+            }
+        });
+
         Self {
             data,
             selected_frame: 0,
@@ -109,9 +162,20 @@ impl TimelineViewerApp {
             selected_trace_robot: None,
             behavior_tree_visualizer: BehaviorTreeVisualizer::default(),
             dock_state,
+            comparison,
         }
     }
 
+    fn comparison_frame(&self) -> Option<usize> {
+        self.comparison.as_ref().map(|comparison| {
+            aligned_frame_index(
+                &self.data.frames,
+                self.selected_frame,
+                &comparison.data.frames,
+            )
+        })
+    }
+
     fn selected_frame(&self) -> Option<&TimelineFrame> {
         self.data.frames.get(self.selected_frame)
     }
@@ -351,12 +415,85 @@ impl TimelineViewerApp {
                     }
                 }
 
+                if self.comparison.is_some() {
+                    ui.separator();
+                    self.show_comparison(ui);
+                }
+
                 ui.separator();
                 self.show_state_inspector(ui);
                 ui.allocate_space(ui.available_size());
             });
     }
 
+    fn show_comparison(&mut self, ui: &mut Ui) {
+        let comparison_frame_index = self.comparison_frame();
+        let Some(comparison) = &self.comparison else {
+            return;
+        };
+
+        ui.heading("Comparison");
+        if let Some(comparison_frame_index) = comparison_frame_index {
+            ui.label(format!("index: {comparison_frame_index}"));
+        }
+        let jump_to_divergence = match &comparison.divergence {
+            Some(divergence) => {
+                ui.colored_label(
+                    DIVERGENCE_COLOR,
+                    format!(
+                        "diverged at {}: {}",
+                        format_time(divergence.time),
+                        divergence.reason
+                    ),
+                );
+                ui.button("jump to divergence")
+                    .clicked()
+                    .then_some(divergence.frame_index)
+            }
+            None => {
+                ui.colored_label(Color32::LIGHT_GREEN, "no divergence");
+                None
+            }
+        };
+
+        if let (Some(frame), Some(comparison_frame)) = (
+            self.selected_frame(),
+            comparison_frame_index.and_then(|index| comparison.data.frames.get(index)),
+        ) {
+            for (robot_id, robot) in &frame.robots {
+                let Some(comparison_robot) = comparison_frame.robots.get(robot_id) else {
+                    ui.label(format!("robot {robot_id}: missing"));
+                    continue;
+                };
+                let position_difference = distance(
+                    robot.ground_to_world.translation(),
+                    comparison_robot.ground_to_world.translation(),
+                );
+                let motion = comparison_frame
+                    .robot_frames
+                    .get(robot_id)
+                    .map_or("no motion", |robot_frame| {
+                        motion_name(&robot_frame.motion_command)
+                    });
+                ui.label(format!(
+                    "robot {robot_id}: {position_difference:.2}m apart, {motion}"
+                ));
+            }
+        }
+
+        if !comparison.data.failures.is_empty() {
+            ui.label("failures:");
+            for failure in &comparison.data.failures {
+                ui.label(failure.to_string());
+            }
+        }
+
+        if let Some(frame_index) = jump_to_divergence {
+            self.is_playing = false;
+            self.set_selected_frame(frame_index);
+        }
+    }
+
     fn show_state_inspector(&mut self, ui: &mut Ui) {
         ui.heading("State Inspector");
         ui.horizontal(|ui| {
@@ -451,12 +588,15 @@ impl TimelineViewerApp {
 
     fn show_dock_area(&mut self, context: &Context) {
         CentralPanel::default().show(context, |ui| {
+            let comparison_frame = self.comparison_frame().unwrap_or_default();
             let mut tab_viewer = TimelineDockViewer {
                 data: &self.data,
                 selected_frame: self.selected_frame,
                 zoom_and_pan: &mut self.zoom_and_pan,
                 selected_trace_robot: &mut self.selected_trace_robot,
                 behavior_tree_visualizer: &mut self.behavior_tree_visualizer,
+                comparison: self.comparison.as_mut(),
+                comparison_frame,
             };
             DockArea::new(&mut self.dock_state).show_inside(ui, &mut tab_viewer);
         });
@@ -686,6 +826,8 @@ struct TimelineDockViewer<'a> {
     zoom_and_pan: &'a mut ZoomAndPanTransform,
     selected_trace_robot: &'a mut Option<SimulatorRobotId>,
     behavior_tree_visualizer: &'a mut BehaviorTreeVisualizer,
+    comparison: Option<&'a mut TimelineComparison>,
+    comparison_frame: usize,
 }
 
 impl TabViewer for TimelineDockViewer<'_> {
@@ -705,6 +847,27 @@ impl TabViewer for TimelineDockViewer<'_> {
                     self.behavior_tree_visualizer,
                 );
             }
+            TimelineViewerTab::ComparisonField => {
+                if let Some(comparison) = &self.comparison {
+                    show_map(
+                        ui,
+                        &comparison.data,
+                        self.comparison_frame,
+                        self.zoom_and_pan,
+                    );
+                }
+            }
+            TimelineViewerTab::ComparisonBehaviorTree => {
+                if let Some(comparison) = &mut self.comparison {
+                    show_behavior_tree(
+                        ui,
+                        &comparison.data,
+                        self.comparison_frame,
+                        self.selected_trace_robot,
+                        &mut comparison.behavior_tree_visualizer,
+                    );
+                }
+            }
         }
     }
 
@@ -712,6 +875,8 @@ impl TabViewer for TimelineDockViewer<'_> {
         match tab {
             TimelineViewerTab::Field => "Field".into(),
             TimelineViewerTab::BehaviorTree => "Behavior Tree".into(),
+            TimelineViewerTab::ComparisonField => "Comparison Field".into(),
+            TimelineViewerTab::ComparisonBehaviorTree => "Comparison Behavior Tree".into(),
         }
     }
 }
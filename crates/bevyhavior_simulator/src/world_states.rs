@@ -19,6 +19,8 @@ use crate::{
     communication::player_states_from_received_hsl_messages,
     coordinates::{ground_to_field_from_world, world_to_field_transform},
     game_controller::{filtered_game_controller_state_for_team, global_field_side_for_team},
+    network::SimulatorNetwork,
+    perception::{SimulatorPerception, is_occluded},
 };
 
 #[derive(Resource, Clone, Debug, Default)]
@@ -32,6 +34,7 @@ pub fn build_world_states(
     rule_obstacles: Res<SimulatorRuleObstacles>,
     scenario_obstacles: Res<SimulatorScenarioObstacles>,
     config: Res<SimulationConfig>,
+    network: Res<SimulatorNetwork>,
     robots: Query<(
         &SimulatorRobot,
         &SimulatorGroundToWorld,
@@ -40,6 +43,7 @@ pub fn build_world_states(
         &SimulatorFallDownState,
         &SimulatorSuggestedSearchPosition,
     )>,
+    mut perception: ResMut<SimulatorPerception>,
     mut world_states: ResMut<SimulatorWorldStates>,
 ) {
     world_states.0.clear();
@@ -61,27 +65,42 @@ pub fn build_world_states(
         let robot_id = robot.id();
         let global_field_side =
             global_field_side_for_team(&game_state.game_controller_state, robot.team);
-        let ground_to_field =
-            ground_to_field_from_world(ground_to_world.ground_to_world, global_field_side);
-        let perceived_ball = perceived_ball_from_pose(
+        let robot_perception = perception.robot(robot_id, config.perception.seed);
+        let ground_to_field = robot_perception.localize(
+            ground_to_field_from_world(ground_to_world.ground_to_world, global_field_side),
+            &config.perception.localization_drift,
+            clock.tick_duration,
+        );
+        let visible_ball = perceived_ball_from_pose(
             ball.state,
             ground_to_world.ground_to_world,
             global_field_side,
             clock.now,
             head_yaw.yaw,
+            &robot_poses,
             &config,
         );
-        let generated_obstacles = perceived_robot_obstacles_from_pose(
+        let visible_robots = perceived_robot_obstacles_from_pose(
             robot_id,
             ground_to_world.ground_to_world,
             head_yaw.yaw,
             &robot_poses,
             &config,
         );
+        let detections = robot_perception.detect(
+            visible_ball,
+            visible_robots,
+            ground_to_world.ground_to_world,
+            ground_to_field,
+            head_yaw.yaw,
+            clock.now,
+            &config,
+        );
+        let detections = robot_perception.delay(detections, config.perception.detection_latency);
         let obstacles = scenario_obstacles
             .obstacles
             .iter()
-            .chain(generated_obstacles.iter())
+            .chain(detections.obstacles.iter())
             .copied()
             .map(|obstacle| obstacle.to_world_state_obstacle(ground_to_world.ground_to_world))
             .collect();
@@ -97,9 +116,12 @@ pub fn build_world_states(
         world_states.0.insert(
             robot_id,
             WorldState {
-                ball: perceived_ball,
+                ball: detections.ball,
                 filtered_game_controller_state: Some(filtered_game_controller_state_for_team(
-                    &game_state.game_controller_state,
+                    network
+                        .game_controller_states
+                        .get(&robot_id)
+                        .unwrap_or(&game_state.game_controller_state),
                     robot.team,
                 )),
                 hypothetical_ball_positions: Vec::new(),
@@ -114,6 +136,7 @@ pub fn build_world_states(
                     ground_to_field: Some(ground_to_field),
                     player_number: robot.player_number,
                     primary_state: primary_state.primary_state,
+                    role: None,
                 },
                 rule_ball: ball.state.map(|ball| {
                     ball.to_ball_state(
@@ -125,6 +148,8 @@ pub fn build_world_states(
                 rule_obstacles,
                 fall_down_state: fall_down_state.fall_down_state,
                 suggested_search_position: suggested_search_position.position,
+                search_candidates: Vec::new(),
+                searched_area: Default::default(),
             },
         );
     }
@@ -166,6 +191,7 @@ fn perceived_ball_from_pose(
     global_field_side: GlobalFieldSide,
     now: SystemTime,
     head_yaw: Orientation2<Ground>,
+    robot_poses: &[(SimulatorRobotId, Isometry2<Ground, World>)],
     config: &SimulationConfig,
 ) -> Option<BallState> {
     let ball = ball?;
@@ -173,6 +199,10 @@ fn perceived_ball_from_pose(
     if !is_visible_from_head(ball_in_ground, head_yaw, config) {
         return None;
     }
+    let occluders = robot_positions_in_ground(ground_to_world, robot_poses);
+    if config.perception.occlusion && is_occluded(ball_in_ground, &occluders, config.robot_radius) {
+        return None;
+    }
 
     Some(ball.to_ball_state(ground_to_world, global_field_side, now))
 }
@@ -193,9 +223,28 @@ fn perceived_robot_obstacles_from_pose(
 
             let position = ground_to_world * Point2::origin();
             let position_in_receiver_ground = receiver_ground_to_world.inverse() * position;
-            is_visible_from_head(position_in_receiver_ground, receiver_head_yaw, config).then(
-                || SimulatorObstacle::robot(position, config.robot_radius, config.robot_radius),
-            )
+            let occluders = robot_positions_in_ground(receiver_ground_to_world, robot_poses);
+            let is_hidden = config.perception.occlusion
+                && is_occluded(position_in_receiver_ground, &occluders, config.robot_radius);
+            (is_visible_from_head(position_in_receiver_ground, receiver_head_yaw, config)
+                && !is_hidden)
+                .then(|| {
+                    SimulatorObstacle::robot(position, config.robot_radius, config.robot_radius)
+                })
+        })
+        .collect()
+}
+
+/// Positions of all robots relative to the viewer. Neither the viewer nor the viewed robot occlude
+/// themselves, since they are not located between the viewer and the viewed position.
+fn robot_positions_in_ground(
+    viewer_ground_to_world: Isometry2<Ground, World>,
+    robot_poses: &[(SimulatorRobotId, Isometry2<Ground, World>)],
+) -> Vec<Point2<Ground>> {
+    robot_poses
+        .iter()
+        .map(|(_, ground_to_world)| {
+            viewer_ground_to_world.inverse() * (ground_to_world * Point2::origin())
         })
         .collect()
 }
@@ -260,6 +309,7 @@ mod tests {
                 age: Duration::from_millis(500),
                 position: point![x + 1.0, y],
             }),
+            searched_area: Default::default(),
         })
     }
 
@@ -289,6 +339,7 @@ mod tests {
                 GlobalFieldSide::Home,
                 SystemTime::UNIX_EPOCH,
                 Orientation2::new(FRAC_PI_2),
+                &[],
                 &config,
             )
             .is_some()
@@ -300,6 +351,7 @@ mod tests {
                 GlobalFieldSide::Home,
                 SystemTime::UNIX_EPOCH,
                 Orientation2::new(FRAC_PI_2),
+                &[],
                 &config,
             )
             .is_none()
@@ -328,6 +380,8 @@ mod tests {
                 robot_radius: 0.25,
                 ..Default::default()
             })
+            .insert_resource(SimulatorNetwork::default())
+            .insert_resource(SimulatorPerception::default())
             .insert_resource(SimulatorWorldStates::default())
             .add_systems(Update, build_world_states);
 
@@ -418,6 +472,8 @@ mod tests {
             .insert_resource(SimulatorRuleObstacles::default())
             .insert_resource(SimulatorScenarioObstacles::default())
             .insert_resource(SimulationConfig::default())
+            .insert_resource(SimulatorNetwork::default())
+            .insert_resource(SimulatorPerception::default())
             .insert_resource(SimulatorWorldStates::default())
             .add_systems(Update, build_world_states);
         app.world_mut().spawn((
@@ -483,6 +539,8 @@ mod tests {
             .insert_resource(SimulatorRuleObstacles::default())
             .insert_resource(SimulatorScenarioObstacles::default())
             .insert_resource(SimulationConfig::default())
+            .insert_resource(SimulatorNetwork::default())
+            .insert_resource(SimulatorPerception::default())
             .insert_resource(SimulatorWorldStates::default())
             .add_systems(Update, build_world_states);
         app.world_mut().spawn((
@@ -545,6 +603,8 @@ mod tests {
             .insert_resource(SimulatorRuleObstacles::default())
             .insert_resource(SimulatorScenarioObstacles::default())
             .insert_resource(SimulationConfig::default())
+            .insert_resource(SimulatorNetwork::default())
+            .insert_resource(SimulatorPerception::default())
             .insert_resource(SimulatorWorldStates::default())
             .add_systems(
                 Update,
@@ -606,6 +666,8 @@ mod tests {
             .insert_resource(SimulatorRuleObstacles::default())
             .insert_resource(SimulatorScenarioObstacles::default())
             .insert_resource(SimulationConfig::default())
+            .insert_resource(SimulatorNetwork::default())
+            .insert_resource(SimulatorPerception::default())
             .insert_resource(SimulatorWorldStates::default())
             .add_systems(Update, build_world_states);
 
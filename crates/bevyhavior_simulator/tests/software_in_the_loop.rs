@@ -0,0 +1,69 @@
+#![cfg(feature = "software_in_the_loop")]
+
+use bevy::prelude::*;
+use bevyhavior_simulator::behavior_tree_simulator::{
+    AppExt, BehaviorTreeSimulatorPlugin, BehaviorTreeSimulatorSet, SimulatorRobotBundle,
+    SimulatorRobotFrames, SoftwareInTheLoopConfig, SoftwareInTheLoopPlugin,
+    default_behavior_parameters,
+};
+use color_eyre::Result;
+use hsl_network_messages::{PlayerNumber, Team};
+use linear_algebra::{Isometry2, vector};
+use types::primary_state::PrimaryState;
+
+const TICKS_WITH_MOTION_COMMAND: u32 = 10;
+const MAXIMUM_TICKS: u32 = 100;
+
+#[test]
+fn robot_stack_publishes_a_motion_command_every_tick() -> Result<()> {
+    let software_in_the_loop = SoftwareInTheLoopPlugin::new(SoftwareInTheLoopConfig {
+        // the scenario tests of the software_in_the_loop binary use the ports from 7449 on
+        listen_endpoint: "tcp/127.0.0.1:7440".to_string(),
+        ..Default::default()
+    })?;
+
+    let mut app = App::new();
+    app.add_plugins(BehaviorTreeSimulatorPlugin {
+        enable_default_behavior: false,
+        ..Default::default()
+    })
+    .add_plugins(software_in_the_loop)
+    .add_systems(
+        Update,
+        count_motion_commands.in_set(BehaviorTreeSimulatorSet::Scenario),
+    );
+    app.world_mut().spawn(
+        SimulatorRobotBundle::new(
+            Team::Hulks,
+            PlayerNumber::Three,
+            Isometry2::from_parts(vector![-1.0, 0.0], 0.0),
+            default_behavior_parameters()?,
+        )?
+        .with_primary_state(PrimaryState::Playing),
+    );
+
+    app.run_to_completion()
+}
+
+/// The plugin fails the run if the stack misses a tick, so every frame carries a fresh command.
+fn count_motion_commands(
+    robot_frames: Res<SimulatorRobotFrames>,
+    mut ticks: Local<u32>,
+    mut ticks_with_motion_command: Local<u32>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    *ticks += 1;
+    if !robot_frames.0.is_empty() {
+        *ticks_with_motion_command += 1;
+    }
+
+    if *ticks_with_motion_command >= TICKS_WITH_MOTION_COMMAND {
+        exit.write(AppExit::Success);
+    } else if *ticks >= MAXIMUM_TICKS {
+        println!(
+            "failed: only {} ticks with motion command",
+            *ticks_with_motion_command
+        );
+        exit.write(AppExit::from_code(1));
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::BTreeSet;
+
+use convert_case::{Case, Casing};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use source_analyzer::{
+    contexts::Field,
+    cyclers::Cyclers,
+    error::Error,
+    node::Node,
+    ros_z::{PublicationKind, RosZNode, SubscriptionKind},
+};
+
+/// Generates a ros-z wrapper module for every node of the given cyclers whose snake case name is
+/// listed in `node_names`. Listed nodes that cannot be expressed with ros-z endpoints are reported
+/// as error, they have to be ported by hand.
+pub fn generate_ros_z_nodes(cyclers: &Cyclers, node_names: &[&str]) -> Result<TokenStream, Error> {
+    let mut generated_modules = BTreeSet::new();
+    let nodes = cyclers
+        .cyclers
+        .iter()
+        .flat_map(|cycler| cycler.iter_nodes())
+        .filter(|node| {
+            let name = node.name.to_case(Case::Snake);
+            node_names.contains(&name.as_str()) && generated_modules.insert(name)
+        })
+        .map(|node| {
+            let ros_z_node = RosZNode::try_from_node(node).map_err(|source| Error::Node {
+                source,
+                node: node.name.clone(),
+                path: node.file_path.clone(),
+            })?;
+            Ok(generate_ros_z_node(node, &ros_z_node))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(quote! {
+        #(#nodes)*
+    })
+}
+
+/// The wrapper cycles the node once per message on its first input, the other inputs are read from
+/// caches. Nodes without inputs cycle every 10 ms.
+pub fn generate_ros_z_node(node: &Node, ros_z_node: &RosZNode) -> TokenStream {
+    let module_name = format_ident!("{}", ros_z_node.name);
+    let node_name = &ros_z_node.name;
+    let node_module = &node.module;
+    let node_type = format_ident!("{}", node.name);
+    let creation_error_message = format!("failed to create node `{}`", node.name);
+    let cycle_error_message = format!("failed to execute cycle of `{}`", node.name);
+
+    let parameters_struct = generate_parameters_struct(ros_z_node);
+    let bind_parameters = generate_bind_parameters(ros_z_node);
+    let take_parameters_snapshot = generate_take_parameters_snapshot(ros_z_node);
+    let subscribers = generate_subscribers(ros_z_node);
+    let publishers = generate_publishers(ros_z_node);
+    let cycler_states = generate_cycler_states(ros_z_node);
+    let input_bindings = generate_input_bindings(ros_z_node);
+    let additional_output_slots = generate_additional_output_slots(ros_z_node);
+    let creation_context_initializers =
+        generate_context_initializers(&node.contexts.creation_context);
+    let cycle_context_initializers = generate_context_initializers(&node.contexts.cycle_context);
+    let main_outputs_binding = if node.contexts.main_outputs.is_empty() {
+        quote! { _ }
+    } else {
+        quote! { main_outputs }
+    };
+    let publish_outputs = generate_publish_outputs(ros_z_node);
+    let (create_timer, wait_for_timer) = if ros_z_node.subscriptions.is_empty() {
+        (
+            quote! {
+                let mut timer = node.create_timer(std::time::Duration::from_millis(10));
+            },
+            quote! {
+                timer.tick().await;
+            },
+        )
+    } else {
+        Default::default()
+    };
+
+    quote! {
+        pub mod #module_name {
+            use color_eyre::eyre::WrapErr;
+            use ros_z::prelude::*;
+
+            #parameters_struct
+
+            pub fn run_boxed(
+                ctx: std::sync::Arc<ros_z::context::Context>,
+            ) -> std::pin::Pin<Box<dyn std::future::Future<Output = color_eyre::Result<()>> + Send>> {
+                Box::pin(run(ctx))
+            }
+
+            pub async fn run(ctx: std::sync::Arc<ros_z::context::Context>) -> color_eyre::Result<()> {
+                let node = ctx.create_node(#node_name).build().await?;
+                #bind_parameters
+                #subscribers
+                #publishers
+                #cycler_states
+
+                #create_timer
+                let mut legacy_node = {
+                    #take_parameters_snapshot
+                    #node_module::#node_type::new(
+                        #node_module::CreationContext::new(
+                            #creation_context_initializers
+                        )
+                    )
+                    .wrap_err(#creation_error_message)?
+                };
+
+                loop {
+                    #wait_for_timer
+                    #input_bindings
+                    #take_parameters_snapshot
+                    #additional_output_slots
+
+                    let #main_outputs_binding = legacy_node
+                        .cycle(
+                            #node_module::CycleContext::new(
+                                #cycle_context_initializers
+                            ),
+                        )
+                        .wrap_err(#cycle_error_message)?;
+
+                    #publish_outputs
+                }
+            }
+        }
+    }
+}
+
+fn generate_parameters_struct(ros_z_node: &RosZNode) -> TokenStream {
+    if ros_z_node.parameters.is_empty() {
+        return Default::default();
+    }
+    let fields = ros_z_node.parameters.iter().map(|parameter| {
+        let name = &parameter.name;
+        let data_type = &parameter.data_type;
+        quote! { pub #name: #data_type, }
+    });
+    quote! {
+        #[derive(Clone, Debug, serde::Deserialize, serde::Serialize, ros_z::Message)]
+        #[serde(deny_unknown_fields)]
+        pub struct Parameters {
+            #(#fields)*
+        }
+    }
+}
+
+fn generate_bind_parameters(ros_z_node: &RosZNode) -> TokenStream {
+    if ros_z_node.parameters.is_empty() {
+        return Default::default();
+    }
+    let node_name = &ros_z_node.name;
+    quote! {
+        let parameters_binding = node.bind_parameter_as::<Parameters>(#node_name)?;
+    }
+}
+
+fn generate_take_parameters_snapshot(ros_z_node: &RosZNode) -> TokenStream {
+    if ros_z_node.parameters.is_empty() {
+        return Default::default();
+    }
+    quote! {
+        let parameters = parameters_binding.snapshot().typed().clone();
+    }
+}
+
+fn generate_subscribers(ros_z_node: &RosZNode) -> TokenStream {
+    ros_z_node
+        .subscriptions
+        .iter()
+        .enumerate()
+        .map(|(index, subscription)| {
+            let subscriber = format_ident!("{}_subscriber", subscription.name);
+            let data_type = &subscription.data_type;
+            let topic = &subscription.topic;
+            let is_trigger = index == 0;
+            let cache = if is_trigger {
+                Default::default()
+            } else {
+                quote! { .cache(1) }
+            };
+            quote! {
+                let #subscriber = node
+                    .subscriber::<#data_type>(#topic)
+                    #cache
+                    .build()
+                    .await?;
+            }
+        })
+        .collect()
+}
+
+fn generate_publishers(ros_z_node: &RosZNode) -> TokenStream {
+    ros_z_node
+        .publications
+        .iter()
+        .map(|publication| {
+            let publisher = format_ident!("{}_publisher", publication.name);
+            let data_type = &publication.data_type;
+            let topic = &publication.topic;
+            quote! {
+                let #publisher = node
+                    .publisher::<#data_type>(#topic)
+                    .build()
+                    .await?;
+            }
+        })
+        .collect()
+}
+
+fn generate_cycler_states(ros_z_node: &RosZNode) -> TokenStream {
+    ros_z_node
+        .cycler_states
+        .iter()
+        .map(|cycler_state| {
+            let name = format_ident!("{}_cycler_state", cycler_state.name);
+            let data_type = &cycler_state.data_type;
+            quote! {
+                let mut #name: #data_type = Default::default();
+            }
+        })
+        .collect()
+}
+
+fn generate_input_bindings(ros_z_node: &RosZNode) -> TokenStream {
+    ros_z_node
+        .subscriptions
+        .iter()
+        .enumerate()
+        .map(|(index, subscription)| {
+            let subscriber = format_ident!("{}_subscriber", subscription.name);
+            let input = format_ident!("{}_input", subscription.name);
+            let is_trigger = index == 0;
+            if is_trigger {
+                // wrapped like a cached message, the context initializers are the same for all inputs
+                return match subscription.kind {
+                    SubscriptionKind::Plain => quote! {
+                        let #input = std::sync::Arc::new(#subscriber.recv().await?);
+                    },
+                    SubscriptionKind::Optional => quote! {
+                        let #input = Some(std::sync::Arc::new(#subscriber.recv().await?));
+                    },
+                    SubscriptionKind::Required => quote! {
+                        let #input = std::sync::Arc::new(#subscriber.recv().await?);
+                        let Some(#input) = Option::as_ref(&#input) else {
+                            continue;
+                        };
+                    },
+                };
+            }
+            match subscription.kind {
+                SubscriptionKind::Plain => quote! {
+                    let Some(#input) = #subscriber.get_latest() else {
+                        continue;
+                    };
+                },
+                SubscriptionKind::Optional => quote! {
+                    let #input = #subscriber.get_latest();
+                },
+                SubscriptionKind::Required => quote! {
+                    let Some(#input) = #subscriber.get_latest() else {
+                        continue;
+                    };
+                    let Some(#input) = Option::as_ref(&#input) else {
+                        continue;
+                    };
+                },
+            }
+        })
+        .collect()
+}
+
+fn generate_additional_output_slots(ros_z_node: &RosZNode) -> TokenStream {
+    ros_z_node
+        .publications
+        .iter()
+        .filter(|publication| publication.kind == PublicationKind::Additional)
+        .map(|publication| {
+            let slot = format_ident!("{}_additional_output", publication.name);
+            quote! {
+                let mut #slot = None;
+            }
+        })
+        .collect()
+}
+
+fn generate_context_initializers(fields: &[Field]) -> TokenStream {
+    let initializers = fields.iter().map(|field| match field {
+        Field::AdditionalOutput { name, .. } => {
+            let publisher = format_ident!("{}_publisher", name);
+            let slot = format_ident!("{}_additional_output", name);
+            quote! {
+                framework::AdditionalOutput::new(#publisher.has_subscribers(), &mut #slot)
+            }
+        }
+        Field::CyclerState { name, .. } => {
+            let cycler_state = format_ident!("{}_cycler_state", name);
+            quote! { &mut #cycler_state }
+        }
+        Field::Input { name, path, .. } => {
+            let input = format_ident!("{}_input", name);
+            if path.contains_optional() {
+                quote! { #input.as_deref().and_then(Option::as_ref) }
+            } else {
+                quote! { #input.as_ref() }
+            }
+        }
+        Field::Parameter { name, path, .. } => {
+            if path.contains_optional() {
+                quote! { parameters.#name.as_ref() }
+            } else {
+                quote! { &parameters.#name }
+            }
+        }
+        Field::RequiredInput { name, .. } => {
+            let input = format_ident!("{}_input", name);
+            quote! { #input }
+        }
+        Field::HardwareInterface { name }
+        | Field::HistoricInput { name, .. }
+        | Field::MainOutput { name, .. }
+        | Field::PerceptionInput { name, .. } => {
+            panic!("unexpected field `{name}` in ros-z context")
+        }
+    });
+    quote! {
+        #(#initializers,)*
+    }
+}
+
+fn generate_publish_outputs(ros_z_node: &RosZNode) -> TokenStream {
+    ros_z_node
+        .publications
+        .iter()
+        .map(|publication| {
+            let name = &publication.name;
+            let publisher = format_ident!("{}_publisher", name);
+            match publication.kind {
+                PublicationKind::Main => quote! {
+                    #publisher.publish(&main_outputs.#name.value).await?;
+                },
+                PublicationKind::Additional => {
+                    let slot = format_ident!("{}_additional_output", name);
+                    quote! {
+                        if let Some(value) = &#slot {
+                            #publisher.publish(value).await?;
+                        }
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use source_analyzer::contexts::Contexts;
+    use syn::parse_str;
+
+    use super::*;
+
+    #[test]
+    fn wrapper_subscribes_inputs_and_publishes_outputs() {
+        let file = parse_str(
+            r#"
+            #[context]
+            pub struct CreationContext {}
+
+            #[context]
+            pub struct CycleContext {
+                ball: Input<Option<BallState>, "ball_state?">,
+                look_action: Parameter<LookActionParameters, "behavior.look_action">,
+            }
+
+            #[context]
+            pub struct MainOutputs {
+                pub position_of_interest: MainOutput<Point2<Ground>>,
+            }
+            "#,
+        )
+        .unwrap();
+        let node = Node {
+            name: "ActiveVision".to_string(),
+            module: parse_str("world_state::active_vision").unwrap(),
+            file_path: "world_state/src/active_vision.rs".into(),
+            contexts: Contexts::try_from_file(&file).unwrap(),
+        };
+        let ros_z_node = RosZNode::try_from_node(&node).unwrap();
+
+        let generated = generate_ros_z_node(&node, &ros_z_node).to_string();
+
+        let expected_fragments = [
+            quote! { ctx.create_node("active_vision") },
+            quote! { node.bind_parameter_as::<Parameters>("active_vision")? },
+            quote! { .subscriber::<Option<BallState> >("ball_state").build() },
+            quote! { Some(std::sync::Arc::new(ball_subscriber.recv().await?)) },
+            quote! { .publisher::<Point2<Ground> >("position_of_interest") },
+            quote! { ball_input.as_deref().and_then(Option::as_ref), &parameters.look_action, },
+            quote! { position_of_interest_publisher.publish(&main_outputs.position_of_interest.value) },
+        ];
+        for fragment in expected_fragments {
+            let fragment = fragment.to_string();
+            assert!(
+                generated.contains(&fragment),
+                "`{fragment}` missing in generated wrapper:\n{generated}"
+            );
+        }
+        assert!(
+            !generated.contains("create_timer"),
+            "node with inputs is cycled by a timer:\n{generated}"
+        );
+        syn::parse2::<syn::File>(generate_ros_z_node(&node, &ros_z_node)).unwrap();
+    }
+}
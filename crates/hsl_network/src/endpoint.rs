@@ -3,6 +3,7 @@ use std::{
     net::{Ipv4Addr as Ipv4AddrStd, SocketAddr, SocketAddrV4},
 };
 
+use hsl_network_messages::HulkMessage;
 use log::warn;
 use ros_z::Message;
 use serde::{Deserialize, Serialize};
@@ -67,7 +68,7 @@ impl Endpoint {
                 },
                 result = self.hsl_socket.recv_from(&mut hsl_buffer) => {
                     let (received_bytes, _address) = result.map_err(Error::ReadError)?;
-                    match bincode::deserialize(&hsl_buffer[0..received_bytes]) {
+                    match HulkMessage::try_from(&hsl_buffer[0..received_bytes]) {
                         Ok(parsed_message) => {
                             break Ok(IncomingMessage::Hsl(parsed_message));
                         }
@@ -88,25 +89,21 @@ impl Endpoint {
                 self.send_game_controller_visual_referee_message(destination, message)
                     .await;
             }
-            OutgoingMessage::Hsl(message) => match bincode::serialize(&message) {
-                Ok(message) => {
-                    let ipv4_addr: Ipv4AddrStd = self.ports.hsl_broadcast_address.into();
+            OutgoingMessage::Hsl(message) => {
+                let message: Vec<u8> = message.into();
+                let ipv4_addr: Ipv4AddrStd = self.ports.hsl_broadcast_address.into();
 
-                    if let Err(error) = self
-                        .hsl_socket
-                        .send_to(
-                            message.as_slice(),
-                            SocketAddr::new(ipv4_addr.into(), self.ports.hsl),
-                        )
-                        .await
-                    {
-                        warn!("Failed to send UDP datagram via HSL socket: {error:?}")
-                    }
+                if let Err(error) = self
+                    .hsl_socket
+                    .send_to(
+                        message.as_slice(),
+                        SocketAddr::new(ipv4_addr.into(), self.ports.hsl),
+                    )
+                    .await
+                {
+                    warn!("Failed to send UDP datagram via HSL socket: {error:?}")
                 }
-                Err(error) => {
-                    warn!("Failed to serialize Hulk Message: {error:?}")
-                }
-            },
+            }
         };
     }
 
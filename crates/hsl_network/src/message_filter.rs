@@ -1,7 +1,7 @@
 use color_eyre::{Result, eyre::Ok};
 use context_attribute::context;
 use framework::MainOutput;
-use hsl_network_messages::{HulkMessage, PlayerNumber, StateMessage};
+use hsl_network_messages::PlayerNumber;
 use serde::{Deserialize, Serialize};
 use types::messages::IncomingMessage;
 
@@ -32,9 +32,9 @@ impl MessageFilter {
             IncomingMessage::GameController(source_address, message) => Some(
                 IncomingMessage::GameController(*source_address, message.clone()),
             ),
-            IncomingMessage::Hsl(
-                message @ HulkMessage::State(StateMessage { player_number, .. }),
-            ) if player_number != context.player_number => Some(IncomingMessage::Hsl(*message)),
+            IncomingMessage::Hsl(message) if message.player_number() != context.player_number => {
+                Some(IncomingMessage::Hsl(*message))
+            }
             _ => None,
         };
         Ok(MainOutputs {
@@ -0,0 +1,372 @@
+//! Bit-packed wire format of [`HulkMessage`]s.
+//!
+//! Every message starts with a protocol version byte followed by a bit stream. Poses and ball
+//! positions are quantized to centimeters, angles to 256 steps per revolution and durations to
+//! 100 ms steps. Durations saturate at the largest representable value. The searched area of a
+//! state message is sent as one bit per cell.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use color_eyre::{Report, Result, eyre::bail};
+use coordinate_systems::Field;
+use linear_algebra::{Point2, Pose2, point};
+
+use crate::{
+    BallPosition, HulkMessage, PlayerNumber, SEARCHED_AREA_CELLS, SearchedArea, StateMessage,
+    StrikerMessage,
+};
+
+pub const HSL_PROTOCOL_VERSION: u8 = 2;
+
+const MESSAGE_KIND_BITS: u32 = 2;
+const PLAYER_NUMBER_BITS: u32 = 3;
+const POSITION_BITS: u32 = 12;
+const POSITION_RESOLUTION: f32 = 0.01;
+const ANGLE_BITS: u32 = 8;
+const BALL_AGE_BITS: u32 = 7;
+const TIME_TO_REACH_KICK_POSITION_BITS: u32 = 8;
+const DURATION_RESOLUTION: Duration = Duration::from_millis(100);
+const SEARCHED_AREA_BITS: u32 = SEARCHED_AREA_CELLS as u32;
+
+const STATE_MESSAGE_KIND: u32 = 0;
+const STRIKER_MESSAGE_KIND: u32 = 1;
+
+impl From<HulkMessage> for Vec<u8> {
+    fn from(message: HulkMessage) -> Self {
+        let mut writer = BitWriter::default();
+        match message {
+            HulkMessage::State(StateMessage {
+                player_number,
+                pose,
+                ball_position,
+                searched_area,
+            }) => {
+                writer.write(STATE_MESSAGE_KIND, MESSAGE_KIND_BITS);
+                writer.write_player_number(player_number);
+                writer.write_pose(pose);
+                writer.write(ball_position.is_some().into(), 1);
+                if let Some(ball_position) = ball_position {
+                    writer.write_ball_position(ball_position);
+                }
+                writer.write(searched_area.cells, SEARCHED_AREA_BITS);
+            }
+            HulkMessage::Striker(StrikerMessage {
+                player_number,
+                pose,
+                ball_position,
+                time_to_reach_kick_position,
+            }) => {
+                writer.write(STRIKER_MESSAGE_KIND, MESSAGE_KIND_BITS);
+                writer.write_player_number(player_number);
+                writer.write_pose(pose);
+                writer.write_ball_position(ball_position);
+                writer.write(
+                    quantize_duration(
+                        time_to_reach_kick_position,
+                        TIME_TO_REACH_KICK_POSITION_BITS,
+                    ),
+                    TIME_TO_REACH_KICK_POSITION_BITS,
+                );
+            }
+        }
+
+        let mut buffer = vec![HSL_PROTOCOL_VERSION];
+        buffer.extend(writer.bytes);
+        buffer
+    }
+}
+
+impl TryFrom<&[u8]> for HulkMessage {
+    type Error = Report;
+
+    fn try_from(buffer: &[u8]) -> Result<Self> {
+        let Some((&version, payload)) = buffer.split_first() else {
+            bail!("empty buffer");
+        };
+        if version != HSL_PROTOCOL_VERSION {
+            bail!("unexpected protocol version {version}, expected {HSL_PROTOCOL_VERSION}");
+        }
+
+        let mut reader = BitReader::new(payload);
+        let message = match reader.read(MESSAGE_KIND_BITS)? {
+            STATE_MESSAGE_KIND => HulkMessage::State(StateMessage {
+                player_number: reader.read_player_number()?,
+                pose: reader.read_pose()?,
+                ball_position: match reader.read(1)? {
+                    0 => None,
+                    _ => Some(reader.read_ball_position()?),
+                },
+                searched_area: SearchedArea {
+                    cells: reader.read(SEARCHED_AREA_BITS)?,
+                },
+            }),
+            STRIKER_MESSAGE_KIND => HulkMessage::Striker(StrikerMessage {
+                player_number: reader.read_player_number()?,
+                pose: reader.read_pose()?,
+                ball_position: reader.read_ball_position()?,
+                time_to_reach_kick_position: dequantize_duration(
+                    reader.read(TIME_TO_REACH_KICK_POSITION_BITS)?,
+                    TIME_TO_REACH_KICK_POSITION_BITS,
+                ),
+            }),
+            kind => bail!("unexpected message kind {kind}"),
+        };
+        Ok(message)
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    number_of_bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        for bit in (0..bits).rev() {
+            if self.number_of_bits % 8 == 0 {
+                self.bytes.push(0);
+            }
+            if (value >> bit) & 1 == 1 {
+                let last_byte = self.bytes.last_mut().expect("byte was pushed above");
+                *last_byte |= 0x80 >> (self.number_of_bits % 8);
+            }
+            self.number_of_bits += 1;
+        }
+    }
+
+    fn write_player_number(&mut self, player_number: PlayerNumber) {
+        let index = match player_number {
+            PlayerNumber::One => 0,
+            PlayerNumber::Two => 1,
+            PlayerNumber::Three => 2,
+            PlayerNumber::Four => 3,
+            PlayerNumber::Five => 4,
+        };
+        self.write(index, PLAYER_NUMBER_BITS);
+    }
+
+    fn write_point(&mut self, point: Point2<Field>) {
+        self.write(quantize_position(point.x()), POSITION_BITS);
+        self.write(quantize_position(point.y()), POSITION_BITS);
+    }
+
+    fn write_pose(&mut self, pose: Pose2<Field>) {
+        self.write_point(pose.position());
+        self.write(quantize_angle(pose.angle()), ANGLE_BITS);
+    }
+
+    fn write_ball_position(&mut self, ball_position: BallPosition<Field>) {
+        self.write_point(ball_position.position);
+        self.write(
+            quantize_duration(ball_position.age, BALL_AGE_BITS),
+            BALL_AGE_BITS,
+        );
+    }
+}
+
+struct BitReader<'buffer> {
+    bytes: &'buffer [u8],
+    position: usize,
+}
+
+impl<'buffer> BitReader<'buffer> {
+    fn new(bytes: &'buffer [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read(&mut self, bits: u32) -> Result<u32> {
+        let mut value = 0;
+        for _ in 0..bits {
+            let Some(byte) = self.bytes.get(self.position / 8) else {
+                bail!("message is truncated");
+            };
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_player_number(&mut self) -> Result<PlayerNumber> {
+        Ok(match self.read(PLAYER_NUMBER_BITS)? {
+            0 => PlayerNumber::One,
+            1 => PlayerNumber::Two,
+            2 => PlayerNumber::Three,
+            3 => PlayerNumber::Four,
+            4 => PlayerNumber::Five,
+            index => bail!("unexpected player number index {index}"),
+        })
+    }
+
+    fn read_point(&mut self) -> Result<Point2<Field>> {
+        let x = dequantize_position(self.read(POSITION_BITS)?);
+        let y = dequantize_position(self.read(POSITION_BITS)?);
+        Ok(point![x, y])
+    }
+
+    fn read_pose(&mut self) -> Result<Pose2<Field>> {
+        let position = self.read_point()?;
+        let angle = dequantize_angle(self.read(ANGLE_BITS)?);
+        Ok(Pose2::new(position, angle))
+    }
+
+    fn read_ball_position(&mut self) -> Result<BallPosition<Field>> {
+        Ok(BallPosition {
+            position: self.read_point()?,
+            age: dequantize_duration(self.read(BALL_AGE_BITS)?, BALL_AGE_BITS),
+        })
+    }
+}
+
+fn quantize_position(value: f32) -> u32 {
+    let offset = (1 << (POSITION_BITS - 1)) as f32;
+    let maximum = ((1 << POSITION_BITS) - 1) as f32;
+    (value / POSITION_RESOLUTION + offset)
+        .round()
+        .clamp(0.0, maximum) as u32
+}
+
+fn dequantize_position(value: u32) -> f32 {
+    let offset = (1 << (POSITION_BITS - 1)) as f32;
+    (value as f32 - offset) * POSITION_RESOLUTION
+}
+
+fn quantize_angle(angle: f32) -> u32 {
+    let steps = (1 << ANGLE_BITS) as f32;
+    ((angle / TAU * steps).round() as i32).rem_euclid(1 << ANGLE_BITS) as u32
+}
+
+fn dequantize_angle(value: u32) -> f32 {
+    let steps = 1 << ANGLE_BITS;
+    let signed_value = if value >= steps / 2 {
+        value as i32 - steps as i32
+    } else {
+        value as i32
+    };
+    signed_value as f32 / steps as f32 * TAU
+}
+
+fn quantize_duration(duration: Duration, bits: u32) -> u32 {
+    let maximum = (1 << bits) - 1;
+    (duration.as_nanos() / DURATION_RESOLUTION.as_nanos()).min(maximum as u128) as u32
+}
+
+/// The largest representable value stands for "at least this long" and decodes to
+/// [`Duration::MAX`].
+fn dequantize_duration(value: u32, bits: u32) -> Duration {
+    if value == (1 << bits) - 1 {
+        return Duration::MAX;
+    }
+    DURATION_RESOLUTION * value
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    fn assert_poses_match(actual: Pose2<Field>, expected: Pose2<Field>) {
+        assert_abs_diff_eq!(
+            actual.position().x(),
+            expected.position().x(),
+            epsilon = 0.005
+        );
+        assert_abs_diff_eq!(
+            actual.position().y(),
+            expected.position().y(),
+            epsilon = 0.005
+        );
+        assert_abs_diff_eq!(actual.angle(), expected.angle(), epsilon = TAU / 512.0);
+    }
+
+    #[test]
+    fn state_message_round_trips_within_quantization() {
+        let message = StateMessage {
+            player_number: PlayerNumber::Four,
+            pose: Pose2::new(point![-3.217, 2.004], 1.2),
+            ball_position: Some(BallPosition {
+                position: point![0.513, -1.499],
+                age: Duration::from_millis(420),
+            }),
+            searched_area: SearchedArea {
+                cells: 0b1010_0000_0000_0000_0000_0011,
+            },
+        };
+
+        let buffer: Vec<u8> = HulkMessage::State(message).into();
+        let HulkMessage::State(decoded) = HulkMessage::try_from(buffer.as_slice()).unwrap() else {
+            panic!("expected state message");
+        };
+
+        assert_eq!(decoded.player_number, PlayerNumber::Four);
+        assert_poses_match(decoded.pose, message.pose);
+        let ball_position = decoded.ball_position.unwrap();
+        assert_abs_diff_eq!(ball_position.position.x(), 0.513, epsilon = 0.005);
+        assert_abs_diff_eq!(ball_position.position.y(), -1.499, epsilon = 0.005);
+        assert_eq!(ball_position.age, Duration::from_millis(400));
+        assert_eq!(decoded.searched_area, message.searched_area);
+    }
+
+    #[test]
+    fn striker_message_round_trips_unknown_time_to_reach() {
+        let message = StrikerMessage {
+            player_number: PlayerNumber::Two,
+            pose: Pose2::new(point![1.0, -0.5], -3.1),
+            ball_position: BallPosition {
+                position: point![2.0, 0.25],
+                age: Duration::from_secs(60),
+            },
+            time_to_reach_kick_position: Duration::MAX,
+        };
+
+        let buffer: Vec<u8> = HulkMessage::Striker(message).into();
+        let HulkMessage::Striker(decoded) = HulkMessage::try_from(buffer.as_slice()).unwrap()
+        else {
+            panic!("expected striker message");
+        };
+
+        assert_eq!(decoded.player_number, PlayerNumber::Two);
+        assert_poses_match(decoded.pose, message.pose);
+        assert_eq!(decoded.ball_position.age, Duration::MAX);
+        assert_eq!(decoded.time_to_reach_kick_position, Duration::MAX);
+    }
+
+    #[test]
+    fn encoded_messages_are_compact() {
+        let message = HulkMessage::Striker(StrikerMessage::default());
+
+        let buffer: Vec<u8> = message.into();
+
+        assert!(buffer.len() <= 12);
+    }
+
+    #[test]
+    fn state_message_with_searched_area_is_compact() {
+        let message = HulkMessage::State(StateMessage {
+            ball_position: Some(BallPosition::default()),
+            searched_area: SearchedArea { cells: u32::MAX },
+            ..Default::default()
+        });
+
+        let buffer: Vec<u8> = message.into();
+
+        assert!(buffer.len() <= 13);
+    }
+
+    #[test]
+    fn unknown_protocol_version_is_rejected() {
+        let mut buffer: Vec<u8> = HulkMessage::State(StateMessage::default()).into();
+        buffer[0] = HSL_PROTOCOL_VERSION + 1;
+
+        assert!(HulkMessage::try_from(buffer.as_slice()).is_err());
+    }
+
+    #[test]
+    fn truncated_message_is_rejected() {
+        let buffer: Vec<u8> = HulkMessage::State(StateMessage::default()).into();
+
+        assert!(HulkMessage::try_from(&buffer[..buffer.len() - 1]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 mod bindings;
+mod encoding;
 mod game_controller_return_message;
 mod game_controller_state_message;
 
@@ -8,11 +9,12 @@ use std::{
 };
 
 use coordinate_systems::Field;
-use linear_algebra::{Point2, Pose2};
+use linear_algebra::{Point2, Pose2, point};
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use ros_z::Message;
 use serde::{Deserialize, Serialize};
 
+pub use encoding::HSL_PROTOCOL_VERSION;
 pub use game_controller_return_message::GameControllerReturnMessage;
 pub use game_controller_state_message::{
     GameControllerStateMessage, GamePhase, GameState, Half, Penalty, PenaltyShoot, Player,
@@ -32,6 +34,7 @@ pub use game_controller_state_message::{
 )]
 pub enum HulkMessage {
     State(StateMessage),
+    Striker(StrikerMessage),
 }
 
 impl Default for HulkMessage {
@@ -40,7 +43,27 @@ impl Default for HulkMessage {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Message)]
+impl HulkMessage {
+    pub fn player_number(&self) -> PlayerNumber {
+        match self {
+            HulkMessage::State(StateMessage { player_number, .. })
+            | HulkMessage::Striker(StrikerMessage { player_number, .. }) => *player_number,
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathDeserialize,
+    PathIntrospect,
+    PathSerialize,
+    Message,
+)]
 pub struct StrikerMessage {
     pub player_number: PlayerNumber,
     pub pose: Pose2<Field>,
@@ -64,6 +87,65 @@ pub struct StateMessage {
     pub player_number: PlayerNumber,
     pub pose: Pose2<Field>,
     pub ball_position: Option<BallPosition<Field>>,
+    pub searched_area: SearchedArea,
+}
+
+pub const SEARCHED_AREA_COLUMNS: usize = 6;
+pub const SEARCHED_AREA_ROWS: usize = 4;
+pub const SEARCHED_AREA_CELLS: usize = SEARCHED_AREA_COLUMNS * SEARCHED_AREA_ROWS;
+
+/// Cells of a coarse grid over the field in which a robot recently looked for the ball without
+/// seeing it. Columns run along the field length, rows along the field width, starting at the own
+/// right corner.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    PathDeserialize,
+    PathIntrospect,
+    PathSerialize,
+    Message,
+)]
+pub struct SearchedArea {
+    pub cells: u32,
+}
+
+impl SearchedArea {
+    pub fn insert(&mut self, index: usize) {
+        self.cells |= 1 << index;
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        (self.cells >> index) & 1 == 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells == 0
+    }
+
+    pub fn cell_index(position: Point2<Field>, field_length: f32, field_width: f32) -> usize {
+        let column = ((position.x() / field_length + 0.5) * SEARCHED_AREA_COLUMNS as f32)
+            .floor()
+            .clamp(0.0, (SEARCHED_AREA_COLUMNS - 1) as f32) as usize;
+        let row = ((position.y() / field_width + 0.5) * SEARCHED_AREA_ROWS as f32)
+            .floor()
+            .clamp(0.0, (SEARCHED_AREA_ROWS - 1) as f32) as usize;
+        row * SEARCHED_AREA_COLUMNS + column
+    }
+
+    pub fn cell_center(index: usize, field_length: f32, field_width: f32) -> Point2<Field> {
+        let column = index % SEARCHED_AREA_COLUMNS;
+        let row = index / SEARCHED_AREA_COLUMNS;
+        point![
+            ((column as f32 + 0.5) / SEARCHED_AREA_COLUMNS as f32 - 0.5) * field_length,
+            ((row as f32 + 0.5) / SEARCHED_AREA_ROWS as f32 - 0.5) * field_width
+        ]
+    }
 }
 
 #[derive(
@@ -138,7 +220,40 @@ mod tests {
                 position: Point2::origin(),
                 age: Duration::MAX,
             }),
+            searched_area: SearchedArea { cells: u32::MAX },
         });
-        assert!(bincode::serialize(&test_message).unwrap().len() <= 128)
+        assert!(Vec::<u8>::from(test_message).len() <= 128)
+    }
+
+    #[test]
+    fn hulk_striker_claim_message_size() {
+        let test_message = HulkMessage::Striker(StrikerMessage {
+            player_number: PlayerNumber::Five,
+            pose: Pose2::default(),
+            ball_position: BallPosition {
+                position: Point2::origin(),
+                age: Duration::MAX,
+            },
+            time_to_reach_kick_position: Duration::MAX,
+        });
+        assert!(Vec::<u8>::from(test_message).len() <= 128)
+    }
+
+    #[test]
+    fn searched_area_cells_cover_field() {
+        let (length, width) = (9.0, 6.0);
+
+        assert_eq!(
+            SearchedArea::cell_index(point![-4.5, -3.0], length, width),
+            0
+        );
+        assert_eq!(
+            SearchedArea::cell_index(point![4.5, 3.0], length, width),
+            SEARCHED_AREA_CELLS - 1
+        );
+        for index in 0..SEARCHED_AREA_CELLS {
+            let center = SearchedArea::cell_center(index, length, width);
+            assert_eq!(SearchedArea::cell_index(center, length, width), index);
+        }
     }
 }
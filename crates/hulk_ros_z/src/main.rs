@@ -108,21 +108,20 @@ fn derive_namespace(robot: &str) -> String {
 async fn spawn_all(ctx: Arc<Context>) -> Result<RunningStack> {
     let mut join_set = JoinSet::new();
 
+    for run in hulk_ros_z_nodes::decision_nodes() {
+        join_set.spawn(run(ctx.clone()));
+    }
+
     join_set.spawn(active_vision::run_boxed(ctx.clone()));
-    join_set.spawn(ball_filter::run_boxed(ctx.clone()));
-    join_set.spawn(ball_state_composer::run_boxed(ctx.clone()));
-    join_set.spawn(behavior_node::run_boxed(ctx.clone()));
     join_set.spawn(booster_sdk_interface::run_boxed(ctx.clone()));
     join_set.spawn(button_event_bridge::run_boxed(ctx.clone()));
     join_set.spawn(button_event_handler::run_boxed(ctx.clone()));
     join_set.spawn(camera_matrix_calculator::run_boxed(ctx.clone()));
     join_set.spawn(detection::run_boxed(ctx.clone()));
-    join_set.spawn(fake_odometry::run_boxed(ctx.clone()));
     join_set.spawn(fall_down_state_receiver::run_boxed(ctx.clone()));
     join_set.spawn(field_border_detection::run_boxed(ctx.clone()));
     join_set.spawn(game_controller_filter::run_boxed(ctx.clone()));
     join_set.spawn(game_controller_state_filter::run_boxed(ctx.clone()));
-    join_set.spawn(global_parameter_provider::run_boxed(ctx.clone()));
     join_set.spawn(ground_provider::run_boxed(ctx.clone()));
     join_set.spawn(head_motion::run_boxed(ctx.clone()));
     join_set.spawn(image_receiver::run_boxed(ctx.clone()));
@@ -134,25 +133,18 @@ async fn spawn_all(ctx: Arc<Context>) -> Result<RunningStack> {
     join_set.spawn(look_around::run_boxed(ctx.clone()));
     join_set.spawn(look_at::run_boxed(ctx.clone()));
     join_set.spawn(low_state_bridge::run_boxed(ctx.clone()));
-    join_set.spawn(message_filter::run_boxed(ctx.clone()));
     join_set.spawn(message_handler::run_boxed(ctx.clone()));
     // join_set.spawn(microphone_recorder::run_boxed(ctx.clone()));
+    join_set.spawn(motion_arbitration::run_boxed(ctx.clone()));
     join_set.spawn(motor_commands_collector::run_boxed(ctx.clone()));
-    join_set.spawn(obstacle_filter::run_boxed(ctx.clone()));
     join_set.spawn(odometer_bridge::run_boxed(ctx.clone()));
-    join_set.spawn(player_state_receiver::run_boxed(ctx.clone()));
     join_set.spawn(primary_state_filter::run_boxed(ctx.clone()));
-    join_set.spawn(rule_obstacle_composer::run_boxed(ctx.clone()));
     join_set.spawn(safe_pose_checker::run_boxed(ctx.clone()));
-    join_set.spawn(search_suggestor::run_boxed(ctx.clone()));
     join_set.spawn(segment_filter::run_boxed(ctx.clone()));
     join_set.spawn(support_foot_estimator::run_boxed(ctx.clone()));
-    join_set.spawn(team_ball_receiver::run_boxed(ctx.clone()));
-    join_set.spawn(time_to_reach_kick_position::run_boxed(ctx.clone()));
     join_set.spawn(trigger::run_boxed(ctx.clone()));
     join_set.spawn(whistle_detection::run_boxed(ctx.clone()));
     join_set.spawn(whistle_filter::run_boxed(ctx.clone()));
-    join_set.spawn(world_state_composer::run_boxed(ctx.clone()));
     join_set.spawn(world_to_field_provider::run_boxed(ctx.clone()));
 
     Ok(RunningStack { join_set })
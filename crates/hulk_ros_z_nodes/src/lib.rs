@@ -0,0 +1,30 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use color_eyre::Result;
+use ros_z::prelude::*;
+
+/// Entry point of a ros-z node crate, e.g. `ball_filter::run_boxed`.
+pub type NodeEntryPoint = fn(Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// The nodes between perception and `behavior/motion_command`.
+///
+/// They do not touch hardware or images, hence the robot stack and the software in the loop
+/// simulator both run them.
+pub fn decision_nodes() -> Vec<NodeEntryPoint> {
+    vec![
+        ball_filter::run_boxed,
+        ball_state_composer::run_boxed,
+        behavior_node::run_boxed,
+        fake_odometry::run_boxed,
+        global_parameter_provider::run_boxed,
+        message_filter::run_boxed,
+        obstacle_filter::run_boxed,
+        player_state_receiver::run_boxed,
+        role_assignment::run_boxed,
+        rule_obstacle_composer::run_boxed,
+        search_suggestor::run_boxed,
+        team_ball_receiver::run_boxed,
+        time_to_reach_kick_position::run_boxed,
+        world_state_composer::run_boxed,
+    ]
+}
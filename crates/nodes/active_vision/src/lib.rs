@@ -1,13 +1,21 @@
 use std::{boxed::Box, future::Future, pin::Pin};
-use std::{future::pending, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
 use color_eyre::Result;
 use coordinate_systems::{Field, Ground};
-use linear_algebra::{Isometry2, Point2};
-use ros_z::{prelude::*, qos::QosDurability};
+use hsl_network_messages::GamePhase;
+use linear_algebra::{Isometry2, Point2, Vector2, point};
+use ordered_float::NotNan;
+use ros_z::{prelude::*, qos::QosDurability, time::Time};
+use ros_z_streams::{CreateAnnouncingPublisher, CreateFutureMapBuilder};
 use types::{
-    field_dimensions::FieldDimensions, filtered_game_controller_state::FilteredGameControllerState,
-    obstacles::Obstacle, parameters::LookActionParameters, world_state::BallState,
+    field_dimensions::FieldDimensions,
+    filtered_game_controller_state::FilteredGameControllerState,
+    filtered_game_state::FilteredGameState,
+    obstacles::{Obstacle, ObstacleKind},
+    parameters::LookActionParameters,
+    point_of_interest::PointOfInterest,
+    world_state::BallState,
 };
 
 pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
@@ -17,8 +25,8 @@ pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> +
 async fn run(ctx: Arc<Context>) -> Result<()> {
     let node = ctx.create_node("active_vision").build().await?;
 
-    let _parameters = node.bind_parameter_as::<LookActionParameters>("active_vision")?;
-    let _field_dimensions_sub = node
+    let parameters = node.bind_parameter_as::<LookActionParameters>("active_vision")?;
+    let field_dimensions_sub = node
         .subscriber::<FieldDimensions>("field_dimensions")
         .qos(QosProfile {
             durability: QosDurability::TransientLocal,
@@ -26,29 +34,316 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         })
         .build()
         .await?;
-    let _ball_sub = node.subscriber::<BallState>("ball_state").build().await?;
-    let _rule_ball_sub = node
-        .subscriber::<BallState>("rule_ball_state")
-        .build()
-        .await?;
-    let _obstacles_sub = node
-        .subscriber::<Vec<Obstacle>>("obstacles")
-        .build()
-        .await?;
-    let _ground_to_field_sub = node
+    let ground_to_field_cache = node
         .subscriber::<Isometry2<Ground, Field>>("ground_to_field")
+        .cache(10)
         .build()
         .await?;
-    let _filtered_game_controller_state_sub = node
+    let filtered_game_controller_state_cache = node
         .subscriber::<FilteredGameControllerState>("filtered_game_controller_state")
+        .cache(1)
         .build()
         .await?;
-    let _position_of_interest_pub = node
-        .publisher::<Point2<Ground>>("position_of_interest")
-        .build()
+    let mut inputs = node
+        .create_future_map_builder()
+        .create_future_subscriber::<Option<BallState>>("ball_state", Duration::from_millis(10))
+        .await?
+        .create_future_subscriber::<Option<BallState>>("rule_ball_state", Duration::from_millis(10))
+        .await?
+        .create_future_subscriber::<Vec<Obstacle>>("obstacles", Duration::from_millis(10))
+        .await?
+        .build();
+    let position_of_interest_pub = node
+        .announcing_publisher::<Point2<Ground>>("position_of_interest")
         .await?;
 
-    pending::<()>().await;
+    let field_dimensions = field_dimensions_sub.recv().await?;
+    let mut active_vision = ActiveVision::new(&field_dimensions);
+    let mut ball = None;
+    let mut rule_ball = None;
+    let mut obstacles = Vec::new();
+
+    loop {
+        let item = inputs.recv().await?;
+        let Some(output_time) = item.persistent.last_key_value().map(|(time, _)| *time) else {
+            continue;
+        };
+        for (_, (received_ball, received_rule_ball, received_obstacles)) in item.persistent {
+            if let Some(received_ball) = received_ball {
+                ball = received_ball;
+            }
+            if let Some(received_rule_ball) = received_rule_ball {
+                rule_ball = received_rule_ball;
+            }
+            if let Some(received_obstacles) = received_obstacles {
+                obstacles = received_obstacles;
+            }
+        }
+
+        let parameters = *parameters.snapshot().typed();
+        let ground_to_field = ground_to_field_cache
+            .get_nearest(output_time)
+            .map(|ground_to_field| *ground_to_field);
+        let filtered_game_controller_state = filtered_game_controller_state_cache.get_latest();
+
+        let position_of_interest = active_vision.update(
+            output_time,
+            &parameters,
+            ball.as_ref(),
+            rule_ball.as_ref(),
+            &obstacles,
+            ground_to_field,
+            filtered_game_controller_state.as_deref(),
+        );
+
+        position_of_interest_pub
+            .announce(output_time)
+            .await?
+            .publish(&position_of_interest)
+            .await?;
+    }
+}
+
+struct ActiveVision {
+    field_mark_positions: Vec<Point2<Field>>,
+    last_point_of_interest_switch: Option<Time>,
+    current_point_of_interest: PointOfInterest,
+}
+
+impl ActiveVision {
+    fn new(field_dimensions: &FieldDimensions) -> Self {
+        Self {
+            field_mark_positions: generate_field_mark_positions(field_dimensions),
+            last_point_of_interest_switch: None,
+            current_point_of_interest: PointOfInterest::default(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update(
+        &mut self,
+        now: Time,
+        parameters: &LookActionParameters,
+        ball: Option<&BallState>,
+        rule_ball: Option<&BallState>,
+        obstacles: &[Obstacle],
+        ground_to_field: Option<Isometry2<Ground, Field>>,
+        filtered_game_controller_state: Option<&FilteredGameControllerState>,
+    ) -> Point2<Ground> {
+        let Some(ground_to_field) = ground_to_field else {
+            return parameters.look_forward_position;
+        };
+
+        match filtered_game_controller_state {
+            Some(FilteredGameControllerState {
+                game_state:
+                    FilteredGameState::Playing {
+                        ball_is_free: false,
+                        kick_off: true,
+                    },
+                ..
+            })
+            | Some(FilteredGameControllerState {
+                game_state: FilteredGameState::Set,
+                game_phase: GamePhase::PenaltyShootout { .. },
+                ..
+            }) => self.current_point_of_interest = PointOfInterest::Ball,
+            _ if self
+                .last_point_of_interest_switch
+                .is_none_or(|last_switch| {
+                    now.duration_since(last_switch)
+                        > parameters.position_of_interest_switch_interval
+                }) =>
+            {
+                self.current_point_of_interest = next_point_of_interest(
+                    self.current_point_of_interest,
+                    &self.field_mark_positions,
+                    obstacles,
+                    *parameters,
+                    ground_to_field,
+                    rule_ball.or(ball),
+                );
+
+                self.last_point_of_interest_switch = Some(now);
+            }
+            _ => (),
+        }
+
+        match self.current_point_of_interest {
+            PointOfInterest::Forward => parameters.look_forward_position,
+            PointOfInterest::FieldMark { absolute_position } => {
+                ground_to_field.inverse() * absolute_position
+            }
+            PointOfInterest::Ball => ball
+                .map(|ball_state| ball_state.ball_in_ground)
+                .unwrap_or(parameters.look_forward_position),
+            PointOfInterest::Obstacle { absolute_position } => {
+                ground_to_field.inverse() * absolute_position
+            }
+        }
+    }
+}
+
+fn is_position_visible(position: Point2<Ground>, parameters: LookActionParameters) -> bool {
+    Vector2::x_axis().angle(&position.coords()).abs() < parameters.angle_threshold
+        && position.coords().norm() < parameters.distance_threshold
+}
+
+fn closest_field_mark_visible(
+    field_mark_positions: &[Point2<Field>],
+    parameters: LookActionParameters,
+    ground_to_field: Isometry2<Ground, Field>,
+) -> Option<Point2<Ground>> {
+    field_mark_positions
+        .iter()
+        .map(|position| ground_to_field.inverse() * position)
+        .filter(|position| is_position_visible(*position, parameters))
+        .min_by_key(|position| NotNan::new(position.coords().norm()).unwrap())
+}
+
+fn closest_interesting_obstacle_visible(
+    obstacles: &[Obstacle],
+    parameters: LookActionParameters,
+) -> Option<Point2<Ground>> {
+    obstacles
+        .iter()
+        .filter(|obstacle| matches!(obstacle.kind, ObstacleKind::Robot | ObstacleKind::Unknown))
+        .map(|obstacle| obstacle.position)
+        .filter(|obstacle_position| is_position_visible(*obstacle_position, parameters))
+        .min_by_key(|position| NotNan::new(position.coords().norm()).unwrap())
+}
+
+fn generate_field_mark_positions(field_dimensions: &FieldDimensions) -> Vec<Point2<Field>> {
+    let left_center_circle_junction = point![0.0, field_dimensions.center_circle_diameter / 2.0];
+    let right_center_circle_junction = point![0.0, -field_dimensions.center_circle_diameter / 2.0];
+    let left_center_t_junction = point![0.0, field_dimensions.width / 2.0];
+    let right_center_t_junction = point![0.0, -field_dimensions.width / 2.0];
+    let left_opponent_penalty_box_corner = point![
+        field_dimensions.length / 2.0 - field_dimensions.penalty_area_length,
+        field_dimensions.penalty_area_width / 2.0
+    ];
+    let right_opponent_penalty_box_corner = point![
+        field_dimensions.length / 2.0 - field_dimensions.penalty_area_length,
+        -field_dimensions.penalty_area_width / 2.0
+    ];
+    let left_own_penalty_box_corner = point![
+        -field_dimensions.length / 2.0 + field_dimensions.penalty_area_length,
+        field_dimensions.penalty_area_width / 2.0
+    ];
+    let right_own_penalty_box_corner = point![
+        -field_dimensions.length / 2.0 + field_dimensions.penalty_area_length,
+        -field_dimensions.penalty_area_width / 2.0
+    ];
+    vec![
+        left_center_circle_junction,
+        right_center_circle_junction,
+        left_center_t_junction,
+        right_center_t_junction,
+        left_opponent_penalty_box_corner,
+        right_opponent_penalty_box_corner,
+        left_own_penalty_box_corner,
+        right_own_penalty_box_corner,
+    ]
+}
+
+fn next_point_of_interest(
+    current_point_of_interest: PointOfInterest,
+    field_mark_positions: &[Point2<Field>],
+    obstacles: &[Obstacle],
+    parameters: LookActionParameters,
+    ground_to_field: Isometry2<Ground, Field>,
+    ball: Option<&BallState>,
+) -> PointOfInterest {
+    let closest_interesting_obstacle =
+        || match closest_interesting_obstacle_visible(obstacles, parameters) {
+            Some(interesting_obstacle_position) => PointOfInterest::Obstacle {
+                absolute_position: ground_to_field * interesting_obstacle_position,
+            },
+            None => PointOfInterest::Forward,
+        };
+
+    match current_point_of_interest {
+        PointOfInterest::Forward => {
+            let field_mark_of_interest =
+                closest_field_mark_visible(field_mark_positions, parameters, ground_to_field);
+
+            match (field_mark_of_interest, ball) {
+                (Some(field_mark_position), _) => PointOfInterest::FieldMark {
+                    absolute_position: ground_to_field * field_mark_position,
+                },
+                (_, Some(_)) => PointOfInterest::Ball,
+                (None, None) => closest_interesting_obstacle(),
+            }
+        }
+        PointOfInterest::FieldMark { .. } => match ball {
+            Some(_) => PointOfInterest::Ball,
+            None => closest_interesting_obstacle(),
+        },
+        PointOfInterest::Ball => closest_interesting_obstacle(),
+        PointOfInterest::Obstacle { .. } => PointOfInterest::Forward,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::vector;
+
+    use super::*;
+
+    fn parameters() -> LookActionParameters {
+        LookActionParameters {
+            angle_threshold: 0.95,
+            distance_threshold: 3.0,
+            look_forward_position: point![1.0, 0.0],
+            position_of_interest_switch_interval: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn looks_forward_without_localization() {
+        let mut active_vision = ActiveVision::new(&FieldDimensions::SPL_2025);
+
+        let position_of_interest =
+            active_vision.update(Time::zero(), &parameters(), None, None, &[], None, None);
+
+        assert_eq!(position_of_interest, point![1.0, 0.0]);
+    }
+
+    #[test]
+    fn keeps_point_of_interest_until_switch_interval_elapsed() {
+        let mut active_vision = ActiveVision::new(&FieldDimensions::SPL_2025);
+        let parameters = parameters();
+        let ground_to_field = Isometry2::from_parts(vector![-1.0, 0.0], 0.0);
+
+        active_vision.update(
+            Time::zero(),
+            &parameters,
+            None,
+            None,
+            &[],
+            Some(ground_to_field),
+            None,
+        );
+        let first_point_of_interest = active_vision.current_point_of_interest;
+        active_vision.update(
+            Time::zero() + Duration::from_millis(500),
+            &parameters,
+            None,
+            None,
+            &[],
+            Some(ground_to_field),
+            None,
+        );
 
-    Ok(())
+        assert!(matches!(
+            (
+                first_point_of_interest,
+                active_vision.current_point_of_interest
+            ),
+            (
+                PointOfInterest::FieldMark { .. },
+                PointOfInterest::FieldMark { .. }
+            )
+        ));
+    }
 }
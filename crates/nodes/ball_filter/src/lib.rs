@@ -85,6 +85,10 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         .publisher::<Option<BallPosition<Ground>>>("ball_filter/ball_position")
         .build()
         .await?;
+    let ball_position_covariance_pub = node
+        .publisher::<Option<Matrix2<f32>>>("ball_filter/ball_position_covariance")
+        .build()
+        .await?;
     let hypothetical_ball_positions_pub = node
         .publisher::<Vec<HypotheticalBallPosition<Ground>>>(
             "ball_filter/hypothetical_ball_positions",
@@ -170,6 +174,8 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
             .await?;
 
         let filtered_ball = best_hypothesis.map(|hypothesis| hypothesis.position());
+        let filtered_ball_covariance =
+            best_hypothesis.map(|hypothesis| hypothesis.position_covariance());
 
         let output_balls: Vec<_> = ball_filter
             .hypotheses
@@ -203,6 +209,9 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
             .publish(&filtered_balls_in_image)
             .await?;
 
+        ball_position_covariance_pub
+            .publish(&filtered_ball_covariance)
+            .await?;
         ball_position_pub.publish(&filtered_ball).await?;
         let hypothetical_ball_positions =
             hypothetical_ball_positions(&ball_filter, parameters.validity_output_threshold);
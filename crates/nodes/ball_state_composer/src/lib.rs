@@ -7,7 +7,9 @@ use coordinate_systems::{Field, Ground};
 use filtering::hysteresis::greater_than_with_hysteresis;
 use hsl_network_messages::{GamePhase, SubState, Team};
 use linear_algebra::{Isometry2, Point2, Vector2, point};
+use nalgebra::Matrix2;
 use ros_z::{prelude::*, qos::QosDurability};
+use ros_z_streams::CreateAnnouncingPublisher;
 use types::{
     ball_position::BallPosition,
     field_dimensions::{FieldDimensions, Side},
@@ -36,6 +38,11 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .subscriber::<Option<BallPosition<Ground>>>("ball_filter/ball_position")
         .build()
         .await?;
+    let ball_position_covariance_cache = node
+        .subscriber::<Option<Matrix2<f32>>>("ball_filter/ball_position_covariance")
+        .cache(1)
+        .build()
+        .await?;
     let ground_to_field_cache = node
         .subscriber::<Isometry2<Ground, Field>>("ground_to_field")
         .cache(10)
@@ -63,12 +70,10 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .build()
         .await?;
     let ball_state_pub = node
-        .publisher::<Option<BallState>>("ball_state")
-        .build()
+        .announcing_publisher::<Option<BallState>>("ball_state")
         .await?;
     let rule_ball_state_pub = node
-        .publisher::<Option<BallState>>("rule_ball_state")
-        .build()
+        .announcing_publisher::<Option<BallState>>("rule_ball_state")
         .await?;
 
     let mut last_ball_field_side = Side::Left;
@@ -84,7 +89,11 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         tokio::select! {
             received_ball_position = ball_position_sub.recv() => {
                 let Some(ball_position) = received_ball_position? else {
-                    ball_state_pub.publish(&None).await?;
+                    ball_state_pub
+                        .announce(node.clock().now())
+                        .await?
+                        .publish(&None)
+                        .await?;
                     last_ball_state = None;
                     continue;
                 };
@@ -94,14 +103,24 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                 };
                 let ground_to_field = *ground_to_field;
 
+                let ball_position_covariance = ball_position_covariance_cache
+                    .get_latest()
+                    .and_then(|covariance| *covariance)
+                    .unwrap_or_default();
+
                 let ball = create_ball_state(
                     ball_position.position,
                     ground_to_field * ball_position.position,
                     ball_position.velocity,
+                    ball_position_covariance,
                     ball_position.last_seen.to_wallclock(),
                     &mut last_ball_field_side,
                 );
-                ball_state_pub.publish(&Some(ball)).await?;
+                ball_state_pub
+                    .announce(node.clock().now())
+                    .await?
+                    .publish(&Some(ball))
+                    .await?;
                 last_ball_state = Some(LastBallState {
                     time: now,
                     ball,
@@ -119,10 +138,15 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                     ground_to_field.inverse() * team_ball.position,
                     team_ball.position,
                     ground_to_field.inverse() * team_ball.velocity,
+                    Matrix2::zeros(),
                     team_ball.last_seen.to_wallclock(),
                     &mut last_ball_field_side,
                 );
-                ball_state_pub.publish(&Some(ball)).await?;
+                ball_state_pub
+                    .announce(node.clock().now())
+                    .await?
+                    .publish(&Some(ball))
+                    .await?;
                 last_ball_state = Some(LastBallState {
                     time: now,
                     ball,
@@ -151,7 +175,11 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                     &mut last_ball_field_side,
                 );
 
-                rule_ball_state_pub.publish(&rule_ball).await?;
+                rule_ball_state_pub
+                    .announce(node.clock().now())
+                    .await?
+                    .publish(&rule_ball)
+                    .await?;
             }
         }
     }
@@ -194,6 +222,7 @@ fn compose_rule_ball_state(
                 ground_to_field.inverse() * penalty_spot_location,
                 penalty_spot_location,
                 Vector2::zeros(),
+                Matrix2::zeros(),
                 cycle_start_time,
                 last_ball_field_side,
             ))
@@ -202,6 +231,7 @@ fn compose_rule_ball_state(
             ground_to_field.inverse() * Point2::origin(),
             Point2::origin(),
             Vector2::zeros(),
+            Matrix2::zeros(),
             cycle_start_time,
             last_ball_field_side,
         )),
@@ -213,6 +243,7 @@ fn create_ball_state(
     ball_in_ground: Point2<Ground>,
     ball_in_field: Point2<Field>,
     ball_in_ground_velocity: Vector2<Ground>,
+    ball_in_ground_covariance: Matrix2<f32>,
     last_seen_ball: SystemTime,
     last_ball_field_side: &mut Side,
 ) -> BallState {
@@ -230,6 +261,7 @@ fn create_ball_state(
         ball_in_ground,
         ball_in_field,
         ball_in_ground_velocity,
+        ball_in_ground_covariance,
         last_seen_ball,
         field_side,
     }
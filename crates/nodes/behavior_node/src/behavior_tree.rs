@@ -1,53 +1,191 @@
-use std::slice::from_ref;
+use std::{borrow::Cow, slice::from_ref, time::Duration};
 
+use ros_z::time::Time;
 use serde::{Serialize, ser::SerializeStruct};
 use types::behavior_tree::{NodeTrace, Status};
 
 type ConditionFunction<Blackboard> = Box<dyn Fn(&mut Blackboard) -> bool + Send + Sync>;
 type ActionFunction<Blackboard> = Box<dyn Fn(&mut Blackboard) -> Status + Send + Sync>;
 
+/// Time source of the blackboard, used by time-based decorators.
+pub trait Clock {
+    fn now(&self) -> Time;
+}
+
 pub enum Node<Blackboard> {
     Action {
-        name: &'static str,
+        name: Cow<'static, str>,
         action: ActionFunction<Blackboard>,
     },
     Condition {
-        name: &'static str,
+        name: Cow<'static, str>,
         condition: ConditionFunction<Blackboard>,
     },
     Failure,
+    /// Swaps Success and Failure, Running is passed through.
     Negation {
-        name: &'static str,
+        name: Cow<'static, str>,
         child: Box<Node<Blackboard>>,
     },
+    /// Ticks children from the first one until one does not fail.
     Selection {
-        name: &'static str,
+        name: Cow<'static, str>,
         children: Vec<Node<Blackboard>>,
     },
+    /// Ticks children from the first one until one does not succeed.
     Sequence {
-        name: &'static str,
+        name: Cow<'static, str>,
+        children: Vec<Node<Blackboard>>,
+    },
+    /// Like [`Node::Selection`], but resumes at the child that was running in the last tick.
+    MemorySelection {
+        name: Cow<'static, str>,
+        children: Vec<Node<Blackboard>>,
+        running_child: usize,
+    },
+    /// Like [`Node::Sequence`], but resumes at the child that was running in the last tick.
+    MemorySequence {
+        name: Cow<'static, str>,
         children: Vec<Node<Blackboard>>,
+        running_child: usize,
+    },
+    /// Fails if the child keeps running for longer than `timeout`.
+    Timeout {
+        name: Cow<'static, str>,
+        timeout: Duration,
+        child: Box<Node<Blackboard>>,
+        started_at: Option<Time>,
+    },
+    /// Fails without ticking the child for `cooldown` after the child succeeded.
+    Cooldown {
+        name: Cow<'static, str>,
+        cooldown: Duration,
+        child: Box<Node<Blackboard>>,
+        succeeded_at: Option<Time>,
+    },
+    /// Reports Running instead of Failure until the child failed `attempts` times in a row.
+    Retry {
+        name: Cow<'static, str>,
+        attempts: usize,
+        child: Box<Node<Blackboard>>,
+        failures: usize,
+    },
+    /// Ticks the child at most once per `interval` and repeats its last status in between. The
+    /// child has no effect on the blackboard in skipped ticks.
+    RateLimit {
+        name: Cow<'static, str>,
+        interval: Duration,
+        child: Box<Node<Blackboard>>,
+        last_tick: Option<(Time, Status)>,
     },
     Subtree {
-        name: &'static str,
+        name: Cow<'static, str>,
         tree: Box<Node<Blackboard>>,
     },
 }
 
 impl<Blackboard> Node<Blackboard> {
-    pub fn tick_with_trace(&self, blackboard: &mut Blackboard) -> (Status, NodeTrace) {
-        let name = match self {
+    pub fn name(&self) -> &str {
+        match self {
             Node::Action { name, .. }
             | Node::Condition { name, .. }
             | Node::Negation { name, .. }
             | Node::Selection { name, .. }
             | Node::Sequence { name, .. }
+            | Node::MemorySelection { name, .. }
+            | Node::MemorySequence { name, .. }
+            | Node::Timeout { name, .. }
+            | Node::Cooldown { name, .. }
+            | Node::Retry { name, .. }
+            | Node::RateLimit { name, .. }
             | Node::Subtree { name, .. } => name,
-            Node::Failure => &"Failure",
-        };
+            Node::Failure => "Failure",
+        }
+    }
+
+    fn children(&self) -> &[Node<Blackboard>] {
+        match self {
+            Node::Selection { children, .. }
+            | Node::Sequence { children, .. }
+            | Node::MemorySelection { children, .. }
+            | Node::MemorySequence { children, .. } => children,
+            Node::Negation { child, .. }
+            | Node::Timeout { child, .. }
+            | Node::Cooldown { child, .. }
+            | Node::Retry { child, .. }
+            | Node::RateLimit { child, .. }
+            | Node::Subtree { tree: child, .. } => from_ref(child.as_ref()),
+            Node::Action { .. } | Node::Condition { .. } | Node::Failure => &[],
+        }
+    }
+
+    /// Forgets the progress of this node and all of its descendants, e.g. when a reactive parent
+    /// switched to another branch while this one was running.
+    pub fn halt(&mut self) {
+        match self {
+            Node::MemorySelection {
+                children,
+                running_child,
+                ..
+            }
+            | Node::MemorySequence {
+                children,
+                running_child,
+                ..
+            } => {
+                *running_child = 0;
+                children.iter_mut().for_each(Node::halt);
+            }
+            Node::Selection { children, .. } | Node::Sequence { children, .. } => {
+                children.iter_mut().for_each(Node::halt);
+            }
+            Node::Timeout {
+                child, started_at, ..
+            } => {
+                *started_at = None;
+                child.halt();
+            }
+            Node::Retry {
+                child, failures, ..
+            } => {
+                *failures = 0;
+                child.halt();
+            }
+            Node::Cooldown { child, .. }
+            | Node::RateLimit { child, .. }
+            | Node::Negation { child, .. }
+            | Node::Subtree { tree: child, .. } => child.halt(),
+            Node::Action { .. } | Node::Condition { .. } | Node::Failure => {}
+        }
+    }
+
+    pub fn static_layout_trace(&self) -> NodeTrace {
+        NodeTrace {
+            name: self.name().to_string(),
+            status: Status::Idle,
+            child_index: 0,
+            duration: Duration::ZERO,
+            children: self
+                .children()
+                .iter()
+                .enumerate()
+                .map(|(index, child)| NodeTrace {
+                    child_index: index,
+                    ..child.static_layout_trace()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<Blackboard: Clock> Node<Blackboard> {
+    pub fn tick_with_trace(&mut self, blackboard: &mut Blackboard) -> (Status, NodeTrace) {
+        let started_ticking = std::time::Instant::now();
         let mut trace = NodeTrace {
-            name: name.to_string(),
+            name: self.name().to_string(),
             status: Status::Failure,
+            child_index: 0,
+            duration: Duration::ZERO,
             children: Vec::new(),
         };
 
@@ -71,30 +209,114 @@ impl<Blackboard> Node<Blackboard> {
                 }
             }
             Node::Selection { children, .. } => {
-                let mut selection_status = Status::Failure;
-                for child in children {
+                tick_children(children, 0, Status::Failure, blackboard, &mut trace).0
+            }
+            Node::Sequence { children, .. } => {
+                tick_children(children, 0, Status::Success, blackboard, &mut trace).0
+            }
+            Node::MemorySelection {
+                children,
+                running_child,
+                ..
+            } => tick_children_with_memory(
+                children,
+                running_child,
+                Status::Failure,
+                blackboard,
+                &mut trace,
+            ),
+            Node::MemorySequence {
+                children,
+                running_child,
+                ..
+            } => tick_children_with_memory(
+                children,
+                running_child,
+                Status::Success,
+                blackboard,
+                &mut trace,
+            ),
+            Node::Timeout {
+                timeout,
+                child,
+                started_at,
+                ..
+            } => {
+                let now = blackboard.now();
+                let started_at = started_at.get_or_insert(now);
+                if now.duration_since(*started_at) > *timeout {
+                    child.halt();
+                    Status::Failure
+                } else {
                     let (child_status, child_trace) = child.tick_with_trace(blackboard);
                     trace.children.push(child_trace);
-
-                    if matches!(child_status, Status::Success) {
-                        selection_status = child_status;
-                        break;
-                    }
+                    child_status
                 }
-                selection_status
             }
-            Node::Sequence { children, .. } => {
-                let mut sequence_status = Status::Success;
-                for child in children {
+            Node::Cooldown {
+                cooldown,
+                child,
+                succeeded_at,
+                ..
+            } => {
+                let now = blackboard.now();
+                if succeeded_at
+                    .is_some_and(|succeeded_at| now.duration_since(succeeded_at) < *cooldown)
+                {
+                    Status::Failure
+                } else {
                     let (child_status, child_trace) = child.tick_with_trace(blackboard);
                     trace.children.push(child_trace);
-
-                    if matches!(child_status, Status::Failure) {
-                        sequence_status = child_status;
-                        break;
+                    if child_status == Status::Success {
+                        *succeeded_at = Some(now);
+                    }
+                    child_status
+                }
+            }
+            Node::Retry {
+                attempts,
+                child,
+                failures,
+                ..
+            } => {
+                let (child_status, child_trace) = child.tick_with_trace(blackboard);
+                trace.children.push(child_trace);
+                match child_status {
+                    Status::Failure => {
+                        *failures += 1;
+                        child.halt();
+                        if *failures < *attempts {
+                            Status::Running
+                        } else {
+                            *failures = 0;
+                            Status::Failure
+                        }
+                    }
+                    Status::Success => {
+                        *failures = 0;
+                        Status::Success
+                    }
+                    _ => child_status,
+                }
+            }
+            Node::RateLimit {
+                interval,
+                child,
+                last_tick,
+                ..
+            } => {
+                let now = blackboard.now();
+                match last_tick {
+                    Some((ticked_at, status)) if now.duration_since(*ticked_at) < *interval => {
+                        status.clone()
+                    }
+                    _ => {
+                        let (child_status, child_trace) = child.tick_with_trace(blackboard);
+                        trace.children.push(child_trace);
+                        *last_tick = Some((now, child_status.clone()));
+                        child_status
                     }
                 }
-                sequence_status
             }
             Node::Subtree { tree, .. } => {
                 let (child_status, child_trace) = tree.tick_with_trace(blackboard);
@@ -103,49 +325,78 @@ impl<Blackboard> Node<Blackboard> {
             }
         };
 
+        if !matches!(status, Status::Running) {
+            self.reset_after_completion();
+        }
+
         trace.status = status.clone();
+        trace.duration = started_ticking.elapsed();
         (status, trace)
     }
 
-    pub fn static_layout_trace(&self) -> NodeTrace {
-        let name = match self {
-            Node::Action { name, .. }
-            | Node::Condition { name, .. }
-            | Node::Negation { name, .. }
-            | Node::Selection { name, .. }
-            | Node::Sequence { name, .. }
-            | Node::Subtree { name, .. } => name,
-            Node::Failure => &"Failure",
-        };
+    fn reset_after_completion(&mut self) {
+        match self {
+            Node::Timeout { started_at, .. } => *started_at = None,
+            Node::MemorySelection { running_child, .. }
+            | Node::MemorySequence { running_child, .. } => *running_child = 0,
+            _ => {}
+        }
+    }
+}
 
-        let children = match self {
-            Node::Selection { children, .. } | Node::Sequence { children, .. } => {
-                children.iter().map(|c| c.static_layout_trace()).collect()
-            }
-            Node::Negation { child, .. } => vec![child.static_layout_trace()],
-            Node::Subtree { tree, .. } => vec![tree.static_layout_trace()],
-            _ => vec![],
-        };
+/// Ticks children starting at `first_child` until one returns something other than
+/// `continue_status`. Children after that one are halted. Returns the resulting status together
+/// with the index of the child that produced it.
+fn tick_children<Blackboard: Clock>(
+    children: &mut [Node<Blackboard>],
+    first_child: usize,
+    continue_status: Status,
+    blackboard: &mut Blackboard,
+    trace: &mut NodeTrace,
+) -> (Status, usize) {
+    let mut result = (continue_status.clone(), children.len());
+    for (index, child) in children.iter_mut().enumerate().skip(first_child) {
+        if result.1 < index {
+            child.halt();
+            continue;
+        }
+        let (child_status, mut child_trace) = child.tick_with_trace(blackboard);
+        child_trace.child_index = index;
+        trace.children.push(child_trace);
 
-        NodeTrace {
-            name: name.to_string(),
-            status: Status::Idle,
-            children,
+        if child_status != continue_status {
+            result = (child_status, index);
         }
     }
+    result
+}
+
+fn tick_children_with_memory<Blackboard: Clock>(
+    children: &mut [Node<Blackboard>],
+    running_child: &mut usize,
+    continue_status: Status,
+    blackboard: &mut Blackboard,
+    trace: &mut NodeTrace,
+) -> Status {
+    let (status, index) =
+        tick_children(children, *running_child, continue_status, blackboard, trace);
+    if status == Status::Running {
+        *running_child = index;
+    }
+    status
 }
 
 #[macro_export]
 macro_rules! action {
     ($func:expr) => {
         $crate::behavior_tree::Node::Action{
-            name: stringify!($func),
+            name: ::std::borrow::Cow::Borrowed(stringify!($func)),
             action: Box::new($func)
         }
     };
     ($func:expr, $($arg:expr),+ $(,)?) => {
         $crate::behavior_tree::Node::Action{
-            name: stringify!($func:$($arg),+),
+            name: ::std::borrow::Cow::Borrowed(stringify!($func:$($arg),+)),
             action: Box::new(move |ctx| {
                 $func(ctx, $($arg.clone()),+)
             })
@@ -157,13 +408,13 @@ macro_rules! action {
 macro_rules! condition {
     ($func:ident) => {
         $crate::behavior_tree::Node::Condition {
-            name: stringify!($func),
+            name: ::std::borrow::Cow::Borrowed(stringify!($func)),
             condition: Box::new($func),
         }
     };
     ($func:ident, $($arg:expr),+ $(,)?) => {
         $crate::behavior_tree::Node::Condition {
-            name: stringify!($func:$($arg),+),
+            name: ::std::borrow::Cow::Borrowed(stringify!($func:$($arg),+)),
             condition: Box::new(move |ctx| {
                 $func(ctx, $($arg.clone()),+)
             }),
@@ -175,7 +426,7 @@ macro_rules! condition {
 macro_rules! negation {
     ($child:expr) => {
         $crate::behavior_tree::Node::Negation {
-            name: "Negation",
+            name: ::std::borrow::Cow::Borrowed("Negation"),
             child: Box::new($child),
         }
     };
@@ -185,7 +436,7 @@ macro_rules! negation {
 macro_rules! selection {
     ($($child:expr),* $(,)?) => {
         $crate::behavior_tree::Node::Selection{
-            name: "Selection",
+            name: ::std::borrow::Cow::Borrowed("Selection"),
             children: vec![$($child),*]
         }
     };
@@ -195,23 +446,93 @@ macro_rules! selection {
 macro_rules! sequence {
     ($($child:expr),* $(,)?) => {
         $crate::behavior_tree::Node::Sequence{
-            name: "Sequence",
+            name: ::std::borrow::Cow::Borrowed("Sequence"),
             children: vec![$($child),*]
         }
     };
 }
 
+#[macro_export]
+macro_rules! memory_selection {
+    ($($child:expr),* $(,)?) => {
+        $crate::behavior_tree::Node::MemorySelection{
+            name: ::std::borrow::Cow::Borrowed("MemorySelection"),
+            children: vec![$($child),*],
+            running_child: 0,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! memory_sequence {
+    ($($child:expr),* $(,)?) => {
+        $crate::behavior_tree::Node::MemorySequence{
+            name: ::std::borrow::Cow::Borrowed("MemorySequence"),
+            children: vec![$($child),*],
+            running_child: 0,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! timeout {
+    ($timeout:expr, $child:expr $(,)?) => {
+        $crate::behavior_tree::Node::Timeout {
+            name: ::std::borrow::Cow::Borrowed("Timeout"),
+            timeout: $timeout,
+            child: Box::new($child),
+            started_at: None,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! cooldown {
+    ($cooldown:expr, $child:expr $(,)?) => {
+        $crate::behavior_tree::Node::Cooldown {
+            name: ::std::borrow::Cow::Borrowed("Cooldown"),
+            cooldown: $cooldown,
+            child: Box::new($child),
+            succeeded_at: None,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! retry {
+    ($attempts:expr, $child:expr $(,)?) => {
+        $crate::behavior_tree::Node::Retry {
+            name: ::std::borrow::Cow::Borrowed("Retry"),
+            attempts: $attempts,
+            child: Box::new($child),
+            failures: 0,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! rate_limit {
+    ($interval:expr, $child:expr $(,)?) => {
+        $crate::behavior_tree::Node::RateLimit {
+            name: ::std::borrow::Cow::Borrowed("RateLimit"),
+            interval: $interval,
+            child: Box::new($child),
+            last_tick: None,
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! subtree {
     ($func:expr) => {
         $crate::behavior_tree::Node::Subtree {
-            name: concat!("subtree_", stringify!($func)),
+            name: ::std::borrow::Cow::Borrowed(concat!("subtree_", stringify!($func))),
             tree: Box::new($func()),
         }
     };
     ($func:expr, $($arg:expr),+ $(,)?) => {
         $crate::behavior_tree::Node::Subtree {
-            name: concat!("subtree_", stringify!($func:$($arg),+)),
+            name: ::std::borrow::Cow::Borrowed(concat!("subtree_", stringify!($func:$($arg),+))),
             tree: Box::new($func($($arg.clone()),+)),
         }
     };
@@ -222,21 +543,31 @@ impl<Blackboard> Serialize for Node<Blackboard> {
     where
         S: serde::Serializer,
     {
-        let (node_type, name, children) = match self {
-            Node::Action { name, .. } => ("Action", *name, None),
-            Node::Condition { name, .. } => ("Condition", *name, None),
-            Node::Failure => ("Failure", "Failure", None),
-            Node::Negation { name, child } => ("Negation", *name, Some(from_ref(child.as_ref()))),
-            Node::Selection { name, children } => ("Selection", *name, Some(children.as_slice())),
-            Node::Sequence { name, children } => ("Sequence", *name, Some(children.as_slice())),
-            Node::Subtree { name, tree } => ("Subtree", *name, Some(from_ref(tree.as_ref()))),
+        let node_type = match self {
+            Node::Action { .. } => "Action",
+            Node::Condition { .. } => "Condition",
+            Node::Failure => "Failure",
+            Node::Negation { .. } => "Negation",
+            Node::Selection { .. } => "Selection",
+            Node::Sequence { .. } => "Sequence",
+            Node::MemorySelection { .. } => "MemorySelection",
+            Node::MemorySequence { .. } => "MemorySequence",
+            Node::Timeout { .. } => "Timeout",
+            Node::Cooldown { .. } => "Cooldown",
+            Node::Retry { .. } => "Retry",
+            Node::RateLimit { .. } => "RateLimit",
+            Node::Subtree { .. } => "Subtree",
+        };
+        let children = match self {
+            Node::Action { .. } | Node::Condition { .. } | Node::Failure => None,
+            _ => Some(self.children()),
         };
 
         let num_fields = if children.is_some() { 3 } else { 2 };
         let mut state = serializer.serialize_struct("Node", num_fields)?;
 
         state.serialize_field("type", node_type)?;
-        state.serialize_field("name", name)?;
+        state.serialize_field("name", self.name())?;
 
         if let Some(c) = children {
             state.serialize_field("children", c)?;
@@ -245,3 +576,129 @@ impl<Blackboard> Serialize for Node<Blackboard> {
         state.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestBlackboard {
+        now: Time,
+        ticks: Vec<&'static str>,
+        results: Vec<Status>,
+    }
+
+    impl TestBlackboard {
+        fn new(results: Vec<Status>) -> Self {
+            Self {
+                now: Time::zero(),
+                ticks: Vec::new(),
+                results,
+            }
+        }
+    }
+
+    impl Clock for TestBlackboard {
+        fn now(&self) -> Time {
+            self.now
+        }
+    }
+
+    fn scripted(name: &'static str) -> Node<TestBlackboard> {
+        Node::Action {
+            name: name.into(),
+            action: Box::new(move |blackboard: &mut TestBlackboard| {
+                blackboard.ticks.push(name);
+                blackboard.results.remove(0)
+            }),
+        }
+    }
+
+    fn succeed(name: &'static str) -> Node<TestBlackboard> {
+        Node::Action {
+            name: name.into(),
+            action: Box::new(move |blackboard: &mut TestBlackboard| {
+                blackboard.ticks.push(name);
+                Status::Success
+            }),
+        }
+    }
+
+    #[test]
+    fn memory_sequence_resumes_at_running_child() {
+        let mut tree = memory_sequence!(succeed("first"), scripted("second"));
+        let mut blackboard = TestBlackboard::new(vec![Status::Running, Status::Success]);
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Running);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Success);
+
+        assert_eq!(blackboard.ticks, ["first", "second", "second"]);
+    }
+
+    #[test]
+    fn reactive_sequence_restarts_from_first_child() {
+        let mut tree = sequence!(succeed("first"), scripted("second"));
+        let mut blackboard = TestBlackboard::new(vec![Status::Running, Status::Success]);
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Running);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Success);
+
+        assert_eq!(blackboard.ticks, ["first", "second", "first", "second"]);
+    }
+
+    #[test]
+    fn timeout_fails_running_child() {
+        let mut tree = timeout!(Duration::from_secs(1), scripted("child"));
+        let mut blackboard = TestBlackboard::new(vec![Status::Running, Status::Running]);
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Running);
+        blackboard.now = blackboard.now + Duration::from_millis(500);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Running);
+        blackboard.now = blackboard.now + Duration::from_millis(600);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Failure);
+
+        assert_eq!(blackboard.ticks, ["child", "child"]);
+    }
+
+    #[test]
+    fn cooldown_blocks_child_after_success() {
+        let mut tree = cooldown!(Duration::from_secs(1), succeed("child"));
+        let mut blackboard = TestBlackboard::new(Vec::new());
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Success);
+        blackboard.now = blackboard.now + Duration::from_millis(500);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Failure);
+        blackboard.now = blackboard.now + Duration::from_millis(600);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Success);
+
+        assert_eq!(blackboard.ticks, ["child", "child"]);
+    }
+
+    #[test]
+    fn retry_reports_running_until_attempts_are_exhausted() {
+        let mut tree = retry!(2, scripted("child"));
+        let mut blackboard = TestBlackboard::new(vec![Status::Failure, Status::Failure]);
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Running);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Failure);
+    }
+
+    #[test]
+    fn rate_limit_repeats_last_status() {
+        let mut tree = rate_limit!(Duration::from_secs(1), scripted("child"));
+        let mut blackboard = TestBlackboard::new(vec![Status::Success, Status::Failure]);
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Success);
+        blackboard.now = blackboard.now + Duration::from_millis(500);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Success);
+        blackboard.now = blackboard.now + Duration::from_millis(600);
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Failure);
+    }
+
+    #[test]
+    fn negation_passes_running_through() {
+        let mut tree = negation!(scripted("child"));
+        let mut blackboard = TestBlackboard::new(vec![Status::Running]);
+
+        assert_eq!(tree.tick_with_trace(&mut blackboard).0, Status::Running);
+    }
+}
@@ -3,6 +3,7 @@ use hsl_network_messages::Team;
 use linear_algebra::{point, vector};
 use types::{
     filtered_game_controller_state::FilteredGameControllerState, primary_state::PrimaryState,
+    roles::Role,
 };
 use voronoi::Ownership;
 
@@ -149,7 +150,17 @@ pub fn is_fallen(blackboard: &mut Blackboard) -> bool {
 }
 
 pub fn is_goalkeeper(blackboard: &mut Blackboard) -> bool {
-    blackboard.world_state.robot.player_number == blackboard.parameters.goal_keeper_number
+    match blackboard.world_state.robot.role {
+        Some(role) => matches!(role, Role::Keeper | Role::ReplacementKeeper),
+        None => blackboard.goal_keeper_number == Some(blackboard.world_state.robot.player_number),
+    }
+}
+
+pub fn is_striker(blackboard: &mut Blackboard) -> bool {
+    match blackboard.world_state.robot.role {
+        Some(role) => role == Role::Striker,
+        None => is_closest_to_ball(blackboard),
+    }
 }
 
 pub fn is_primary_state(blackboard: &mut Blackboard, primary_state: PrimaryState) -> bool {
@@ -0,0 +1,294 @@
+use coordinate_systems::{Field, Ground};
+use geometry::line_segment::LineSegment;
+use linear_algebra::{Isometry2, Orientation2, Point2, Pose2, Vector2, distance, point};
+use path_planner::path_planner::PathPlanner;
+use types::{
+    behavior_tree::Status,
+    motion_command::{BodyMotion, OrientationMode},
+    motion_type::MotionType,
+    obstacles::ObstacleKind,
+    path::PathSegment,
+};
+
+use crate::{
+    action,
+    behavior_tree::Node,
+    node::Blackboard,
+    subtree,
+    switch_motion_type::switch_motion_type,
+    walk::{walk_alternatives_subtree, walk_to},
+};
+
+pub fn dribble_subtree() -> Node<Blackboard> {
+    switch_motion_type(
+        MotionType::Walk,
+        action!(dribble),
+        subtree!(walk_alternatives_subtree),
+    )
+}
+
+/// Whether a robot that is not a known teammate is close to the ball.
+pub fn is_under_pressure(blackboard: &mut Blackboard) -> bool {
+    opponent_distance_to_ball(blackboard)
+        .is_some_and(|distance| distance < blackboard.parameters.dribbling.pressure_distance)
+}
+
+/// Distance of the closest robot that is not a known teammate to the ball.
+pub fn opponent_distance_to_ball(blackboard: &Blackboard) -> Option<f32> {
+    let ball = blackboard.world_state.ball.as_ref()?;
+    let ground_to_field = blackboard.world_state.robot.ground_to_field?;
+    let own_player_number = blackboard.world_state.robot.player_number;
+    let teammate_radius = 2.0 * blackboard.parameters.path_planning.robot_radius;
+    let teammates: Vec<Point2<Ground>> = blackboard
+        .world_state
+        .player_states
+        .iter()
+        .filter(|(player_number, _)| *player_number != own_player_number)
+        .filter_map(|(_, player_state)| {
+            Some(ground_to_field.inverse() * player_state.as_ref()?.pose.position())
+        })
+        .collect();
+
+    blackboard
+        .world_state
+        .obstacles
+        .iter()
+        .filter(|obstacle| matches!(obstacle.kind, ObstacleKind::Robot | ObstacleKind::Unknown))
+        .filter(|obstacle| {
+            teammates
+                .iter()
+                .all(|teammate| distance(*teammate, obstacle.position) > teammate_radius)
+        })
+        .map(|obstacle| distance(obstacle.position, ball.ball_in_ground))
+        .min_by(f32::total_cmp)
+}
+
+/// Pushes the ball towards the opponent goal along a path around obstacles. The robot walks to a
+/// pose behind the ball whenever it is not aligned with the push direction or lost the ball.
+pub fn dribble(blackboard: &mut Blackboard) -> Status {
+    let (Some(ball), Some(ground_to_field)) = (
+        blackboard.world_state.ball,
+        blackboard.world_state.robot.ground_to_field,
+    ) else {
+        blackboard.was_dribbling = false;
+        return Status::Failure;
+    };
+    let ball_in_ground = ball.ball_in_ground;
+    let goal_in_ground =
+        ground_to_field.inverse() * point![blackboard.field_dimensions.length / 2.0, 0.0];
+    let Some(push_direction) =
+        plan_ball_direction(blackboard, ball_in_ground, goal_in_ground, ground_to_field)
+    else {
+        blackboard.was_dribbling = false;
+        return Status::Failure;
+    };
+
+    let parameters = &blackboard.parameters.dribbling;
+    let ball_ahead = ball_in_ground.coords().dot(&push_direction);
+    let lateral_offset = cross(push_direction, ball_in_ground.coords());
+    let heading_error = Orientation2::from_vector(push_direction).angle();
+    let maximum_ball_distance = if blackboard.was_dribbling {
+        parameters.ball_loss_distance
+    } else {
+        parameters.approach_distance + parameters.lateral_tolerance
+    };
+    let is_behind_ball = ball_ahead > 0.0
+        && ball_ahead <= maximum_ball_distance
+        && lateral_offset.abs() <= parameters.lateral_tolerance
+        && heading_error.abs() <= parameters.alignment_angle_threshold;
+
+    if is_behind_ball {
+        let to_push_line = ball_in_ground.coords() - push_direction * ball_ahead;
+        blackboard.body_motion = Some(BodyMotion::WalkWithVelocity {
+            velocity: push_direction * parameters.push_speed + to_push_line,
+            angular_velocity: heading_error,
+        });
+        blackboard.was_dribbling = true;
+        return Status::Success;
+    }
+
+    blackboard.was_dribbling = false;
+    let approach_position = ball_in_ground - push_direction * parameters.approach_distance;
+    walk_to(
+        blackboard,
+        Pose2::from_parts(approach_position, Orientation2::from_vector(push_direction)),
+        blackboard.parameters.walk_speed.kicking,
+        OrientationMode::AlignWithPath,
+        blackboard
+            .parameters
+            .walk_and_stand
+            .normal_distance_to_be_aligned,
+        blackboard.parameters.walk_and_stand.hysteresis,
+    )
+}
+
+/// Plans a path for the ball around obstacles and returns the direction of its first segment.
+fn plan_ball_direction(
+    blackboard: &Blackboard,
+    ball_in_ground: Point2<Ground>,
+    target_in_ground: Point2<Ground>,
+    ground_to_field: Isometry2<Ground, Field>,
+) -> Option<Vector2<Ground>> {
+    let parameters = &blackboard.parameters.path_planning;
+    let field_dimensions = &blackboard.field_dimensions;
+
+    let mut planner = PathPlanner {
+        obstacle_escape_spline_segments: parameters.obstacle_escape_spline_segments,
+        ..Default::default()
+    };
+    let obstacles: Vec<_> = blackboard
+        .world_state
+        .obstacles
+        .iter()
+        .filter(|obstacle| obstacle.kind != ObstacleKind::Ball)
+        .copied()
+        .collect();
+    planner.with_obstacles(&obstacles, parameters.robot_radius);
+    planner.with_field_borders(
+        ground_to_field,
+        field_dimensions.length,
+        field_dimensions.width,
+        field_dimensions.border_strip_width,
+        parameters.field_border_weight,
+    );
+    planner.with_goal_support_structures(ground_to_field.inverse(), field_dimensions);
+
+    let waypoint = match planner.plan(ball_in_ground, target_in_ground) {
+        Ok(Some(path)) => segment_end(path.first_segment()),
+        _ => target_in_ground,
+    };
+    (waypoint - ball_in_ground).try_normalize(f32::EPSILON)
+}
+
+fn segment_end(segment: &PathSegment) -> Point2<Ground> {
+    match segment {
+        PathSegment::LineSegment(LineSegment(_, end)) => *end,
+        PathSegment::Arc(arc) => arc.circle.center + arc.end.as_unit_vector() * arc.circle.radius,
+    }
+}
+
+fn cross(left: Vector2<Ground>, right: Vector2<Ground>) -> f32 {
+    left.x() * right.y() - left.y() * right.x()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use approx::assert_relative_eq;
+    use hsl_network_messages::PlayerNumber;
+    use linear_algebra::vector;
+    use types::{
+        field_dimensions::{FieldDimensions, Side},
+        obstacles::Obstacle,
+        parameters::BehaviorParameters,
+        world_state::{BallState, PlayerState},
+    };
+
+    use super::*;
+
+    fn blackboard(ball_in_ground: Point2<Ground>) -> Blackboard {
+        let parameters: BehaviorParameters = json5::from_str(include_str!(
+            "../../../../etc/parameters/ros_z/base/behavior_node.json5"
+        ))
+        .unwrap();
+        let mut blackboard = Blackboard::new(FieldDimensions::SPL_2025, parameters);
+        blackboard.world_state.robot.player_number = PlayerNumber::Three;
+        blackboard.world_state.robot.ground_to_field = Some(Isometry2::identity());
+        blackboard.world_state.ball = Some(BallState {
+            ball_in_ground,
+            ball_in_field: point![ball_in_ground.x(), ball_in_ground.y()],
+            ball_in_ground_velocity: Vector2::zeros(),
+            ball_in_ground_covariance: Default::default(),
+            last_seen_ball: SystemTime::UNIX_EPOCH,
+            field_side: Side::Left,
+        });
+        blackboard
+    }
+
+    fn robot(position: Point2<Ground>) -> Obstacle {
+        Obstacle::robot(position, 0.2, 0.3)
+    }
+
+    #[test]
+    fn pressure_only_counts_robots_that_are_not_teammates() {
+        let mut blackboard = blackboard(point![1.0, 0.0]);
+        assert!(!is_under_pressure(&mut blackboard));
+
+        blackboard.world_state.obstacles = vec![robot(point![3.0, 0.0])];
+        assert!(!is_under_pressure(&mut blackboard));
+
+        blackboard.world_state.obstacles = vec![robot(point![1.5, 0.0])];
+        assert!(is_under_pressure(&mut blackboard));
+
+        blackboard.world_state.player_states.four = Some(PlayerState {
+            pose: Pose2::new(point![1.5, 0.0], 0.0),
+            ball_position: None,
+        });
+        assert!(!is_under_pressure(&mut blackboard));
+    }
+
+    #[test]
+    fn ball_direction_avoids_obstacles() {
+        let mut blackboard = blackboard(point![1.0, 0.0]);
+        let ball = point![1.0, 0.0];
+        let target = point![3.5, 0.0];
+
+        let free = plan_ball_direction(&blackboard, ball, target, Isometry2::identity()).unwrap();
+        assert_relative_eq!(free, vector![1.0, 0.0], epsilon = 1e-3);
+
+        blackboard.world_state.obstacles = vec![robot(point![2.0, 0.0])];
+        let blocked =
+            plan_ball_direction(&blackboard, ball, target, Isometry2::identity()).unwrap();
+        assert!(blocked.x() > 0.0, "{blocked:?}");
+        assert!(blocked.y().abs() > 0.1, "{blocked:?}");
+    }
+
+    #[test]
+    fn pushes_ball_when_behind_it() {
+        let mut blackboard = blackboard(point![0.2, 0.0]);
+
+        assert_eq!(dribble(&mut blackboard), Status::Success);
+        assert!(blackboard.was_dribbling);
+        let Some(BodyMotion::WalkWithVelocity {
+            velocity,
+            angular_velocity,
+        }) = blackboard.body_motion
+        else {
+            panic!("unexpected body motion {:?}", blackboard.body_motion);
+        };
+        let push_speed = blackboard.parameters.dribbling.push_speed;
+        assert_relative_eq!(velocity, vector![push_speed, 0.0], epsilon = 1e-2);
+        assert_relative_eq!(angular_velocity, 0.0, epsilon = 1e-2);
+    }
+
+    #[test]
+    fn approaches_ball_when_not_aligned() {
+        let mut blackboard = blackboard(point![0.2, 0.3]);
+
+        dribble(&mut blackboard);
+
+        assert!(!blackboard.was_dribbling);
+        assert!(
+            matches!(blackboard.body_motion, Some(BodyMotion::Walk { .. })),
+            "{:?}",
+            blackboard.body_motion
+        );
+    }
+
+    #[test]
+    fn keeps_dribbling_until_ball_is_lost() {
+        let mut blackboard = blackboard(point![0.4, 0.0]);
+
+        dribble(&mut blackboard);
+        assert!(!blackboard.was_dribbling);
+
+        blackboard.was_dribbling = true;
+        dribble(&mut blackboard);
+        assert!(blackboard.was_dribbling);
+
+        blackboard.world_state.ball = None;
+        assert_eq!(dribble(&mut blackboard), Status::Failure);
+        assert!(!blackboard.was_dribbling);
+    }
+}
@@ -0,0 +1,658 @@
+use coordinate_systems::{Field, Ground};
+use geometry::{
+    arc::Arc, circle::Circle, direction::Direction, line_segment::LineSegment,
+    two_line_segments::TwoLineSegments,
+};
+use hsl_network_messages::{GamePhase, Team};
+use linear_algebra::{Isometry2, Orientation2, Point2, Pose2, Vector2, distance, point, vector};
+use nalgebra::{Matrix2, Rotation2};
+use types::{
+    behavior_tree::Status,
+    field_dimensions::FieldDimensions,
+    filtered_game_controller_state::FilteredGameControllerState,
+    motion_command::{BodyMotion, OrientationMode},
+    motion_type::MotionType,
+    parameters::{GoalkeeperParameters, PenaltyShotDirectionParameters},
+    penalty_shot_direction::PenaltyShotDirection,
+};
+
+use crate::{
+    action,
+    actions::stand,
+    behavior_tree::Node,
+    condition,
+    head::look_at_ball_subtree,
+    kick::kick_subtree,
+    node::Blackboard,
+    selection, sequence, subtree,
+    switch_motion_type::switch_motion_type,
+    walk::{walk_alternatives_subtree, walk_to},
+};
+
+pub fn goalkeeper_subtree() -> Node<Blackboard> {
+    sequence!(
+        subtree!(look_at_ball_subtree),
+        selection!(
+            sequence!(condition!(is_ball_clearable), subtree!(kick_subtree)),
+            switch_motion_type(
+                MotionType::Walk,
+                action!(guard_goal),
+                subtree!(walk_alternatives_subtree),
+            ),
+            action!(stand),
+        )
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShotPrediction {
+    /// Point at which the ball crosses the own goal line
+    pub crossing: Point2<Field>,
+    pub time_to_crossing: f32,
+    /// Standard deviation of the crossing point along the goal line
+    pub lateral_deviation: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GoalkeeperReaction {
+    /// Walk to the target while facing the ball
+    Step { target: Point2<Field> },
+    /// Lunge towards the target as fast as possible
+    Dive { target: Point2<Field> },
+    /// Kick the ball away from the own goal
+    Clear,
+}
+
+/// Whether the ball is slow and close enough to the own goal for the goalkeeper to kick it away.
+pub fn is_ball_clearable(blackboard: &mut Blackboard) -> bool {
+    matches!(reaction(blackboard), Some(GoalkeeperReaction::Clear))
+}
+
+/// Positions the goalkeeper on the blocking arc and reacts to shots on the own goal.
+///
+/// There is no dedicated dive motion, so a dive is a lunge with the maximum lateral walk velocity
+/// towards the predicted crossing point of the ball.
+pub fn guard_goal(blackboard: &mut Blackboard) -> Status {
+    let (Some(reaction), Some(ball), Some(ground_to_field)) = (
+        reaction(blackboard),
+        blackboard.world_state.ball,
+        blackboard.world_state.robot.ground_to_field,
+    ) else {
+        return Status::Failure;
+    };
+    let field_to_ground = ground_to_field.inverse();
+
+    match reaction {
+        GoalkeeperReaction::Step { target } => {
+            let target = field_to_ground * target;
+            let orientation = Orientation2::from_vector(ball.ball_in_ground - target);
+            walk_to(
+                blackboard,
+                Pose2::from_parts(target, orientation),
+                blackboard.parameters.walk_speed.blocking,
+                OrientationMode::LookAt {
+                    target: ball.ball_in_ground,
+                    tolerance: blackboard.parameters.walk_and_stand.orientation_tolerance,
+                },
+                blackboard
+                    .parameters
+                    .walk_and_stand
+                    .normal_distance_to_be_aligned,
+                blackboard.parameters.walk_and_stand.hysteresis,
+            )
+        }
+        GoalkeeperReaction::Dive { target } => {
+            let direction = (field_to_ground * target)
+                .coords()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector2::zeros);
+            blackboard.body_motion = Some(BodyMotion::WalkWithVelocity {
+                velocity: direction * blackboard.parameters.goalkeeper.dive_speed,
+                angular_velocity: 0.0,
+            });
+            Status::Success
+        }
+        GoalkeeperReaction::Clear => Status::Failure,
+    }
+}
+
+fn reaction(blackboard: &Blackboard) -> Option<GoalkeeperReaction> {
+    let ball = blackboard.world_state.ball?;
+    let ground_to_field = blackboard.world_state.robot.ground_to_field?;
+    let parameters = &blackboard.parameters.goalkeeper;
+    let field_dimensions = &blackboard.field_dimensions;
+
+    let ball_velocity = ground_to_field * ball.ball_in_ground_velocity;
+    if is_opponent_penalty_shootout(blackboard) {
+        let direction = penalty_shot_direction(
+            ball.ball_in_field,
+            ball_velocity,
+            field_dimensions,
+            &parameters.penalty_shot_direction,
+        );
+        return Some(penalty_shootout_reaction(
+            direction,
+            field_dimensions,
+            parameters,
+        ));
+    }
+
+    let prediction = predict_shot(
+        ball.ball_in_field,
+        ball_velocity,
+        covariance_in_field(ball.ball_in_ground_covariance, ground_to_field),
+        field_dimensions,
+        parameters,
+    );
+
+    Some(decide_reaction(
+        ball.ball_in_field,
+        ball_velocity,
+        ground_to_field.translation(),
+        prediction.as_ref(),
+        field_dimensions,
+        parameters,
+        blackboard.parameters.path_planning.robot_radius,
+    ))
+}
+
+fn is_opponent_penalty_shootout(blackboard: &Blackboard) -> bool {
+    matches!(
+        blackboard.world_state.filtered_game_controller_state,
+        Some(FilteredGameControllerState {
+            game_phase: GamePhase::PenaltyShootout {
+                kicking_team: Team::Opponent
+            },
+            ..
+        })
+    )
+}
+
+fn covariance_in_field(
+    covariance: Matrix2<f32>,
+    ground_to_field: Isometry2<Ground, Field>,
+) -> Matrix2<f32> {
+    let rotation = Rotation2::new(ground_to_field.orientation().angle());
+    rotation.matrix() * covariance * rotation.matrix().transpose()
+}
+
+/// Predicts where a ball rolling with constant deceleration crosses the own goal line. Returns
+/// `None` if the ball is too slow, rolls away from the goal or stops before the goal line.
+pub fn predict_shot(
+    ball: Point2<Field>,
+    velocity: Vector2<Field>,
+    covariance: Matrix2<f32>,
+    field_dimensions: &FieldDimensions,
+    parameters: &GoalkeeperParameters,
+) -> Option<ShotPrediction> {
+    let speed = velocity.norm();
+    if speed < parameters.minimum_shot_velocity {
+        return None;
+    }
+    let direction = velocity.try_normalize(f32::EPSILON)?;
+    if direction.x() > -f32::EPSILON {
+        return None;
+    }
+
+    let goal_line = -field_dimensions.length / 2.0;
+    let travel_distance = (goal_line - ball.x()) / direction.x();
+    if travel_distance < 0.0 {
+        return None;
+    }
+    let time_to_crossing = time_to_travel(speed, parameters.ball_deceleration, travel_distance)?;
+
+    let normal = vector![-direction.y(), direction.x()];
+    let perpendicular_variance = normal.inner.dot(&(covariance * normal.inner)).max(0.0);
+
+    Some(ShotPrediction {
+        crossing: ball + direction * travel_distance,
+        time_to_crossing,
+        lateral_deviation: perpendicular_variance.sqrt() / -direction.x(),
+    })
+}
+
+/// Arc around the own goal center on which the goalkeeper blocks shots.
+pub fn blocking_arc(
+    field_dimensions: &FieldDimensions,
+    parameters: &GoalkeeperParameters,
+) -> Arc<Field> {
+    Arc::new(
+        Circle::new(
+            own_goal_center(field_dimensions),
+            parameters.blocking_arc_radius,
+        ),
+        Orientation2::new(-parameters.maximum_arc_angle),
+        Orientation2::new(parameters.maximum_arc_angle),
+        Direction::Counterclockwise,
+    )
+}
+
+/// Intersection of the bisector of the shot cone from the ball to the goal posts with the blocking
+/// arc. If the ball is inside the arc, the goalkeeper stays between the ball and the goal center.
+pub fn blocking_position(
+    ball: Point2<Field>,
+    field_dimensions: &FieldDimensions,
+    parameters: &GoalkeeperParameters,
+) -> Point2<Field> {
+    let arc = blocking_arc(field_dimensions, parameters);
+    let (left_post, right_post) = goal_posts(field_dimensions);
+
+    let bisector = (left_post - ball)
+        .try_normalize(f32::EPSILON)
+        .zip((right_post - ball).try_normalize(f32::EPSILON))
+        .and_then(|(left, right)| (left + right).try_normalize(f32::EPSILON));
+    let angle = bisector
+        .and_then(|bisector| ray_entry_into_circle(ball, bisector, &arc.circle))
+        .map(|entry| Orientation2::from_vector(entry - arc.circle.center).angle())
+        .unwrap_or_else(|| Orientation2::from_vector(ball - arc.circle.center).angle());
+
+    arc.circle.point_at_angle(Orientation2::new(
+        angle.clamp(arc.start.angle(), arc.end.angle()),
+    ))
+}
+
+/// Fraction of the shot cone from the ball to the segment between `left` and `right` that is
+/// shadowed by a circle with radius `reach` around the goalkeeper.
+pub fn shot_cone_coverage(
+    ball: Point2<Field>,
+    goalkeeper: Point2<Field>,
+    reach: f32,
+    left: Point2<Field>,
+    right: Point2<Field>,
+) -> f32 {
+    let reference = point![(left.x() + right.x()) / 2.0, (left.y() + right.y()) / 2.0] - ball;
+    if reference.norm() <= f32::EPSILON || (goalkeeper - ball).dot(&reference) <= 0.0 {
+        return 0.0;
+    }
+    let angle_to = |target: Point2<Field>| {
+        let to_target = target - ball;
+        cross(reference, to_target).atan2(reference.dot(&to_target))
+    };
+
+    let Some(TwoLineSegments(LineSegment(first_tangent, _), LineSegment(second_tangent, _))) =
+        Circle::new(goalkeeper, reach).tangents_with_point(ball)
+    else {
+        return 1.0;
+    };
+    let (cone_start, cone_end) = ordered(angle_to(left), angle_to(right));
+    let (shadow_start, shadow_end) = ordered(angle_to(first_tangent), angle_to(second_tangent));
+
+    let cone_width = cone_end - cone_start;
+    if cone_width <= f32::EPSILON {
+        return if (shadow_start..=shadow_end).contains(&cone_start) {
+            1.0
+        } else {
+            0.0
+        };
+    }
+    ((cone_end.min(shadow_end) - cone_start.max(shadow_start)) / cone_width).clamp(0.0, 1.0)
+}
+
+/// Clears slow balls close to the own goal. Shots the goalkeeper already shadows are awaited in
+/// place, reachable shots are intercepted by stepping and all others by diving. Without a shot the
+/// goalkeeper walks to the blocking position.
+pub fn decide_reaction(
+    ball: Point2<Field>,
+    ball_velocity: Vector2<Field>,
+    goalkeeper: Point2<Field>,
+    prediction: Option<&ShotPrediction>,
+    field_dimensions: &FieldDimensions,
+    parameters: &GoalkeeperParameters,
+    robot_radius: f32,
+) -> GoalkeeperReaction {
+    if ball_velocity.norm() < parameters.minimum_shot_velocity
+        && distance(ball, own_goal_center(field_dimensions)) < parameters.clearing_distance
+    {
+        return GoalkeeperReaction::Clear;
+    }
+
+    let half_goal_width = field_dimensions.goal_inner_width / 2.0 + field_dimensions.ball_radius;
+    let Some(prediction) =
+        prediction.filter(|prediction| prediction.crossing.y().abs() <= half_goal_width)
+    else {
+        return GoalkeeperReaction::Step {
+            target: blocking_position(ball, field_dimensions, parameters),
+        };
+    };
+
+    let uncertainty = parameters.uncertainty_factor * prediction.lateral_deviation;
+    let band_left = prediction.crossing + vector![0.0, uncertainty];
+    let band_right = prediction.crossing - vector![0.0, uncertainty];
+    if shot_cone_coverage(ball, goalkeeper, robot_radius, band_left, band_right) >= 1.0 {
+        return GoalkeeperReaction::Step { target: goalkeeper };
+    }
+
+    let shot = prediction.crossing - ball;
+    let along = ((goalkeeper - ball).dot(&shot) / shot.norm_squared()).clamp(0.0, 1.0);
+    let intercept = ball + shot * along;
+    let time_to_intercept = time_to_travel(
+        ball_velocity.norm(),
+        parameters.ball_deceleration,
+        distance(ball, intercept),
+    )
+    .unwrap_or(prediction.time_to_crossing);
+    let required_distance = (distance(goalkeeper, intercept) + uncertainty - robot_radius).max(0.0);
+
+    let is_reachable_by_step = required_distance <= parameters.step_speed * time_to_intercept;
+    let is_reachable_by_dive = required_distance
+        <= parameters.dive_reach
+            + dive_distance(
+                parameters.dive_speed,
+                parameters.dive_acceleration,
+                time_to_intercept,
+            );
+
+    // A dive that cannot reach the ball only leaves the goal empty, keep stepping instead
+    if !is_reachable_by_step && is_reachable_by_dive {
+        GoalkeeperReaction::Dive { target: intercept }
+    } else {
+        GoalkeeperReaction::Step { target: intercept }
+    }
+}
+
+/// Classifies the penalty shot by the direction in which the ball moved off the penalty spot.
+pub fn penalty_shot_direction(
+    ball: Point2<Field>,
+    ball_velocity: Vector2<Field>,
+    field_dimensions: &FieldDimensions,
+    parameters: &PenaltyShotDirectionParameters,
+) -> PenaltyShotDirection {
+    let penalty_spot = point![
+        -(field_dimensions.length / 2.0 - field_dimensions.penalty_marker_distance),
+        0.0
+    ];
+    let displacement = ball - penalty_spot;
+    if displacement.norm() < parameters.moving_distance_threshold
+        || ball_velocity.norm() < parameters.minimum_velocity
+        || displacement.x() >= 0.0
+    {
+        return PenaltyShotDirection::NotMoving;
+    }
+
+    let goal_line = -field_dimensions.length / 2.0;
+    let crossing_y =
+        penalty_spot.y() + displacement.y() * (goal_line - penalty_spot.x()) / displacement.x();
+    if crossing_y.abs() < parameters.center_jump_trigger_radius {
+        PenaltyShotDirection::Center
+    } else if crossing_y > 0.0 {
+        PenaltyShotDirection::Left
+    } else {
+        PenaltyShotDirection::Right
+    }
+}
+
+/// During an opponent penalty shot the goalkeeper stays on the goal line and dives to the side the
+/// ball is shot to.
+pub fn penalty_shootout_reaction(
+    direction: PenaltyShotDirection,
+    field_dimensions: &FieldDimensions,
+    parameters: &GoalkeeperParameters,
+) -> GoalkeeperReaction {
+    let stance = point![
+        -field_dimensions.length / 2.0 + parameters.goal_line_offset,
+        0.0
+    ];
+    let side = field_dimensions.goal_inner_width / 2.0;
+    match direction {
+        PenaltyShotDirection::NotMoving | PenaltyShotDirection::Center => {
+            GoalkeeperReaction::Step { target: stance }
+        }
+        PenaltyShotDirection::Left => GoalkeeperReaction::Dive {
+            target: stance + vector![0.0, side],
+        },
+        PenaltyShotDirection::Right => GoalkeeperReaction::Dive {
+            target: stance - vector![0.0, side],
+        },
+    }
+}
+
+/// Distance a dive from standstill covers in the given time, the motion arbitration limits its
+/// acceleration like that of any other walk.
+fn dive_distance(speed: f32, acceleration: f32, time: f32) -> f32 {
+    if acceleration <= 0.0 {
+        return speed * time;
+    }
+    let acceleration_time = (speed / acceleration).min(time);
+    acceleration * acceleration_time.powi(2) / 2.0 + speed * (time - acceleration_time)
+}
+
+/// Time a ball with constant deceleration needs to roll the distance, `None` if it stops before.
+fn time_to_travel(speed: f32, deceleration: f32, distance: f32) -> Option<f32> {
+    let remaining_speed_squared = speed.powi(2) - 2.0 * deceleration * distance;
+    if remaining_speed_squared < 0.0 || speed <= 0.0 {
+        return None;
+    }
+    if deceleration > 0.0 {
+        Some((speed - remaining_speed_squared.sqrt()) / deceleration)
+    } else {
+        Some(distance / speed)
+    }
+}
+
+fn own_goal_center(field_dimensions: &FieldDimensions) -> Point2<Field> {
+    point![-field_dimensions.length / 2.0, 0.0]
+}
+
+/// Inner sides of the left and right goal post of the own goal.
+fn goal_posts(field_dimensions: &FieldDimensions) -> (Point2<Field>, Point2<Field>) {
+    let goal_line = -field_dimensions.length / 2.0;
+    let half_width = field_dimensions.goal_inner_width / 2.0;
+    (
+        point![goal_line, half_width],
+        point![goal_line, -half_width],
+    )
+}
+
+fn ray_entry_into_circle(
+    origin: Point2<Field>,
+    direction: Vector2<Field>,
+    circle: &Circle<Field>,
+) -> Option<Point2<Field>> {
+    let to_origin = origin - circle.center;
+    let projection = to_origin.dot(&direction);
+    let discriminant = projection.powi(2) - (to_origin.norm_squared() - circle.radius.powi(2));
+    if discriminant < 0.0 {
+        return None;
+    }
+    let distance = -projection - discriminant.sqrt();
+    (distance >= 0.0).then(|| origin + direction * distance)
+}
+
+fn ordered(first: f32, second: f32) -> (f32, f32) {
+    (first.min(second), first.max(second))
+}
+
+fn cross(left: Vector2<Field>, right: Vector2<Field>) -> f32 {
+    left.x() * right.y() - left.y() * right.x()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use serde::Deserialize;
+    use types::{parameters::BehaviorParameters, step::Step};
+
+    use super::*;
+
+    fn parameters() -> GoalkeeperParameters {
+        GoalkeeperParameters {
+            blocking_arc_radius: 0.6,
+            maximum_arc_angle: 1.2,
+            ball_deceleration: 0.4,
+            minimum_shot_velocity: 0.3,
+            uncertainty_factor: 1.0,
+            step_speed: 0.3,
+            dive_reach: 0.35,
+            dive_speed: 0.5,
+            dive_acceleration: 1.0,
+            clearing_distance: 1.2,
+            goal_line_offset: 0.15,
+            penalty_shot_direction: PenaltyShotDirectionParameters {
+                moving_distance_threshold: 0.1,
+                minimum_velocity: 0.2,
+                center_jump_trigger_radius: 0.3,
+            },
+        }
+    }
+
+    #[test]
+    fn predicts_crossing_of_decelerating_shot() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let goal_line = -field_dimensions.length / 2.0;
+        let covariance = Matrix2::new(0.0, 0.0, 0.0, 0.04);
+
+        let prediction = predict_shot(
+            point![goal_line + 2.0, 0.0],
+            vector![-2.0, 0.5],
+            covariance,
+            &field_dimensions,
+            &parameters(),
+        )
+        .unwrap();
+
+        assert_relative_eq!(prediction.crossing, point![goal_line, 0.5], epsilon = 1e-5);
+        assert!(prediction.time_to_crossing > 1.0, "{prediction:?}");
+        assert!(prediction.lateral_deviation > 0.1, "{prediction:?}");
+
+        let too_slow = predict_shot(
+            point![goal_line + 2.0, 0.0],
+            vector![-1.0, 0.0],
+            covariance,
+            &field_dimensions,
+            &parameters(),
+        );
+        assert_eq!(too_slow, None);
+    }
+
+    #[test]
+    fn blocks_on_shot_cone_bisector() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let goal_line = -field_dimensions.length / 2.0;
+        let (left_post, right_post) = goal_posts(&field_dimensions);
+
+        let centered = blocking_position(point![0.0, 0.0], &field_dimensions, &parameters());
+        assert_relative_eq!(centered, point![goal_line + 0.6, 0.0], epsilon = 1e-5);
+
+        let ball = point![goal_line + 1.5, 2.0];
+        let position = blocking_position(ball, &field_dimensions, &parameters());
+        assert!(position.y() > 0.0, "{position:?}");
+        assert!(
+            shot_cone_coverage(ball, position, 0.3, left_post, right_post) > 0.5,
+            "{position:?}"
+        );
+    }
+
+    #[test]
+    fn dives_for_shots_out_of_reach() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let goal_line = -field_dimensions.length / 2.0;
+        let ball = point![goal_line + 2.0, 0.0];
+        let velocity = vector![-3.0, 1.0];
+        let prediction = predict_shot(
+            ball,
+            velocity,
+            Matrix2::zeros(),
+            &field_dimensions,
+            &parameters(),
+        );
+
+        let reaction = decide_reaction(
+            ball,
+            velocity,
+            point![goal_line + 0.6, 0.0],
+            prediction.as_ref(),
+            &field_dimensions,
+            &parameters(),
+            0.2,
+        );
+
+        assert!(
+            matches!(reaction, GoalkeeperReaction::Dive { target } if target.y() > 0.0),
+            "{reaction:?}"
+        );
+    }
+
+    #[test]
+    fn steps_for_shots_out_of_dive_reach() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let goal_line = -field_dimensions.length / 2.0;
+        let ball = point![goal_line + 1.0, 0.0];
+        let velocity = vector![-8.0, 5.6];
+        let prediction = predict_shot(
+            ball,
+            velocity,
+            Matrix2::zeros(),
+            &field_dimensions,
+            &parameters(),
+        );
+
+        let reaction = decide_reaction(
+            ball,
+            velocity,
+            point![goal_line + 0.6, -0.5],
+            prediction.as_ref(),
+            &field_dimensions,
+            &parameters(),
+            0.2,
+        );
+
+        assert!(
+            matches!(reaction, GoalkeeperReaction::Step { .. }),
+            "{reaction:?}"
+        );
+    }
+
+    #[test]
+    fn dive_accelerates_to_the_arbitration_limits() {
+        assert_relative_eq!(dive_distance(0.5, 1.0, 0.25), 0.03125, epsilon = 1e-6);
+        assert_relative_eq!(dive_distance(0.5, 1.0, 1.0), 0.375, epsilon = 1e-6);
+
+        #[derive(Deserialize)]
+        struct MotionArbitrationParameters {
+            walk_limits: WalkLimits,
+        }
+        #[derive(Deserialize)]
+        struct WalkLimits {
+            velocity: Step,
+            acceleration: Step,
+        }
+        let behavior: BehaviorParameters = json5::from_str(include_str!(
+            "../../../../etc/parameters/ros_z/base/behavior_node.json5"
+        ))
+        .unwrap();
+        let arbitration: MotionArbitrationParameters = json5::from_str(include_str!(
+            "../../../../etc/parameters/ros_z/base/motion_arbitration.json5"
+        ))
+        .unwrap();
+        assert!(behavior.goalkeeper.dive_speed <= arbitration.walk_limits.velocity.left);
+        assert!(behavior.goalkeeper.dive_acceleration <= arbitration.walk_limits.acceleration.left);
+    }
+
+    #[test]
+    fn classifies_penalty_shot_direction() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let penalty_spot_x =
+            -(field_dimensions.length / 2.0 - field_dimensions.penalty_marker_distance);
+        let parameters = parameters().penalty_shot_direction;
+
+        let direction =
+            |ball, velocity| penalty_shot_direction(ball, velocity, &field_dimensions, &parameters);
+
+        assert_eq!(
+            direction(point![penalty_spot_x, 0.0], vector![0.0, 0.0]),
+            PenaltyShotDirection::NotMoving
+        );
+        assert_eq!(
+            direction(point![penalty_spot_x - 0.3, 0.02], vector![-1.0, 0.0]),
+            PenaltyShotDirection::Center
+        );
+        assert_eq!(
+            direction(point![penalty_spot_x - 0.3, 0.2], vector![-1.0, 0.6]),
+            PenaltyShotDirection::Left
+        );
+        assert_eq!(
+            direction(point![penalty_spot_x - 0.3, -0.2], vector![-1.0, -0.6]),
+            PenaltyShotDirection::Right
+        );
+    }
+}
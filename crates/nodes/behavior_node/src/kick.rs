@@ -5,13 +5,16 @@ use types::{
     behavior_tree::Status,
     motion_command::{BodyMotion, KickPower, MotionCommand},
     motion_type::MotionType,
+    obstacles::ObstacleKind,
 };
 
 use crate::{
     action,
     actions::stand,
     behavior_tree::Node,
-    condition, negation,
+    condition,
+    kick_decision::{KickDecision, KickObstacle, decide_kick},
+    negation,
     node::Blackboard,
     selection, sequence, subtree,
     switch_motion_type::{is_last_motion_type, switch_motion_type},
@@ -67,10 +70,14 @@ pub fn select_kick_target(blackboard: &mut Blackboard) -> Status {
         blackboard.world_state.robot.ground_to_field,
         &blackboard.ball,
     ) {
-        let goal_position: Point2<Field> = point!(blackboard.field_dimensions.length / 2.0, 0.0);
+        let kick_decision = decide_kick_from_blackboard(blackboard, ball.position);
+        let target_in_field = kick_decision
+            .as_ref()
+            .map(|decision| decision.target)
+            .unwrap_or_else(|| point!(blackboard.field_dimensions.length / 2.0, 0.0));
         let field_to_ground = ground_to_field.inverse();
 
-        let target_position = field_to_ground * goal_position;
+        let target_position = field_to_ground * target_in_field;
 
         let ball_in_ground = field_to_ground * ball.position;
         let kick_direction = Orientation2::from_vector(target_position - ball_in_ground);
@@ -85,6 +92,7 @@ pub fn select_kick_target(blackboard: &mut Blackboard) -> Status {
                 Rotation2::new(blackboard.parameters.kicking.kick_target_offset_angle)
                     * target_position;
             *motion_kick_direction = kick_direction;
+            blackboard.kick_decision = kick_decision;
 
             return Status::Success;
         }
@@ -92,12 +100,49 @@ pub fn select_kick_target(blackboard: &mut Blackboard) -> Status {
     Status::Failure
 }
 
+fn decide_kick_from_blackboard(
+    blackboard: &Blackboard,
+    ball: Point2<Field>,
+) -> Option<KickDecision> {
+    let ground_to_field = blackboard.world_state.robot.ground_to_field?;
+    let own_player_number = blackboard.world_state.robot.player_number;
+    let obstacles: Vec<_> = blackboard
+        .world_state
+        .obstacles
+        .iter()
+        .filter(|obstacle| obstacle.kind != ObstacleKind::Ball)
+        .map(|obstacle| KickObstacle {
+            position: ground_to_field * obstacle.position,
+            radius: obstacle.radius_at_foot_height,
+        })
+        .collect();
+    let teammates: Vec<_> = blackboard
+        .world_state
+        .player_states
+        .iter()
+        .filter(|(player_number, _)| *player_number != own_player_number)
+        .filter_map(|(player_number, player_state)| {
+            Some((player_number, player_state.as_ref()?.pose.position()))
+        })
+        .collect();
+
+    decide_kick(
+        ball,
+        &obstacles,
+        &teammates,
+        &blackboard.field_dimensions,
+        &blackboard.parameters.kicking.target_selection,
+        blackboard.parameters.kicking.allow_schlong,
+    )
+}
+
 pub fn kick_power_subtree() -> Node<Blackboard> {
     selection!(
         sequence!(
             condition!(is_last_motion_type, MotionType::Kick),
             action!(use_last_kick_power)
         ),
+        action!(use_decided_kick_power),
         sequence!(
             negation!(condition!(is_close_to_target)),
             condition!(allow_schlong),
@@ -143,6 +188,13 @@ pub fn use_last_kick_power(blackboard: &mut Blackboard) -> Status {
     Status::Failure
 }
 
+pub fn use_decided_kick_power(blackboard: &mut Blackboard) -> Status {
+    match &blackboard.kick_decision {
+        Some(decision) => use_kick_power(blackboard, decision.kick_power),
+        None => Status::Failure,
+    }
+}
+
 pub fn use_kick_power(blackboard: &mut Blackboard, kick_power: KickPower) -> Status {
     if let Some(BodyMotion::VisualKick {
         kick_power: motion_kick_power,
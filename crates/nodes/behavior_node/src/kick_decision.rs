@@ -0,0 +1,271 @@
+use coordinate_systems::Field;
+use hsl_network_messages::PlayerNumber;
+use linear_algebra::{Point2, Vector2, distance, point};
+use ros_z::Message;
+use serde::{Deserialize, Serialize};
+use types::{
+    field_dimensions::FieldDimensions, motion_command::KickPower,
+    parameters::KickTargetSelectionParameters,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Message)]
+pub enum KickTargetKind {
+    Goal,
+    Pass { player_number: PlayerNumber },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Message)]
+pub struct KickDecision {
+    pub target: Point2<Field>,
+    pub kick_power: KickPower,
+    pub kind: KickTargetKind,
+    /// Estimated probability that the ball reaches the target without being blocked
+    pub success_probability: f32,
+    pub score: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct KickObstacle {
+    pub position: Point2<Field>,
+    pub radius: f32,
+}
+
+/// Samples shots into the opponent goal and passes to teammates and returns the candidate with the
+/// highest expected value. Returns `None` if no candidate is in range of any allowed kick power.
+pub fn decide_kick(
+    ball: Point2<Field>,
+    obstacles: &[KickObstacle],
+    teammates: &[(PlayerNumber, Point2<Field>)],
+    field_dimensions: &FieldDimensions,
+    parameters: &KickTargetSelectionParameters,
+    allow_schlong: bool,
+) -> Option<KickDecision> {
+    let goal_candidates = goal_targets(field_dimensions, parameters).filter_map(|target| {
+        let kick_power =
+            kick_power_for_distance(distance(ball, target), parameters, allow_schlong)?;
+        let success_probability =
+            probability_of_passing_obstacles(
+                ball,
+                target,
+                obstacles,
+                field_dimensions.ball_radius,
+                parameters.kick_direction_deviation,
+            ) * probability_of_hitting_goal(ball, target, field_dimensions, parameters);
+        Some(KickDecision {
+            target,
+            kick_power,
+            kind: KickTargetKind::Goal,
+            success_probability,
+            score: success_probability * parameters.goal_value,
+        })
+    });
+
+    let pass_candidates = teammates
+        .iter()
+        .filter(|(_, position)| distance(ball, *position) >= parameters.minimum_pass_distance)
+        .filter_map(|&(player_number, target)| {
+            let kick_power =
+                kick_power_for_distance(distance(ball, target), parameters, allow_schlong)?;
+            let success_probability = probability_of_passing_obstacles(
+                ball,
+                target,
+                obstacles,
+                field_dimensions.ball_radius,
+                parameters.kick_direction_deviation,
+            );
+            let field_position = (target.x() / field_dimensions.length + 0.5).clamp(0.0, 1.0);
+            Some(KickDecision {
+                target,
+                kick_power,
+                kind: KickTargetKind::Pass { player_number },
+                success_probability,
+                score: success_probability * parameters.pass_value_factor * field_position,
+            })
+        });
+
+    goal_candidates
+        .chain(pass_candidates)
+        .max_by(|left, right| left.score.total_cmp(&right.score))
+}
+
+fn goal_targets(
+    field_dimensions: &FieldDimensions,
+    parameters: &KickTargetSelectionParameters,
+) -> impl Iterator<Item = Point2<Field>> {
+    let goal_line = field_dimensions.length / 2.0;
+    let half_opening = inner_half_goal_opening(field_dimensions);
+    let number_of_samples = parameters.number_of_goal_samples.max(1);
+    (0..number_of_samples).map(move |index| {
+        let fraction = if number_of_samples == 1 {
+            0.5
+        } else {
+            index as f32 / (number_of_samples - 1) as f32
+        };
+        point![goal_line, -half_opening + 2.0 * half_opening * fraction]
+    })
+}
+
+/// Half of the width in which the ball fits between the goal posts.
+fn inner_half_goal_opening(field_dimensions: &FieldDimensions) -> f32 {
+    (field_dimensions.goal_inner_width / 2.0 - field_dimensions.ball_radius).max(0.0)
+}
+
+fn kick_power_for_distance(
+    distance: f32,
+    parameters: &KickTargetSelectionParameters,
+    allow_schlong: bool,
+) -> Option<KickPower> {
+    if distance <= parameters.rumpelstilzchen_range {
+        Some(KickPower::Rumpelstilzchen)
+    } else if allow_schlong && distance <= parameters.schlong_range {
+        Some(KickPower::Schlong)
+    } else {
+        None
+    }
+}
+
+/// The kick direction is assumed to be normally distributed around the direction to the target,
+/// so the lateral error of the ball grows linearly with the travelled distance.
+fn probability_of_passing_obstacles(
+    ball: Point2<Field>,
+    target: Point2<Field>,
+    obstacles: &[KickObstacle],
+    ball_radius: f32,
+    kick_direction_deviation: f32,
+) -> f32 {
+    let to_target = target - ball;
+    let Some(direction) = to_target.try_normalize(f32::EPSILON) else {
+        return 1.0;
+    };
+    let travel_distance = to_target.norm();
+
+    obstacles
+        .iter()
+        .filter(|obstacle| distance(obstacle.position, target) > obstacle.radius + ball_radius)
+        .map(|obstacle| {
+            let to_obstacle = obstacle.position - ball;
+            let along = to_obstacle.dot(&direction);
+            if along <= 0.0 || along > travel_distance + obstacle.radius {
+                return 1.0;
+            }
+            let lateral = cross(direction, to_obstacle).abs();
+            let clearance = lateral - obstacle.radius - ball_radius;
+            let lateral_deviation = (kick_direction_deviation * along).max(f32::EPSILON);
+            standard_normal_cdf(clearance / lateral_deviation)
+        })
+        .product()
+}
+
+fn probability_of_hitting_goal(
+    ball: Point2<Field>,
+    target: Point2<Field>,
+    field_dimensions: &FieldDimensions,
+    parameters: &KickTargetSelectionParameters,
+) -> f32 {
+    let half_opening = inner_half_goal_opening(field_dimensions);
+    let lateral_deviation =
+        (parameters.kick_direction_deviation * distance(ball, target)).max(f32::EPSILON);
+    let left_margin = half_opening - target.y();
+    let right_margin = half_opening + target.y();
+    (standard_normal_cdf(left_margin / lateral_deviation)
+        + standard_normal_cdf(right_margin / lateral_deviation)
+        - 1.0)
+        .max(0.0)
+}
+
+fn cross(left: Vector2<Field>, right: Vector2<Field>) -> f32 {
+    left.x() * right.y() - left.y() * right.x()
+}
+
+/// Logistic approximation of the standard normal cumulative distribution function.
+fn standard_normal_cdf(x: f32) -> f32 {
+    1.0 / (1.0 + (-1.702 * x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn parameters() -> KickTargetSelectionParameters {
+        KickTargetSelectionParameters {
+            rumpelstilzchen_range: 3.0,
+            schlong_range: 7.0,
+            number_of_goal_samples: 9,
+            kick_direction_deviation: 0.1,
+            minimum_pass_distance: 1.0,
+            goal_value: 1.0,
+            pass_value_factor: 0.8,
+        }
+    }
+
+    #[test]
+    fn shoots_past_keeper_into_open_corner() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let keeper = KickObstacle {
+            position: point![field_dimensions.length / 2.0 - 0.3, -0.1],
+            radius: 0.3,
+        };
+
+        let decision = decide_kick(
+            point![field_dimensions.length / 2.0 - 2.0, 0.0],
+            &[keeper],
+            &[],
+            &field_dimensions,
+            &parameters(),
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(decision.kind, KickTargetKind::Goal);
+        assert_eq!(decision.kick_power, KickPower::Rumpelstilzchen);
+        assert!(decision.target.y() > 0.2, "{decision:?}");
+    }
+
+    #[test]
+    fn passes_forward_when_goal_is_out_of_range() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let teammate = point![1.0, 1.0];
+
+        let decision = decide_kick(
+            point![-3.0, 0.0],
+            &[],
+            &[(PlayerNumber::Three, teammate)],
+            &field_dimensions,
+            &parameters(),
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(
+            decision.kind,
+            KickTargetKind::Pass {
+                player_number: PlayerNumber::Three
+            }
+        );
+        assert_eq!(decision.target, teammate);
+        assert_eq!(decision.kick_power, KickPower::Schlong);
+        assert_relative_eq!(decision.success_probability, 1.0);
+    }
+
+    #[test]
+    fn obstacle_on_the_path_lowers_success_probability() {
+        let ball = point![0.0, 0.0];
+        let target = point![2.0, 0.0];
+        let blocking = KickObstacle {
+            position: point![1.0, 0.0],
+            radius: 0.2,
+        };
+        let aside = KickObstacle {
+            position: point![1.0, 1.5],
+            radius: 0.2,
+        };
+
+        let blocked = probability_of_passing_obstacles(ball, target, &[blocking], 0.05, 0.1);
+        let free = probability_of_passing_obstacles(ball, target, &[aside], 0.05, 0.1);
+
+        assert!(blocked < 0.01, "{blocked}");
+        assert!(free > 0.99, "{free}");
+    }
+}
@@ -1,15 +1,22 @@
 pub mod actions;
 pub mod behavior_tree;
 pub mod conditions;
+pub mod dribble;
+pub mod goalkeeper;
 pub mod head;
 pub mod kick;
+pub mod kick_decision;
 pub mod motion_assembler;
 pub mod node;
+pub mod registry;
 pub mod search;
 pub mod send_message;
+pub mod set_play;
 pub mod substates;
 pub mod switch_motion_type;
+pub mod trace;
 pub mod tree;
+pub mod utility;
 pub mod voronoi;
 pub mod walk;
 
@@ -1,34 +1,35 @@
 use std::{net::SocketAddr, pin::Pin, sync::Arc, time::Duration};
 
-use booster::FallDownState;
 use color_eyre::Result;
 
 use coordinate_systems::{Field, Ground};
-use hsl_network_messages::PlayerNumber;
-use linear_algebra::{Isometry2, Point2, Pose2, Vector2};
+use hsl_network_messages::{PlayerNumber, StrikerMessage};
+use linear_algebra::{Point2, Pose2, Vector2};
 use ros_z::{prelude::*, qos::QosDurability, time::Time};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 use types::{
-    ball_position::HypotheticalBallPosition,
     behavior_tree::NodeTrace,
     field_dimensions::{FieldDimensions, Side},
-    filtered_game_controller_state::FilteredGameControllerState,
     messages::OutgoingMessage,
     motion_command::{BodyMotion, HeadMotion, MotionCommand},
     motion_type::MotionType,
-    obstacles::Obstacle,
-    parameters::BehaviorParameters,
+    parameters::{BehaviorParameters, UtilityCandidate, UtilityTable},
     path_obstacles::PathObstacle,
-    players::Players,
-    primary_state::PrimaryState,
-    rule_obstacles::RuleObstacle,
     time_wrapper::TimeWrapper,
-    world_state::{BallState, PlayerState, RobotState, WorldState},
+    world_state::WorldState,
 };
 use voronoi::VoronoiGrid;
 
-use crate::{motion_assembler::assemble_motion_command, tree::create_tree};
+use crate::{
+    behavior_tree::Clock,
+    kick_decision::KickDecision,
+    motion_assembler::assemble_motion_command,
+    registry::default_registry,
+    send_message::HslSender,
+    trace::{BlackboardSnapshot, GetTraceHistory, TraceDiff, TraceRecorder},
+    tree::create_tree_from_description,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
 pub struct LastBall {
@@ -42,6 +43,7 @@ pub struct LastBall {
 pub struct Blackboard {
     pub field_dimensions: FieldDimensions,
     pub parameters: BehaviorParameters,
+    pub goal_keeper_number: Option<PlayerNumber>,
     pub world_state: WorldState,
 
     pub path_obstacles_output: Vec<PathObstacle>,
@@ -57,8 +59,9 @@ pub struct Blackboard {
     pub last_motion_switch_time: Time,
     pub last_motion_type: Option<MotionType>,
     pub last_sent_game_controller_return_message_time: Option<Time>,
-    pub last_sent_hsl_message_time: Option<Time>,
     pub last_closest_to_ball: bool,
+    pub was_dribbling: bool,
+    pub utility_selection: Option<UtilityCandidate>,
     pub closest_to_ball_entered_area_since: Option<Time>,
     pub closest_to_ball_left_area_since: Option<Time>,
 
@@ -67,6 +70,52 @@ pub struct Blackboard {
     pub body_motion: Option<BodyMotion>,
     pub head_motion: Option<HeadMotion>,
     pub voronoi_map: Option<VoronoiGrid>,
+    pub kick_decision: Option<KickDecision>,
+    pub utility_table: Option<UtilityTable>,
+}
+
+impl Clock for Blackboard {
+    fn now(&self) -> Time {
+        self.world_state.now
+    }
+}
+
+impl Blackboard {
+    pub fn new(field_dimensions: FieldDimensions, parameters: BehaviorParameters) -> Self {
+        Self {
+            field_dimensions,
+            parameters,
+            goal_keeper_number: None,
+            world_state: WorldState::default(),
+
+            path_obstacles_output: Vec::new(),
+            time_since_last_switch: Duration::ZERO,
+            direction_difference: 0.0,
+            voronoi_inputs: Vec::new(),
+
+            ball: None,
+            last_ball: None,
+            last_close_enough_to_kick: false,
+            last_kick_target: None,
+            last_motion_command: MotionCommand::default(),
+            last_motion_switch_time: Time::zero(),
+            last_motion_type: None,
+            last_sent_game_controller_return_message_time: None,
+            last_closest_to_ball: false,
+            was_dribbling: false,
+            utility_selection: None,
+            closest_to_ball_entered_area_since: None,
+            closest_to_ball_left_area_since: None,
+
+            is_injected_motion_command: false,
+            walk_position: None,
+            body_motion: None,
+            head_motion: None,
+            voronoi_map: None,
+            kick_decision: None,
+            utility_table: None,
+        }
+    }
 }
 
 pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
@@ -127,6 +176,16 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
 
     let parameters = node.bind_parameter_as::<BehaviorParameters>("behavior_node")?;
     parameters.add_validation_hook(validate_behavior_parameters)?;
+    let registry = Arc::new(default_registry());
+    parameters.add_validation_hook({
+        let registry = registry.clone();
+        move |parameters: &BehaviorParameters| {
+            create_tree_from_description(parameters.tree.as_ref(), &registry)
+                .map(drop)
+                .map_err(|error| format!("tree: {error}"))
+        }
+    })?;
+    let mut parameters_subscription = parameters.subscribe();
     let field_dimensions_cache = node
         .subscriber::<FieldDimensions>("field_dimensions")
         .qos(QosProfile {
@@ -136,9 +195,8 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         .cache(1)
         .build()
         .await?;
-
-    let player_number_cache = node
-        .subscriber::<PlayerNumber>("player_number")
+    let goal_keeper_number_cache = node
+        .subscriber::<PlayerNumber>("goal_keeper_number")
         .qos(QosProfile {
             durability: QosDurability::TransientLocal,
             ..Default::default()
@@ -146,23 +204,14 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         .cache(1)
         .build()
         .await?;
-    let player_states_cache = node
-        .subscriber::<Players<Option<TimeWrapper<PlayerState>>>>("player_states")
-        .cache(1)
-        .build()
-        .await?;
-    let fall_down_state_cache = node
-        .subscriber::<FallDownState>("inputs/fall_down_state")
-        .cache(1)
-        .build()
-        .await?;
-    let ball_state_cache = node
-        .subscriber::<Option<BallState>>("ball_state")
+
+    let world_state_cache = node
+        .subscriber::<WorldState>("world_state")
         .cache(1)
         .build()
         .await?;
-    let filtered_game_controller_state_cache = node
-        .subscriber::<FilteredGameControllerState>("filtered_game_controller_state")
+    let striker_claim_cache = node
+        .subscriber::<TimeWrapper<StrikerMessage>>("role_assignment/striker_claim")
         .cache(1)
         .build()
         .await?;
@@ -171,52 +220,16 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         .cache(1)
         .build()
         .await?;
-    let ground_to_field_cache = node
-        .subscriber::<Isometry2<Ground, Field>>("ground_to_field")
-        .cache(1)
-        .build()
-        .await?;
-    let hypothetical_ball_positions_cache = node
-        .subscriber::<Vec<HypotheticalBallPosition<Ground>>>("hypothetical_ball_positions")
-        .cache(1)
-        .build()
-        .await?;
-    let obstacles_cache = node
-        .subscriber::<Vec<Obstacle>>("obstacles")
-        .cache(1)
-        .build()
-        .await?;
-    let position_of_interest_cache = node
-        .subscriber::<Point2<Ground>>("position_of_interest")
-        .cache(1)
-        .build()
-        .await?;
-    let primary_state_cache = node
-        .subscriber::<PrimaryState>("primary_state")
-        .qos(QosProfile {
-            durability: QosDurability::TransientLocal,
-            ..Default::default()
-        })
-        .cache(1)
-        .build()
-        .await?;
-    let rule_ball_cache = node
-        .subscriber::<Option<BallState>>("rule_ball_state")
-        .cache(1)
+    let additional_behavior_trace_pub = node
+        .publisher::<NodeTrace>("behavior/trace")
         .build()
         .await?;
-    let rule_obstacles_cache = node
-        .subscriber::<Vec<RuleObstacle>>("rule_obstacles")
-        .cache(1)
+    let additional_behavior_trace_diff_pub = node
+        .publisher::<TraceDiff>("behavior/trace_diff")
         .build()
         .await?;
-    let suggested_search_position_cache = node
-        .subscriber::<Point2<Field>>("suggested_search_position")
-        .cache(1)
-        .build()
-        .await?;
-    let additional_behavior_trace_pub = node
-        .publisher::<NodeTrace>("behavior/trace")
+    let mut trace_history_server = node
+        .service_server::<GetTraceHistory>("behavior/trace_history")
         .build()
         .await?;
     let additional_behavior_tree_layout_pub = node
@@ -231,6 +244,10 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         .publisher::<Blackboard>("behavior/blackboard")
         .build()
         .await?;
+    let additional_utility_table_pub = node
+        .publisher::<Option<UtilityTable>>("behavior/utility_table")
+        .build()
+        .await?;
     let outgoing_message_pub = node
         .publisher::<OutgoingMessage>("outputs/message")
         .build()
@@ -240,45 +257,23 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         .build()
         .await?;
 
-    let tree = create_tree();
-    let static_layout = tree.static_layout_trace();
+    let mut tree_description = parameters.snapshot().typed().tree.clone();
+    let mut tree = create_tree_from_description(tree_description.as_ref(), &registry)?;
+    let mut static_layout = tree.static_layout_trace();
     additional_behavior_tree_layout_pub
-        .publish_if_subscribed(|| async { static_layout })
+        .publish_if_subscribed(|| async { static_layout.clone() })
         .await?;
+    let mut trace_recorder = TraceRecorder::default();
+    let mut hsl_sender = HslSender::default();
     let mut timer = node.create_timer(Duration::from_millis(10));
 
-    let mut blackboard = Blackboard {
-        field_dimensions: field_dimensions_cache
+    let mut blackboard = Blackboard::new(
+        field_dimensions_cache
             .get_latest()
             .map(|dimensions| *dimensions)
             .unwrap_or_default(),
-        parameters: parameters.snapshot().typed().clone(),
-        world_state: WorldState::default(),
-
-        path_obstacles_output: Vec::new(),
-        time_since_last_switch: Duration::ZERO,
-        direction_difference: 0.0,
-        voronoi_inputs: Vec::new(),
-
-        ball: None,
-        last_ball: None,
-        last_close_enough_to_kick: false,
-        last_kick_target: None,
-        last_motion_command: MotionCommand::default(),
-        last_motion_switch_time: Time::zero(),
-        last_motion_type: None,
-        last_sent_game_controller_return_message_time: None,
-        last_sent_hsl_message_time: None,
-        last_closest_to_ball: false,
-        closest_to_ball_entered_area_since: None,
-        closest_to_ball_left_area_since: None,
-
-        is_injected_motion_command: false,
-        walk_position: None,
-        body_motion: None,
-        head_motion: None,
-        voronoi_map: None,
-    };
+        parameters.snapshot().typed().clone(),
+    );
 
     loop {
         blackboard.path_obstacles_output.clear();
@@ -291,64 +286,37 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
         blackboard.body_motion = None;
         blackboard.head_motion = None;
         blackboard.voronoi_map = None;
-
-        let player_number = player_number_cache
-            .get_latest()
-            .map(|n| *n)
-            .unwrap_or_default();
+        blackboard.kick_decision = None;
+        blackboard.utility_table = None;
+
+        if parameters_subscription.has_changed().unwrap_or(false) {
+            let snapshot = parameters_subscription.borrow_and_update().clone();
+            let new_tree_description = &snapshot.typed().tree;
+            if *new_tree_description != tree_description {
+                match create_tree_from_description(new_tree_description.as_ref(), &registry) {
+                    Ok(new_tree) => {
+                        info!("reloaded behavior tree from parameters");
+                        tree = new_tree;
+                        tree_description.clone_from(new_tree_description);
+                        static_layout = tree.static_layout_trace();
+                        trace_recorder.reset();
+                        additional_behavior_tree_layout_pub
+                            .publish_if_subscribed(|| async { static_layout.clone() })
+                            .await?;
+                    }
+                    Err(error) => warn!("keeping previous behavior tree: {error}"),
+                }
+            }
+        }
         blackboard.parameters = parameters.snapshot().typed().clone();
-
-        let player_states = player_states_cache
+        blackboard.goal_keeper_number = goal_keeper_number_cache
             .get_latest()
-            .map(|player_states| {
-                player_states
-                    .as_ref()
-                    .clone()
-                    .map(|player_state| player_state.map(|state| state.inner))
-            })
-            .unwrap_or_default();
-
-        blackboard.world_state.robot = RobotState {
-            ground_to_field: ground_to_field_cache
-                .get_latest()
-                .map(|ground_to_field| *ground_to_field),
-            player_number,
-            primary_state: primary_state_cache
-                .get_latest()
-                .map(|s| *s)
-                .unwrap_or_default(),
-        };
+            .map(|goal_keeper_number| *goal_keeper_number);
 
-        blackboard.world_state.ball = ball_state_cache.get_latest().and_then(|ball| *ball);
-        blackboard.world_state.fall_down_state = fall_down_state_cache
-            .get_latest()
-            .map(|fall_down_state| *fall_down_state.as_ref());
-        blackboard.world_state.filtered_game_controller_state =
-            filtered_game_controller_state_cache.get_latest().map(
-                |filtered_game_controller_state| filtered_game_controller_state.as_ref().clone(),
-            );
-        blackboard.world_state.hypothetical_ball_positions = hypothetical_ball_positions_cache
-            .get_latest()
-            .map(|positions| positions.as_ref().clone())
-            .unwrap_or_default();
+        if let Some(world_state) = world_state_cache.get_latest() {
+            blackboard.world_state = world_state.as_ref().clone();
+        }
         blackboard.world_state.now = node.clock().now();
-        blackboard.world_state.obstacles = obstacles_cache
-            .get_latest()
-            .map(|obstacles| obstacles.as_ref().clone())
-            .unwrap_or_default();
-        blackboard.world_state.player_states = player_states;
-        blackboard.world_state.position_of_interest = position_of_interest_cache
-            .get_latest()
-            .map(|position| *position)
-            .unwrap_or_default();
-        blackboard.world_state.rule_ball = rule_ball_cache.get_latest().and_then(|ball| *ball);
-        blackboard.world_state.rule_obstacles = rule_obstacles_cache
-            .get_latest()
-            .map(|obstacles| obstacles.as_ref().clone())
-            .unwrap_or_default();
-        blackboard.world_state.suggested_search_position = suggested_search_position_cache
-            .get_latest()
-            .map(|position| *position);
 
         if let Some(ball) = blackboard.world_state.ball {
             blackboard.ball = Some(LastBall {
@@ -406,13 +374,38 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
             outgoing_message_pub.publish(&message).await?;
         }
 
-        if let Some(message) = blackboard.state_message() {
+        if let Some(striker_claim) = striker_claim_cache.get_latest() {
+            hsl_sender.claim_striker(&striker_claim);
+        }
+        if let Some(message) =
+            hsl_sender.message(&blackboard.world_state, &blackboard.parameters.hsl_network)
+        {
             outgoing_message_pub.publish(&message).await?;
         }
 
+        let trace_diff = trace_recorder.record(
+            &static_layout,
+            &trace,
+            blackboard.world_state.now,
+            BlackboardSnapshot::new(&blackboard),
+            &blackboard.parameters.trace,
+        );
+        while let Some(request) = trace_history_server.try_take_request()? {
+            let number_of_ticks = request.message().number_of_ticks as usize;
+            request
+                .reply_async(&trace_recorder.history(&static_layout, number_of_ticks))
+                .await?;
+        }
+
         additional_behavior_trace_pub
             .publish_if_subscribed(|| async { trace })
             .await?;
+        additional_behavior_trace_diff_pub
+            .publish_if_subscribed(|| async { trace_diff })
+            .await?;
+        additional_utility_table_pub
+            .publish_if_subscribed(|| async { blackboard.utility_table.clone() })
+            .await?;
         additional_black_board_pub
             .publish_if_subscribed(|| async { blackboard.clone() })
             .await?;
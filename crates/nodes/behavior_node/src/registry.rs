@@ -0,0 +1,497 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use hsl_network_messages::SubState;
+use serde::de::{DeserializeOwned, IntoDeserializer, value::StrDeserializer};
+use thiserror::Error;
+use types::{
+    behavior_tree::{NodeDescription, Status},
+    motion_command::KickPower,
+    motion_type::MotionType,
+    parameters::UtilityCandidate,
+    primary_state::PrimaryState,
+};
+
+use crate::{
+    actions::{damping, injected_motion_command, prepare, remote_control, stand, stand_up},
+    behavior_tree::Node,
+    conditions::{
+        has_ball_position, has_hypothetical_ball_position, has_new_ball_position,
+        hulks_is_kicking_team, is_ball_interception_candidate, is_close_to_ball,
+        is_close_to_ball_aligned, is_closest_to_ball, is_fallen, is_goalkeeper, is_primary_state,
+        is_remote_controlled, is_remote_kick_mode, is_striker,
+    },
+    dribble::{dribble, dribble_subtree, is_under_pressure},
+    goalkeeper::{goalkeeper_subtree, guard_goal, is_ball_clearable},
+    head::{
+        look_at_ball, look_at_ball_subtree, look_at_hypothetical_ball_position,
+        look_straight_ahead, search_for_lost_ball, search_for_lost_ball_subtree,
+    },
+    kick::{
+        allow_schlong, intercept, is_close_to_target, kick, kick_alternatives_subtree,
+        kick_power_subtree, kick_subtree, select_kick_target, set_kick_target_in_front,
+        use_decided_kick_power, use_kick_power, use_last_kick_power,
+    },
+    node::Blackboard,
+    search::{has_suggested_search_position, leuchtturm, walk_to_search_position},
+    set_play::{
+        is_in_wall, is_set_play, is_set_play_execution_time, set_play_subtree,
+        walk_to_pass_receiving_position, walk_to_wall_position,
+    },
+    substates::{
+        is_in_sub_state, is_sub_state, set_block_position_field, set_block_position_penalty_kick,
+        sub_state_subtree,
+    },
+    switch_motion_type::{is_allowed_to_switch, is_last_motion_type, switch_motion_type},
+    tree::{
+        create_tree, playing_subtree, ready_subtree, remote_control_subtree, search_subtree,
+        supporter_subtree,
+    },
+    utility::{evaluate_utilities, is_utility_selected, utility_subtree},
+    voronoi::calculate_voronoi_grid,
+    walk::{
+        walk_alternatives_subtree, walk_to_ball, walk_to_ball_subtree, walk_to_block_position,
+        walk_to_centroid, walk_to_kickoff_pose,
+    },
+};
+
+type LeafFactory = Box<dyn Fn(&[String]) -> Result<Node<Blackboard>, RegistryError> + Send + Sync>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RegistryError {
+    #[error("unknown action `{name}`")]
+    UnknownAction { name: String },
+    #[error("unknown condition `{name}`")]
+    UnknownCondition { name: String },
+    #[error("unknown subtree `{name}`")]
+    UnknownSubtree { name: String },
+    #[error("`{name}` expects {expected} argument(s) but got {actual}")]
+    Arity {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("invalid argument `{argument}` for `{name}`: {message}")]
+    InvalidArgument {
+        name: String,
+        argument: String,
+        message: String,
+    },
+}
+
+/// Maps the names used in a [`NodeDescription`] to the actions, conditions and subtrees of the
+/// behavior node.
+#[derive(Default)]
+pub struct Registry {
+    actions: BTreeMap<&'static str, LeafFactory>,
+    conditions: BTreeMap<&'static str, LeafFactory>,
+    subtrees: BTreeMap<&'static str, fn() -> Node<Blackboard>>,
+}
+
+impl Registry {
+    pub fn register_action(&mut self, name: &'static str, action: fn(&mut Blackboard) -> Status) {
+        self.actions.insert(
+            name,
+            Box::new(move |arguments| {
+                check_arity(name, 0, arguments)?;
+                Ok(Node::Action {
+                    name: Cow::Borrowed(name),
+                    action: Box::new(action),
+                })
+            }),
+        );
+    }
+
+    pub fn register_action_with<Argument>(
+        &mut self,
+        name: &'static str,
+        action: fn(&mut Blackboard, Argument) -> Status,
+    ) where
+        Argument: DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        self.actions.insert(
+            name,
+            Box::new(move |arguments| {
+                check_arity(name, 1, arguments)?;
+                let argument = parse_argument::<Argument>(name, &arguments[0])?;
+                Ok(Node::Action {
+                    name: Cow::Owned(format!("{name}:{}", arguments[0])),
+                    action: Box::new(move |blackboard| action(blackboard, argument.clone())),
+                })
+            }),
+        );
+    }
+
+    pub fn register_condition(
+        &mut self,
+        name: &'static str,
+        condition: fn(&mut Blackboard) -> bool,
+    ) {
+        self.conditions.insert(
+            name,
+            Box::new(move |arguments| {
+                check_arity(name, 0, arguments)?;
+                Ok(Node::Condition {
+                    name: Cow::Borrowed(name),
+                    condition: Box::new(condition),
+                })
+            }),
+        );
+    }
+
+    pub fn register_condition_with<Argument>(
+        &mut self,
+        name: &'static str,
+        condition: fn(&mut Blackboard, Argument) -> bool,
+    ) where
+        Argument: DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        self.conditions.insert(
+            name,
+            Box::new(move |arguments| {
+                check_arity(name, 1, arguments)?;
+                let argument = parse_argument::<Argument>(name, &arguments[0])?;
+                Ok(Node::Condition {
+                    name: Cow::Owned(format!("{name}:{}", arguments[0])),
+                    condition: Box::new(move |blackboard| condition(blackboard, argument.clone())),
+                })
+            }),
+        );
+    }
+
+    pub fn register_subtree(&mut self, name: &'static str, subtree: fn() -> Node<Blackboard>) {
+        self.subtrees.insert(name, subtree);
+    }
+
+    pub fn build_tree(
+        &self,
+        description: &NodeDescription,
+    ) -> Result<Node<Blackboard>, RegistryError> {
+        let node = match description {
+            NodeDescription::Action { name, arguments } => {
+                let factory = self
+                    .actions
+                    .get(name.as_str())
+                    .ok_or_else(|| RegistryError::UnknownAction { name: name.clone() })?;
+                factory(arguments)?
+            }
+            NodeDescription::Condition { name, arguments } => {
+                let factory = self
+                    .conditions
+                    .get(name.as_str())
+                    .ok_or_else(|| RegistryError::UnknownCondition { name: name.clone() })?;
+                factory(arguments)?
+            }
+            NodeDescription::Subtree { name } => {
+                let subtree = self
+                    .subtrees
+                    .get(name.as_str())
+                    .ok_or_else(|| RegistryError::UnknownSubtree { name: name.clone() })?;
+                Node::Subtree {
+                    name: Cow::Owned(format!("subtree_{name}")),
+                    tree: Box::new(subtree()),
+                }
+            }
+            NodeDescription::Failure => Node::Failure,
+            NodeDescription::Negation { child } => Node::Negation {
+                name: Cow::Borrowed("Negation"),
+                child: Box::new(self.build_tree(child)?),
+            },
+            NodeDescription::Selection { children } => Node::Selection {
+                name: Cow::Borrowed("Selection"),
+                children: self.build_children(children)?,
+            },
+            NodeDescription::Sequence { children } => Node::Sequence {
+                name: Cow::Borrowed("Sequence"),
+                children: self.build_children(children)?,
+            },
+            NodeDescription::MemorySelection { children } => Node::MemorySelection {
+                name: Cow::Borrowed("MemorySelection"),
+                children: self.build_children(children)?,
+                running_child: 0,
+            },
+            NodeDescription::MemorySequence { children } => Node::MemorySequence {
+                name: Cow::Borrowed("MemorySequence"),
+                children: self.build_children(children)?,
+                running_child: 0,
+            },
+            NodeDescription::Timeout { timeout, child } => Node::Timeout {
+                name: Cow::Borrowed("Timeout"),
+                timeout: *timeout,
+                child: Box::new(self.build_tree(child)?),
+                started_at: None,
+            },
+            NodeDescription::Cooldown { cooldown, child } => Node::Cooldown {
+                name: Cow::Borrowed("Cooldown"),
+                cooldown: *cooldown,
+                child: Box::new(self.build_tree(child)?),
+                succeeded_at: None,
+            },
+            NodeDescription::Retry { attempts, child } => Node::Retry {
+                name: Cow::Borrowed("Retry"),
+                attempts: *attempts,
+                child: Box::new(self.build_tree(child)?),
+                failures: 0,
+            },
+            NodeDescription::RateLimit { interval, child } => Node::RateLimit {
+                name: Cow::Borrowed("RateLimit"),
+                interval: *interval,
+                child: Box::new(self.build_tree(child)?),
+                last_tick: None,
+            },
+            NodeDescription::SwitchMotionType {
+                motion_type,
+                action,
+                alternatives,
+            } => switch_motion_type(
+                *motion_type,
+                self.build_tree(action)?,
+                self.build_tree(alternatives)?,
+            ),
+        };
+        Ok(node)
+    }
+
+    fn build_children(
+        &self,
+        children: &[NodeDescription],
+    ) -> Result<Vec<Node<Blackboard>>, RegistryError> {
+        children
+            .iter()
+            .map(|child| self.build_tree(child))
+            .collect()
+    }
+}
+
+fn check_arity(name: &str, expected: usize, arguments: &[String]) -> Result<(), RegistryError> {
+    if arguments.len() == expected {
+        Ok(())
+    } else {
+        Err(RegistryError::Arity {
+            name: name.to_string(),
+            expected,
+            actual: arguments.len(),
+        })
+    }
+}
+
+fn parse_argument<Argument: DeserializeOwned>(
+    name: &str,
+    argument: &str,
+) -> Result<Argument, RegistryError> {
+    let deserializer: StrDeserializer<'_, serde::de::value::Error> = argument.into_deserializer();
+    Argument::deserialize(deserializer).map_err(|error| RegistryError::InvalidArgument {
+        name: name.to_string(),
+        argument: argument.to_string(),
+        message: error.to_string(),
+    })
+}
+
+/// Registry containing all actions, conditions and subtrees of the behavior node. The built-in
+/// tree is available as the subtree `default`.
+pub fn default_registry() -> Registry {
+    let mut registry = Registry::default();
+
+    registry.register_action("calculate_voronoi_grid", calculate_voronoi_grid);
+    registry.register_action("damping", damping);
+    registry.register_action("dribble", dribble);
+    registry.register_action("evaluate_utilities", evaluate_utilities);
+    registry.register_action("guard_goal", guard_goal);
+    registry.register_action("injected_motion_command", injected_motion_command);
+    registry.register_action("intercept", intercept);
+    registry.register_action("kick", kick);
+    registry.register_action("leuchtturm", leuchtturm);
+    registry.register_action("look_at_ball", look_at_ball);
+    registry.register_action(
+        "look_at_hypothetical_ball_position",
+        look_at_hypothetical_ball_position,
+    );
+    registry.register_action("look_straight_ahead", look_straight_ahead);
+    registry.register_action("prepare", prepare);
+    registry.register_action("remote_control", remote_control);
+    registry.register_action("search_for_lost_ball", search_for_lost_ball);
+    registry.register_action("select_kick_target", select_kick_target);
+    registry.register_action("set_block_position_field", set_block_position_field);
+    registry.register_action(
+        "set_block_position_penalty_kick",
+        set_block_position_penalty_kick,
+    );
+    registry.register_action("set_kick_target_in_front", set_kick_target_in_front);
+    registry.register_action("stand", stand);
+    registry.register_action("stand_up", stand_up);
+    registry.register_action("use_decided_kick_power", use_decided_kick_power);
+    registry.register_action_with::<KickPower>("use_kick_power", use_kick_power);
+    registry.register_action("use_last_kick_power", use_last_kick_power);
+    registry.register_action("walk_to_ball", walk_to_ball);
+    registry.register_action("walk_to_block_position", walk_to_block_position);
+    registry.register_action("walk_to_centroid", walk_to_centroid);
+    registry.register_action("walk_to_kickoff_pose", walk_to_kickoff_pose);
+    registry.register_action(
+        "walk_to_pass_receiving_position",
+        walk_to_pass_receiving_position,
+    );
+    registry.register_action("walk_to_search_position", walk_to_search_position);
+    registry.register_action("walk_to_wall_position", walk_to_wall_position);
+
+    registry.register_condition("allow_schlong", allow_schlong);
+    registry.register_condition("has_ball_position", has_ball_position);
+    registry.register_condition(
+        "has_hypothetical_ball_position",
+        has_hypothetical_ball_position,
+    );
+    registry.register_condition("has_new_ball_position", has_new_ball_position);
+    registry.register_condition(
+        "has_suggested_search_position",
+        has_suggested_search_position,
+    );
+    registry.register_condition("hulks_is_kicking_team", hulks_is_kicking_team);
+    registry.register_condition("is_allowed_to_switch", is_allowed_to_switch);
+    registry.register_condition(
+        "is_ball_interception_candidate",
+        is_ball_interception_candidate,
+    );
+    registry.register_condition("is_ball_clearable", is_ball_clearable);
+    registry.register_condition("is_close_to_ball", is_close_to_ball);
+    registry.register_condition("is_close_to_ball_aligned", is_close_to_ball_aligned);
+    registry.register_condition("is_close_to_target", is_close_to_target);
+    registry.register_condition("is_closest_to_ball", is_closest_to_ball);
+    registry.register_condition("is_fallen", is_fallen);
+    registry.register_condition("is_goalkeeper", is_goalkeeper);
+    registry.register_condition("is_in_sub_state", is_in_sub_state);
+    registry.register_condition("is_in_wall", is_in_wall);
+    registry.register_condition_with::<MotionType>("is_last_motion_type", is_last_motion_type);
+    registry.register_condition_with::<PrimaryState>("is_primary_state", is_primary_state);
+    registry.register_condition("is_under_pressure", is_under_pressure);
+    registry.register_condition("is_remote_controlled", is_remote_controlled);
+    registry.register_condition("is_remote_kick_mode", is_remote_kick_mode);
+    registry.register_condition("is_set_play", is_set_play);
+    registry.register_condition("is_set_play_execution_time", is_set_play_execution_time);
+    registry.register_condition("is_striker", is_striker);
+    registry.register_condition_with::<SubState>("is_sub_state", is_sub_state);
+    registry
+        .register_condition_with::<UtilityCandidate>("is_utility_selected", is_utility_selected);
+
+    registry.register_subtree("default", create_tree);
+    registry.register_subtree("dribble", dribble_subtree);
+    registry.register_subtree("goalkeeper", goalkeeper_subtree);
+    registry.register_subtree("kick", kick_subtree);
+    registry.register_subtree("kick_alternatives", kick_alternatives_subtree);
+    registry.register_subtree("kick_power", kick_power_subtree);
+    registry.register_subtree("look_at_ball", look_at_ball_subtree);
+    registry.register_subtree("playing", playing_subtree);
+    registry.register_subtree("ready", ready_subtree);
+    registry.register_subtree("remote_control", remote_control_subtree);
+    registry.register_subtree("search", search_subtree);
+    registry.register_subtree("search_for_lost_ball", search_for_lost_ball_subtree);
+    registry.register_subtree("set_play", set_play_subtree);
+    registry.register_subtree("sub_state", sub_state_subtree);
+    registry.register_subtree("supporter", supporter_subtree);
+    registry.register_subtree("utility", utility_subtree);
+    registry.register_subtree("walk_alternatives", walk_alternatives_subtree);
+    registry.register_subtree("walk_to_ball", walk_to_ball_subtree);
+
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(description: &str) -> NodeDescription {
+        json5::from_str(description).unwrap()
+    }
+
+    #[test]
+    fn builds_description_with_arguments_and_subtrees() {
+        let description = parse(
+            r#"{
+                selection: {
+                    children: [
+                        {
+                            sequence: {
+                                children: [
+                                    { condition: { name: "is_primary_state", arguments: ["Playing"] } },
+                                    { subtree: { name: "look_at_ball" } },
+                                    { action: { name: "stand" } },
+                                ],
+                            },
+                        },
+                        "failure",
+                    ],
+                },
+            }"#,
+        );
+
+        let tree = default_registry().build_tree(&description).unwrap();
+        let layout = tree.static_layout_trace();
+
+        assert_eq!(layout.name, "Selection");
+        let names: Vec<_> = layout.children[0]
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["is_primary_state:Playing", "subtree_look_at_ball", "stand"]
+        );
+        assert_eq!(layout.children[1].name, "Failure");
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        let registry = default_registry();
+
+        let error = registry
+            .build_tree(&parse(r#"{ action: { name: "dance" } }"#))
+            .err();
+        assert_eq!(
+            error,
+            Some(RegistryError::UnknownAction {
+                name: "dance".to_string()
+            })
+        );
+
+        let error = registry
+            .build_tree(&parse(
+                r#"{ negation: { child: { condition: { name: "stand" } } } }"#,
+            ))
+            .err();
+        assert_eq!(
+            error,
+            Some(RegistryError::UnknownCondition {
+                name: "stand".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_arity_and_invalid_arguments() {
+        let registry = default_registry();
+
+        let error = registry
+            .build_tree(&parse(r#"{ condition: { name: "is_primary_state" } }"#))
+            .err();
+        assert_eq!(
+            error,
+            Some(RegistryError::Arity {
+                name: "is_primary_state".to_string(),
+                expected: 1,
+                actual: 0,
+            })
+        );
+
+        let error = registry
+            .build_tree(&parse(
+                r#"{ action: { name: "stand", arguments: ["Playing"] } }"#,
+            ))
+            .err();
+        assert!(matches!(error, Some(RegistryError::Arity { .. })));
+
+        let error = registry
+            .build_tree(&parse(
+                r#"{ action: { name: "use_kick_power", arguments: ["Tiny"] } }"#,
+            ))
+            .err();
+        assert!(matches!(error, Some(RegistryError::InvalidArgument { .. })));
+    }
+}
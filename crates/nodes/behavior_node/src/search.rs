@@ -1,13 +1,43 @@
-use linear_algebra::{Pose2, vector};
+use coordinate_systems::Field;
+use hsl_network_messages::PlayerNumber;
+use linear_algebra::{Point2, Pose2, vector};
 use types::{
     behavior_tree::Status,
     motion_command::{BodyMotion, MotionCommand, OrientationMode},
 };
+use voronoi::{Ownership, VoronoiGrid};
 
 use crate::{node::Blackboard, walk::walk_to};
 
 pub fn has_suggested_search_position(blackboard: &mut Blackboard) -> bool {
-    blackboard.world_state.suggested_search_position.is_some()
+    search_position(blackboard).is_some()
+}
+
+/// The suggested search position, restricted to the own voronoi cell if search candidates and the
+/// voronoi grid are known so that teammates search different spots.
+fn search_position(blackboard: &Blackboard) -> Option<Point2<Field>> {
+    let (Some(map), false) = (
+        &blackboard.voronoi_map,
+        blackboard.world_state.search_candidates.is_empty(),
+    ) else {
+        return blackboard.world_state.suggested_search_position;
+    };
+    owned_search_position(
+        &blackboard.world_state.search_candidates,
+        map,
+        blackboard.world_state.robot.player_number,
+    )
+}
+
+/// The hottest search candidate inside the voronoi cell of the player.
+pub fn owned_search_position(
+    search_candidates: &[Point2<Field>],
+    map: &VoronoiGrid,
+    player_number: PlayerNumber,
+) -> Option<Point2<Field>> {
+    search_candidates.iter().copied().find(|candidate| {
+        map.nearest_non_blocked_ownership(*candidate) == Some(Ownership::Robot(player_number))
+    })
 }
 
 pub fn leuchtturm(blackboard: &mut Blackboard) -> Status {
@@ -45,7 +75,7 @@ fn get_leuchtturm_direction(blackboard: &Blackboard) -> f32 {
 
 pub fn walk_to_search_position(blackboard: &mut Blackboard) -> Status {
     if let (Some(search_position), Some(ground_to_field)) = (
-        blackboard.world_state.suggested_search_position,
+        search_position(blackboard),
         blackboard.world_state.robot.ground_to_field,
     ) {
         let search_position_in_ground = ground_to_field.inverse() * search_position;
@@ -65,3 +95,45 @@ pub fn walk_to_search_position(blackboard: &mut Blackboard) -> Status {
 
     Status::Failure
 }
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::point;
+    use voronoi::VoronoiBounds;
+
+    use super::*;
+
+    #[test]
+    fn search_positions_are_partitioned_by_voronoi_cells() {
+        let mut map = VoronoiGrid::new(
+            VoronoiBounds {
+                grid_min: point![-4.5, -3.0],
+                grid_max: point![4.5, 3.0],
+                centroid_min: point![-4.5, -3.0],
+                centroid_max: point![4.5, 3.0],
+            },
+            0.25,
+        );
+        map.multi_source_dijkstra(
+            &[
+                (Pose2::new(point![-2.0, 0.0], 0.0), PlayerNumber::Two),
+                (Pose2::new(point![2.0, 0.0], 0.0), PlayerNumber::Three),
+            ],
+            0.0,
+        );
+        let candidates = [point![3.0, 1.0], point![-1.0, -1.0]];
+
+        assert_eq!(
+            owned_search_position(&candidates, &map, PlayerNumber::Two),
+            Some(point![-1.0, -1.0])
+        );
+        assert_eq!(
+            owned_search_position(&candidates, &map, PlayerNumber::Three),
+            Some(point![3.0, 1.0])
+        );
+        assert_eq!(
+            owned_search_position(&candidates, &map, PlayerNumber::Four),
+            None
+        );
+    }
+}
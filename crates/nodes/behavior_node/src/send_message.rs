@@ -1,9 +1,17 @@
 use std::{net::SocketAddr, time::Duration};
 
 use booster::FallDownStateType;
-use hsl_network_messages::{GameControllerReturnMessage, HulkMessage, StateMessage};
+use hsl_network_messages::{
+    GameControllerReturnMessage, HulkMessage, StateMessage, StrikerMessage,
+};
 use ros_z::time::Time;
-use types::{messages::OutgoingMessage, parameters::HslNetworkParameters};
+use types::{
+    message_scheduler::{MessageBudget, MessagePriority, MessageScheduler},
+    messages::OutgoingMessage,
+    parameters::HslNetworkParameters,
+    time_wrapper::TimeWrapper,
+    world_state::WorldState,
+};
 
 use crate::node::Blackboard;
 
@@ -59,34 +67,85 @@ impl Blackboard {
             hsl_network_parameters.game_controller_return_message_interval,
         )
     }
+}
 
-    pub fn state_message(&mut self) -> Option<OutgoingMessage> {
-        let now = self.world_state.now;
-        let remaining_amount_of_messages = self
-            .world_state
-            .filtered_game_controller_state
-            .as_ref()
-            .map(|state| state.remaining_number_of_messages);
+/// Sends the HSL messages of one robot through a single scheduler, so striker claims and state
+/// messages share the team budget.
+#[derive(Debug, Default)]
+pub struct HslSender {
+    scheduler: MessageScheduler,
+    last_sent_message_had_ball: bool,
+    pending_striker_claim: Option<StrikerMessage>,
+    last_striker_claim_time: Option<Time>,
+}
 
-        if !self.is_state_message_cooldown_elapsed(now, &self.parameters.hsl_network) {
-            return None;
+impl HslSender {
+    /// Queues a striker claim of the role assignment, it is sent as urgent message.
+    pub fn claim_striker(&mut self, claim: &TimeWrapper<StrikerMessage>) {
+        if self
+            .last_striker_claim_time
+            .is_some_and(|last_claim_time| claim.time <= last_claim_time)
+        {
+            return;
         }
-        if remaining_amount_of_messages.is_none_or(|remaining_amount_of_messages| {
-            remaining_amount_of_messages
-                < self
-                    .parameters
-                    .hsl_network
-                    .remaining_amount_of_messages_to_stop_sending
-        }) {
+        self.last_striker_claim_time = Some(claim.time);
+        self.pending_striker_claim = Some(claim.inner);
+    }
+
+    pub fn message(
+        &mut self,
+        world_state: &WorldState,
+        hsl_network_parameters: &HslNetworkParameters,
+    ) -> Option<OutgoingMessage> {
+        let now = world_state.now;
+        let game_controller_state = world_state.filtered_game_controller_state.as_ref()?;
+
+        let number_of_teammates = world_state
+            .player_states
+            .iter()
+            .filter(|(player_number, player_state)| {
+                *player_number != world_state.robot.player_number && player_state.is_some()
+            })
+            .count();
+        let budget = MessageBudget::new(
+            game_controller_state,
+            number_of_teammates + 1,
+            hsl_network_parameters.half_duration,
+        );
+
+        if let Some(striker_claim) = self.pending_striker_claim {
+            if !self.scheduler.is_due(
+                now,
+                MessagePriority::Urgent,
+                &budget,
+                hsl_network_parameters,
+            ) {
+                return None;
+            }
+            self.scheduler.mark_sent(now);
+            self.pending_striker_claim = None;
+            self.last_sent_message_had_ball = true;
+            return Some(OutgoingMessage::Hsl(HulkMessage::Striker(striker_claim)));
+        }
+
+        let sees_ball = world_state.ball.is_some();
+        let priority = if sees_ball && !self.last_sent_message_had_ball {
+            MessagePriority::Urgent
+        } else {
+            MessagePriority::Regular
+        };
+        if !self
+            .scheduler
+            .is_due(now, priority, &budget, hsl_network_parameters)
+        {
             return None;
         }
 
-        let ground_to_field = self.world_state.robot.ground_to_field.unwrap_or_default();
+        let ground_to_field = world_state.robot.ground_to_field.unwrap_or_default();
 
         let pose = ground_to_field.as_pose();
 
-        let ball_position = self
-            .world_state
+        let ball_position = world_state
             .ball
             .map(|ball| hsl_network_messages::BallPosition {
                 age: now
@@ -97,27 +156,17 @@ impl Blackboard {
             });
 
         let message = HulkMessage::State(StateMessage {
-            player_number: self.world_state.robot.player_number,
+            player_number: world_state.robot.player_number,
             pose,
             ball_position,
+            searched_area: world_state.searched_area,
         });
 
-        self.last_sent_hsl_message_time = Some(now);
+        self.scheduler.mark_sent(now);
+        self.last_sent_message_had_ball = sees_ball;
 
         Some(OutgoingMessage::Hsl(message))
     }
-
-    fn is_state_message_cooldown_elapsed(
-        &self,
-        now: Time,
-        hsl_network_parameters: &HslNetworkParameters,
-    ) -> bool {
-        is_cooldown_elapsed(
-            now,
-            self.last_sent_hsl_message_time,
-            hsl_network_parameters.hsl_state_message_send_interval,
-        )
-    }
 }
 
 fn is_cooldown_elapsed(now: Time, last: Option<Time>, cooldown: Duration) -> bool {
@@ -126,3 +175,136 @@ fn is_cooldown_elapsed(now: Time, last: Option<Time>, cooldown: Duration) -> boo
         Some(last_time) => now.duration_since(last_time) > cooldown,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hsl_network_messages::{BallPosition, PlayerNumber};
+    use linear_algebra::{Pose2, point};
+    use types::filtered_game_controller_state::FilteredGameControllerState;
+
+    use super::*;
+
+    fn parameters() -> HslNetworkParameters {
+        HslNetworkParameters {
+            remaining_amount_of_messages_to_stop_sending: 20,
+            remaining_amount_of_messages_to_stop_sending_urgent: 5,
+            silence_interval_between_messages: Duration::from_secs(1),
+            hsl_state_message_send_interval: Duration::from_secs(2),
+            half_duration: Duration::from_secs(600),
+            ..Default::default()
+        }
+    }
+
+    fn world_state(now: Time, remaining_number_of_messages: u16) -> WorldState {
+        WorldState {
+            now,
+            filtered_game_controller_state: Some(FilteredGameControllerState {
+                remaining_time_in_half: Duration::from_secs(600),
+                remaining_number_of_messages,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn striker_claim(time: Time) -> TimeWrapper<StrikerMessage> {
+        TimeWrapper {
+            time,
+            inner: StrikerMessage {
+                player_number: PlayerNumber::Three,
+                pose: Pose2::default(),
+                ball_position: BallPosition {
+                    position: point![1.0, 0.0],
+                    age: Duration::ZERO,
+                },
+                time_to_reach_kick_position: Duration::from_secs(2),
+            },
+        }
+    }
+
+    fn is_striker_message(message: Option<OutgoingMessage>) -> bool {
+        matches!(message, Some(OutgoingMessage::Hsl(HulkMessage::Striker(_))))
+    }
+
+    #[test]
+    fn striker_claim_is_sent_before_next_regular_message_is_due() {
+        let parameters = parameters();
+        let mut sender = HslSender::default();
+        let start = Time::zero() + Duration::from_secs(10);
+
+        assert!(
+            sender
+                .message(&world_state(start, 1200), &parameters)
+                .is_some()
+        );
+
+        let claim_time = start + Duration::from_millis(1500);
+        sender.claim_striker(&striker_claim(claim_time));
+        assert!(is_striker_message(
+            sender.message(&world_state(claim_time, 1200), &parameters)
+        ));
+    }
+
+    #[test]
+    fn striker_claim_waits_for_silence_interval() {
+        let parameters = parameters();
+        let mut sender = HslSender::default();
+        let start = Time::zero() + Duration::from_secs(10);
+
+        assert!(
+            sender
+                .message(&world_state(start, 1200), &parameters)
+                .is_some()
+        );
+
+        let claim_time = start + Duration::from_millis(500);
+        sender.claim_striker(&striker_claim(claim_time));
+        assert!(
+            sender
+                .message(&world_state(claim_time, 1200), &parameters)
+                .is_none()
+        );
+        assert!(is_striker_message(sender.message(
+            &world_state(start + Duration::from_millis(1100), 1200),
+            &parameters
+        )));
+    }
+
+    #[test]
+    fn striker_claim_is_sent_once() {
+        let parameters = parameters();
+        let mut sender = HslSender::default();
+        let claim = striker_claim(Time::zero() + Duration::from_secs(10));
+
+        sender.claim_striker(&claim);
+        assert!(is_striker_message(
+            sender.message(&world_state(claim.time, 1200), &parameters)
+        ));
+
+        sender.claim_striker(&claim);
+        assert!(!is_striker_message(sender.message(
+            &world_state(claim.time + Duration::from_secs(2), 1200),
+            &parameters
+        )));
+    }
+
+    #[test]
+    fn striker_claim_uses_urgent_reserve() {
+        let parameters = parameters();
+        let mut sender = HslSender::default();
+        let now = Time::zero() + Duration::from_secs(10);
+
+        assert!(sender.message(&world_state(now, 10), &parameters).is_none());
+
+        sender.claim_striker(&striker_claim(now));
+        assert!(is_striker_message(
+            sender.message(&world_state(now, 10), &parameters)
+        ));
+        sender.claim_striker(&striker_claim(now + Duration::from_secs(2)));
+        assert!(
+            sender
+                .message(&world_state(now + Duration::from_secs(2), 4), &parameters)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use coordinate_systems::Field;
+use hsl_network_messages::{PlayerNumber, SubState, Team};
+use linear_algebra::{Orientation2, Point2, Pose2, Vector2, distance, point, vector};
+use types::{
+    behavior_tree::Status, field_dimensions::FieldDimensions,
+    filtered_game_controller_state::FilteredGameControllerState, motion_command::OrientationMode,
+    motion_type::MotionType, parameters::SetPlayParameters,
+};
+
+use crate::{
+    action,
+    actions::stand,
+    behavior_tree::Node,
+    condition,
+    conditions::{hulks_is_kicking_team, is_close_to_ball_aligned, is_striker},
+    kick::kick_subtree,
+    negation,
+    node::Blackboard,
+    selection, sequence, subtree,
+    switch_motion_type::switch_motion_type,
+    walk::{walk_alternatives_subtree, walk_to, walk_to_ball_subtree, walk_to_centroid},
+};
+
+pub fn set_play_subtree() -> Node<Blackboard> {
+    selection!(
+        sequence!(
+            condition!(hulks_is_kicking_team),
+            selection!(
+                sequence!(
+                    condition!(is_striker),
+                    selection!(
+                        sequence!(
+                            negation!(condition!(is_close_to_ball_aligned)),
+                            subtree!(walk_to_ball_subtree)
+                        ),
+                        sequence!(
+                            condition!(is_set_play_execution_time),
+                            subtree!(kick_subtree)
+                        ),
+                        action!(stand)
+                    )
+                ),
+                switch_motion_type(
+                    MotionType::Walk,
+                    action!(walk_to_pass_receiving_position),
+                    subtree!(walk_alternatives_subtree),
+                )
+            )
+        ),
+        sequence!(
+            condition!(is_in_wall),
+            switch_motion_type(
+                MotionType::Walk,
+                action!(walk_to_wall_position),
+                subtree!(walk_alternatives_subtree),
+            )
+        ),
+        action!(walk_to_centroid),
+        action!(stand)
+    )
+}
+
+/// Kick-ins, corner kicks, goal kicks and pushing free kicks.
+pub fn is_set_play(blackboard: &mut Blackboard) -> bool {
+    matches!(
+        blackboard.world_state.filtered_game_controller_state,
+        Some(FilteredGameControllerState {
+            sub_state: Some(
+                SubState::ThrowIn
+                    | SubState::CornerKick
+                    | SubState::GoalKick
+                    | SubState::DirectFreeKick
+                    | SubState::IndirectFreeKick
+            ),
+            kicking_team: Some(_),
+            ..
+        })
+    )
+}
+
+/// Whether the remaining time of the set play is short enough for the kicker to execute it.
+pub fn is_set_play_execution_time(blackboard: &mut Blackboard) -> bool {
+    blackboard
+        .world_state
+        .filtered_game_controller_state
+        .as_ref()
+        .is_some_and(|game_controller_state| {
+            game_controller_state.secondary_time
+                <= blackboard.parameters.set_play.execution_remaining_time
+        })
+}
+
+/// Whether the opponent executes the set play and the robot is one of the wall members.
+pub fn is_in_wall(blackboard: &mut Blackboard) -> bool {
+    matches!(
+        blackboard.world_state.filtered_game_controller_state,
+        Some(FilteredGameControllerState {
+            kicking_team: Some(Team::Opponent),
+            ..
+        })
+    ) && own_wall_position(blackboard).is_some()
+}
+
+pub fn walk_to_wall_position(blackboard: &mut Blackboard) -> Status {
+    let (Some(wall_position), Some(ball), Some(ground_to_field)) = (
+        own_wall_position(blackboard),
+        &blackboard.ball,
+        blackboard.world_state.robot.ground_to_field,
+    ) else {
+        return Status::Failure;
+    };
+    let ball_in_ground = ground_to_field.inverse() * ball.position;
+    let wall_position = ground_to_field.inverse() * wall_position;
+
+    walk_to(
+        blackboard,
+        Pose2::from_parts(
+            wall_position,
+            Orientation2::from_vector(ball_in_ground - wall_position),
+        ),
+        blackboard.parameters.walk_speed.blocking,
+        OrientationMode::LookAt {
+            target: ball_in_ground,
+            tolerance: blackboard.parameters.walk_and_stand.orientation_tolerance,
+        },
+        blackboard
+            .parameters
+            .walk_and_stand
+            .normal_distance_to_be_aligned,
+        blackboard.parameters.walk_and_stand.hysteresis,
+    )
+}
+
+/// Walks into the own voronoi cell at a distance to the ball suitable for receiving a pass.
+pub fn walk_to_pass_receiving_position(blackboard: &mut Blackboard) -> Status {
+    let (Some(ball), Some(ground_to_field), Some(map)) = (
+        &blackboard.ball,
+        blackboard.world_state.robot.ground_to_field,
+        &blackboard.voronoi_map,
+    ) else {
+        return Status::Failure;
+    };
+    let Some(centroid) = map.centroid_for_player(blackboard.world_state.robot.player_number) else {
+        return Status::Failure;
+    };
+    let target = ground_to_field.inverse()
+        * pass_receiving_position(
+            ball.position,
+            centroid,
+            &blackboard.field_dimensions,
+            &blackboard.parameters.set_play,
+        );
+    let ball_in_ground = ground_to_field.inverse() * ball.position;
+
+    walk_to(
+        blackboard,
+        Pose2::from_parts(target, Orientation2::from_vector(ball_in_ground - target)),
+        blackboard.parameters.walk_speed.kicking,
+        OrientationMode::LookAt {
+            target: ball_in_ground,
+            tolerance: blackboard.parameters.walk_and_stand.orientation_tolerance,
+        },
+        blackboard
+            .parameters
+            .walk_and_stand
+            .normal_distance_to_be_aligned,
+        blackboard.parameters.walk_and_stand.hysteresis,
+    )
+}
+
+fn own_wall_position(blackboard: &Blackboard) -> Option<Point2<Field>> {
+    let ball = blackboard.ball.as_ref()?;
+    let ground_to_field = blackboard.world_state.robot.ground_to_field?;
+    let own_player_number = blackboard.world_state.robot.player_number;
+
+    let robots: Vec<_> = blackboard
+        .world_state
+        .player_states
+        .iter()
+        .filter(|(player_number, _)| {
+            *player_number != own_player_number
+                && Some(*player_number) != blackboard.goal_keeper_number
+        })
+        .filter_map(|(player_number, player_state)| {
+            Some((player_number, player_state.as_ref()?.pose.position()))
+        })
+        .chain([(own_player_number, ground_to_field.translation())])
+        .collect();
+
+    wall_position(
+        own_player_number,
+        &robots,
+        ball.position,
+        &blackboard.field_dimensions,
+        &blackboard.parameters.set_play,
+        blackboard.parameters.path_planning.robot_radius,
+    )
+}
+
+/// The robots closest to the ball form a wall between the ball and the own goal at the distance
+/// required by the rules. Returns `None` if the robot is not part of the wall.
+pub fn wall_position(
+    own_player_number: PlayerNumber,
+    robots: &[(PlayerNumber, Point2<Field>)],
+    ball: Point2<Field>,
+    field_dimensions: &FieldDimensions,
+    parameters: &SetPlayParameters,
+    robot_radius: f32,
+) -> Option<Point2<Field>> {
+    let direction = (point![-field_dimensions.length / 2.0, 0.0] - ball)
+        .try_normalize(f32::EPSILON)
+        .unwrap_or(vector![-1.0, 0.0]);
+    let lateral = vector![-direction.y(), direction.x()];
+    let lateral_offset = |position: Point2<Field>| (position - ball).dot(&lateral);
+
+    let mut members = robots.to_vec();
+    members.sort_by(|left, right| distance(left.1, ball).total_cmp(&distance(right.1, ball)));
+    members.truncate(parameters.maximum_wall_size);
+    members.sort_by(|left, right| lateral_offset(left.1).total_cmp(&lateral_offset(right.1)));
+    let slot = members
+        .iter()
+        .position(|(player_number, _)| *player_number == own_player_number)?;
+
+    let wall_distance = field_dimensions.center_circle_diameter / 2.0
+        + robot_radius
+        + parameters.wall_distance_offset;
+    let slot_offset = slot as f32 - (members.len() - 1) as f32 / 2.0;
+    Some(ball + direction * wall_distance + lateral * (slot_offset * parameters.wall_spacing))
+}
+
+/// Moves the voronoi centroid of the robot to a passing distance from the ball.
+pub fn pass_receiving_position(
+    ball: Point2<Field>,
+    centroid: Point2<Field>,
+    field_dimensions: &FieldDimensions,
+    parameters: &SetPlayParameters,
+) -> Point2<Field> {
+    let offset = centroid - ball;
+    let direction: Vector2<Field> = offset
+        .try_normalize(f32::EPSILON)
+        .unwrap_or(vector![1.0, 0.0]);
+    let receiving_distance = offset.norm().clamp(
+        parameters.minimum_pass_receiving_distance,
+        parameters.maximum_pass_receiving_distance,
+    );
+    let target = ball + direction * receiving_distance;
+    point![
+        target.x().clamp(
+            -field_dimensions.length / 2.0,
+            field_dimensions.length / 2.0
+        ),
+        target
+            .y()
+            .clamp(-field_dimensions.width / 2.0, field_dimensions.width / 2.0)
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn parameters() -> SetPlayParameters {
+        SetPlayParameters {
+            execution_remaining_time: Duration::from_secs(20),
+            maximum_wall_size: 2,
+            wall_spacing: 0.35,
+            wall_distance_offset: 0.1,
+            minimum_pass_receiving_distance: 1.5,
+            maximum_pass_receiving_distance: 3.0,
+        }
+    }
+
+    #[test]
+    fn closest_robots_form_wall_between_ball_and_goal() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let ball = point![0.0, 0.0];
+        let robots = [
+            (PlayerNumber::Two, point![-1.0, 1.0]),
+            (PlayerNumber::Three, point![-1.0, -1.0]),
+            (PlayerNumber::Four, point![-3.0, 0.0]),
+        ];
+        let wall_distance = field_dimensions.center_circle_diameter / 2.0 + 0.2 + 0.1;
+
+        let position = |player_number| {
+            wall_position(
+                player_number,
+                &robots,
+                ball,
+                &field_dimensions,
+                &parameters(),
+                0.2,
+            )
+        };
+
+        assert_relative_eq!(
+            position(PlayerNumber::Two).unwrap(),
+            point![-wall_distance, 0.175],
+            epsilon = 1e-5
+        );
+        assert_relative_eq!(
+            position(PlayerNumber::Three).unwrap(),
+            point![-wall_distance, -0.175],
+            epsilon = 1e-5
+        );
+        assert_eq!(position(PlayerNumber::Four), None);
+    }
+
+    #[test]
+    fn pass_receiving_position_keeps_passing_distance() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let ball = point![0.0, 0.0];
+
+        let close =
+            pass_receiving_position(ball, point![0.5, 0.0], &field_dimensions, &parameters());
+        let far =
+            pass_receiving_position(ball, point![0.0, -5.0], &field_dimensions, &parameters());
+
+        assert_relative_eq!(close, point![1.5, 0.0], epsilon = 1e-5);
+        assert_relative_eq!(far, point![0.0, -3.0], epsilon = 1e-5);
+    }
+}
@@ -1,9 +1,6 @@
 use hsl_network_messages::SubState;
-use linear_algebra::{Rotation2, point};
-use types::{
-    behavior_tree::Status, field_dimensions::Side,
-    filtered_game_controller_state::FilteredGameControllerState,
-};
+use linear_algebra::point;
+use types::{behavior_tree::Status, filtered_game_controller_state::FilteredGameControllerState};
 
 use crate::{
     action,
@@ -17,6 +14,7 @@ use crate::{
     walk::{walk_to_ball_subtree, walk_to_block_position},
 };
 
+/// Sub states not handled as set plays, i.e. penalty kicks during the game.
 pub fn sub_state_subtree() -> Node<Blackboard> {
     selection!(
         sequence!(
@@ -30,25 +28,8 @@ pub fn sub_state_subtree() -> Node<Blackboard> {
             )
         ),
         sequence!(
-            selection!(
-                sequence!(
-                    selection!(
-                        condition!(is_sub_state, SubState::ThrowIn),
-                        condition!(is_sub_state, SubState::IndirectFreeKick),
-                        condition!(is_sub_state, SubState::DirectFreeKick),
-                        condition!(is_sub_state, SubState::GoalKick),
-                    ),
-                    action!(set_block_position_field),
-                ),
-                sequence!(
-                    condition!(is_sub_state, SubState::CornerKick),
-                    action!(set_block_position_corner),
-                ),
-                sequence!(
-                    condition!(is_sub_state, SubState::PenaltyKick),
-                    action!(set_block_position_penalty_kick),
-                )
-            ),
+            condition!(is_sub_state, SubState::PenaltyKick),
+            action!(set_block_position_penalty_kick),
             action!(walk_to_block_position)
         )
     )
@@ -100,39 +81,6 @@ pub fn set_block_position_field(blackboard: &mut Blackboard) -> Status {
     }
 }
 
-pub fn set_block_position_corner(blackboard: &mut Blackboard) -> Status {
-    if let (Some(ball), Some(ground_to_field)) = (
-        &blackboard.ball,
-        &blackboard.world_state.robot.ground_to_field,
-    ) {
-        let ball_position = ground_to_field.inverse() * ball.position;
-        let goal_position =
-            ground_to_field.inverse() * point!(-blackboard.field_dimensions.length / 2.0, 0.0);
-        let angle_direction = match ball.field_side {
-            Side::Left => 1.0,
-            Side::Right => -1.0,
-        };
-
-        let parameters = &blackboard.parameters.substates;
-
-        let direction = Rotation2::new(angle_direction * parameters.corner_kick_blocking_angle)
-            * (goal_position - ball_position).normalize();
-
-        let distance_to_ball = (blackboard.field_dimensions.center_circle_diameter / 2.0
-            + blackboard.parameters.substates.blocking_distance_offset)
-            .max(
-                blackboard.field_dimensions.center_circle_diameter / 2.0
-                    + blackboard.parameters.path_planning.robot_radius,
-            );
-
-        blackboard.walk_position = Some(ball_position + direction * distance_to_ball);
-
-        Status::Success
-    } else {
-        Status::Failure
-    }
-}
-
 pub fn set_block_position_penalty_kick(blackboard: &mut Blackboard) -> Status {
     if let Some(ground_to_field) = &blackboard.world_state.robot.ground_to_field {
         let field_dimensions = blackboard.field_dimensions;
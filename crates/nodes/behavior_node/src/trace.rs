@@ -0,0 +1,381 @@
+use std::{collections::VecDeque, time::Duration};
+
+use coordinate_systems::{Field, Ground};
+use linear_algebra::{Point2, Pose2};
+use ros_z::{Message, Service, ServiceTypeInfo, TypeInfo, time::Time};
+use ros_z_schema::ServiceDef;
+use serde::{Deserialize, Serialize};
+use types::{
+    behavior_tree::{NodeTrace, Status},
+    motion_type::MotionType,
+    parameters::{BehaviorTraceParameters, UtilityCandidate},
+    roles::Role,
+};
+
+use crate::node::Blackboard;
+
+/// Status and evaluation time of a node in one tick. Nodes are numbered in depth-first order of the
+/// static tree layout.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Message)]
+pub struct NodeChange {
+    pub index: u32,
+    /// `None` if the node was not ticked
+    pub status: Option<Status>,
+    pub duration: Duration,
+}
+
+/// Blackboard values needed to explain a decision after the fact.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Message)]
+pub struct BlackboardSnapshot {
+    pub robot_pose: Option<Pose2<Field>>,
+    pub ball: Option<Point2<Field>>,
+    pub role: Option<Role>,
+    pub utility_selection: Option<UtilityCandidate>,
+    pub walk_target: Option<Point2<Ground>>,
+    pub kick_target: Option<Point2<Field>>,
+    pub motion_type: Option<MotionType>,
+}
+
+impl BlackboardSnapshot {
+    pub fn new(blackboard: &Blackboard) -> Self {
+        Self {
+            robot_pose: blackboard
+                .world_state
+                .robot
+                .ground_to_field
+                .map(|ground_to_field| ground_to_field.as_pose()),
+            ball: blackboard.ball.as_ref().map(|ball| ball.position),
+            role: blackboard.world_state.robot.role,
+            utility_selection: blackboard.utility_selection,
+            walk_target: blackboard.walk_position,
+            kick_target: blackboard.last_kick_target,
+            motion_type: blackboard.last_motion_type,
+        }
+    }
+}
+
+/// Difference of a tick to the previous one.
+#[derive(Clone, Debug, Serialize, Deserialize, Message)]
+pub struct TraceDiff {
+    pub tick: u64,
+    pub time: Time,
+    pub tick_duration: Duration,
+    /// Nodes from the root to the leaf that decided the result of the tick
+    pub branch_path: Vec<u32>,
+    /// Nodes whose status changed or whose evaluation time changed noticeably
+    pub changes: Vec<NodeChange>,
+    pub blackboard: BlackboardSnapshot,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Message)]
+pub struct TraceHistoryRequest {
+    /// Number of most recent ticks to return, all stored ticks if zero
+    pub number_of_ticks: u32,
+}
+
+/// Recent ticks of the behavior tree. `base` holds the state of all ticked nodes before the first
+/// record, applying the changes of the records in order reconstructs every tick.
+#[derive(Clone, Debug, Serialize, Deserialize, Message)]
+pub struct TraceHistory {
+    pub layout: NodeTrace,
+    pub base: Vec<NodeChange>,
+    pub records: Vec<TraceDiff>,
+}
+
+pub struct GetTraceHistory;
+
+impl Service for GetTraceHistory {
+    type Request = TraceHistoryRequest;
+    type Response = TraceHistory;
+}
+
+impl ServiceTypeInfo for GetTraceHistory {
+    fn service_type_info() -> TypeInfo {
+        let descriptor = ServiceDef::new(
+            "behavior_node::GetTraceHistory",
+            TraceHistoryRequest::type_name(),
+            TraceHistory::type_name(),
+        )
+        .expect("trace history service descriptor should be static and valid");
+        let hash = ros_z_schema::compute_hash(&descriptor)
+            .expect("trace history service hash should be static and valid");
+        TypeInfo::new(descriptor.type_name.as_str(), hash)
+    }
+}
+
+type NodeStates = Vec<Option<(Status, Duration)>>;
+
+/// Turns full traces into diffs and keeps a ring buffer of them.
+#[derive(Default)]
+pub struct TraceRecorder {
+    tick: u64,
+    previous: NodeStates,
+    base: NodeStates,
+    history: VecDeque<TraceDiff>,
+}
+
+impl TraceRecorder {
+    /// Forgets all ticks, e.g. after the tree was replaced and node indices changed.
+    pub fn reset(&mut self) {
+        self.previous.clear();
+        self.base.clear();
+        self.history.clear();
+    }
+
+    pub fn record(
+        &mut self,
+        layout: &NodeTrace,
+        trace: &NodeTrace,
+        time: Time,
+        blackboard: BlackboardSnapshot,
+        parameters: &BehaviorTraceParameters,
+    ) -> TraceDiff {
+        let mut states = Vec::new();
+        let mut branch_path = Vec::new();
+        flatten(layout, Some(trace), true, &mut states, &mut branch_path);
+        self.previous.resize(states.len(), None);
+
+        let changes = states
+            .iter()
+            .zip(&self.previous)
+            .enumerate()
+            .filter(|(_, (current, previous))| {
+                has_changed(current, previous, parameters.minimum_duration_change)
+            })
+            .map(|(index, (current, _))| node_change(index, current))
+            .collect();
+        self.previous = states;
+
+        let diff = TraceDiff {
+            tick: self.tick,
+            time,
+            tick_duration: trace.duration,
+            branch_path,
+            changes,
+            blackboard,
+        };
+        self.tick += 1;
+
+        self.history.push_back(diff.clone());
+        while self.history.len() > parameters.history_length {
+            if let Some(evicted) = self.history.pop_front() {
+                apply(&mut self.base, &evicted.changes);
+            }
+        }
+        diff
+    }
+
+    pub fn history(&self, layout: &NodeTrace, number_of_ticks: usize) -> TraceHistory {
+        let skipped = match number_of_ticks {
+            0 => 0,
+            number_of_ticks => self.history.len().saturating_sub(number_of_ticks),
+        };
+        let mut base = self.base.clone();
+        for diff in self.history.iter().take(skipped) {
+            apply(&mut base, &diff.changes);
+        }
+
+        TraceHistory {
+            layout: layout.clone(),
+            base: base
+                .iter()
+                .enumerate()
+                .filter(|(_, state)| state.is_some())
+                .map(|(index, state)| node_change(index, state))
+                .collect(),
+            records: self.history.iter().skip(skipped).cloned().collect(),
+        }
+    }
+}
+
+/// Collects the states of all layout nodes in depth-first order. The branch path follows the last
+/// ticked child of every node since composites stop ticking at the child that decides the result.
+fn flatten(
+    layout: &NodeTrace,
+    trace: Option<&NodeTrace>,
+    is_on_branch: bool,
+    states: &mut NodeStates,
+    branch_path: &mut Vec<u32>,
+) {
+    let index = states.len() as u32;
+    states.push(trace.map(|trace| (trace.status.clone(), trace.duration)));
+    let is_on_branch = is_on_branch && trace.is_some();
+    if is_on_branch {
+        branch_path.push(index);
+    }
+
+    let deciding_child = trace
+        .and_then(|trace| trace.children.last())
+        .map(|child| child.child_index);
+    for (child_index, child_layout) in layout.children.iter().enumerate() {
+        let child_trace = trace.and_then(|trace| {
+            trace
+                .children
+                .iter()
+                .find(|child| child.child_index == child_index)
+        });
+        flatten(
+            child_layout,
+            child_trace,
+            is_on_branch && deciding_child == Some(child_index),
+            states,
+            branch_path,
+        );
+    }
+}
+
+fn has_changed(
+    current: &Option<(Status, Duration)>,
+    previous: &Option<(Status, Duration)>,
+    minimum_duration_change: Duration,
+) -> bool {
+    match (current, previous) {
+        (Some((status, duration)), Some((previous_status, previous_duration))) => {
+            status != previous_status
+                || duration.abs_diff(*previous_duration) > minimum_duration_change
+        }
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+fn node_change(index: usize, state: &Option<(Status, Duration)>) -> NodeChange {
+    NodeChange {
+        index: index as u32,
+        status: state.as_ref().map(|(status, _)| status.clone()),
+        duration: state
+            .as_ref()
+            .map_or(Duration::ZERO, |(_, duration)| *duration),
+    }
+}
+
+fn apply(states: &mut NodeStates, changes: &[NodeChange]) {
+    for change in changes {
+        let index = change.index as usize;
+        if states.len() <= index {
+            states.resize(index + 1, None);
+        }
+        states[index] = change
+            .status
+            .clone()
+            .map(|status| (status, change.duration));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, child_index: usize, status: Status, children: Vec<NodeTrace>) -> NodeTrace {
+        NodeTrace {
+            name: name.to_string(),
+            status,
+            child_index,
+            duration: Duration::ZERO,
+            children,
+        }
+    }
+
+    fn layout() -> NodeTrace {
+        node(
+            "Selection",
+            0,
+            Status::Idle,
+            vec![
+                node("has_ball", 0, Status::Idle, Vec::new()),
+                node("search", 1, Status::Idle, Vec::new()),
+            ],
+        )
+    }
+
+    fn parameters(history_length: usize) -> BehaviorTraceParameters {
+        BehaviorTraceParameters {
+            history_length,
+            minimum_duration_change: Duration::from_micros(50),
+        }
+    }
+
+    #[test]
+    fn diffs_contain_changed_nodes_and_branch_path() {
+        let mut recorder = TraceRecorder::default();
+        let with_ball = node(
+            "Selection",
+            0,
+            Status::Success,
+            vec![node("has_ball", 0, Status::Success, Vec::new())],
+        );
+        let without_ball = node(
+            "Selection",
+            0,
+            Status::Success,
+            vec![
+                node("has_ball", 0, Status::Failure, Vec::new()),
+                node("search", 1, Status::Success, Vec::new()),
+            ],
+        );
+
+        let first = recorder.record(
+            &layout(),
+            &with_ball,
+            Time::zero(),
+            BlackboardSnapshot::default(),
+            &parameters(10),
+        );
+        let repeated = recorder.record(
+            &layout(),
+            &with_ball,
+            Time::zero(),
+            BlackboardSnapshot::default(),
+            &parameters(10),
+        );
+        let switched = recorder.record(
+            &layout(),
+            &without_ball,
+            Time::zero(),
+            BlackboardSnapshot::default(),
+            &parameters(10),
+        );
+
+        assert_eq!(first.branch_path, vec![0, 1]);
+        assert_eq!(first.changes.len(), 2);
+        assert!(repeated.changes.is_empty());
+        assert_eq!(switched.branch_path, vec![0, 2]);
+        assert_eq!(
+            switched
+                .changes
+                .iter()
+                .map(|change| (change.index, change.status.clone()))
+                .collect::<Vec<_>>(),
+            vec![(1, Some(Status::Failure)), (2, Some(Status::Success))]
+        );
+    }
+
+    #[test]
+    fn history_base_reconstructs_evicted_ticks() {
+        let mut recorder = TraceRecorder::default();
+        let trace = node(
+            "Selection",
+            0,
+            Status::Success,
+            vec![node("has_ball", 0, Status::Success, Vec::new())],
+        );
+        for _ in 0..3 {
+            recorder.record(
+                &layout(),
+                &trace,
+                Time::zero(),
+                BlackboardSnapshot::default(),
+                &parameters(2),
+            );
+        }
+
+        let history = recorder.history(&layout(), 0);
+        let latest = recorder.history(&layout(), 1);
+
+        assert_eq!(history.records.len(), 2);
+        assert_eq!(history.records[0].tick, 1);
+        assert_eq!(history.base.len(), 2);
+        assert_eq!(latest.records.len(), 1);
+        assert_eq!(latest.base, history.base);
+    }
+}
@@ -1,4 +1,4 @@
-use types::{motion_type::MotionType, primary_state::PrimaryState};
+use types::{behavior_tree::NodeDescription, motion_type::MotionType, primary_state::PrimaryState};
 
 use crate::{
     action,
@@ -6,24 +6,36 @@ use crate::{
     behavior_tree::Node,
     condition,
     conditions::{
-        has_ball_position, is_ball_interception_candidate, is_close_to_ball, is_closest_to_ball,
-        is_fallen, is_goalkeeper, is_primary_state, is_remote_controlled, is_remote_kick_mode,
+        has_ball_position, is_fallen, is_goalkeeper, is_primary_state, is_remote_controlled,
+        is_remote_kick_mode,
     },
+    goalkeeper::goalkeeper_subtree,
     head::{look_at_ball_subtree, look_straight_ahead, search_for_lost_ball_subtree},
-    kick::{intercept, kick, kick_power_subtree, kick_subtree, set_kick_target_in_front},
+    kick::{kick, kick_power_subtree, set_kick_target_in_front},
     negation,
     node::Blackboard,
+    registry::{Registry, RegistryError},
     search::{has_suggested_search_position, leuchtturm, walk_to_search_position},
     selection, sequence,
-    substates::{is_in_sub_state, sub_state_subtree},
+    set_play::{is_set_play, set_play_subtree},
     subtree,
     switch_motion_type::switch_motion_type,
+    utility::{evaluate_utilities, utility_subtree},
     voronoi::calculate_voronoi_grid,
-    walk::{
-        walk_alternatives_subtree, walk_to_ball_subtree, walk_to_centroid, walk_to_kickoff_pose,
-    },
+    walk::{walk_alternatives_subtree, walk_to_centroid, walk_to_kickoff_pose},
 };
 
+/// Builds the tree from the description in the parameters, or the built-in tree if there is none.
+pub fn create_tree_from_description(
+    description: Option<&NodeDescription>,
+    registry: &Registry,
+) -> Result<Node<Blackboard>, RegistryError> {
+    match description {
+        Some(description) => registry.build_tree(description),
+        None => Ok(create_tree()),
+    }
+}
+
 pub fn create_tree() -> Node<Blackboard> {
     selection!(
         sequence!(
@@ -69,30 +81,28 @@ pub fn create_tree() -> Node<Blackboard> {
     )
 }
 
-fn ready_subtree() -> Node<Blackboard> {
+pub fn ready_subtree() -> Node<Blackboard> {
     sequence!(action!(walk_to_kickoff_pose))
 }
 
-fn playing_subtree() -> Node<Blackboard> {
+pub fn playing_subtree() -> Node<Blackboard> {
     selection!(
         sequence!(condition!(is_goalkeeper), subtree!(goalkeeper_subtree)),
         sequence!(
-            negation!(condition!(has_ball_position)),
-            subtree!(search_subtree)
+            action!(calculate_voronoi_grid),
+            selection!(
+                sequence!(condition!(is_set_play), subtree!(set_play_subtree)),
+                sequence!(action!(evaluate_utilities), subtree!(utility_subtree)),
+            )
         ),
         sequence!(
-            action!(calculate_voronoi_grid),
-            condition!(is_closest_to_ball),
-            subtree!(striker_subtree)
+            negation!(condition!(has_ball_position)),
+            subtree!(search_subtree)
         ),
         subtree!(supporter_subtree),
     )
 }
 
-fn goalkeeper_subtree() -> Node<Blackboard> {
-    sequence!(subtree!(look_at_ball_subtree), action!(stand))
-}
-
 pub fn search_subtree() -> Node<Blackboard> {
     sequence!(
         subtree!(search_for_lost_ball_subtree),
@@ -110,33 +120,14 @@ pub fn search_subtree() -> Node<Blackboard> {
     )
 }
 
-fn striker_subtree() -> Node<Blackboard> {
-    sequence!(
-        subtree!(look_at_ball_subtree),
-        selection!(
-            sequence!(condition!(is_in_sub_state), subtree!(sub_state_subtree),),
-            sequence!(
-                negation!(condition!(is_close_to_ball)),
-                subtree!(walk_to_ball_subtree)
-            ),
-            sequence!(
-                condition!(is_ball_interception_candidate),
-                subtree!(kick_subtree),
-                action!(intercept),
-            ),
-            subtree!(kick_subtree)
-        )
-    )
-}
-
-fn supporter_subtree() -> Node<Blackboard> {
+pub fn supporter_subtree() -> Node<Blackboard> {
     sequence!(
         subtree!(look_at_ball_subtree),
         selection!(action!(walk_to_centroid), action!(stand)),
     )
 }
 
-fn remote_control_subtree() -> Node<Blackboard> {
+pub fn remote_control_subtree() -> Node<Blackboard> {
     sequence!(
         condition!(is_remote_controlled),
         selection!(
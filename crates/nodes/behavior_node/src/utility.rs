@@ -0,0 +1,434 @@
+use coordinate_systems::Ground;
+use linear_algebra::{Vector2, distance, point};
+use types::{
+    behavior_tree::Status,
+    parameters::{
+        UtilityCandidate, UtilityCurveParameters, UtilityParameters, UtilityScore, UtilityTable,
+    },
+    roles::Role,
+};
+
+use crate::{
+    action,
+    behavior_tree::Node,
+    condition,
+    conditions::{is_close_to_ball, is_striker},
+    dribble::{dribble_subtree, opponent_distance_to_ball},
+    head::look_at_ball_subtree,
+    kick::{intercept, kick_subtree},
+    negation,
+    node::Blackboard,
+    selection, sequence,
+    substates::{is_in_sub_state, set_block_position_field, sub_state_subtree},
+    subtree,
+    tree::{search_subtree, supporter_subtree},
+    walk::{walk_to_ball_subtree, walk_to_block_position},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UtilityInputs {
+    /// Whether the robot is the assigned striker, only the striker may kick or dribble
+    pub is_striker: bool,
+    /// Whether the role lets the robot intercept, only strikers and defenders may
+    pub may_intercept: bool,
+    /// Whether the role lets the robot block the way to the own goal, only defenders may
+    pub may_block: bool,
+    /// Distance of the robot to the trajectory of a ball rolling towards it
+    pub interception_distance: Option<f32>,
+    /// Own distance to the ball minus the smallest distance of a teammate to the ball, the negative
+    /// own distance if no teammate is known
+    pub ball_distance_advantage: Option<f32>,
+    pub opponent_distance_to_ball: Option<f32>,
+    pub ball_distance_to_own_goal: Option<f32>,
+    /// `None` if the ball is lost
+    pub time_since_ball_seen: Option<f32>,
+}
+
+pub fn utility_subtree() -> Node<Blackboard> {
+    selection!(
+        sequence!(
+            condition!(is_utility_selected, UtilityCandidate::Search),
+            subtree!(search_subtree)
+        ),
+        sequence!(
+            condition!(is_utility_selected, UtilityCandidate::Intercept),
+            subtree!(look_at_ball_subtree),
+            subtree!(kick_subtree),
+            action!(intercept)
+        ),
+        sequence!(
+            condition!(is_utility_selected, UtilityCandidate::Dribble),
+            subtree!(look_at_ball_subtree),
+            subtree!(dribble_subtree)
+        ),
+        sequence!(
+            condition!(is_utility_selected, UtilityCandidate::Kick),
+            subtree!(look_at_ball_subtree),
+            selection!(
+                sequence!(condition!(is_in_sub_state), subtree!(sub_state_subtree)),
+                sequence!(
+                    negation!(condition!(is_close_to_ball)),
+                    subtree!(walk_to_ball_subtree)
+                ),
+                subtree!(kick_subtree)
+            )
+        ),
+        sequence!(
+            condition!(is_utility_selected, UtilityCandidate::Block),
+            subtree!(look_at_ball_subtree),
+            action!(set_block_position_field),
+            action!(walk_to_block_position)
+        ),
+        sequence!(
+            condition!(is_utility_selected, UtilityCandidate::Support),
+            subtree!(supporter_subtree)
+        ),
+    )
+}
+
+/// Scores all candidate behaviors and selects the best one, preferring the previous selection.
+///
+/// The role restricts the candidates: only the striker kicks or dribbles, only strikers and
+/// defenders intercept and only defenders block. Without a role every candidate is allowed.
+pub fn evaluate_utilities(blackboard: &mut Blackboard) -> Status {
+    let is_striker = is_striker(blackboard);
+    let parameters = &blackboard.parameters.utility;
+    let scores = score_candidates(&utility_inputs(blackboard, is_striker), parameters);
+    let selected = select_candidate(
+        &scores,
+        blackboard.utility_selection,
+        parameters.commitment_bonus,
+    );
+
+    blackboard.utility_selection = selected;
+    blackboard.utility_table = Some(UtilityTable { scores, selected });
+    Status::Success
+}
+
+pub fn is_utility_selected(blackboard: &mut Blackboard, candidate: UtilityCandidate) -> bool {
+    blackboard.utility_selection == Some(candidate)
+}
+
+fn utility_inputs(blackboard: &Blackboard, is_striker: bool) -> UtilityInputs {
+    let role = blackboard.world_state.robot.role;
+    let may_intercept = role.is_none_or(|role| matches!(role, Role::Striker | Role::Defender));
+    let may_block = role.is_none_or(|role| role == Role::Defender);
+    let time_since_ball_seen = blackboard.ball.as_ref().map(|ball| {
+        blackboard
+            .world_state
+            .now
+            .duration_since(ball.age)
+            .as_secs_f32()
+    });
+    let (Some(ball), Some(ground_to_field)) = (
+        &blackboard.ball,
+        blackboard.world_state.robot.ground_to_field,
+    ) else {
+        return UtilityInputs {
+            is_striker,
+            may_intercept,
+            may_block,
+            time_since_ball_seen,
+            ..Default::default()
+        };
+    };
+
+    let own_player_number = blackboard.world_state.robot.player_number;
+    let own_distance = distance(ground_to_field.translation(), ball.position);
+    let teammate_distance = blackboard
+        .world_state
+        .player_states
+        .iter()
+        .filter(|(player_number, _)| {
+            *player_number != own_player_number
+                && Some(*player_number) != blackboard.goal_keeper_number
+        })
+        .filter_map(|(_, player_state)| {
+            Some(distance(
+                player_state.as_ref()?.pose.position(),
+                ball.position,
+            ))
+        })
+        .min_by(f32::total_cmp);
+
+    let to_robot = -(ground_to_field.inverse() * ball.position).coords();
+    let is_rolling_towards_robot = ball.velocity.norm()
+        > blackboard.parameters.intercept_ball.minimum_ball_velocity
+        && ball.velocity.dot(&to_robot) > 0.0;
+    let interception_distance = ball
+        .velocity
+        .try_normalize(f32::EPSILON)
+        .filter(|_| is_rolling_towards_robot)
+        .map(|direction| cross(direction, to_robot).abs());
+
+    let own_goal = point![-blackboard.field_dimensions.length / 2.0, 0.0];
+
+    UtilityInputs {
+        is_striker,
+        may_intercept,
+        may_block,
+        interception_distance,
+        ball_distance_advantage: Some(
+            teammate_distance.map_or(-own_distance, |teammate| own_distance - teammate),
+        ),
+        opponent_distance_to_ball: opponent_distance_to_ball(blackboard),
+        ball_distance_to_own_goal: Some(distance(ball.position, own_goal)),
+        time_since_ball_seen,
+    }
+}
+
+pub fn score_candidates(
+    inputs: &UtilityInputs,
+    parameters: &UtilityParameters,
+) -> Vec<UtilityScore> {
+    let advantage = inputs.ball_distance_advantage;
+    let striker_advantage = advantage.filter(|_| inputs.is_striker);
+    let score = |candidate, input: Option<f32>, score: Option<f32>| UtilityScore {
+        candidate,
+        input,
+        score: score.unwrap_or(0.0),
+    };
+
+    vec![
+        score(
+            UtilityCandidate::Intercept,
+            inputs.interception_distance,
+            inputs
+                .interception_distance
+                .filter(|_| inputs.may_intercept)
+                .map(|input| evaluate(&parameters.intercept, input)),
+        ),
+        score(
+            UtilityCandidate::Kick,
+            advantage,
+            striker_advantage.map(|input| evaluate(&parameters.kick, input)),
+        ),
+        score(
+            UtilityCandidate::Dribble,
+            inputs.opponent_distance_to_ball,
+            inputs
+                .opponent_distance_to_ball
+                .zip(striker_advantage)
+                .map(|(input, advantage)| {
+                    evaluate(&parameters.dribble, input) * logistic(&parameters.kick, advantage)
+                }),
+        ),
+        score(
+            UtilityCandidate::Support,
+            advantage,
+            advantage.map(|input| evaluate(&parameters.support, input)),
+        ),
+        score(
+            UtilityCandidate::Block,
+            inputs.ball_distance_to_own_goal,
+            inputs
+                .ball_distance_to_own_goal
+                .zip(advantage)
+                .filter(|_| inputs.may_block)
+                .map(|(input, advantage)| {
+                    evaluate(&parameters.block, input) * logistic(&parameters.support, advantage)
+                }),
+        ),
+        score(
+            UtilityCandidate::Search,
+            inputs.time_since_ball_seen,
+            Some(
+                inputs
+                    .time_since_ball_seen
+                    .map_or(parameters.search.weight, |input| {
+                        evaluate(&parameters.search, input)
+                    }),
+            ),
+        ),
+    ]
+}
+
+/// Selects the candidate with the highest score, the previous selection gets a bonus.
+pub fn select_candidate(
+    scores: &[UtilityScore],
+    previous: Option<UtilityCandidate>,
+    commitment_bonus: f32,
+) -> Option<UtilityCandidate> {
+    scores
+        .iter()
+        .map(|score| {
+            let bonus = if Some(score.candidate) == previous {
+                commitment_bonus
+            } else {
+                0.0
+            };
+            (score.candidate, score.score + bonus)
+        })
+        .max_by(|(_, left), (_, right)| left.total_cmp(right))
+        .map(|(candidate, _)| candidate)
+}
+
+fn evaluate(curve: &UtilityCurveParameters, input: f32) -> f32 {
+    curve.weight * logistic(curve, input)
+}
+
+fn logistic(curve: &UtilityCurveParameters, input: f32) -> f32 {
+    1.0 / (1.0 + (-curve.steepness * (input - curve.midpoint)).exp())
+}
+
+fn cross(left: Vector2<Ground>, right: Vector2<Ground>) -> f32 {
+    left.x() * right.y() - left.y() * right.x()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(weight: f32, midpoint: f32, steepness: f32) -> UtilityCurveParameters {
+        UtilityCurveParameters {
+            weight,
+            midpoint,
+            steepness,
+        }
+    }
+
+    fn parameters() -> UtilityParameters {
+        UtilityParameters {
+            commitment_bonus: 0.15,
+            intercept: curve(1.2, 0.4, -10.0),
+            kick: curve(1.0, 0.0, -4.0),
+            dribble: curve(1.1, 0.8, -6.0),
+            support: curve(0.8, 0.0, 4.0),
+            block: curve(0.9, 2.5, -3.0),
+            search: curve(2.0, 2.0, 4.0),
+        }
+    }
+
+    fn best(inputs: UtilityInputs, previous: Option<UtilityCandidate>) -> UtilityCandidate {
+        let parameters = parameters();
+        select_candidate(
+            &score_candidates(&inputs, &parameters),
+            previous,
+            parameters.commitment_bonus,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn selects_behavior_from_situation() {
+        let closest_to_ball = UtilityInputs {
+            is_striker: true,
+            may_intercept: true,
+            may_block: true,
+            ball_distance_advantage: Some(-1.0),
+            ball_distance_to_own_goal: Some(5.0),
+            time_since_ball_seen: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(best(closest_to_ball, None), UtilityCandidate::Kick);
+
+        let pressed = UtilityInputs {
+            opponent_distance_to_ball: Some(0.3),
+            ..closest_to_ball
+        };
+        assert_eq!(best(pressed, None), UtilityCandidate::Dribble);
+
+        let far_from_ball = UtilityInputs {
+            ball_distance_advantage: Some(2.0),
+            ..closest_to_ball
+        };
+        assert_eq!(best(far_from_ball, None), UtilityCandidate::Support);
+
+        let ball_near_own_goal = UtilityInputs {
+            ball_distance_to_own_goal: Some(1.5),
+            ..far_from_ball
+        };
+        assert_eq!(best(ball_near_own_goal, None), UtilityCandidate::Block);
+
+        let ball_rolling_at_robot = UtilityInputs {
+            interception_distance: Some(0.1),
+            ..far_from_ball
+        };
+        assert_eq!(
+            best(ball_rolling_at_robot, None),
+            UtilityCandidate::Intercept
+        );
+
+        assert_eq!(
+            best(UtilityInputs::default(), None),
+            UtilityCandidate::Search
+        );
+    }
+
+    #[test]
+    fn commitment_keeps_previous_selection_on_close_scores() {
+        let undecided = UtilityInputs {
+            is_striker: true,
+            ball_distance_advantage: Some(0.05),
+            ball_distance_to_own_goal: Some(5.0),
+            time_since_ball_seen: Some(0.0),
+            ..Default::default()
+        };
+
+        assert_eq!(best(undecided, None), UtilityCandidate::Kick);
+        assert_eq!(
+            best(undecided, Some(UtilityCandidate::Support)),
+            UtilityCandidate::Support
+        );
+    }
+
+    #[test]
+    fn only_striker_kicks_or_dribbles() {
+        let closest_to_ball = UtilityInputs {
+            is_striker: false,
+            ball_distance_advantage: Some(-1.0),
+            opponent_distance_to_ball: Some(0.3),
+            ball_distance_to_own_goal: Some(5.0),
+            time_since_ball_seen: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(best(closest_to_ball, None), UtilityCandidate::Support);
+
+        let scores = score_candidates(&closest_to_ball, &parameters());
+        assert!(
+            scores
+                .iter()
+                .filter(|score| matches!(
+                    score.candidate,
+                    UtilityCandidate::Kick | UtilityCandidate::Dribble
+                ))
+                .all(|score| score.score == 0.0)
+        );
+    }
+
+    #[test]
+    fn only_defenders_block_and_only_strikers_or_defenders_intercept() {
+        let supporter = UtilityInputs {
+            ball_distance_advantage: Some(2.0),
+            ball_distance_to_own_goal: Some(1.5),
+            time_since_ball_seen: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(best(supporter, None), UtilityCandidate::Support);
+        assert_eq!(
+            best(
+                UtilityInputs {
+                    interception_distance: Some(0.1),
+                    ..supporter
+                },
+                None
+            ),
+            UtilityCandidate::Support
+        );
+
+        let defender = UtilityInputs {
+            may_intercept: true,
+            may_block: true,
+            ..supporter
+        };
+        assert_eq!(best(defender, None), UtilityCandidate::Block);
+
+        let striker = UtilityInputs {
+            is_striker: true,
+            may_intercept: true,
+            interception_distance: Some(0.1),
+            ..supporter
+        };
+        assert_eq!(best(striker, None), UtilityCandidate::Intercept);
+    }
+}
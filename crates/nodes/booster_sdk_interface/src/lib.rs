@@ -22,7 +22,7 @@ use types::motion_command::MotionCommand;
 mod control;
 mod kick_transport;
 
-const MOTION_COMMAND_TOPIC: &str = "behavior/motion_command";
+const MOTION_COMMAND_TOPIC: &str = "motion_arbitration/motion_command";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
 #[serde(deny_unknown_fields)]
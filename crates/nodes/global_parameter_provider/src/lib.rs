@@ -11,6 +11,7 @@ use types::field_dimensions::FieldDimensions;
 #[serde(deny_unknown_fields)]
 pub struct Parameters {
     pub player_number: PlayerNumber,
+    pub goal_keeper_number: PlayerNumber,
     pub field_dimensions: FieldDimensions,
 }
 
@@ -32,6 +33,15 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .build()
         .await?;
 
+    let goal_keeper_number_pub = node
+        .publisher::<PlayerNumber>("goal_keeper_number")
+        .qos(QosProfile {
+            durability: QosDurability::TransientLocal,
+            ..Default::default()
+        })
+        .build()
+        .await?;
+
     let field_dimensions_pub = node
         .publisher::<FieldDimensions>("field_dimensions")
         .qos(QosProfile {
@@ -44,6 +54,9 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
     let parameters_snapshot = node_parameters.snapshot();
     let parameters = parameters_snapshot.typed();
     player_number_pub.publish(&parameters.player_number).await?;
+    goal_keeper_number_pub
+        .publish(&parameters.goal_keeper_number)
+        .await?;
     field_dimensions_pub
         .publish(&parameters.field_dimensions)
         .await?;
@@ -55,6 +68,9 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         let parameters = parameters.typed();
 
         player_number_pub.publish(&parameters.player_number).await?;
+        goal_keeper_number_pub
+            .publish(&parameters.goal_keeper_number)
+            .await?;
         field_dimensions_pub
             .publish(&parameters.field_dimensions.clone())
             .await?;
@@ -12,7 +12,7 @@ use types::{
     parameters::HeadMotionParameters,
 };
 
-const MOTION_COMMAND_TOPIC: &str = "behavior/motion_command";
+const MOTION_COMMAND_TOPIC: &str = "motion_arbitration/motion_command";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
 #[serde(deny_unknown_fields)]
@@ -15,7 +15,7 @@ use types::{
     support_foot::Side,
 };
 
-const MOTION_COMMAND_TOPIC: &str = "behavior/motion_command";
+const MOTION_COMMAND_TOPIC: &str = "motion_arbitration/motion_command";
 
 pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
     Box::pin(run(ctx))
@@ -18,7 +18,7 @@ use types::{
     time_wrapper::TimeWrapper,
 };
 
-const MOTION_COMMAND_TOPIC: &str = "behavior/motion_command";
+const MOTION_COMMAND_TOPIC: &str = "motion_arbitration/motion_command";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
 #[serde(deny_unknown_fields)]
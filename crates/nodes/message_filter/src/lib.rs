@@ -3,7 +3,7 @@ use std::{boxed::Box, future::Future, pin::Pin};
 
 use color_eyre::Result;
 
-use hsl_network_messages::{HulkMessage, PlayerNumber, StateMessage};
+use hsl_network_messages::PlayerNumber;
 use ros_z::{prelude::*, qos::QosDurability};
 use types::{messages::IncomingMessage, time_wrapper::TimeWrapper};
 
@@ -39,9 +39,10 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                 let Some(current_player_number) = player_number else {continue;};
                 let time_wrapped_message = received_time_wrapped_message?;
 
-                let should_filter_message_out = matches!(time_wrapped_message.inner, IncomingMessage::Hsl(
-                        HulkMessage::State(StateMessage { player_number, .. }),
-                    ) if player_number == current_player_number);
+                let should_filter_message_out = matches!(
+                    time_wrapped_message.inner,
+                    IncomingMessage::Hsl(message) if message.player_number() == current_player_number
+                );
 
                 if !should_filter_message_out {
                     filtered_message_pub.publish(
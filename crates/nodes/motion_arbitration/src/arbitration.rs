@@ -0,0 +1,429 @@
+use std::time::Duration;
+
+use booster::{FallDownState, FallDownStateType};
+use linear_algebra::{distance, vector};
+use ros_z::time::Time;
+use types::{
+    motion_command::{HeadMotion, ImageRegion, MotionCommand},
+    step::Step,
+    world_state::BallState,
+};
+
+use crate::{
+    ArbitrationReport, BallImplausibility, KickPlausibilityParameters, Modification, MotionSource,
+    Parameters, limiter::VelocityLimiter,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct Inputs {
+    pub now: Time,
+    /// Latest command of every source together with the time it was sent
+    pub commands: Vec<(MotionSource, Time, MotionCommand)>,
+    pub fall_down_state: Option<FallDownState>,
+    pub is_safe_pose: bool,
+    pub ball: Option<BallState>,
+}
+
+#[derive(Default)]
+pub struct MotionArbitration {
+    last_update: Option<Time>,
+    is_in_soccer_mode: bool,
+    limiter: VelocityLimiter,
+}
+
+impl MotionArbitration {
+    /// Selects the command of the highest priority source and makes it safe to execute. Returns
+    /// `None` if no source sent a command recently.
+    pub fn update(
+        &mut self,
+        parameters: &Parameters,
+        inputs: Inputs,
+    ) -> Option<(MotionCommand, ArbitrationReport)> {
+        let cycle_time = self.last_update.map_or(Duration::ZERO, |last_update| {
+            inputs.now.duration_since(last_update)
+        });
+        self.last_update = Some(inputs.now);
+
+        let (source, command) = select_command(
+            inputs.commands,
+            &parameters.source_priorities,
+            inputs.now,
+            parameters.command_timeout,
+        )?;
+        let mut modifications = Vec::new();
+
+        let command = match fall_down_command(&command, inputs.fall_down_state) {
+            Some(fall_down_command) => {
+                modifications.push(Modification::FallenDown);
+                fall_down_command
+            }
+            None => command,
+        };
+
+        let command = if is_soccer_mode(&command) && !self.is_in_soccer_mode && !inputs.is_safe_pose
+        {
+            modifications.push(Modification::UnsafePose);
+            MotionCommand::Prepare
+        } else {
+            command
+        };
+        self.is_in_soccer_mode = is_soccer_mode(&command);
+
+        let command = match check_ball(&command, inputs.ball.as_ref(), inputs.now, &parameters.kick)
+        {
+            Some(implausibility) => {
+                modifications.push(Modification::KickRefused(implausibility));
+                MotionCommand::Stand {
+                    head: command.head_motion().unwrap_or(HeadMotion::Center {
+                        image_region_target: ImageRegion::Center,
+                    }),
+                }
+            }
+            None => command,
+        };
+
+        let command = self.limit_walk(command, cycle_time, parameters, &mut modifications);
+
+        Some((
+            command,
+            ArbitrationReport {
+                source,
+                modifications,
+            },
+        ))
+    }
+
+    fn limit_walk(
+        &mut self,
+        command: MotionCommand,
+        cycle_time: Duration,
+        parameters: &Parameters,
+        modifications: &mut Vec<Modification>,
+    ) -> MotionCommand {
+        match command {
+            MotionCommand::WalkWithVelocity {
+                head,
+                velocity,
+                angular_velocity,
+            } => {
+                let step = self.limiter.limit(
+                    Step {
+                        forward: velocity.x(),
+                        left: velocity.y(),
+                        turn: angular_velocity,
+                    },
+                    cycle_time,
+                    &parameters.walk_limits,
+                    modifications,
+                );
+                MotionCommand::WalkWithVelocity {
+                    head,
+                    velocity: vector![step.forward, step.left],
+                    angular_velocity: step.turn,
+                }
+            }
+            // the direction and turn rate of path walking are only known to the motion, so only
+            // the speed along the path is limited
+            MotionCommand::Walk {
+                head,
+                path,
+                orientation_mode,
+                target_orientation,
+                distance_to_be_aligned,
+                speed,
+            } => {
+                let step = self.limiter.limit(
+                    Step {
+                        forward: speed,
+                        ..Step::ZERO
+                    },
+                    cycle_time,
+                    &parameters.walk_limits,
+                    modifications,
+                );
+                MotionCommand::Walk {
+                    head,
+                    path,
+                    orientation_mode,
+                    target_orientation,
+                    distance_to_be_aligned,
+                    speed: step.forward,
+                }
+            }
+            command => {
+                self.limiter.reset();
+                command
+            }
+        }
+    }
+}
+
+fn select_command(
+    commands: Vec<(MotionSource, Time, MotionCommand)>,
+    priorities: &[MotionSource],
+    now: Time,
+    timeout: Duration,
+) -> Option<(MotionSource, MotionCommand)> {
+    priorities.iter().find_map(|priority| {
+        commands
+            .iter()
+            .find(|(source, stamp, _)| source == priority && now.duration_since(*stamp) <= timeout)
+            .map(|(source, _, command)| (*source, command.clone()))
+    })
+}
+
+fn is_soccer_mode(command: &MotionCommand) -> bool {
+    matches!(
+        command,
+        MotionCommand::Stand { .. }
+            | MotionCommand::VisualKick { .. }
+            | MotionCommand::Walk { .. }
+            | MotionCommand::WalkWithVelocity { .. }
+    )
+}
+
+/// Replaces commands that move the robot while it is not standing. A robot that is able to
+/// recover stands up, otherwise it goes limp to protect its joints.
+fn fall_down_command(
+    command: &MotionCommand,
+    fall_down_state: Option<FallDownState>,
+) -> Option<MotionCommand> {
+    let fall_down_state = fall_down_state?;
+    if fall_down_state.fall_down_state == FallDownStateType::IsReady || !is_soccer_mode(command) {
+        return None;
+    }
+    match fall_down_state.fall_down_state {
+        FallDownStateType::IsGettingUp => Some(MotionCommand::StandUp),
+        _ if fall_down_state.is_recovery_available => Some(MotionCommand::StandUp),
+        _ => Some(MotionCommand::Damping),
+    }
+}
+
+fn check_ball(
+    command: &MotionCommand,
+    ball: Option<&BallState>,
+    now: Time,
+    parameters: &KickPlausibilityParameters,
+) -> Option<BallImplausibility> {
+    let MotionCommand::VisualKick { ball_position, .. } = command else {
+        return None;
+    };
+    let Some(ball) = ball else {
+        return Some(BallImplausibility::NotSeen);
+    };
+    let age = now
+        .to_wallclock()
+        .duration_since(ball.last_seen_ball)
+        .unwrap_or_default();
+    if age > parameters.maximum_ball_age {
+        return Some(BallImplausibility::Outdated);
+    }
+    if distance(*ball_position, ball.ball_in_ground) > parameters.maximum_ball_deviation {
+        return Some(BallImplausibility::Deviating);
+    }
+    if ball_position.coords().norm() > parameters.maximum_ball_distance {
+        return Some(BallImplausibility::OutOfReach);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::{Orientation2, point};
+    use types::motion_command::KickPower;
+
+    use super::*;
+    use crate::WalkLimits;
+
+    fn parameters() -> Parameters {
+        Parameters {
+            source_priorities: vec![MotionSource::RemoteControl, MotionSource::Behavior],
+            command_timeout: Duration::from_millis(500),
+            walk_limits: WalkLimits {
+                velocity: Step {
+                    forward: 0.5,
+                    left: 0.3,
+                    turn: 1.0,
+                },
+                acceleration: Step {
+                    forward: 1.0,
+                    left: 1.0,
+                    turn: 2.0,
+                },
+                jerk: Step {
+                    forward: 10.0,
+                    left: 10.0,
+                    turn: 20.0,
+                },
+            },
+            kick: KickPlausibilityParameters {
+                maximum_ball_age: Duration::from_secs(1),
+                maximum_ball_deviation: 0.2,
+                maximum_ball_distance: 0.6,
+            },
+        }
+    }
+
+    fn stand() -> MotionCommand {
+        MotionCommand::Stand {
+            head: HeadMotion::Center {
+                image_region_target: ImageRegion::Center,
+            },
+        }
+    }
+
+    fn visual_kick() -> MotionCommand {
+        MotionCommand::VisualKick {
+            head: HeadMotion::Center {
+                image_region_target: ImageRegion::Center,
+            },
+            ball_position: point![0.2, 0.0],
+            kick_direction: Orientation2::identity(),
+            target_position: point![3.0, 0.0],
+            robot_theta_to_field: Orientation2::identity(),
+            kick_power: KickPower::Rumpelstilzchen,
+        }
+    }
+
+    fn inputs(now: Time, commands: Vec<(MotionSource, Time, MotionCommand)>) -> Inputs {
+        Inputs {
+            now,
+            commands,
+            is_safe_pose: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fresh_command_of_highest_priority_wins() {
+        let mut arbitration = MotionArbitration::default();
+        let now = Time::from_nanos(10_000_000_000);
+        let stale = now.saturating_sub(Duration::from_secs(1));
+
+        let (command, report) = arbitration
+            .update(
+                &parameters(),
+                inputs(
+                    now,
+                    vec![
+                        (MotionSource::Behavior, now, MotionCommand::Prepare),
+                        (MotionSource::RemoteControl, now, MotionCommand::Damping),
+                    ],
+                ),
+            )
+            .unwrap();
+        assert_eq!(report.source, MotionSource::RemoteControl);
+        assert_eq!(command, MotionCommand::Damping);
+
+        let (command, report) = arbitration
+            .update(
+                &parameters(),
+                inputs(
+                    now,
+                    vec![
+                        (MotionSource::Behavior, now, MotionCommand::Prepare),
+                        (MotionSource::RemoteControl, stale, MotionCommand::Damping),
+                    ],
+                ),
+            )
+            .unwrap();
+        assert_eq!(report.source, MotionSource::Behavior);
+        assert_eq!(command, MotionCommand::Prepare);
+
+        assert!(
+            arbitration
+                .update(
+                    &parameters(),
+                    inputs(now, vec![(MotionSource::Behavior, stale, stand())])
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn leaving_prepare_requires_safe_pose() {
+        let mut arbitration = MotionArbitration::default();
+        let now = Time::from_nanos(10_000_000_000);
+
+        let (command, report) = arbitration
+            .update(
+                &parameters(),
+                Inputs {
+                    is_safe_pose: false,
+                    ..inputs(now, vec![(MotionSource::Behavior, now, stand())])
+                },
+            )
+            .unwrap();
+        assert_eq!(command, MotionCommand::Prepare);
+        assert_eq!(report.modifications, vec![Modification::UnsafePose]);
+
+        let (command, _) = arbitration
+            .update(
+                &parameters(),
+                inputs(now, vec![(MotionSource::Behavior, now, stand())]),
+            )
+            .unwrap();
+        assert_eq!(command, stand());
+    }
+
+    #[test]
+    fn fallen_robot_stands_up_instead_of_walking() {
+        let mut arbitration = MotionArbitration::default();
+        let now = Time::from_nanos(10_000_000_000);
+
+        let (command, report) = arbitration
+            .update(
+                &parameters(),
+                Inputs {
+                    fall_down_state: Some(FallDownState {
+                        fall_down_state: FallDownStateType::HasFallen,
+                        is_recovery_available: true,
+                    }),
+                    ..inputs(now, vec![(MotionSource::Behavior, now, stand())])
+                },
+            )
+            .unwrap();
+
+        assert_eq!(command, MotionCommand::StandUp);
+        assert_eq!(report.modifications, vec![Modification::FallenDown]);
+    }
+
+    #[test]
+    fn kick_is_refused_for_implausible_ball() {
+        let mut arbitration = MotionArbitration::default();
+        let now = Time::from_nanos(10_000_000_000);
+        let ball = |position| BallState {
+            ball_in_ground: position,
+            last_seen_ball: now.to_wallclock(),
+            ..Default::default()
+        };
+        let mut kick = |ball| {
+            arbitration
+                .update(
+                    &parameters(),
+                    Inputs {
+                        ball,
+                        ..inputs(now, vec![(MotionSource::Behavior, now, visual_kick())])
+                    },
+                )
+                .unwrap()
+        };
+
+        let (command, report) = kick(Some(ball(point![0.25, 0.0])));
+        assert_eq!(command, visual_kick());
+        assert!(report.modifications.is_empty());
+
+        let (command, report) = kick(None);
+        assert_eq!(command, stand());
+        assert_eq!(
+            report.modifications,
+            vec![Modification::KickRefused(BallImplausibility::NotSeen)]
+        );
+
+        let (_, report) = kick(Some(ball(point![1.0, 0.0])));
+        assert_eq!(
+            report.modifications,
+            vec![Modification::KickRefused(BallImplausibility::Deviating)]
+        );
+    }
+}
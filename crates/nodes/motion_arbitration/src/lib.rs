@@ -0,0 +1,159 @@
+mod arbitration;
+mod limiter;
+
+use std::{boxed::Box, future::Future, pin::Pin};
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use booster::FallDownState;
+use ros_z::prelude::*;
+use types::{motion_command::MotionCommand, step::Step, world_state::BallState};
+
+pub use arbitration::{Inputs, MotionArbitration};
+pub use limiter::VelocityLimiter;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[serde(deny_unknown_fields)]
+pub struct Parameters {
+    /// Sources in order of decreasing priority, sources not listed are ignored
+    pub source_priorities: Vec<MotionSource>,
+    pub command_timeout: Duration,
+    pub walk_limits: WalkLimits,
+    pub kick: KickPlausibilityParameters,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[serde(deny_unknown_fields)]
+pub struct WalkLimits {
+    pub velocity: Step,
+    pub acceleration: Step,
+    pub jerk: Step,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[serde(deny_unknown_fields)]
+pub struct KickPlausibilityParameters {
+    pub maximum_ball_age: Duration,
+    /// Maximum distance between the ball of the kick command and the filtered ball
+    pub maximum_ball_deviation: f32,
+    pub maximum_ball_distance: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Message)]
+pub enum MotionSource {
+    Behavior,
+    RemoteControl,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Message)]
+pub enum BallImplausibility {
+    NotSeen,
+    Outdated,
+    Deviating,
+    OutOfReach,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Message)]
+pub enum Modification {
+    VelocityLimited,
+    AccelerationLimited,
+    JerkLimited,
+    KickRefused(BallImplausibility),
+    /// Soccer mode is only entered from the safe prepare pose
+    UnsafePose,
+    FallenDown,
+}
+
+/// Source of the executed command and the reasons it differs from the requested one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Message)]
+pub struct ArbitrationReport {
+    pub source: MotionSource,
+    pub modifications: Vec<Modification>,
+}
+
+pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+    Box::pin(run(ctx))
+}
+
+async fn run(ctx: Arc<Context>) -> Result<()> {
+    let node = ctx.create_node("motion_arbitration").build().await?;
+
+    let parameters = node.bind_parameter_as::<Parameters>("motion_arbitration")?;
+    let behavior_command_cache = node
+        .subscriber::<MotionCommand>("behavior/motion_command")
+        .cache(1)
+        .build()
+        .await?;
+    // Injected by operators from tooling, hence not published by any node
+    let remote_control_command_cache = node
+        .subscriber::<MotionCommand>("remote_control/motion_command")
+        .cache(1)
+        .build()
+        .await?;
+    let fall_down_state_cache = node
+        .subscriber::<FallDownState>("inputs/fall_down_state")
+        .cache(1)
+        .build()
+        .await?;
+    let is_safe_pose_cache = node
+        .subscriber::<bool>("is_safe_pose")
+        .cache(1)
+        .build()
+        .await?;
+    let ball_state_cache = node
+        .subscriber::<Option<BallState>>("ball_state")
+        .cache(1)
+        .build()
+        .await?;
+
+    let motion_command_pub = node
+        .publisher::<MotionCommand>("motion_arbitration/motion_command")
+        .build()
+        .await?;
+    let report_pub = node
+        .publisher::<ArbitrationReport>("motion_arbitration/report")
+        .build()
+        .await?;
+
+    let mut timer = node.create_timer(Duration::from_millis(10));
+    let mut arbitration = MotionArbitration::default();
+
+    loop {
+        timer.tick().await;
+        let parameters = parameters.snapshot().typed().clone();
+
+        let commands = [
+            (MotionSource::Behavior, &behavior_command_cache),
+            (MotionSource::RemoteControl, &remote_control_command_cache),
+        ]
+        .into_iter()
+        .filter_map(|(source, cache)| {
+            Some((
+                source,
+                cache.latest_stamp()?,
+                (*cache.get_latest()?).clone(),
+            ))
+        })
+        .collect();
+        let inputs = Inputs {
+            now: node.clock().now(),
+            commands,
+            fall_down_state: fall_down_state_cache
+                .get_latest()
+                .map(|fall_down_state| *fall_down_state),
+            // the robot is only assumed to be safe once the checker confirmed it
+            is_safe_pose: is_safe_pose_cache
+                .get_latest()
+                .is_some_and(|is_safe_pose| *is_safe_pose),
+            ball: ball_state_cache.get_latest().and_then(|ball| *ball),
+        };
+
+        let Some((motion_command, report)) = arbitration.update(&parameters, inputs) else {
+            continue;
+        };
+        motion_command_pub.publish(&motion_command).await?;
+        report_pub.publish(&report).await?;
+    }
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use types::step::Step;
+
+use crate::{Modification, WalkLimits};
+
+#[derive(Clone, Copy, Debug, Default)]
+struct AxisState {
+    velocity: f32,
+    acceleration: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct AxisLimits {
+    velocity: f32,
+    acceleration: f32,
+    jerk: f32,
+}
+
+/// Keeps commanded walking velocities within velocity, acceleration and jerk limits.
+#[derive(Clone, Debug, Default)]
+pub struct VelocityLimiter {
+    forward: AxisState,
+    left: AxisState,
+    turn: AxisState,
+}
+
+impl VelocityLimiter {
+    /// Forgets the previous velocity, e.g. after the robot stopped walking.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn limit(
+        &mut self,
+        target: Step,
+        cycle_time: Duration,
+        limits: &WalkLimits,
+        modifications: &mut Vec<Modification>,
+    ) -> Step {
+        let cycle_time = cycle_time.as_secs_f32().max(f32::EPSILON);
+        let axis_limits = |select: fn(&Step) -> f32| AxisLimits {
+            velocity: select(&limits.velocity),
+            acceleration: select(&limits.acceleration),
+            jerk: select(&limits.jerk),
+        };
+        Step {
+            forward: limit_axis(
+                &mut self.forward,
+                target.forward,
+                cycle_time,
+                axis_limits(|step| step.forward),
+                modifications,
+            ),
+            left: limit_axis(
+                &mut self.left,
+                target.left,
+                cycle_time,
+                axis_limits(|step| step.left),
+                modifications,
+            ),
+            turn: limit_axis(
+                &mut self.turn,
+                target.turn,
+                cycle_time,
+                axis_limits(|step| step.turn),
+                modifications,
+            ),
+        }
+    }
+}
+
+fn limit_axis(
+    state: &mut AxisState,
+    target: f32,
+    cycle_time: f32,
+    limits: AxisLimits,
+    modifications: &mut Vec<Modification>,
+) -> f32 {
+    let mut record = |modification| {
+        if !modifications.contains(&modification) {
+            modifications.push(modification);
+        }
+    };
+
+    let target = if target.abs() > limits.velocity {
+        record(Modification::VelocityLimited);
+        target.clamp(-limits.velocity, limits.velocity)
+    } else {
+        target
+    };
+
+    let desired_acceleration = (target - state.velocity) / cycle_time;
+    let maximum_acceleration_change = limits.jerk * cycle_time;
+    let mut acceleration = desired_acceleration.clamp(
+        state.acceleration - maximum_acceleration_change,
+        state.acceleration + maximum_acceleration_change,
+    );
+    if acceleration != desired_acceleration {
+        record(Modification::JerkLimited);
+    }
+    if acceleration.abs() > limits.acceleration {
+        record(Modification::AccelerationLimited);
+        acceleration = acceleration.clamp(-limits.acceleration, limits.acceleration);
+    }
+
+    let mut velocity = state.velocity + acceleration * cycle_time;
+    // the jerk limit must not make the velocity overshoot the target
+    if (state.velocity <= target && velocity > target)
+        || (state.velocity >= target && velocity < target)
+    {
+        velocity = target;
+    }
+    velocity = velocity.clamp(-limits.velocity, limits.velocity);
+
+    state.acceleration = (velocity - state.velocity) / cycle_time;
+    state.velocity = velocity;
+    velocity
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn limits() -> WalkLimits {
+        WalkLimits {
+            velocity: Step {
+                forward: 0.5,
+                left: 0.3,
+                turn: 1.0,
+            },
+            acceleration: Step {
+                forward: 1.0,
+                left: 1.0,
+                turn: 2.0,
+            },
+            jerk: Step {
+                forward: 10.0,
+                left: 10.0,
+                turn: 20.0,
+            },
+        }
+    }
+
+    #[test]
+    fn velocity_ramps_up_to_clamped_target() {
+        let mut limiter = VelocityLimiter::default();
+        let target = Step {
+            forward: 2.0,
+            left: 0.0,
+            turn: 0.0,
+        };
+        let mut modifications = Vec::new();
+
+        let first = limiter.limit(
+            target,
+            Duration::from_millis(10),
+            &limits(),
+            &mut modifications,
+        );
+        assert_relative_eq!(first.forward, 0.001, epsilon = 1e-6);
+        assert!(modifications.contains(&Modification::VelocityLimited));
+        assert!(modifications.contains(&Modification::JerkLimited));
+
+        let mut last = first;
+        for _ in 0..200 {
+            let step = limiter.limit(
+                target,
+                Duration::from_millis(10),
+                &limits(),
+                &mut Vec::new(),
+            );
+            let acceleration = (step.forward - last.forward) / 0.01;
+            assert!(acceleration <= 1.0 + 1e-3);
+            last = step;
+        }
+        assert_relative_eq!(last.forward, 0.5);
+    }
+
+    #[test]
+    fn velocity_within_limits_is_not_modified() {
+        let mut limiter = VelocityLimiter::default();
+        let mut modifications = Vec::new();
+
+        let step = limiter.limit(
+            Step::ZERO,
+            Duration::from_millis(10),
+            &limits(),
+            &mut modifications,
+        );
+
+        assert_eq!(step, Step::ZERO);
+        assert!(modifications.is_empty());
+    }
+}
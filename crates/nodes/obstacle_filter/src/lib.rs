@@ -15,7 +15,7 @@ use coordinate_systems::{Field, Ground};
 use linear_algebra::{IntoFramed, Isometry2, Point2, center, point};
 use projection::{Projection, camera_matrix::CameraMatrix};
 use ros_z::{prelude::*, qos::QosDurability, time::Time};
-use ros_z_streams::CreateFutureMapBuilder;
+use ros_z_streams::{CreateAnnouncingPublisher, CreateFutureMapBuilder};
 use types::{
     field_dimensions::FieldDimensions,
     multivariate_normal_distribution::MultivariateNormalDistribution,
@@ -134,7 +134,9 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .publisher::<Vec<Hypothesis>>("obstacle_filter_hypotheses")
         .build()
         .await?;
-    let obstacles_pub = node.publisher::<Vec<Obstacle>>("obstacles").build().await?;
+    let obstacles_pub = node
+        .announcing_publisher::<Vec<Obstacle>>("obstacles")
+        .await?;
 
     let mut obstacle_filter = ObstacleFilter::default();
     let mut last_processed_player_state_times = Players::new(None);
@@ -185,7 +187,11 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                     obstacle_filter_hypotheses_pub
                         .publish(&obstacle_filter.hypotheses)
                         .await?;
-                    obstacles_pub.publish(&obstacles).await?;
+                    obstacles_pub
+                        .announce(node.clock().now())
+                        .await?
+                        .publish(&obstacles)
+                        .await?;
                 }
             }
             received_player_states = player_states_subscriber.recv() => {
@@ -245,7 +251,11 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                 obstacle_filter_hypotheses_pub
                     .publish(&obstacle_filter.hypotheses)
                     .await?;
-                obstacles_pub.publish(&obstacles).await?;
+                obstacles_pub
+                    .announce(node.clock().now())
+                    .await?
+                    .publish(&obstacles)
+                    .await?;
             }
         }
     }
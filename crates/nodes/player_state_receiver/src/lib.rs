@@ -106,6 +106,7 @@ mod tests {
                     player_number: PlayerNumber::Two,
                     pose,
                     ball_position: None,
+                    searched_area: Default::default(),
                 })),
             },
         );
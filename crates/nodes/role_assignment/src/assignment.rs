@@ -0,0 +1,523 @@
+use std::time::Duration;
+
+use coordinate_systems::{Field, Ground};
+use hsl_network_messages::{HulkMessage, PlayerNumber, StrikerMessage};
+use hungarian_algorithm::AssignmentProblem;
+use linear_algebra::{Isometry2, Point2, distance, point};
+use ndarray::Array2;
+use ordered_float::NotNan;
+use ros_z::time::Time;
+use types::{
+    ball_position::BallPosition,
+    field_dimensions::FieldDimensions,
+    filtered_game_controller_state::FilteredGameControllerState,
+    players::Players,
+    primary_state::PrimaryState,
+    roles::Role,
+    time_wrapper::TimeWrapper,
+    world_state::{BallState, PlayerState},
+};
+
+use crate::Parameters;
+
+#[derive(Clone, Debug, Default)]
+pub struct Inputs {
+    pub now: Time,
+    pub player_number: PlayerNumber,
+    pub goal_keeper_number: PlayerNumber,
+    pub primary_state: PrimaryState,
+    pub ground_to_field: Option<Isometry2<Ground, Field>>,
+    pub ball: Option<BallState>,
+    pub team_ball: Option<BallPosition<Field>>,
+    pub player_states: Players<Option<TimeWrapper<PlayerState>>>,
+    pub filtered_game_controller_state: Option<FilteredGameControllerState>,
+    pub time_to_reach_kick_position: Option<Duration>,
+    pub field_dimensions: FieldDimensions,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct StrikerClaim {
+    received_at: Time,
+    ball_position: Point2<Field>,
+    time_to_reach_kick_position: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct RoleAssignment {
+    role: Role,
+    striker_claims: Players<Option<StrikerClaim>>,
+    keeper_last_heard: Option<Time>,
+    loser_since: Option<Time>,
+    last_striker_claim: Option<Time>,
+    is_striker_claim_pending: bool,
+}
+
+impl RoleAssignment {
+    pub fn receive_message(&mut self, message: &HulkMessage, received_at: Time) {
+        if let HulkMessage::Striker(StrikerMessage {
+            player_number,
+            ball_position,
+            time_to_reach_kick_position,
+            ..
+        }) = *message
+        {
+            self.striker_claims[player_number] = Some(StrikerClaim {
+                received_at,
+                ball_position: ball_position.position,
+                time_to_reach_kick_position,
+            });
+        }
+    }
+
+    pub fn update(&mut self, parameters: &Parameters, inputs: &Inputs) -> Role {
+        let role = self.decide_role(parameters, inputs);
+
+        if role == Role::Striker && self.role != Role::Striker {
+            self.is_striker_claim_pending = true;
+        }
+        if role == Role::Loser && self.role != Role::Loser {
+            self.loser_since = Some(inputs.now);
+        }
+        self.role = role;
+
+        role
+    }
+
+    /// Striker claim to be sent by the behavior node, which schedules it as urgent message within
+    /// the team message budget.
+    pub fn striker_claim(
+        &mut self,
+        parameters: &Parameters,
+        inputs: &Inputs,
+    ) -> Option<StrikerMessage> {
+        if self.role != Role::Striker || inputs.primary_state != PrimaryState::Playing {
+            return None;
+        }
+
+        if !self.is_striker_claim_pending
+            && self.last_striker_claim.is_some_and(|last_claim| {
+                inputs.now.duration_since(last_claim)
+                    < parameters
+                        .hsl_network_parameters
+                        .hsl_striker_message_send_interval
+            })
+        {
+            return None;
+        }
+
+        let ground_to_field = inputs.ground_to_field?;
+        let ball_position = inputs
+            .ball
+            .map(|ball| hsl_network_messages::BallPosition {
+                position: ball.ball_in_field,
+                age: inputs
+                    .now
+                    .to_wallclock()
+                    .duration_since(ball.last_seen_ball)
+                    .unwrap_or_default(),
+            })
+            .or_else(|| {
+                trusted_team_ball(parameters, inputs).map(|team_ball| {
+                    hsl_network_messages::BallPosition {
+                        position: team_ball.position,
+                        age: team_ball.age_at(inputs.now).unwrap_or_default(),
+                    }
+                })
+            })?;
+
+        self.last_striker_claim = Some(inputs.now);
+        self.is_striker_claim_pending = false;
+
+        Some(StrikerMessage {
+            player_number: inputs.player_number,
+            pose: ground_to_field.as_pose(),
+            ball_position,
+            time_to_reach_kick_position: inputs
+                .time_to_reach_kick_position
+                .unwrap_or(Duration::MAX),
+        })
+    }
+
+    fn decide_role(&mut self, parameters: &Parameters, inputs: &Inputs) -> Role {
+        if let Some(forced_role) = parameters.forced_role {
+            return forced_role;
+        }
+
+        let is_keeper = inputs.player_number == inputs.goal_keeper_number;
+        if inputs.primary_state != PrimaryState::Playing {
+            self.striker_claims = Players::default();
+            self.keeper_last_heard = None;
+            return if is_keeper {
+                Role::Keeper
+            } else {
+                Role::Searcher
+            };
+        }
+        if is_keeper {
+            return Role::Keeper;
+        }
+
+        self.expire_striker_claims(parameters, inputs);
+        let keeper_is_active = self.is_keeper_active(parameters, inputs);
+
+        let own_ball = inputs.ball.map(|ball| ball.ball_in_field);
+        let team_ball = trusted_team_ball(parameters, inputs).map(|team_ball| team_ball.position);
+
+        let can_claim_striker =
+            own_ball.is_some() || (parameters.claim_striker_from_team_ball && team_ball.is_some());
+        let own_claim = can_claim_striker.then_some((
+            inputs.time_to_reach_kick_position.unwrap_or(Duration::MAX),
+            inputs.player_number,
+        ));
+        let striker = self
+            .striker_claims
+            .iter()
+            .filter_map(|(player_number, claim)| {
+                claim.map(|claim| (claim.time_to_reach_kick_position, player_number))
+            })
+            .chain(own_claim)
+            .min()
+            .map(|(_, player_number)| player_number);
+
+        if striker == Some(inputs.player_number) {
+            return Role::Striker;
+        }
+        if striker.is_none() && self.is_loser(parameters, inputs.now) {
+            return Role::Loser;
+        }
+
+        let Some(ground_to_field) = inputs.ground_to_field else {
+            return Role::Searcher;
+        };
+        let ball = own_ball
+            .or_else(|| {
+                striker.and_then(|striker| {
+                    self.striker_claims[striker].map(|claim| claim.ball_position)
+                })
+            })
+            .or(team_ball);
+
+        let mut positions = vec![ground_to_field.as_pose().position()];
+        positions.extend(inputs.player_states.iter().filter_map(
+            |(player_number, player_state)| {
+                let player_state = player_state.as_ref()?;
+                let is_available = player_number != inputs.player_number
+                    && Some(player_number) != striker
+                    && !(keeper_is_active && player_number == inputs.goal_keeper_number)
+                    && inputs.now.duration_since(player_state.time)
+                        <= parameters.player_state_timeout;
+                is_available.then(|| player_state.inner.pose.position())
+            },
+        ));
+
+        let mut roles = Vec::new();
+        if !keeper_is_active {
+            roles.push(Role::ReplacementKeeper);
+        }
+        if ball.is_some() {
+            if striker.is_none() {
+                roles.push(Role::Striker);
+            }
+            roles.extend(parameters.optional_roles.iter().copied());
+        }
+        roles.truncate(positions.len());
+
+        assign_roles(&positions, &roles, ball, &inputs.field_dimensions)[0]
+            .unwrap_or(Role::Searcher)
+    }
+
+    fn expire_striker_claims(&mut self, parameters: &Parameters, inputs: &Inputs) {
+        let receive_timeout = parameters
+            .hsl_network_parameters
+            .hsl_striker_message_receive_timeout;
+        let penalties = inputs
+            .filtered_game_controller_state
+            .as_ref()
+            .map(|state| state.penalties)
+            .unwrap_or_default();
+
+        self.striker_claims = self.striker_claims.map(|claim| {
+            claim.filter(|claim| inputs.now.duration_since(claim.received_at) <= receive_timeout)
+        });
+        for (player_number, penalty) in penalties.iter() {
+            if penalty.is_some() {
+                self.striker_claims[player_number] = None;
+            }
+        }
+    }
+
+    fn is_keeper_active(&mut self, parameters: &Parameters, inputs: &Inputs) -> bool {
+        let keeper = inputs.goal_keeper_number;
+        let keeper_is_penalized = inputs
+            .filtered_game_controller_state
+            .as_ref()
+            .is_some_and(|state| state.penalties[keeper].is_some());
+
+        let keeper_last_heard = inputs.player_states[keeper]
+            .as_ref()
+            .map(|player_state| player_state.time)
+            .into_iter()
+            .chain(Some(self.keeper_last_heard.unwrap_or(inputs.now)))
+            .max();
+        self.keeper_last_heard = keeper_last_heard;
+
+        !keeper_is_penalized
+            && keeper_last_heard.is_some_and(|last_heard| {
+                inputs.now.duration_since(last_heard)
+                    <= parameters.keeper_replacement_keeper_switch_time
+            })
+    }
+
+    fn is_loser(&self, parameters: &Parameters, now: Time) -> bool {
+        match self.role {
+            Role::Striker => true,
+            Role::Loser => self
+                .loser_since
+                .is_some_and(|since| now.duration_since(since) < parameters.loser_timeout),
+            _ => false,
+        }
+    }
+}
+
+fn trusted_team_ball(parameters: &Parameters, inputs: &Inputs) -> Option<BallPosition<Field>> {
+    let team_ball = inputs.team_ball?;
+    let is_recent = team_ball
+        .age_at(inputs.now)
+        .is_some_and(|age| age <= parameters.maximum_trusted_team_ball_age);
+    let agrees_with_own_ball = inputs.ball.is_none_or(|ball| {
+        distance(ball.ball_in_field, team_ball.position)
+            <= parameters.maximum_trusted_team_ball_distance
+    });
+
+    (is_recent && agrees_with_own_ball).then_some(team_ball)
+}
+
+fn assign_roles(
+    positions: &[Point2<Field>],
+    roles: &[Role],
+    ball: Option<Point2<Field>>,
+    field_dimensions: &FieldDimensions,
+) -> Vec<Option<Role>> {
+    if roles.is_empty() {
+        return vec![None; positions.len()];
+    }
+
+    let own_goal = point![-field_dimensions.length / 2.0, 0.0];
+    let costs = Array2::from_shape_fn((positions.len(), roles.len()), |(player, role)| {
+        let target = reference_position(roles[role], own_goal, ball);
+        NotNan::new(-distance(positions[player], target)).expect("distance to role is NaN")
+    });
+
+    AssignmentProblem::from_costs(costs)
+        .solve()
+        .into_iter()
+        .map(|assignment| assignment.map(|assignment| roles[assignment.to]))
+        .collect()
+}
+
+fn reference_position(
+    role: Role,
+    own_goal: Point2<Field>,
+    ball: Option<Point2<Field>>,
+) -> Point2<Field> {
+    let Some(ball) = ball else {
+        return own_goal;
+    };
+
+    match role {
+        Role::Keeper | Role::ReplacementKeeper => own_goal,
+        Role::Defender => own_goal.lerp(ball, 0.3),
+        Role::Midfielder => own_goal.lerp(ball, 0.6),
+        Role::StrikerSupporter => own_goal.lerp(ball, 0.85),
+        Role::Loser | Role::Searcher | Role::Striker => ball,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hsl_network_messages::Penalty;
+    use linear_algebra::{Pose2, vector};
+    use types::field_dimensions::Side;
+
+    use crate::HslNetworkParameters;
+
+    use super::*;
+
+    fn parameters() -> Parameters {
+        Parameters {
+            optional_roles: vec![Role::Defender, Role::Midfielder, Role::StrikerSupporter],
+            forced_role: None,
+            keeper_replacement_keeper_switch_time: Duration::from_secs(12),
+            player_state_timeout: Duration::from_secs(5),
+            maximum_trusted_team_ball_age: Duration::from_secs(1),
+            maximum_trusted_team_ball_distance: 0.5,
+            loser_timeout: Duration::from_secs(5),
+            claim_striker_from_team_ball: false,
+            hsl_network_parameters: HslNetworkParameters {
+                hsl_striker_message_receive_timeout: Duration::from_secs(3),
+                hsl_striker_message_send_interval: Duration::from_secs(2),
+            },
+        }
+    }
+
+    fn player_state(now: Time, x: f32, y: f32) -> Option<TimeWrapper<PlayerState>> {
+        Some(TimeWrapper {
+            time: now,
+            inner: PlayerState {
+                pose: Pose2::new(point![x, y], 0.0),
+                ball_position: None,
+            },
+        })
+    }
+
+    fn playing_inputs(player_number: PlayerNumber, x: f32, y: f32) -> Inputs {
+        let now = Time::from_nanos(100_000_000_000);
+        Inputs {
+            now,
+            player_number,
+            goal_keeper_number: PlayerNumber::One,
+            primary_state: PrimaryState::Playing,
+            ground_to_field: Some(Isometry2::from_parts(vector![x, y], 0.0)),
+            player_states: Players {
+                one: player_state(now, -4.0, 0.0),
+                ..Default::default()
+            },
+            filtered_game_controller_state: Some(FilteredGameControllerState {
+                remaining_number_of_messages: 1200,
+                ..Default::default()
+            }),
+            field_dimensions: FieldDimensions {
+                length: 9.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn ball_at(now: Time, x: f32, y: f32) -> Option<BallState> {
+        Some(BallState {
+            ball_in_ground: Point2::origin(),
+            ball_in_field: point![x, y],
+            ball_in_ground_velocity: Default::default(),
+            ball_in_ground_covariance: Default::default(),
+            last_seen_ball: now.to_wallclock(),
+            field_side: Side::Left,
+        })
+    }
+
+    fn striker_claim(player_number: PlayerNumber, time_to_reach: Duration) -> HulkMessage {
+        HulkMessage::Striker(StrikerMessage {
+            player_number,
+            pose: Pose2::default(),
+            ball_position: hsl_network_messages::BallPosition {
+                position: point![1.0, 0.0],
+                age: Duration::ZERO,
+            },
+            time_to_reach_kick_position: time_to_reach,
+        })
+    }
+
+    #[test]
+    fn faster_player_wins_striker_negotiation() {
+        let parameters = parameters();
+        let mut inputs = playing_inputs(PlayerNumber::Three, 0.0, 0.0);
+        inputs.ball = ball_at(inputs.now, 1.0, 0.0);
+        inputs.time_to_reach_kick_position = Some(Duration::from_secs(4));
+
+        let mut role_assignment = RoleAssignment::default();
+        assert_eq!(role_assignment.update(&parameters, &inputs), Role::Striker);
+        assert!(
+            role_assignment
+                .striker_claim(&parameters, &inputs)
+                .is_some()
+        );
+
+        role_assignment.receive_message(
+            &striker_claim(PlayerNumber::Four, Duration::from_secs(2)),
+            inputs.now,
+        );
+        inputs.player_states.four = player_state(inputs.now, 0.5, 0.0);
+        assert_ne!(role_assignment.update(&parameters, &inputs), Role::Striker);
+        assert!(
+            role_assignment
+                .striker_claim(&parameters, &inputs)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn expired_striker_claims_are_ignored() {
+        let parameters = parameters();
+        let mut inputs = playing_inputs(PlayerNumber::Three, 0.0, 0.0);
+        inputs.ball = ball_at(inputs.now, 1.0, 0.0);
+        inputs.time_to_reach_kick_position = Some(Duration::from_secs(4));
+
+        let mut role_assignment = RoleAssignment::default();
+        role_assignment.receive_message(
+            &striker_claim(PlayerNumber::Four, Duration::from_secs(2)),
+            inputs.now - Duration::from_secs(10),
+        );
+
+        assert_eq!(role_assignment.update(&parameters, &inputs), Role::Striker);
+    }
+
+    #[test]
+    fn striker_without_ball_becomes_loser_until_timeout() {
+        let parameters = parameters();
+        let mut inputs = playing_inputs(PlayerNumber::Three, 0.0, 0.0);
+        inputs.ball = ball_at(inputs.now, 1.0, 0.0);
+
+        let mut role_assignment = RoleAssignment::default();
+        assert_eq!(role_assignment.update(&parameters, &inputs), Role::Striker);
+
+        inputs.ball = None;
+        assert_eq!(role_assignment.update(&parameters, &inputs), Role::Loser);
+
+        inputs.now = inputs.now + parameters.loser_timeout;
+        inputs.player_states.one = player_state(inputs.now, -4.0, 0.0);
+        assert_eq!(role_assignment.update(&parameters, &inputs), Role::Searcher);
+    }
+
+    #[test]
+    fn closest_player_replaces_penalized_keeper() {
+        let parameters = parameters();
+        let mut inputs = playing_inputs(PlayerNumber::Two, -3.5, 0.0);
+        inputs.player_states.one = None;
+        inputs.player_states.three = player_state(inputs.now, 2.0, 0.0);
+        inputs
+            .filtered_game_controller_state
+            .as_mut()
+            .unwrap()
+            .penalties
+            .one = Some(Penalty::PickUp {
+            remaining: Duration::from_secs(45),
+        });
+
+        let mut role_assignment = RoleAssignment::default();
+        assert_eq!(
+            role_assignment.update(&parameters, &inputs),
+            Role::ReplacementKeeper
+        );
+    }
+
+    #[test]
+    fn remaining_roles_follow_distance_to_reference_positions() {
+        let positions = [point![-3.0, 0.0], point![2.0, 0.0]];
+        let roles = [Role::Midfielder, Role::Defender];
+
+        let assignment = assign_roles(
+            &positions,
+            &roles,
+            Some(point![3.0, 0.0]),
+            &FieldDimensions {
+                length: 9.0,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            assignment,
+            vec![Some(Role::Defender), Some(Role::Midfielder)]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+mod assignment;
+
+use std::{boxed::Box, future::Future, pin::Pin};
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use coordinate_systems::{Field, Ground};
+use hsl_network_messages::{PlayerNumber, StrikerMessage};
+use linear_algebra::Isometry2;
+use ros_z::{prelude::*, qos::QosDurability};
+use types::{
+    ball_position::BallPosition,
+    field_dimensions::FieldDimensions,
+    filtered_game_controller_state::FilteredGameControllerState,
+    messages::IncomingMessage,
+    players::Players,
+    primary_state::PrimaryState,
+    roles::Role,
+    time_wrapper::TimeWrapper,
+    world_state::{BallState, PlayerState},
+};
+
+pub use assignment::{Inputs, RoleAssignment};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[serde(deny_unknown_fields)]
+pub struct Parameters {
+    pub optional_roles: Vec<Role>,
+    pub forced_role: Option<Role>,
+    pub keeper_replacement_keeper_switch_time: Duration,
+    pub player_state_timeout: Duration,
+    pub maximum_trusted_team_ball_age: Duration,
+    pub maximum_trusted_team_ball_distance: f32,
+    pub loser_timeout: Duration,
+    pub claim_striker_from_team_ball: bool,
+    pub hsl_network_parameters: HslNetworkParameters,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[serde(deny_unknown_fields)]
+pub struct HslNetworkParameters {
+    pub hsl_striker_message_receive_timeout: Duration,
+    pub hsl_striker_message_send_interval: Duration,
+}
+
+pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+    Box::pin(run(ctx))
+}
+
+async fn run(ctx: Arc<Context>) -> Result<()> {
+    let node = ctx.create_node("role_assignment").build().await?;
+
+    let parameters = node.bind_parameter_as::<Parameters>("role_assignment")?;
+    let field_dimensions_cache = node
+        .subscriber::<FieldDimensions>("field_dimensions")
+        .qos(QosProfile {
+            durability: QosDurability::TransientLocal,
+            ..Default::default()
+        })
+        .cache(1)
+        .build()
+        .await?;
+    let player_number_cache = node
+        .subscriber::<PlayerNumber>("player_number")
+        .qos(QosProfile {
+            durability: QosDurability::TransientLocal,
+            ..Default::default()
+        })
+        .cache(1)
+        .build()
+        .await?;
+    let goal_keeper_number_cache = node
+        .subscriber::<PlayerNumber>("goal_keeper_number")
+        .qos(QosProfile {
+            durability: QosDurability::TransientLocal,
+            ..Default::default()
+        })
+        .cache(1)
+        .build()
+        .await?;
+    let primary_state_cache = node
+        .subscriber::<PrimaryState>("primary_state")
+        .qos(QosProfile {
+            durability: QosDurability::TransientLocal,
+            ..Default::default()
+        })
+        .cache(1)
+        .build()
+        .await?;
+    let ground_to_field_cache = node
+        .subscriber::<Isometry2<Ground, Field>>("ground_to_field")
+        .cache(1)
+        .build()
+        .await?;
+    let ball_state_cache = node
+        .subscriber::<Option<BallState>>("ball_state")
+        .cache(1)
+        .build()
+        .await?;
+    let team_ball_cache = node
+        .subscriber::<BallPosition<Field>>("team_ball")
+        .cache(1)
+        .build()
+        .await?;
+    let player_states_cache = node
+        .subscriber::<Players<Option<TimeWrapper<PlayerState>>>>("player_states")
+        .cache(1)
+        .build()
+        .await?;
+    let filtered_game_controller_state_cache = node
+        .subscriber::<FilteredGameControllerState>("filtered_game_controller_state")
+        .cache(1)
+        .build()
+        .await?;
+    let time_to_reach_kick_position_cache = node
+        .subscriber::<Duration>("time_to_reach_kick_position")
+        .cache(1)
+        .build()
+        .await?;
+    let filtered_message_sub = node
+        .subscriber::<TimeWrapper<IncomingMessage>>("filtered_message")
+        .build()
+        .await?;
+
+    let role_pub = node.publisher::<Role>("role").build().await?;
+    let striker_claim_pub = node
+        .publisher::<TimeWrapper<StrikerMessage>>("role_assignment/striker_claim")
+        .build()
+        .await?;
+
+    let mut timer = node.create_timer(Duration::from_millis(10));
+    let mut role_assignment = RoleAssignment::default();
+
+    loop {
+        tokio::select! {
+            received_message = filtered_message_sub.recv() => {
+                let TimeWrapper { time, inner } = received_message?;
+                if let IncomingMessage::Hsl(message) = inner {
+                    role_assignment.receive_message(&message, time);
+                }
+            }
+            _ = timer.tick() => {
+                let Some(goal_keeper_number) = goal_keeper_number_cache.get_latest() else {
+                    // without the keeper every role would be assigned from a guess
+                    continue;
+                };
+                let parameters = parameters.snapshot().typed().clone();
+                let inputs = Inputs {
+                    now: node.clock().now(),
+                    player_number: player_number_cache
+                        .get_latest()
+                        .map(|player_number| *player_number)
+                        .unwrap_or_default(),
+                    goal_keeper_number: *goal_keeper_number,
+                    primary_state: primary_state_cache
+                        .get_latest()
+                        .map(|primary_state| *primary_state)
+                        .unwrap_or_default(),
+                    ground_to_field: ground_to_field_cache
+                        .get_latest()
+                        .map(|ground_to_field| *ground_to_field),
+                    ball: ball_state_cache.get_latest().and_then(|ball| *ball),
+                    team_ball: team_ball_cache.get_latest().map(|team_ball| *team_ball),
+                    player_states: player_states_cache
+                        .get_latest()
+                        .map(|player_states| (*player_states).clone())
+                        .unwrap_or_default(),
+                    filtered_game_controller_state: filtered_game_controller_state_cache
+                        .get_latest()
+                        .map(|state| (*state).clone()),
+                    time_to_reach_kick_position: time_to_reach_kick_position_cache
+                        .get_latest()
+                        .map(|duration| *duration),
+                    field_dimensions: field_dimensions_cache
+                        .get_latest()
+                        .map(|dimensions| *dimensions)
+                        .unwrap_or_default(),
+                };
+
+                let role = role_assignment.update(&parameters, &inputs);
+                if let Some(striker_claim) = role_assignment.striker_claim(&parameters, &inputs) {
+                    striker_claim_pub
+                        .publish(&TimeWrapper {
+                            time: inputs.now,
+                            inner: striker_claim,
+                        })
+                        .await?;
+                }
+                role_pub.publish(&role).await?;
+            }
+        }
+    }
+}
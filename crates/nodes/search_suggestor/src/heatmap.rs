@@ -2,9 +2,11 @@ use std::{ops::Range, time::SystemTime};
 
 use coordinate_systems::{Field, Ground};
 use geometry::direction::{Direction, Rotate90Degrees};
-use hsl_network_messages::{HulkMessage, StateMessage, SubState, Team};
+use hsl_network_messages::{
+    HulkMessage, SearchedArea, StateMessage, StrikerMessage, SubState, Team,
+};
 use itertools::Itertools;
-use linear_algebra::{Isometry2, Point2, Vector2, vector};
+use linear_algebra::{Isometry2, Point2, Vector2, distance, point, vector};
 use nalgebra::clamp;
 use ndarray::Array2;
 use ros_z::time::Time;
@@ -97,6 +99,65 @@ impl Heatmap {
             message,
             parameters.team_ball_weight,
         );
+        if let HulkMessage::State(StateMessage { searched_area, .. }) = message {
+            self.decay_searched_area(
+                field_dimensions,
+                searched_area,
+                parameters.team_search_decay,
+            );
+        }
+    }
+
+    /// Reduces the heat in all tiles a teammate already looked at without seeing the ball.
+    pub(crate) fn decay_searched_area(
+        &mut self,
+        field_dimensions: FieldDimensions,
+        searched_area: SearchedArea,
+        team_search_decay: f32,
+    ) {
+        if searched_area.is_empty() {
+            return;
+        }
+        let cells_per_meter = self.cells_per_meter;
+        self.map.indexed_iter_mut().for_each(|(index, value)| {
+            let tile_center = tile_center(field_dimensions, cells_per_meter, index);
+            let cell = SearchedArea::cell_index(
+                tile_center,
+                field_dimensions.length,
+                field_dimensions.width,
+            );
+            if searched_area.contains(cell) {
+                *value *= 1.0 - team_search_decay;
+            }
+        });
+    }
+
+    /// The hottest tiles with at least `search_candidate_spacing` between them, hottest first.
+    pub(crate) fn search_candidates(
+        &self,
+        field_dimensions: FieldDimensions,
+        parameters: &SearchSuggestorParameters,
+    ) -> Vec<Point2<Field>> {
+        let mut tiles: Vec<_> = self
+            .map
+            .indexed_iter()
+            .filter(|(_, value)| **value > parameters.minimum_validity)
+            .collect();
+        tiles.sort_by(|(_, left), (_, right)| right.total_cmp(left));
+
+        let mut candidates: Vec<Point2<Field>> = Vec::new();
+        for (index, _) in tiles {
+            if candidates.len() >= parameters.number_of_search_candidates {
+                break;
+            }
+            let position = tile_center(field_dimensions, self.cells_per_meter, index);
+            if candidates.iter().all(|candidate| {
+                distance(*candidate, position) >= parameters.search_candidate_spacing
+            }) {
+                candidates.push(position);
+            }
+        }
+        candidates
     }
 
     pub(crate) fn get_maximum_position(&self, minimum_validity: f32) -> Option<(usize, usize)> {
@@ -127,9 +188,7 @@ impl Heatmap {
                 ((y as f32 + 1.0 / 2.0) / self.cells_per_meter - field_dimensions.width / 2.0)
             ];
             let robot_to_tile = tile_center_in_field - robot_position;
-            let is_inside_sight = get_direction(left_edge, robot_to_tile)
-                == Direction::Counterclockwise
-                && get_direction(right_edge, robot_to_tile) == Direction::Clockwise;
+            let is_inside_sight = is_inside_sight(left_edge, right_edge, robot_to_tile);
             let distance_to_tile = robot_to_tile.norm();
             let relative_distance_to_tile =
                 clamp(distance_to_tile / heatmap_decay_range.end, 0.0, 1.0);
@@ -169,6 +228,11 @@ impl Heatmap {
                     last_seen: Time::from_wallclock(time) - ball.age,
                 })
             }
+            HulkMessage::Striker(StrikerMessage { ball_position, .. }) => Some(BallPosition {
+                position: ball_position.position,
+                velocity: Vector2::zeros(),
+                last_seen: Time::from_wallclock(time) - ball_position.age,
+            }),
         };
         if let Some(ball_position) = ball {
             let heatmap_point = self.field_to_heatmap(field_dimensions, ball_position.position);
@@ -177,6 +241,26 @@ impl Heatmap {
     }
 }
 
+fn tile_center(
+    field_dimensions: FieldDimensions,
+    cells_per_meter: f32,
+    (x, y): (usize, usize),
+) -> Point2<Field> {
+    point![
+        (x as f32 + 1.0 / 2.0) / cells_per_meter - field_dimensions.length / 2.0,
+        (y as f32 + 1.0 / 2.0) / cells_per_meter - field_dimensions.width / 2.0
+    ]
+}
+
+pub(crate) fn is_inside_sight(
+    left_edge: Vector2<Field>,
+    right_edge: Vector2<Field>,
+    robot_to_position: Vector2<Field>,
+) -> bool {
+    get_direction(left_edge, robot_to_position) == Direction::Counterclockwise
+        && get_direction(right_edge, robot_to_position) == Direction::Clockwise
+}
+
 fn get_rule_hypotheses(
     primary_state: PrimaryState,
     filtered_game_controller_state: &FilteredGameControllerState,
@@ -245,3 +329,80 @@ fn get_direction(base_vector: Vector2<Field>, vector_to_test: Vector2<Field>) ->
         f => panic!("directed cathetus was not a real number: {f}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> SearchSuggestorParameters {
+        SearchSuggestorParameters {
+            minimum_validity: 0.0,
+            number_of_search_candidates: 3,
+            search_candidate_spacing: 1.5,
+            ..Default::default()
+        }
+    }
+
+    fn uniform_heatmap(field_dimensions: FieldDimensions) -> Heatmap {
+        let cells_per_meter = 2.0;
+        let dimensions = (
+            (field_dimensions.length * cells_per_meter) as usize,
+            (field_dimensions.width * cells_per_meter) as usize,
+        );
+        Heatmap {
+            map: Array2::from_elem(dimensions, 1.0),
+            cells_per_meter,
+            last_maximum_heatmap_position: None,
+            has_decided_for_heatmap_tile: false,
+        }
+    }
+
+    #[test]
+    fn teammate_searched_area_reduces_heat() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let mut heatmap = uniform_heatmap(field_dimensions);
+        let searched_position = point![-4.0, -2.5];
+        let mut searched_area = SearchedArea::default();
+        searched_area.insert(SearchedArea::cell_index(
+            searched_position,
+            field_dimensions.length,
+            field_dimensions.width,
+        ));
+
+        heatmap.decay_searched_area(field_dimensions, searched_area, 0.5);
+
+        let searched_tile = heatmap.field_to_heatmap(field_dimensions, searched_position);
+        let other_tile = heatmap.field_to_heatmap(field_dimensions, point![4.0, 2.5]);
+        assert_eq!(heatmap.map[searched_tile], 0.5);
+        assert_eq!(heatmap.map[other_tile], 1.0);
+    }
+
+    #[test]
+    fn search_candidates_keep_spacing() {
+        let field_dimensions = FieldDimensions::SPL_2025;
+        let mut heatmap = uniform_heatmap(field_dimensions);
+        for position in [point![1.0, 1.0], point![1.5, 1.0], point![-2.0, -1.0]] {
+            let tile = heatmap.field_to_heatmap(field_dimensions, position);
+            heatmap.map[tile] = 2.0;
+        }
+
+        let candidates = heatmap.search_candidates(field_dimensions, &parameters());
+
+        assert_eq!(candidates.len(), 3);
+        assert!(candidates.iter().enumerate().all(|(index, candidate)| {
+            candidates[..index]
+                .iter()
+                .all(|other| distance(*other, *candidate) >= 1.5)
+        }));
+        let hottest_tiles: Vec<_> = candidates[..2]
+            .iter()
+            .map(|candidate| heatmap.field_to_heatmap(field_dimensions, *candidate))
+            .collect();
+        assert!(
+            hottest_tiles.contains(&heatmap.field_to_heatmap(field_dimensions, point![1.0, 1.0]))
+        );
+        assert!(
+            hottest_tiles.contains(&heatmap.field_to_heatmap(field_dimensions, point![-2.0, -1.0]))
+        );
+    }
+}
@@ -18,7 +18,10 @@ use types::{
     time_wrapper::TimeWrapper,
 };
 mod heatmap;
+mod searched_area;
 use heatmap::Heatmap;
+use hsl_network_messages::SearchedArea;
+use searched_area::SearchedAreaTracker;
 
 pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
     Box::pin(run(ctx))
@@ -76,6 +79,14 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .publisher::<Point2<Field>>("suggested_search_position")
         .build()
         .await?;
+    let search_candidates_pub = node
+        .publisher::<Vec<Point2<Field>>>("search_candidates")
+        .build()
+        .await?;
+    let searched_area_pub = node
+        .publisher::<SearchedArea>("searched_area")
+        .build()
+        .await?;
 
     let field_dimensions = field_dimensions_sub.recv().await?;
     let initial_parameters_snapshot = parameters.snapshot();
@@ -101,10 +112,12 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         last_maximum_heatmap_position: None,
         has_decided_for_heatmap_tile: false,
     };
+    let mut searched_area_tracker = SearchedAreaTracker::default();
 
     loop {
         let parameters_snapshot = parameters.snapshot();
         let parameters = parameters_snapshot.typed();
+        let now = node.clock().now().to_wallclock();
 
         let ground_to_field = ground_to_field_cache
             .get_latest()
@@ -169,6 +182,16 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                 parameters.decay_distance_factor,
                 parameters.heatmap_decay_range.clone(),
             );
+            searched_area_tracker.mark_cells_in_sight(
+                now,
+                field_dimensions,
+                robot_position,
+                left_edge,
+                right_edge,
+                parameters.heatmap_decay_range.end,
+            );
+        } else if ball_was_seen {
+            searched_area_tracker.clear();
         }
 
         let kernel = create_kernel(parameters.heatmap_convolution_kernel_weight);
@@ -204,6 +227,12 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
                 .publish(&suggested_search_position)
                 .await?;
         }
+        search_candidates_pub
+            .publish(&heatmap.search_candidates(field_dimensions, parameters))
+            .await?;
+        searched_area_pub
+            .publish(&searched_area_tracker.searched_area(now, parameters.searched_area_retention))
+            .await?;
 
         additional_heatmap_pub
             .publish_if_subscribed(|| async { heatmap.to_message() })
@@ -0,0 +1,55 @@
+use std::time::{Duration, SystemTime};
+
+use coordinate_systems::Field;
+use hsl_network_messages::{SEARCHED_AREA_CELLS, SearchedArea};
+use linear_algebra::Vector2;
+use types::field_dimensions::FieldDimensions;
+
+use crate::heatmap::is_inside_sight;
+
+/// Remembers when the robot last looked at each cell of the coarse search grid without seeing the
+/// ball.
+#[derive(Default)]
+pub(crate) struct SearchedAreaTracker {
+    last_searched: [Option<SystemTime>; SEARCHED_AREA_CELLS],
+}
+
+impl SearchedAreaTracker {
+    pub(crate) fn mark_cells_in_sight(
+        &mut self,
+        now: SystemTime,
+        field_dimensions: FieldDimensions,
+        robot_position: Vector2<Field>,
+        left_edge: Vector2<Field>,
+        right_edge: Vector2<Field>,
+        maximum_distance: f32,
+    ) {
+        for (index, last_searched) in self.last_searched.iter_mut().enumerate() {
+            let robot_to_cell =
+                SearchedArea::cell_center(index, field_dimensions.length, field_dimensions.width)
+                    .coords()
+                    - robot_position;
+            if robot_to_cell.norm() < maximum_distance
+                && is_inside_sight(left_edge, right_edge, robot_to_cell)
+            {
+                *last_searched = Some(now);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.last_searched = [None; SEARCHED_AREA_CELLS];
+    }
+
+    pub(crate) fn searched_area(&self, now: SystemTime, retention: Duration) -> SearchedArea {
+        let mut searched_area = SearchedArea::default();
+        for (index, last_searched) in self.last_searched.iter().enumerate() {
+            if last_searched.is_some_and(|last_searched| {
+                now.duration_since(last_searched).unwrap_or_default() < retention
+            }) {
+                searched_area.insert(index);
+            }
+        }
+        searched_area
+    }
+}
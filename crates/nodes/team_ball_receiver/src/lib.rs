@@ -8,7 +8,7 @@ use coordinate_systems::Field;
 use ros_z::prelude::*;
 use types::{
     ball_position::BallPosition, filtered_game_controller_state::FilteredGameControllerState,
-    messages::IncomingMessage, players::Players,
+    messages::IncomingMessage, players::Players, time_wrapper::TimeWrapper,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, Message)]
@@ -30,7 +30,7 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .build()
         .await?;
     let _network_message_sub = node
-        .subscriber::<IncomingMessage>("filtered_message")
+        .subscriber::<TimeWrapper<IncomingMessage>>("filtered_message")
         .build()
         .await?;
     let _team_balls_pub = node
@@ -1,11 +1,26 @@
-use std::{boxed::Box, future::Future, pin::Pin};
-use std::{future::pending, sync::Arc, time::Duration};
+use std::{boxed::Box, f32::consts::PI, future::Future, pin::Pin};
+use std::{sync::Arc, time::Duration};
 
 use color_eyre::Result;
+use serde::{Deserialize, Serialize};
 
+use booster::{FallDownState, FallDownStateType};
+use coordinate_systems::Ground;
+use linear_algebra::{Point2, Vector2};
 use ros_z::prelude::*;
+use ros_z_streams::{CreateAnnouncingPublisher, CreateFutureMapBuilder};
 use types::world_state::BallState;
 
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
+#[serde(deny_unknown_fields)]
+pub struct Parameters {
+    pub walking_speed: f32,
+    /// Duration of turning around by 180 degrees
+    pub half_rotation: Duration,
+    /// Added while the robot is not standing upright
+    pub delay_when_fallen: Duration,
+}
+
 pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
     Box::pin(run(ctx))
 }
@@ -15,13 +30,112 @@ async fn run(ctx: Arc<Context>) -> Result<()> {
         .create_node("time_to_reach_kick_position")
         .build()
         .await?;
-    let _ball_state_sub = node.subscriber::<BallState>("ball_state").build().await?;
-    let _time_to_reach_kick_position_pub = node
-        .publisher::<Duration>("time_to_reach_kick_position")
+    let parameters = node.bind_parameter_as::<Parameters>("time_to_reach_kick_position")?;
+    let mut ball_states = node
+        .create_future_map_builder()
+        .create_future_subscriber::<Option<BallState>>("ball_state", Duration::from_millis(10))
+        .await?
+        .build();
+    let fall_down_state_cache = node
+        .subscriber::<FallDownState>("inputs/fall_down_state")
+        .cache(1)
         .build()
         .await?;
+    let time_to_reach_kick_position_pub = node
+        .announcing_publisher::<Duration>("time_to_reach_kick_position")
+        .await?;
+
+    loop {
+        let item = ball_states.recv().await?;
+        let Some((&time, (Some(ball_state),))) = item.persistent.last_key_value() else {
+            continue;
+        };
+
+        let parameters_snapshot = parameters.snapshot();
+        let time_to_reach_kick_position = time_to_reach_kick_position(
+            ball_state
+                .as_ref()
+                .map(|ball_state| ball_state.ball_in_ground),
+            fall_down_state_cache
+                .get_latest()
+                .map(|fall_down_state| *fall_down_state),
+            parameters_snapshot.typed(),
+        );
+        time_to_reach_kick_position_pub
+            .announce(time)
+            .await?
+            .publish(&time_to_reach_kick_position)
+            .await?;
+    }
+}
+
+/// Walks straight to the ball after turning towards it, standing up first if fallen.
+fn time_to_reach_kick_position(
+    ball_in_ground: Option<Point2<Ground>>,
+    fall_down_state: Option<FallDownState>,
+    parameters: &Parameters,
+) -> Duration {
+    let Some(ball_in_ground) = ball_in_ground else {
+        return Duration::MAX;
+    };
+
+    let walk_time = ball_in_ground.coords().norm() / parameters.walking_speed;
+    let turn_angle = ball_in_ground.coords().angle(&Vector2::x_axis());
+    let turn_time = parameters.half_rotation.as_secs_f32() * turn_angle / PI;
+    let stand_up_time = if fall_down_state
+        .is_some_and(|state| state.fall_down_state != FallDownStateType::IsReady)
+    {
+        parameters.delay_when_fallen.as_secs_f32()
+    } else {
+        0.0
+    };
+
+    // a ball at an invalid position can not be reached
+    Duration::try_from_secs_f32(walk_time + turn_time + stand_up_time).unwrap_or(Duration::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::point;
+
+    use super::*;
+
+    fn parameters() -> Parameters {
+        Parameters {
+            walking_speed: 0.5,
+            half_rotation: Duration::from_secs(4),
+            delay_when_fallen: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn unknown_ball_is_never_reached() {
+        assert_eq!(
+            time_to_reach_kick_position(None, None, &parameters()),
+            Duration::MAX
+        );
+        assert_eq!(
+            time_to_reach_kick_position(Some(point![f32::NAN, 1.0]), None, &parameters()),
+            Duration::MAX
+        );
+    }
+
+    #[test]
+    fn walking_turning_and_standing_up_add_up() {
+        let ahead = time_to_reach_kick_position(Some(point![1.0, 0.0]), None, &parameters());
+        assert!((ahead.as_secs_f32() - 2.0).abs() < 1e-3);
 
-    pending::<()>().await;
+        let sideways = time_to_reach_kick_position(Some(point![0.0, -1.0]), None, &parameters());
+        assert!((sideways.as_secs_f32() - 4.0).abs() < 1e-3);
 
-    Ok(())
+        let fallen = time_to_reach_kick_position(
+            Some(point![1.0, 0.0]),
+            Some(FallDownState {
+                fall_down_state: FallDownStateType::HasFallen,
+                is_recovery_available: true,
+            }),
+            &parameters(),
+        );
+        assert!((fallen.as_secs_f32() - 7.0).abs() < 1e-3);
+    }
 }
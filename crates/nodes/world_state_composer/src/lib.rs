@@ -1,20 +1,24 @@
 use std::{boxed::Box, future::Future, pin::Pin};
-use std::{future::pending, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
 use color_eyre::Result;
 
 use booster::FallDownState;
 use coordinate_systems::{Field, Ground};
-use hsl_network_messages::PlayerNumber;
+use hsl_network_messages::{PlayerNumber, SearchedArea};
 use linear_algebra::{Isometry2, Point2};
 use ros_z::{prelude::*, qos::QosDurability};
+use ros_z_streams::{CreateAnnouncingPublisher, CreateFutureMapBuilder};
 use types::{
     ball_position::HypotheticalBallPosition,
     filtered_game_controller_state::FilteredGameControllerState,
     obstacles::Obstacle,
+    players::Players,
     primary_state::PrimaryState,
+    roles::Role,
     rule_obstacles::RuleObstacle,
-    world_state::{BallState, WorldState},
+    time_wrapper::TimeWrapper,
+    world_state::{BallState, PlayerState, RobotState, WorldState},
 };
 
 pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
@@ -24,62 +28,166 @@ pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> +
 async fn run(ctx: Arc<Context>) -> Result<()> {
     let node = ctx.create_node("world_state_composer").build().await?;
 
-    let _player_number_sub = node
+    let player_number_cache = node
         .subscriber::<PlayerNumber>("player_number")
         .qos(QosProfile {
             durability: QosDurability::TransientLocal,
             ..Default::default()
         })
+        .cache(1)
         .build()
         .await?;
-    let _fall_down_state_sub = node
+    let fall_down_state_cache = node
         .subscriber::<FallDownState>("inputs/fall_down_state")
+        .cache(10)
         .build()
         .await?;
-    let _ball_sub = node.subscriber::<BallState>("ball_state").build().await?;
-    let _filtered_game_controller_state_sub = node
+    let filtered_game_controller_state_cache = node
         .subscriber::<FilteredGameControllerState>("filtered_game_controller_state")
+        .cache(1)
         .build()
         .await?;
-    let _ground_to_field_sub = node
+    let ground_to_field_cache = node
         .subscriber::<Isometry2<Ground, Field>>("ground_to_field")
+        .cache(10)
         .build()
         .await?;
-    let _hypothetical_ball_position_sub = node
-        .subscriber::<Vec<HypotheticalBallPosition<Ground>>>("hypothetical_ball_positions")
+    let hypothetical_ball_positions_cache = node
+        .subscriber::<Vec<HypotheticalBallPosition<Ground>>>(
+            "ball_filter/hypothetical_ball_positions",
+        )
+        .cache(1)
         .build()
         .await?;
-    let _obstacles_sub = node
-        .subscriber::<Vec<Obstacle>>("obstacles")
+    let player_states_cache = node
+        .subscriber::<Players<Option<TimeWrapper<PlayerState>>>>("player_states")
+        .cache(1)
         .build()
         .await?;
-    let _position_of_interest_sub = node
-        .subscriber::<Point2<Ground>>("position_of_interest")
-        .build()
-        .await?;
-    let _primary_state_sub = node
+    let primary_state_cache = node
         .subscriber::<PrimaryState>("primary_state")
         .qos(QosProfile {
             durability: QosDurability::TransientLocal,
             ..Default::default()
         })
+        .cache(1)
         .build()
         .await?;
-    let _rule_ball_sub = node
-        .subscriber::<BallState>("rule_ball_state")
-        .build()
-        .await?;
-    let _rule_obstacles_sub = node
+    let role_cache = node.subscriber::<Role>("role").cache(1).build().await?;
+    let rule_obstacles_cache = node
         .subscriber::<Vec<RuleObstacle>>("rule_obstacles")
+        .cache(1)
         .build()
         .await?;
-    let _suggested_search_position_sub = node
+    let suggested_search_position_cache = node
         .subscriber::<Point2<Field>>("suggested_search_position")
+        .cache(1)
+        .build()
+        .await?;
+    let search_candidates_cache = node
+        .subscriber::<Vec<Point2<Field>>>("search_candidates")
+        .cache(1)
         .build()
         .await?;
-    let _world_state_pub = node.publisher::<WorldState>("world_state").build().await?;
+    let searched_area_cache = node
+        .subscriber::<SearchedArea>("searched_area")
+        .cache(1)
+        .build()
+        .await?;
+    let mut inputs = node
+        .create_future_map_builder()
+        .create_future_subscriber::<Option<BallState>>("ball_state", Duration::from_millis(10))
+        .await?
+        .create_future_subscriber::<Option<BallState>>("rule_ball_state", Duration::from_millis(10))
+        .await?
+        .create_future_subscriber::<Vec<Obstacle>>("obstacles", Duration::from_millis(10))
+        .await?
+        .create_future_subscriber::<Point2<Ground>>(
+            "position_of_interest",
+            Duration::from_millis(10),
+        )
+        .await?
+        .build();
+    let world_state_pub = node
+        .announcing_publisher::<WorldState>("world_state")
+        .await?;
+
+    let mut world_state = WorldState::default();
+
+    loop {
+        let item = inputs.recv().await?;
+        let Some(output_time) = item.persistent.last_key_value().map(|(time, _)| *time) else {
+            continue;
+        };
+        for (_, (ball, rule_ball, obstacles, position_of_interest)) in item.persistent {
+            if let Some(ball) = ball {
+                world_state.ball = ball;
+            }
+            if let Some(rule_ball) = rule_ball {
+                world_state.rule_ball = rule_ball;
+            }
+            if let Some(obstacles) = obstacles {
+                world_state.obstacles = obstacles;
+            }
+            if let Some(position_of_interest) = position_of_interest {
+                world_state.position_of_interest = position_of_interest;
+            }
+        }
 
-    pending::<()>().await;
+        world_state.now = output_time;
+        world_state.robot = RobotState {
+            ground_to_field: ground_to_field_cache
+                .get_nearest(output_time)
+                .map(|ground_to_field| *ground_to_field),
+            player_number: player_number_cache
+                .get_latest()
+                .map(|player_number| *player_number)
+                .unwrap_or_default(),
+            primary_state: primary_state_cache
+                .get_latest()
+                .map(|primary_state| *primary_state)
+                .unwrap_or_default(),
+            role: role_cache.get_latest().map(|role| *role),
+        };
+        world_state.fall_down_state = fall_down_state_cache
+            .get_nearest(output_time)
+            .map(|fall_down_state| *fall_down_state);
+        world_state.filtered_game_controller_state = filtered_game_controller_state_cache
+            .get_latest()
+            .map(|filtered_game_controller_state| filtered_game_controller_state.as_ref().clone());
+        world_state.hypothetical_ball_positions = hypothetical_ball_positions_cache
+            .get_latest()
+            .map(|positions| positions.as_ref().clone())
+            .unwrap_or_default();
+        world_state.player_states = player_states_cache
+            .get_latest()
+            .map(|player_states| {
+                player_states
+                    .as_ref()
+                    .clone()
+                    .map(|player_state| player_state.map(|state| state.inner))
+            })
+            .unwrap_or_default();
+        world_state.rule_obstacles = rule_obstacles_cache
+            .get_latest()
+            .map(|obstacles| obstacles.as_ref().clone())
+            .unwrap_or_default();
+        world_state.suggested_search_position = suggested_search_position_cache
+            .get_latest()
+            .map(|position| *position);
+        world_state.search_candidates = search_candidates_cache
+            .get_latest()
+            .map(|candidates| candidates.as_ref().clone())
+            .unwrap_or_default();
+        world_state.searched_area = searched_area_cache
+            .get_latest()
+            .map(|searched_area| *searched_area)
+            .unwrap_or_default();
 
-    Ok(())
+        world_state_pub
+            .announce(output_time)
+            .await?
+            .publish(&world_state)
+            .await?;
+    }
 }
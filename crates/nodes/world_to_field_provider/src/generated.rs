@@ -0,0 +1,50 @@
+// Generated by `code_generation::ros_z_nodes`, regenerate with
+// `UPDATE_GENERATED_SOURCE=1 cargo test -p world_to_field_provider` instead of editing.
+
+pub mod world_to_field_provider {
+    use color_eyre::eyre::WrapErr;
+    use ros_z::prelude::*;
+
+    pub fn run_boxed(
+        ctx: std::sync::Arc<ros_z::context::Context>,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = color_eyre::Result<()>> + Send>> {
+        Box::pin(run(ctx))
+    }
+
+    pub async fn run(ctx: std::sync::Arc<ros_z::context::Context>) -> color_eyre::Result<()> {
+        let node = ctx.create_node("world_to_field_provider").build().await?;
+        let game_controller_state_subscriber = node
+            .subscriber::<Option<types::game_controller_state::GameControllerState>>(
+                "game_controller_state",
+            )
+            .build()
+            .await?;
+        let world_to_field_publisher =
+            node.publisher::<Option<
+                linear_algebra::Isometry2<coordinate_systems::World, coordinate_systems::Field>,
+            >>("world_to_field")
+                .build()
+                .await?;
+        let mut legacy_node = {
+            world_state::world_to_field_provider::WorldToFieldProvider::new(
+                world_state::world_to_field_provider::CreationContext::new(),
+            )
+            .wrap_err("failed to create node `WorldToFieldProvider`")?
+        };
+        loop {
+            let game_controller_state_input = Some(std::sync::Arc::new(
+                game_controller_state_subscriber.recv().await?,
+            ));
+            let main_outputs = legacy_node
+                .cycle(world_state::world_to_field_provider::CycleContext::new(
+                    game_controller_state_input
+                        .as_deref()
+                        .and_then(Option::as_ref),
+                ))
+                .wrap_err("failed to execute cycle of `WorldToFieldProvider`")?;
+            world_to_field_publisher
+                .publish(&main_outputs.world_to_field.value)
+                .await?;
+        }
+    }
+}
@@ -1,39 +1,7 @@
-use std::{boxed::Box, future::Future, pin::Pin};
-use std::{f32::consts::PI, sync::Arc};
+//! ros-z wrapper of the legacy `world_state::world_to_field_provider` node, generated by
+//! `code_generation::ros_z_nodes`. The wrapper is checked in so that the topic graph sees its
+//! endpoints, `tests/generated_source.rs` fails once it is outdated.
 
-use color_eyre::Result;
+mod generated;
 
-use coordinate_systems::{Field, World};
-use linear_algebra::Isometry2;
-use ros_z::prelude::*;
-use types::{field_dimensions::GlobalFieldSide, game_controller_state::GameControllerState};
-
-pub fn run_boxed(ctx: Arc<Context>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
-    Box::pin(run(ctx))
-}
-
-async fn run(ctx: Arc<Context>) -> Result<()> {
-    let node = ctx.create_node("world_to_field_provider").build().await?;
-    let game_controller_state_sub = node
-        .subscriber::<Option<GameControllerState>>("game_controller_state")
-        .build()
-        .await?;
-
-    let world_to_field_pub = node
-        .publisher::<Option<Isometry2<World, Field>>>("world_to_field")
-        .build()
-        .await?;
-
-    loop {
-        let game_controller_state = game_controller_state_sub.recv().await?;
-
-        let world_to_field = game_controller_state.map(|game_controller_state| {
-            match game_controller_state.global_field_side {
-                GlobalFieldSide::Home => Isometry2::identity(),
-                GlobalFieldSide::Away => Isometry2::rotation(PI),
-            }
-        });
-
-        world_to_field_pub.publish(&world_to_field).await?;
-    }
-}
+pub use generated::world_to_field_provider::{run, run_boxed};
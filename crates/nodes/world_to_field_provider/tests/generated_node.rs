@@ -0,0 +1,88 @@
+use std::{
+    f32::consts::PI,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::Result;
+use coordinate_systems::{Field, World};
+use hsl_network_messages::{
+    GamePhase, GameState, HULKS_TEAM_NUMBER, Half, PlayerNumber, Team, TeamColor, TeamState,
+};
+use linear_algebra::Isometry2;
+use ros_z::prelude::*;
+use types::{
+    field_dimensions::GlobalFieldSide, game_controller_state::GameControllerState, players::Players,
+};
+
+fn team_state(team_number: u8) -> TeamState {
+    TeamState {
+        team_number,
+        field_player_color: TeamColor::Green,
+        goal_keeper_color: TeamColor::Red,
+        goal_keeper_player_number: Some(PlayerNumber::One),
+        score: 0,
+        penalty_shoot_index: 0,
+        penalty_shoots: Vec::new(),
+        remaining_amount_of_messages: 1200,
+        players: Vec::new(),
+    }
+}
+
+fn game_controller_state(global_field_side: GlobalFieldSide) -> GameControllerState {
+    GameControllerState {
+        game_state: GameState::Playing,
+        stopped: false,
+        game_phase: GamePhase::Normal,
+        half: Half::First,
+        remaining_time_in_half: Duration::ZERO,
+        secondary_time: Duration::ZERO,
+        kicking_team: Some(Team::Hulks),
+        last_game_state_change: SystemTime::UNIX_EPOCH,
+        penalties: Players::new(None),
+        opponent_penalties: Players::new(None),
+        sub_state: None,
+        global_field_side,
+        hulks_team: team_state(HULKS_TEAM_NUMBER),
+        opponent_team: team_state(HULKS_TEAM_NUMBER + 1),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn generated_node_publishes_main_output_for_received_input() -> Result<()> {
+    let ctx = Arc::new(
+        ContextBuilder::default()
+            .with_mode("router")
+            .disable_multicast_scouting()
+            .with_connect_endpoints(std::iter::empty::<&str>())
+            .with_listen_endpoints(["tcp/127.0.0.1:0"])
+            .build()
+            .await?,
+    );
+    let node = ctx.create_node("generated_node_test").build().await?;
+    let game_controller_state_pub = node
+        .publisher::<Option<GameControllerState>>("game_controller_state")
+        .build()
+        .await?;
+    let world_to_field_sub = node
+        .subscriber::<Option<Isometry2<World, Field>>>("world_to_field")
+        .build()
+        .await?;
+    let running_node = tokio::spawn(world_to_field_provider::run_boxed(ctx.clone()));
+
+    let world_to_field = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            game_controller_state_pub
+                .publish(&Some(game_controller_state(GlobalFieldSide::Away)))
+                .await?;
+            if let Some(world_to_field) = world_to_field_sub.recv().await? {
+                return Ok::<_, color_eyre::Report>(world_to_field);
+            }
+        }
+    })
+    .await??;
+
+    running_node.abort();
+    assert_eq!(world_to_field, Isometry2::rotation(PI));
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use std::{
+    env,
+    fs::{read_to_string, write},
+    path::Path,
+};
+
+use code_generation::ros_z_nodes::generate_ros_z_nodes;
+use color_eyre::Result;
+use hulk_manifest::collect_hulk_cyclers;
+
+const HEADER: &str = "// Generated by `code_generation::ros_z_nodes`, regenerate with
+// `UPDATE_GENERATED_SOURCE=1 cargo test -p world_to_field_provider` instead of editing.
+
+";
+
+/// Formatting and comments are ignored, both sides are printed from their syntax tree.
+#[test]
+fn checked_in_wrapper_matches_generated_one() -> Result<()> {
+    let manifest_directory = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let cyclers = collect_hulk_cyclers(manifest_directory.join("../.."))?;
+    let generated = prettyplease::unparse(&syn::parse2(generate_ros_z_nodes(
+        &cyclers,
+        &["world_to_field_provider"],
+    )?)?);
+
+    let path = manifest_directory.join("src/generated.rs");
+    if env::var_os("UPDATE_GENERATED_SOURCE").is_some() {
+        write(&path, format!("{HEADER}{generated}"))?;
+        return Ok(());
+    }
+    let checked_in = prettyplease::unparse(&syn::parse_file(&read_to_string(&path)?)?);
+    assert_eq!(
+        checked_in, generated,
+        "src/generated.rs is outdated, regenerate it with \
+         `UPDATE_GENERATED_SOURCE=1 cargo test -p world_to_field_provider` and `cargo fmt`"
+    );
+    Ok(())
+}
@@ -23,12 +23,19 @@ pub fn scenario(_attribute: TokenStream, item: TokenStream) -> TokenStream {
         mod test {
             #[test]
             fn #function_name() -> color_eyre::Result<()> {
-                use bevyhavior_simulator::behavior_tree_simulator::{AppExt, BehaviorTreeSimulatorPlugin};
+                use bevyhavior_simulator::behavior_tree_simulator::{
+                    AppExt, BehaviorTreeSimulatorPlugin, keep_timeline_of_failure,
+                    record_timeline_to_directory,
+                };
 
-                bevy::app::App::new()
-                    .add_plugins(BehaviorTreeSimulatorPlugin::default())
-                    .add_plugins(super::#function_name)
-                    .run_to_completion()
+                let mut app = bevy::app::App::new();
+                app.add_plugins(BehaviorTreeSimulatorPlugin::default())
+                    .add_plugins(super::#function_name);
+                let timeline_path =
+                    record_timeline_to_directory(&mut app, stringify!(#function_name))?;
+                let result = app.run_to_completion();
+                keep_timeline_of_failure(timeline_path, &result)?;
+                result
             }
         }
     }
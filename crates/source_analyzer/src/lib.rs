@@ -5,7 +5,9 @@ pub mod manifest;
 pub mod node;
 pub mod path;
 pub mod pretty;
+pub mod ros_z;
 pub mod struct_hierarchy;
 pub mod structs;
 mod to_absolute;
+pub mod topic_graph;
 mod uses;
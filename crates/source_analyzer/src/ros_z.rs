@@ -0,0 +1,299 @@
+use convert_case::{Case, Casing};
+use syn::{Ident, Type};
+
+use crate::{contexts::Field, error::ParseError, node::Node, path::Path};
+
+/// A legacy `#[context]` node described in terms of ros-z endpoints.
+///
+/// Inputs become subscriptions, main and additional outputs become publications and all
+/// parameters are collected into a single parameter set bound under the node name.
+#[derive(Clone, Debug)]
+pub struct RosZNode {
+    pub name: String,
+    pub parameters: Vec<RosZParameter>,
+    pub subscriptions: Vec<Subscription>,
+    pub publications: Vec<Publication>,
+    pub cycler_states: Vec<RosZCyclerState>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RosZParameter {
+    pub name: Ident,
+    pub data_type: Type,
+    pub is_optional: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Subscription {
+    pub name: Ident,
+    pub topic: String,
+    pub data_type: Type,
+    pub kind: SubscriptionKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SubscriptionKind {
+    /// The node cannot cycle before the first message arrived.
+    Plain,
+    /// The topic carries an `Option<T>` which is handed to the node as `Option<&T>`.
+    Optional,
+    /// The topic carries an `Option<T>`, the node is skipped while it is `None`.
+    Required,
+}
+
+#[derive(Clone, Debug)]
+pub struct Publication {
+    pub name: Ident,
+    pub topic: String,
+    pub data_type: Type,
+    pub kind: PublicationKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PublicationKind {
+    Main,
+    Additional,
+}
+
+#[derive(Clone, Debug)]
+pub struct RosZCyclerState {
+    pub name: Ident,
+    pub data_type: Type,
+}
+
+impl RosZNode {
+    pub fn try_from_node(node: &Node) -> Result<Self, ParseError> {
+        let name = node.name.to_case(Case::Snake);
+        let mut ros_z_node = Self {
+            name,
+            parameters: Vec::new(),
+            subscriptions: Vec::new(),
+            publications: Vec::new(),
+            cycler_states: Vec::new(),
+        };
+
+        for field in node
+            .contexts
+            .creation_context
+            .iter()
+            .chain(&node.contexts.cycle_context)
+            .chain(&node.contexts.main_outputs)
+        {
+            ros_z_node.add_field(field)?;
+        }
+
+        Ok(ros_z_node)
+    }
+
+    fn add_field(&mut self, field: &Field) -> Result<(), ParseError> {
+        match field {
+            Field::AdditionalOutput {
+                data_type,
+                name,
+                path,
+            } => {
+                self.publications.push(Publication {
+                    name: name.clone(),
+                    topic: format!("{}/{}", self.name, topic_from_path(name, path)?),
+                    data_type: data_type.clone(),
+                    kind: PublicationKind::Additional,
+                });
+            }
+            Field::CyclerState {
+                data_type, name, ..
+            } => {
+                if !self.cycler_states.iter().any(|state| state.name == *name) {
+                    self.cycler_states.push(RosZCyclerState {
+                        name: name.clone(),
+                        data_type: data_type.clone(),
+                    });
+                }
+            }
+            Field::Input {
+                data_type,
+                name,
+                path,
+                ..
+            } => {
+                let kind = if path.contains_optional() {
+                    SubscriptionKind::Optional
+                } else {
+                    SubscriptionKind::Plain
+                };
+                self.subscriptions.push(Subscription {
+                    name: name.clone(),
+                    topic: topic_from_input_path(name, path)?,
+                    data_type: data_type.clone(),
+                    kind,
+                });
+            }
+            Field::MainOutput { data_type, name } => {
+                self.publications.push(Publication {
+                    name: name.clone(),
+                    topic: name.to_string(),
+                    data_type: data_type.clone(),
+                    kind: PublicationKind::Main,
+                });
+            }
+            Field::Parameter {
+                data_type,
+                name,
+                path,
+            } => {
+                if !self
+                    .parameters
+                    .iter()
+                    .any(|parameter| parameter.name == *name)
+                {
+                    self.parameters.push(RosZParameter {
+                        name: name.clone(),
+                        data_type: data_type.clone(),
+                        is_optional: path.contains_optional(),
+                    });
+                }
+            }
+            Field::RequiredInput {
+                data_type,
+                name,
+                path,
+                ..
+            } => {
+                self.subscriptions.push(Subscription {
+                    name: name.clone(),
+                    topic: topic_from_input_path(name, path)?,
+                    data_type: data_type.clone(),
+                    kind: SubscriptionKind::Required,
+                });
+            }
+            Field::HardwareInterface { name }
+            | Field::HistoricInput { name, .. }
+            | Field::PerceptionInput { name, .. } => {
+                return Err(ParseError::new_spanned(
+                    name,
+                    format!("`{name}` has no ros-z equivalent, port this node by hand"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn topic_from_path(name: &Ident, path: &Path) -> Result<String, ParseError> {
+    if path.contains_variable() {
+        return Err(ParseError::new_spanned(
+            name,
+            format!("`{name}` uses a cycler instance variable, which cannot be mapped to a topic"),
+        ));
+    }
+    Ok(path.to_segments().join("/"))
+}
+
+/// Inputs subscribe to whole main outputs, so only paths naming a main output directly can be
+/// mapped to a topic.
+fn topic_from_input_path(name: &Ident, path: &Path) -> Result<String, ParseError> {
+    if path.segments.len() != 1 {
+        return Err(ParseError::new_spanned(
+            name,
+            format!("`{name}` reads into a main output, subscribe to the whole output instead"),
+        ));
+    }
+    topic_from_path(name, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_str;
+
+    use crate::contexts::Contexts;
+
+    use super::*;
+
+    fn node_from_source(source: &str) -> Node {
+        let file = parse_str(source).unwrap();
+        Node {
+            name: "ExampleNode".to_string(),
+            module: parse_str("example::example_node").unwrap(),
+            file_path: "example/src/example_node.rs".into(),
+            contexts: Contexts::try_from_file(&file).unwrap(),
+        }
+    }
+
+    #[test]
+    fn context_fields_are_mapped_to_endpoints() {
+        let node = node_from_source(
+            r#"
+            #[context]
+            pub struct CreationContext {
+                field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+            }
+
+            #[context]
+            pub struct CycleContext {
+                ball: Input<Option<BallState>, "ball_state?">,
+                cycle_time: RequiredInput<Option<CycleTime>, "cycle_time?">,
+                obstacles: Input<Vec<Obstacle>, "obstacles">,
+                field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+                heatmap: AdditionalOutput<Heatmap, "ball_search.heatmap">,
+            }
+
+            #[context]
+            pub struct MainOutputs {
+                pub position_of_interest: MainOutput<Point2<Ground>>,
+            }
+            "#,
+        );
+
+        let ros_z_node = RosZNode::try_from_node(&node).unwrap();
+
+        assert_eq!(ros_z_node.name, "example_node");
+        assert_eq!(ros_z_node.parameters.len(), 1);
+        let subscriptions: Vec<_> = ros_z_node
+            .subscriptions
+            .iter()
+            .map(|subscription| (subscription.topic.as_str(), subscription.kind))
+            .collect();
+        assert_eq!(
+            subscriptions,
+            [
+                ("ball_state", SubscriptionKind::Optional),
+                ("cycle_time", SubscriptionKind::Required),
+                ("obstacles", SubscriptionKind::Plain),
+            ]
+        );
+        let publications: Vec<_> = ros_z_node
+            .publications
+            .iter()
+            .map(|publication| (publication.topic.as_str(), publication.kind))
+            .collect();
+        assert_eq!(
+            publications,
+            [
+                (
+                    "example_node/ball_search/heatmap",
+                    PublicationKind::Additional
+                ),
+                ("position_of_interest", PublicationKind::Main),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_input_paths_are_rejected() {
+        let node = node_from_source(
+            r#"
+            #[context]
+            pub struct CreationContext {}
+
+            #[context]
+            pub struct CycleContext {
+                start_time: Input<SystemTime, "cycle_time.start_time">,
+            }
+
+            #[context]
+            pub struct MainOutputs {}
+            "#,
+        );
+
+        assert!(RosZNode::try_from_node(&node).is_err());
+    }
+}
@@ -0,0 +1,542 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs::read_dir,
+    mem::replace,
+    path::{Path, PathBuf},
+};
+
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use syn::{Expr, ExprMethodCall, GenericArgument, Ident, Item, Lit, visit::Visit};
+use thiserror::Error;
+
+use crate::{
+    error::{Error, ParseError},
+    node::parse_rust_file,
+    to_absolute::ToAbsolute,
+    uses::{Uses, uses_from_items},
+};
+
+/// All topics of the ros-z node crates together with the nodes publishing and subscribing them.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TopicGraph {
+    pub topics: BTreeMap<String, Topic>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Topic {
+    pub publishers: Vec<Endpoint>,
+    pub subscriptions: Vec<Endpoint>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Endpoint {
+    pub node: String,
+    pub data_type: String,
+    pub kind: EndpointKind,
+    pub file_path: PathBuf,
+    pub line: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointKind {
+    Publisher,
+    AnnouncingPublisher,
+    Subscriber,
+    Cache,
+    FutureSubscriber,
+}
+
+impl EndpointKind {
+    fn from_method(method: &Ident, is_cached: bool) -> Option<Self> {
+        match method.to_string().as_str() {
+            "publisher" => Some(Self::Publisher),
+            "announcing_publisher" => Some(Self::AnnouncingPublisher),
+            "subscriber" if is_cached => Some(Self::Cache),
+            "subscriber" => Some(Self::Subscriber),
+            "create_future_subscriber" => Some(Self::FutureSubscriber),
+            _ => None,
+        }
+    }
+
+    fn is_publisher(self) -> bool {
+        matches!(self, Self::Publisher | Self::AnnouncingPublisher)
+    }
+}
+
+/// Exceptions to the topic graph checks.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TopicGraphRules {
+    /// Topics produced outside of the node crates, e.g. by tooling or the simulator.
+    #[serde(default)]
+    pub external_topics: BTreeSet<String>,
+    /// Topics multiple nodes intentionally publish to.
+    #[serde(default)]
+    pub shared_topics: BTreeSet<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Violation {
+    #[error("`{topic}` is used with different types: {}", format_data_types(.data_types))]
+    TypeMismatch {
+        topic: String,
+        data_types: BTreeMap<String, BTreeSet<String>>,
+    },
+    #[error("`{node}` subscribes to `{topic}`, but it is never published")]
+    OrphanedSubscription { topic: String, node: String },
+    #[error("`{topic}` is published more than once: {}", .nodes.join(", "))]
+    DuplicatePublishers { topic: String, nodes: Vec<String> },
+    #[error("`{node}` awaits `{topic}` in a future map, but `{publisher}` does not announce it")]
+    UnannouncedFutureSubscription {
+        topic: String,
+        node: String,
+        publisher: String,
+    },
+}
+
+fn format_data_types(data_types: &BTreeMap<String, BTreeSet<String>>) -> String {
+    data_types
+        .iter()
+        .map(|(data_type, nodes)| {
+            let nodes: Vec<_> = nodes.iter().map(String::as_str).collect();
+            format!("`{data_type}` ({})", nodes.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl TopicGraph {
+    /// Collects the endpoints of every node crate in `nodes_directory`, e.g. `crates/nodes`. The
+    /// name of a node is the name of its crate directory, which is also assumed to be the crate
+    /// name when resolving types the node defines itself.
+    pub fn try_from_nodes_directory(nodes_directory: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut graph = Self::default();
+        for crate_directory in sorted_directory_entries(nodes_directory.as_ref())? {
+            let source_directory = crate_directory.join("src");
+            if !source_directory.is_dir() {
+                continue;
+            }
+            let node = crate_directory
+                .file_name()
+                .expect("directory entries always have a file name")
+                .to_string_lossy()
+                .into_owned();
+            for file_path in rust_files(&source_directory)? {
+                let module = module_path(&node, &source_directory, &file_path);
+                graph.add_file(&node, &module, &file_path)?;
+            }
+        }
+        Ok(graph)
+    }
+
+    fn add_file(&mut self, node: &str, module: &[String], file_path: &Path) -> Result<(), Error> {
+        let file = parse_rust_file(file_path)?;
+        let endpoints = endpoints_from_file(node, module, file_path, &file).map_err(|source| {
+            Error::RustParse {
+                source,
+                path: file_path.to_path_buf(),
+            }
+        })?;
+        for (topic, endpoint) in endpoints {
+            self.add_endpoint(topic, endpoint);
+        }
+        Ok(())
+    }
+
+    fn add_endpoint(&mut self, topic: String, endpoint: Endpoint) {
+        let topic = self.topics.entry(topic).or_default();
+        if endpoint.kind.is_publisher() {
+            topic.publishers.push(endpoint);
+        } else {
+            topic.subscriptions.push(endpoint);
+        }
+    }
+
+    pub fn check(&self, rules: &TopicGraphRules) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (name, topic) in &self.topics {
+            let mut data_types: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+            for endpoint in topic.publishers.iter().chain(&topic.subscriptions) {
+                data_types
+                    .entry(endpoint.data_type.clone())
+                    .or_default()
+                    .insert(endpoint.node.clone());
+            }
+            if data_types.len() > 1 {
+                violations.push(Violation::TypeMismatch {
+                    topic: name.clone(),
+                    data_types,
+                });
+            }
+
+            if topic.publishers.is_empty() && !rules.external_topics.contains(name) {
+                violations.extend(topic.subscriptions.iter().map(|subscription| {
+                    Violation::OrphanedSubscription {
+                        topic: name.clone(),
+                        node: subscription.node.clone(),
+                    }
+                }));
+            }
+
+            if topic.publishers.len() > 1 && !rules.shared_topics.contains(name) {
+                violations.push(Violation::DuplicatePublishers {
+                    topic: name.clone(),
+                    nodes: topic
+                        .publishers
+                        .iter()
+                        .map(|publisher| publisher.node.clone())
+                        .collect(),
+                });
+            }
+
+            for subscription in &topic.subscriptions {
+                if subscription.kind != EndpointKind::FutureSubscriber {
+                    continue;
+                }
+                violations.extend(
+                    topic
+                        .publishers
+                        .iter()
+                        .filter(|publisher| publisher.kind != EndpointKind::AnnouncingPublisher)
+                        .map(|publisher| Violation::UnannouncedFutureSubscription {
+                            topic: name.clone(),
+                            node: subscription.node.clone(),
+                            publisher: publisher.node.clone(),
+                        }),
+                );
+            }
+        }
+        violations
+    }
+
+    /// Renders the graph in Graphviz format. Nodes are boxes, topics are ellipses labeled with
+    /// their type, and future subscriptions are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph topics {\n    rankdir=LR;\n    node [shape=box];\n");
+        let nodes: BTreeSet<_> = self
+            .topics
+            .values()
+            .flat_map(|topic| topic.publishers.iter().chain(&topic.subscriptions))
+            .map(|endpoint| endpoint.node.as_str())
+            .collect();
+        for node in nodes {
+            writeln!(dot, "    {node:?};").unwrap();
+        }
+        for (name, topic) in &self.topics {
+            let topic_id = format!("topic:{name}");
+            let data_types: BTreeSet<_> = topic
+                .publishers
+                .iter()
+                .chain(&topic.subscriptions)
+                .map(|endpoint| endpoint.data_type.as_str())
+                .collect();
+            let label = format!(
+                "{name}\n{}",
+                data_types.into_iter().collect::<Vec<_>>().join("\n")
+            );
+            writeln!(dot, "    {topic_id:?} [shape=ellipse, label={label:?}];").unwrap();
+            for publisher in &topic.publishers {
+                writeln!(dot, "    {:?} -> {topic_id:?};", publisher.node).unwrap();
+            }
+            for subscription in &topic.subscriptions {
+                let style = match subscription.kind {
+                    EndpointKind::FutureSubscriber => " [style=dashed]",
+                    _ => "",
+                };
+                writeln!(dot, "    {topic_id:?} -> {:?}{style};", subscription.node).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn sorted_directory_entries(directory: &Path) -> Result<Vec<PathBuf>, Error> {
+    let wrap_error = |source| Error::Io {
+        source,
+        path: directory.to_path_buf(),
+    };
+    let mut entries = read_dir(directory)
+        .map_err(wrap_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(wrap_error)?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn rust_files(directory: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for path in sorted_directory_entries(directory)? {
+        if path.is_dir() {
+            files.extend(rust_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Module path of a source file, e.g. `["localization", "filter"]` for `src/filter.rs`.
+fn module_path(crate_name: &str, source_directory: &Path, file_path: &Path) -> Vec<String> {
+    let relative_path = file_path
+        .strip_prefix(source_directory)
+        .expect("source files are collected from the source directory");
+    let mut module = vec![crate_name.to_string()];
+    for component in relative_path.with_extension("").components() {
+        let component = component.as_os_str().to_string_lossy();
+        if component != "lib" && component != "mod" {
+            module.push(component.into_owned());
+        }
+    }
+    module
+}
+
+/// Types defined in the file itself are referred to by their full path from other crates, so they
+/// are resolved like imported ones.
+fn uses_with_local_types(module: &[String], items: &[Item]) -> Uses {
+    let mut uses = uses_from_items(items);
+    for item in items {
+        let identifier = match item {
+            Item::Enum(item) => &item.ident,
+            Item::Struct(item) => &item.ident,
+            Item::Type(item) => &item.ident,
+            _ => continue,
+        };
+        let path = module
+            .iter()
+            .map(|segment| Ident::new(segment, identifier.span()))
+            .chain([identifier.clone()])
+            .collect();
+        uses.insert(identifier.clone(), path);
+    }
+    uses
+}
+
+fn endpoints_from_file(
+    node: &str,
+    module: &[String],
+    file_path: &Path,
+    file: &syn::File,
+) -> Result<Vec<(String, Endpoint)>, ParseError> {
+    let mut collector = EndpointCollector {
+        node,
+        file_path,
+        uses: uses_with_local_types(module, &file.items),
+        constants: string_constants(&file.items),
+        is_cached: false,
+        endpoints: Vec::new(),
+        error: None,
+    };
+    collector.visit_file(file);
+    match collector.error {
+        Some(error) => Err(error),
+        None => Ok(collector.endpoints),
+    }
+}
+
+fn string_constants(items: &[Item]) -> BTreeMap<Ident, String> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Const(constant) => match &*constant.expr {
+                Expr::Lit(literal) => match &literal.lit {
+                    Lit::Str(string) => Some((constant.ident.clone(), string.value())),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+struct EndpointCollector<'a> {
+    node: &'a str,
+    file_path: &'a Path,
+    uses: Uses,
+    constants: BTreeMap<Ident, String>,
+    is_cached: bool,
+    endpoints: Vec<(String, Endpoint)>,
+    error: Option<ParseError>,
+}
+
+impl EndpointCollector<'_> {
+    fn collect(&mut self, call: &ExprMethodCall, kind: EndpointKind) -> Result<(), ParseError> {
+        let Some(turbofish) = &call.turbofish else {
+            return Ok(());
+        };
+        let mut arguments = turbofish.args.iter();
+        let (Some(GenericArgument::Type(data_type)), None) = (arguments.next(), arguments.next())
+        else {
+            return Err(ParseError::new_spanned(
+                turbofish,
+                "expected exactly one message type",
+            ));
+        };
+        let topic = match call.args.first() {
+            Some(Expr::Lit(literal)) => match &literal.lit {
+                Lit::Str(string) => Some(string.value()),
+                _ => None,
+            },
+            Some(Expr::Path(path)) => path
+                .path
+                .get_ident()
+                .and_then(|identifier| self.constants.get(identifier))
+                .cloned(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            ParseError::new_spanned(
+                &call.args,
+                "topic has to be a string literal or a string constant of this file",
+            )
+        })?;
+        self.endpoints.push((
+            topic,
+            Endpoint {
+                node: self.node.to_string(),
+                data_type: data_type
+                    .to_absolute(&self.uses)
+                    .to_token_stream()
+                    .to_string(),
+                kind,
+                file_path: self.file_path.to_path_buf(),
+                line: call.method.span().start().line,
+            },
+        ));
+        Ok(())
+    }
+}
+
+impl<'ast> Visit<'ast> for EndpointCollector<'_> {
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        // `.cache(...)` is chained after `.subscriber::<T>(...)`, so it is seen first while
+        // descending into the receivers
+        let is_cached = replace(&mut self.is_cached, false);
+        if let Some(kind) = EndpointKind::from_method(&call.method, is_cached) {
+            if let Err(error) = self.collect(call, kind) {
+                self.error.get_or_insert(error);
+            }
+        }
+        for argument in &call.args {
+            self.visit_expr(argument);
+        }
+        self.is_cached = is_cached || call.method == "cache";
+        self.visit_expr(&call.receiver);
+        self.is_cached = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_str;
+
+    use super::*;
+
+    fn graph_from_sources(sources: &[(&str, &str)]) -> TopicGraph {
+        let mut graph = TopicGraph::default();
+        for (node, source) in sources {
+            let file = parse_str(source).unwrap();
+            for (topic, endpoint) in
+                endpoints_from_file(node, &[node.to_string()], Path::new(node), &file).unwrap()
+            {
+                graph.add_endpoint(topic, endpoint);
+            }
+        }
+        graph
+    }
+
+    #[test]
+    fn endpoints_are_collected_with_absolute_types() {
+        let graph = graph_from_sources(&[(
+            "world_state_composer",
+            r#"
+            use types::world_state::BallState;
+
+            const ROLE_TOPIC: &str = "role";
+
+            async fn run() {
+                let role = node.subscriber::<Role>(ROLE_TOPIC).cache(1).build().await?;
+                let inputs = node
+                    .create_future_map_builder()
+                    .create_future_subscriber::<Option<BallState>>("ball_state", lag)
+                    .await?
+                    .build();
+                let world_state = node.announcing_publisher::<WorldState>("world_state").await?;
+            }
+            "#,
+        )]);
+
+        let role = &graph.topics["role"].subscriptions[0];
+        assert_eq!(role.kind, EndpointKind::Cache);
+        let ball_state = &graph.topics["ball_state"].subscriptions[0];
+        assert_eq!(ball_state.kind, EndpointKind::FutureSubscriber);
+        assert_eq!(
+            ball_state.data_type,
+            quote::quote!(Option<types::world_state::BallState>).to_string()
+        );
+        assert_eq!(
+            graph.topics["world_state"].publishers[0].kind,
+            EndpointKind::AnnouncingPublisher
+        );
+    }
+
+    #[test]
+    fn inconsistent_topics_are_reported() {
+        let graph = graph_from_sources(&[
+            (
+                "producer",
+                r#"
+                async fn run() {
+                    node.publisher::<Option<BallState>>("ball_state").build().await?;
+                    node.publisher::<Role>("role").build().await?;
+                }
+                "#,
+            ),
+            (
+                "other_producer",
+                r#"
+                async fn run() {
+                    node.publisher::<Role>("role").build().await?;
+                }
+                "#,
+            ),
+            (
+                "consumer",
+                r#"
+                async fn run() {
+                    node.subscriber::<BallState>("ball_state").build().await?;
+                    node.subscriber::<Obstacles>("obstacles").build().await?;
+                    node.create_future_map_builder()
+                        .create_future_subscriber::<Role>("role", lag)
+                        .await?
+                        .build();
+                }
+                "#,
+            ),
+        ]);
+
+        let violations = graph.check(&TopicGraphRules::default());
+
+        assert!(matches!(
+            violations[..],
+            [
+                Violation::TypeMismatch { .. },
+                Violation::OrphanedSubscription { .. },
+                Violation::DuplicatePublishers { .. },
+                Violation::UnannouncedFutureSubscription { .. },
+                Violation::UnannouncedFutureSubscription { .. },
+            ]
+        ));
+
+        let rules = TopicGraphRules {
+            external_topics: BTreeSet::from(["obstacles".to_string()]),
+            shared_topics: BTreeSet::from(["role".to_string()]),
+        };
+        assert_eq!(graph.check(&rules).len(), 3);
+    }
+}
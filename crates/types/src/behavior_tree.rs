@@ -1,13 +1,18 @@
-use path_serde::{PathIntrospect, PathSerialize};
+use std::time::Duration;
+
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use ros_z::Message;
 use serde::{Deserialize, Serialize};
 
+use crate::motion_type::MotionType;
+
 #[derive(
     PartialEq, Debug, Clone, Serialize, Deserialize, PathSerialize, PathIntrospect, Message,
 )]
 pub enum Status {
     Success,
     Failure,
+    Running,
     Idle,
 }
 
@@ -15,5 +20,77 @@ pub enum Status {
 pub struct NodeTrace {
     pub name: String,
     pub status: Status,
+    /// Position of this node among the children of its parent, children that were not ticked are
+    /// missing in a trace
+    pub child_index: usize,
+    /// Time spent ticking this node including its children
+    pub duration: Duration,
     pub children: Vec<NodeTrace>,
 }
+
+/// Data description of a behavior tree. Leaves refer to actions, conditions and subtrees by the
+/// name they are registered with in the behavior node.
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    Serialize,
+    PartialEq,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    Message,
+)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum NodeDescription {
+    Action {
+        name: String,
+        #[serde(default)]
+        arguments: Vec<String>,
+    },
+    Condition {
+        name: String,
+        #[serde(default)]
+        arguments: Vec<String>,
+    },
+    Subtree {
+        name: String,
+    },
+    Failure,
+    Negation {
+        child: Box<NodeDescription>,
+    },
+    Selection {
+        children: Vec<NodeDescription>,
+    },
+    Sequence {
+        children: Vec<NodeDescription>,
+    },
+    MemorySelection {
+        children: Vec<NodeDescription>,
+    },
+    MemorySequence {
+        children: Vec<NodeDescription>,
+    },
+    Timeout {
+        timeout: Duration,
+        child: Box<NodeDescription>,
+    },
+    Cooldown {
+        cooldown: Duration,
+        child: Box<NodeDescription>,
+    },
+    Retry {
+        attempts: usize,
+        child: Box<NodeDescription>,
+    },
+    RateLimit {
+        interval: Duration,
+        child: Box<NodeDescription>,
+    },
+    SwitchMotionType {
+        motion_type: MotionType,
+        action: Box<NodeDescription>,
+        alternatives: Box<NodeDescription>,
+    },
+}
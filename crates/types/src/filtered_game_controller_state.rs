@@ -1,6 +1,6 @@
 use std::{collections::HashMap, time::Duration};
 
-use hsl_network_messages::{GamePhase, Penalty, PlayerNumber, SubState, Team};
+use hsl_network_messages::{GamePhase, Half, Penalty, PlayerNumber, SubState, Team};
 use path_serde::{PathIntrospect, PathSerialize};
 use ros_z::Message;
 use serde::{Deserialize, Serialize};
@@ -15,7 +15,10 @@ use crate::{
 pub struct FilteredGameControllerState {
     pub game_state: FilteredGameState,
     pub opponent_game_state: FilteredGameState,
+    pub half: Half,
     pub remaining_time_in_half: Duration,
+    /// Remaining time of the current set play, ready or kick-off phase
+    pub secondary_time: Duration,
     pub game_phase: GamePhase,
     pub kicking_team: Option<Team>,
     pub penalties: Players<Option<Penalty>>,
@@ -32,7 +35,9 @@ impl Default for FilteredGameControllerState {
         Self {
             game_state: Default::default(),
             opponent_game_state: Default::default(),
+            half: Half::First,
             remaining_time_in_half: Duration::ZERO,
+            secondary_time: Duration::ZERO,
             game_phase: Default::default(),
             kicking_team: Default::default(),
             penalties: Default::default(),
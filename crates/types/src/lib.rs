@@ -31,6 +31,7 @@ pub mod last_filtered_game_controller_state_change;
 pub mod line_data;
 pub mod localization;
 pub mod message_event;
+pub mod message_scheduler;
 pub mod messages;
 pub mod motion_command;
 pub mod motion_runtime;
@@ -49,6 +50,7 @@ pub mod point_of_interest;
 pub mod pose_detection;
 pub mod pose_kinds;
 pub mod primary_state;
+pub mod roles;
 pub mod rule_obstacles;
 pub mod samples;
 pub mod step;
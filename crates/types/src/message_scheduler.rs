@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use hsl_network_messages::Half;
+use ros_z::time::Time;
+
+use crate::{
+    filtered_game_controller_state::FilteredGameControllerState, parameters::HslNetworkParameters,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessagePriority {
+    /// Periodic updates, spread over the remaining game time.
+    Regular,
+    /// Content teammates need right away, e.g. striker claims or fresh ball sightings.
+    Urgent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MessageBudget {
+    pub remaining_messages: u16,
+    pub remaining_game_time: Duration,
+    pub number_of_senders: usize,
+}
+
+impl MessageBudget {
+    pub fn new(
+        game_controller_state: &FilteredGameControllerState,
+        number_of_senders: usize,
+        half_duration: Duration,
+    ) -> Self {
+        let remaining_time_in_later_halves = match game_controller_state.half {
+            Half::First => half_duration,
+            Half::Second => Duration::ZERO,
+        };
+        Self {
+            remaining_messages: game_controller_state.remaining_number_of_messages,
+            remaining_game_time: game_controller_state.remaining_time_in_half
+                + remaining_time_in_later_halves,
+            number_of_senders: number_of_senders.max(1),
+        }
+    }
+
+    /// Interval at which every sender may send so that the team keeps `reserve` messages until the
+    /// end of the game.
+    pub fn fair_interval(&self, reserve: u16) -> Duration {
+        let usable_messages = self.remaining_messages.saturating_sub(reserve);
+        if usable_messages == 0 {
+            return Duration::MAX;
+        }
+        self.remaining_game_time
+            .mul_f64(self.number_of_senders as f64 / f64::from(usable_messages))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MessageScheduler {
+    last_sent: Option<Time>,
+}
+
+impl MessageScheduler {
+    pub fn is_due(
+        &self,
+        now: Time,
+        priority: MessagePriority,
+        budget: &MessageBudget,
+        parameters: &HslNetworkParameters,
+    ) -> bool {
+        let (reserve, interval) = match priority {
+            MessagePriority::Regular => {
+                let reserve = parameters.remaining_amount_of_messages_to_stop_sending;
+                (
+                    reserve,
+                    budget
+                        .fair_interval(reserve)
+                        .max(parameters.hsl_state_message_send_interval),
+                )
+            }
+            MessagePriority::Urgent => (
+                parameters.remaining_amount_of_messages_to_stop_sending_urgent,
+                parameters.silence_interval_between_messages,
+            ),
+        };
+
+        budget.remaining_messages >= reserve
+            && self
+                .last_sent
+                .is_none_or(|last_sent| now.duration_since(last_sent) > interval)
+    }
+
+    pub fn mark_sent(&mut self, now: Time) {
+        self.last_sent = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> HslNetworkParameters {
+        HslNetworkParameters {
+            remaining_amount_of_messages_to_stop_sending: 20,
+            remaining_amount_of_messages_to_stop_sending_urgent: 5,
+            silence_interval_between_messages: Duration::from_secs(1),
+            hsl_state_message_send_interval: Duration::from_secs(2),
+            half_duration: Duration::from_secs(600),
+            ..Default::default()
+        }
+    }
+
+    fn budget(remaining_messages: u16) -> MessageBudget {
+        MessageBudget {
+            remaining_messages,
+            remaining_game_time: Duration::from_secs(1200),
+            number_of_senders: 5,
+        }
+    }
+
+    #[test]
+    fn budget_is_spread_over_remaining_game_time() {
+        let game_controller_state = FilteredGameControllerState {
+            half: Half::First,
+            remaining_time_in_half: Duration::from_secs(300),
+            remaining_number_of_messages: 920,
+            ..Default::default()
+        };
+
+        let budget = MessageBudget::new(&game_controller_state, 4, Duration::from_secs(600));
+
+        assert_eq!(budget.remaining_game_time, Duration::from_secs(900));
+        assert_eq!(budget.fair_interval(20), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn regular_messages_wait_for_fair_interval() {
+        let parameters = parameters();
+        let budget = budget(1220);
+        let mut scheduler = MessageScheduler::default();
+        let now = Time::zero() + Duration::from_secs(10);
+
+        assert!(scheduler.is_due(now, MessagePriority::Regular, &budget, &parameters));
+        scheduler.mark_sent(now);
+
+        let fair_interval = budget.fair_interval(20);
+        assert_eq!(fair_interval, Duration::from_secs(5));
+        assert!(!scheduler.is_due(
+            now + Duration::from_secs(4),
+            MessagePriority::Regular,
+            &budget,
+            &parameters
+        ));
+        assert!(scheduler.is_due(
+            now + Duration::from_secs(6),
+            MessagePriority::Regular,
+            &budget,
+            &parameters
+        ));
+    }
+
+    #[test]
+    fn urgent_messages_only_respect_silence_interval() {
+        let parameters = parameters();
+        let budget = budget(1220);
+        let mut scheduler = MessageScheduler::default();
+        let now = Time::zero() + Duration::from_secs(10);
+        scheduler.mark_sent(now);
+
+        assert!(!scheduler.is_due(
+            now + Duration::from_millis(500),
+            MessagePriority::Urgent,
+            &budget,
+            &parameters
+        ));
+        assert!(scheduler.is_due(
+            now + Duration::from_millis(1500),
+            MessagePriority::Urgent,
+            &budget,
+            &parameters
+        ));
+    }
+
+    #[test]
+    fn urgent_messages_may_use_reserve() {
+        let parameters = parameters();
+        let budget = budget(10);
+        let scheduler = MessageScheduler::default();
+        let now = Time::zero();
+
+        assert!(!scheduler.is_due(now, MessagePriority::Regular, &budget, &parameters));
+        assert!(scheduler.is_due(now, MessagePriority::Urgent, &budget, &parameters));
+    }
+}
@@ -1,6 +1,5 @@
 use std::{ops::Range, path::PathBuf, time::Duration};
 
-use hsl_network_messages::PlayerNumber;
 use kinematics::joints::{Joints, head::HeadJoints};
 use ros_z::Message;
 use serde::{Deserialize, Serialize};
@@ -10,7 +9,8 @@ use linear_algebra::{Framed, Point2, Vector2, Vector3};
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 
 use crate::{
-    field_color::FieldColorParameters, motion_command::MotionCommand, players::Players, step::Step,
+    behavior_tree::NodeDescription, field_color::FieldColorParameters,
+    motion_command::MotionCommand, players::Players, step::Step,
 };
 
 #[derive(
@@ -79,7 +79,6 @@ pub struct VoronoiParameters {
 pub struct BehaviorParameters {
     pub allow_switch: AllowSwitchParameters,
     pub injected_motion_command: Option<MotionCommand>,
-    pub goal_keeper_number: PlayerNumber,
     pub last_ball_timeout: Duration,
     pub lost_ball: LostBallParameters,
     pub path_planning: PathPlanningParameters,
@@ -92,13 +91,20 @@ pub struct BehaviorParameters {
     pub walk_with_velocity: WalkWithVelocityParameters,
     pub maximum_lookaround_duration: Duration,
     pub kicking: KickingParameters,
+    pub dribbling: DribblingParameters,
+    pub goalkeeper: GoalkeeperParameters,
     pub walk_speed: WalkSpeedParameters,
     pub intercept_ball: InterceptBallParameters,
     pub substates: SubstatesParameters,
+    pub set_play: SetPlayParameters,
+    pub utility: UtilityParameters,
+    pub trace: BehaviorTraceParameters,
     pub voronoi: VoronoiParameters,
     pub hsl_network: HslNetworkParameters,
     pub closest_to_ball_enter_duration: Duration,
     pub closest_to_ball_exit_duration: Duration,
+    /// Replaces the built-in tree when set, see `behavior_node::registry` for the available names
+    pub tree: Option<NodeDescription>,
 }
 
 #[derive(
@@ -371,9 +377,11 @@ pub struct HeadMotionParameters {
 pub struct HslNetworkParameters {
     pub game_controller_return_message_interval: Duration,
     pub remaining_amount_of_messages_to_stop_sending: u16,
+    pub remaining_amount_of_messages_to_stop_sending_urgent: u16,
     pub silence_interval_between_messages: Duration,
     pub hsl_striker_message_receive_timeout: Duration,
     pub hsl_state_message_send_interval: Duration,
+    pub half_duration: Duration,
 }
 
 #[derive(
@@ -543,6 +551,12 @@ pub struct SearchSuggestorParameters {
     pub tile_switch_hysteresis: f32,
     pub decay_distance_factor: f32,
     pub heatmap_decay_range: Range<f32>,
+    /// Duration for which a cell looked at without seeing the ball is shared with the team
+    pub searched_area_retention: Duration,
+    /// Factor by which the heat is reduced in cells a teammate reports as searched
+    pub team_search_decay: f32,
+    pub number_of_search_candidates: usize,
+    pub search_candidate_spacing: f32,
 }
 
 #[derive(
@@ -745,6 +759,97 @@ pub struct KickingParameters {
     pub kick_target_offset_angle: f32,
     pub target_distance_kick_power_threshold: f32,
     pub kick_position_ball_distance: f32,
+    pub target_selection: KickTargetSelectionParameters,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct KickTargetSelectionParameters {
+    /// Distance the ball rolls after a Rumpelstilzchen kick
+    pub rumpelstilzchen_range: f32,
+    /// Distance the ball rolls after a Schlong kick
+    pub schlong_range: f32,
+    /// Number of targets sampled between the inner edges of the opponent goal posts
+    pub number_of_goal_samples: usize,
+    /// Standard deviation of the kick direction in radians
+    pub kick_direction_deviation: f32,
+    /// Teammates closer to the ball than this are not considered as pass targets
+    pub minimum_pass_distance: f32,
+    /// Value of a scored goal, passes are valued by the field position they reach in `[0, 1]`
+    pub goal_value: f32,
+    /// Factor applied to the value of passes to prefer shooting over equally good passes
+    pub pass_value_factor: f32,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct DribblingParameters {
+    /// Distance behind the ball at which the robot aligns before pushing
+    pub approach_distance: f32,
+    /// Maximum distance between the robot and the line the ball is pushed along
+    pub lateral_tolerance: f32,
+    pub alignment_angle_threshold: f32,
+    pub push_speed: f32,
+    /// Maximum distance to the ball while pushing, the robot re-approaches the ball beyond it
+    pub ball_loss_distance: f32,
+    /// Opponents closer to the ball than this make the striker dribble instead of kick
+    pub pressure_distance: f32,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct GoalkeeperParameters {
+    /// Radius of the arc around the own goal center on which the goalkeeper positions itself
+    pub blocking_arc_radius: f32,
+    /// Maximum angle between the blocking position and the field's x-axis as seen from the goal
+    pub maximum_arc_angle: f32,
+    /// Constant deceleration of a rolling ball used to predict shots
+    pub ball_deceleration: f32,
+    /// Balls rolling slower than this are not considered shots
+    pub minimum_shot_velocity: f32,
+    /// Number of standard deviations of the predicted crossing point the goalkeeper has to cover
+    pub uncertainty_factor: f32,
+    pub step_speed: f32,
+    /// Lateral distance covered by the goalkeeper's body when diving, shots further away than the
+    /// reach plus the dive movement are not dived for
+    pub dive_reach: f32,
+    /// Lateral velocity of a dive, must not exceed the left walk limit of the motion arbitration
+    pub dive_speed: f32,
+    /// Lateral acceleration of a dive, must not exceed the left walk limit of the motion
+    /// arbitration
+    pub dive_acceleration: f32,
+    /// Slow balls closer to the own goal center than this are cleared
+    pub clearing_distance: f32,
+    /// Distance in front of the goal line the goalkeeper stands on during penalty shootouts
+    pub goal_line_offset: f32,
+    pub penalty_shot_direction: PenaltyShotDirectionParameters,
 }
 
 #[derive(
@@ -818,6 +923,122 @@ pub struct SubstatesParameters {
     pub corner_kick_blocking_angle: f32,
 }
 
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct SetPlayParameters {
+    /// The kicker of the kicking team waits at the ball until the remaining set play time falls
+    /// below this, giving the receivers time to get into position
+    pub execution_remaining_time: Duration,
+    pub maximum_wall_size: usize,
+    /// Distance between neighbouring robots in the wall
+    pub wall_spacing: f32,
+    /// Additional distance of the wall to the ball beyond the distance required by the rules
+    pub wall_distance_offset: f32,
+    pub minimum_pass_receiving_distance: f32,
+    pub maximum_pass_receiving_distance: f32,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct UtilityParameters {
+    /// Added to the score of the currently selected behavior to avoid oscillating decisions
+    pub commitment_bonus: f32,
+    /// Curve over the distance of the robot to the trajectory of a ball rolling towards it
+    pub intercept: UtilityCurveParameters,
+    /// Curve over the own distance to the ball minus the smallest distance of a teammate
+    pub kick: UtilityCurveParameters,
+    /// Curve over the distance of the closest opponent to the ball, scaled by the kick curve
+    pub dribble: UtilityCurveParameters,
+    /// Curve over the own distance to the ball minus the smallest distance of a teammate
+    pub support: UtilityCurveParameters,
+    /// Curve over the distance of the ball to the own goal, scaled by the support curve
+    pub block: UtilityCurveParameters,
+    /// Curve over the time in seconds since the ball was last seen
+    pub search: UtilityCurveParameters,
+}
+
+/// Logistic curve `weight / (1 + exp(-steepness * (input - midpoint)))`. A negative steepness
+/// prefers small inputs.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct UtilityCurveParameters {
+    pub weight: f32,
+    pub midpoint: f32,
+    pub steepness: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Message)]
+pub enum UtilityCandidate {
+    Intercept,
+    Kick,
+    Dribble,
+    Support,
+    Block,
+    Search,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Message)]
+pub struct UtilityScore {
+    pub candidate: UtilityCandidate,
+    /// Input of the candidate's curve, `None` if the candidate is not applicable
+    pub input: Option<f32>,
+    pub score: f32,
+}
+
+/// Scores of all candidates evaluated with [`UtilityParameters`] in one behavior tick
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Message)]
+pub struct UtilityTable {
+    pub scores: Vec<UtilityScore>,
+    pub selected: Option<UtilityCandidate>,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub struct BehaviorTraceParameters {
+    /// Number of ticks kept in the trace history
+    pub history_length: usize,
+    /// Evaluation times of nodes whose status did not change are only reported again after
+    /// changing by more than this
+    pub minimum_duration_change: Duration,
+}
+
 #[derive(
     Clone,
     Debug,
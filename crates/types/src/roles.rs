@@ -0,0 +1,29 @@
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Hash,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+    ros_z::Message,
+)]
+pub enum Role {
+    Defender,
+    Keeper,
+    Loser,
+    Midfielder,
+    ReplacementKeeper,
+    #[default]
+    Searcher,
+    Striker,
+    StrikerSupporter,
+}
@@ -1,12 +1,13 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use booster::FallDownState;
-use hsl_network_messages::PlayerNumber;
+use hsl_network_messages::{PlayerNumber, SearchedArea};
 use ros_z::{Message, time::Time};
 use serde::{Deserialize, Serialize};
 
 use coordinate_systems::{Field, Ground};
 use linear_algebra::{Isometry2, Point2, Pose2, Vector2};
+use nalgebra::Matrix2;
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 
 use crate::{
@@ -16,6 +17,7 @@ use crate::{
     obstacles::Obstacle,
     players::Players,
     primary_state::PrimaryState,
+    roles::Role,
     rule_obstacles::RuleObstacle,
 };
 
@@ -34,6 +36,9 @@ pub struct WorldState {
     pub rule_obstacles: Vec<RuleObstacle>,
     pub fall_down_state: Option<FallDownState>,
     pub suggested_search_position: Option<Point2<Field>>,
+    /// Promising search positions, ordered by decreasing heat
+    pub search_candidates: Vec<Point2<Field>>,
+    pub searched_area: SearchedArea,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -52,6 +57,8 @@ impl Default for WorldState {
             rule_obstacles: Default::default(),
             fall_down_state: Default::default(),
             suggested_search_position: Default::default(),
+            search_candidates: Default::default(),
+            searched_area: Default::default(),
         }
     }
 }
@@ -72,6 +79,9 @@ pub struct BallState {
     pub ball_in_ground: Point2<Ground>,
     pub ball_in_field: Point2<Field>,
     pub ball_in_ground_velocity: Vector2<Ground>,
+    /// Covariance of `ball_in_ground` estimated by the ball filter, zero if unknown
+    #[path_serde(leaf)]
+    pub ball_in_ground_covariance: Matrix2<f32>,
     pub last_seen_ball: SystemTime,
     pub field_side: Side,
 }
@@ -99,6 +109,7 @@ impl Default for BallState {
             ball_in_ground: Point2::origin(),
             ball_in_field: Point2::origin(),
             ball_in_ground_velocity: Vector2::zeros(),
+            ball_in_ground_covariance: Matrix2::zeros(),
             last_seen_ball: UNIX_EPOCH,
             field_side: Side::Left,
         }
@@ -111,6 +122,7 @@ impl BallState {
             ball_in_field: Point2::origin(),
             ball_in_ground: ground_to_field.inverse() * Point2::origin(),
             ball_in_ground_velocity: Vector2::zeros(),
+            ball_in_ground_covariance: Matrix2::zeros(),
             last_seen_ball: UNIX_EPOCH,
             field_side: Side::Left,
         }
@@ -132,6 +144,7 @@ pub struct RobotState {
     pub ground_to_field: Option<Isometry2<Ground, Field>>,
     pub player_number: PlayerNumber,
     pub primary_state: PrimaryState,
+    pub role: Option<Role>,
 }
 
 #[derive(
@@ -163,6 +163,7 @@ fn create_ball_state(
         ball_in_ground,
         ball_in_field,
         ball_in_ground_velocity,
+        ball_in_ground_covariance: Default::default(),
         last_seen_ball,
         field_side,
     }
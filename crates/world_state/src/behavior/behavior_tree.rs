@@ -1,4 +1,4 @@
-use std::slice::from_ref;
+use std::{slice::from_ref, time::Duration};
 
 use serde::{Serialize, ser::SerializeStruct};
 use types::behavior_tree::{NodeTrace, Status};
@@ -48,6 +48,8 @@ impl<Blackboard> Node<Blackboard> {
         let mut trace = NodeTrace {
             name: name.to_string(),
             status: Status::Failure,
+            child_index: 0,
+            duration: Duration::ZERO,
             children: Vec::new(),
         };
 
@@ -72,8 +74,9 @@ impl<Blackboard> Node<Blackboard> {
             }
             Node::Selection { children, .. } => {
                 let mut selection_status = Status::Failure;
-                for child in children {
-                    let (child_status, child_trace) = child.tick_with_trace(blackboard);
+                for (index, child) in children.iter().enumerate() {
+                    let (child_status, mut child_trace) = child.tick_with_trace(blackboard);
+                    child_trace.child_index = index;
                     trace.children.push(child_trace);
 
                     if matches!(child_status, Status::Success) {
@@ -85,8 +88,9 @@ impl<Blackboard> Node<Blackboard> {
             }
             Node::Sequence { children, .. } => {
                 let mut sequence_status = Status::Success;
-                for child in children {
-                    let (child_status, child_trace) = child.tick_with_trace(blackboard);
+                for (index, child) in children.iter().enumerate() {
+                    let (child_status, mut child_trace) = child.tick_with_trace(blackboard);
+                    child_trace.child_index = index;
                     trace.children.push(child_trace);
 
                     if matches!(child_status, Status::Failure) {
@@ -119,9 +123,14 @@ impl<Blackboard> Node<Blackboard> {
         };
 
         let children = match self {
-            Node::Selection { children, .. } | Node::Sequence { children, .. } => {
-                children.iter().map(|c| c.static_layout_trace()).collect()
-            }
+            Node::Selection { children, .. } | Node::Sequence { children, .. } => children
+                .iter()
+                .enumerate()
+                .map(|(index, c)| NodeTrace {
+                    child_index: index,
+                    ..c.static_layout_trace()
+                })
+                .collect(),
             Node::Negation { child, .. } => vec![child.static_layout_trace()],
             Node::Subtree { tree, .. } => vec![tree.static_layout_trace()],
             _ => vec![],
@@ -130,6 +139,8 @@ impl<Blackboard> Node<Blackboard> {
         NodeTrace {
             name: name.to_string(),
             status: Status::Idle,
+            child_index: 0,
+            duration: Duration::ZERO,
             children,
         }
     }
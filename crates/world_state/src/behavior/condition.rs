@@ -156,7 +156,7 @@ pub fn is_fallen(blackboard: &mut Blackboard) -> bool {
 }
 
 pub fn is_goalkeeper(blackboard: &mut Blackboard) -> bool {
-    blackboard.world_state.robot.player_number == blackboard.parameters.goal_keeper_number
+    blackboard.world_state.robot.player_number == blackboard.goal_keeper_number
 }
 
 pub fn is_primary_state(blackboard: &mut Blackboard, primary_state: PrimaryState) -> bool {
@@ -7,7 +7,7 @@ use context_attribute::context;
 use coordinate_systems::{Field, Ground};
 use framework::{AdditionalOutput, MainOutput};
 use hardware::NetworkInterface;
-use hsl_network_messages::HulkMessage;
+use hsl_network_messages::{HulkMessage, PlayerNumber};
 use linear_algebra::{Point2, Pose2, Vector2};
 use serde::{Deserialize, Serialize};
 use types::{
@@ -78,6 +78,7 @@ pub struct Blackboard {
     pub field_dimensions: FieldDimensions,
     pub free_kick_obstacle_radius: f32,
     pub parameters: BehaviorParameters,
+    pub goal_keeper_number: PlayerNumber,
     pub world_state: WorldState,
 
     pub path_obstacles_output: Vec<PathObstacle>,
@@ -115,6 +116,7 @@ pub struct CycleContext {
 
     field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
     parameters: Parameter<BehaviorParameters, "behavior">,
+    goal_keeper_number: Parameter<PlayerNumber, "goal_keeper_number">,
     free_kick_obstacle_radius: Parameter<f32, "rule_obstacles.free_kick_obstacle_radius">,
 
     behavior_trace: AdditionalOutput<NodeTrace, "behavior.trace">,
@@ -192,6 +194,7 @@ impl Behavior {
             field_dimensions: *context.field_dimensions,
             free_kick_obstacle_radius: *context.free_kick_obstacle_radius,
             parameters: context.parameters.clone(),
+            goal_keeper_number: *context.goal_keeper_number,
             world_state: context.world_state.clone(),
 
             path_obstacles_output: Vec::new(),
@@ -103,6 +103,7 @@ impl Behavior {
             player_number: world_state.robot.player_number,
             pose,
             ball_position,
+            searched_area: Default::default(),
         });
 
         self.last_sent_hsl_message_time = Some(now);
@@ -130,7 +130,9 @@ impl GameControllerStateFilter {
         let filtered_game_controller_state = FilteredGameControllerState {
             game_state: game_states.own,
             opponent_game_state: game_states.opponent,
+            half: context.game_controller_state.half,
             remaining_time_in_half: context.game_controller_state.remaining_time_in_half,
+            secondary_time: context.game_controller_state.secondary_time,
             game_phase: context.game_controller_state.game_phase,
             kicking_team,
             penalties: context.game_controller_state.penalties,
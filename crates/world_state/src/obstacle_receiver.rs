@@ -49,6 +49,7 @@ impl ObstacleReceiver {
         for message in messages.clone() {
             let pose = match message {
                 HulkMessage::State(state_message) => state_message.pose,
+                HulkMessage::Striker(striker_message) => striker_message.pose,
             };
             let sender_position = context.ground_to_field.inverse() * pose.position();
             network_robot_obstacles.push(sender_position);
@@ -97,6 +97,7 @@ impl PlayerStatesReceiver {
                         }),
                     });
                 }
+                HulkMessage::Striker(_) => {}
             }
         }
         self.last_player_states = player_states;
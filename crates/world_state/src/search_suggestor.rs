@@ -9,7 +9,7 @@ use context_attribute::context;
 use coordinate_systems::{Field, Ground};
 use framework::{AdditionalOutput, MainOutput, PerceptionInput};
 use geometry::direction::{Direction, Rotate90Degrees};
-use hsl_network_messages::{HulkMessage, StateMessage, SubState, Team};
+use hsl_network_messages::{HulkMessage, StateMessage, StrikerMessage, SubState, Team};
 use itertools::Itertools;
 use linear_algebra::{Isometry2, Point2, Vector2, point, vector};
 use nalgebra::clamp;
@@ -257,6 +257,11 @@ impl Heatmap {
                     last_seen: Time::from_wallclock(time) - ball.age,
                 })
             }
+            HulkMessage::Striker(StrikerMessage { ball_position, .. }) => Some(BallPosition {
+                position: ball_position.position,
+                velocity: Vector2::zeros(),
+                last_seen: Time::from_wallclock(time) - ball_position.age,
+            }),
         };
         if let Some(ball_position) = ball {
             self[ball_position.position] = team_ball_weight;
@@ -62,6 +62,7 @@ impl WorldStateComposer {
             ground_to_field: context.ground_to_field.copied(),
             player_number: *context.player_number,
             primary_state: *context.primary_state,
+            role: None,
         };
 
         let world_state = WorldState {
@@ -77,6 +78,8 @@ impl WorldStateComposer {
             rule_ball: context.rule_ball.copied(),
             rule_obstacles: context.rule_obstacles.clone(),
             suggested_search_position: context.suggested_search_position.copied(),
+            search_candidates: Vec::new(),
+            searched_area: Default::default(),
         };
 
         Ok(MainOutputs {
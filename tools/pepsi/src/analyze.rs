@@ -1,14 +1,22 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
 
 use bat::{PagingMode, PrettyPrinter};
-use clap::Subcommand;
+use clap::{Subcommand, ValueEnum};
 use color_eyre::{
     Result,
-    eyre::{WrapErr, eyre},
+    eyre::{WrapErr, bail, eyre},
 };
 
 use repository::Repository;
-use source_analyzer::{contexts::Contexts, node::parse_rust_file, pretty::to_string_pretty};
+use source_analyzer::{
+    contexts::Contexts,
+    node::parse_rust_file,
+    pretty::to_string_pretty,
+    topic_graph::{TopicGraph, TopicGraphRules},
+};
 
 fn find_latest_file(path_pattern: impl AsRef<Path>) -> Result<PathBuf> {
     let matching_paths: Vec<_> = glob::glob(
@@ -48,6 +56,22 @@ pub enum Arguments {
         /// File name to dump (may contain wildcard characters usable by glob())
         file_name: String,
     },
+    /// Print the topics published and subscribed by all ros-z nodes
+    TopicGraph {
+        /// Output format of the graph
+        #[arg(long, value_enum, default_value_t = TopicGraphFormat::Json)]
+        format: TopicGraphFormat,
+        /// Fail on type mismatches, orphaned subscriptions and duplicate publishers instead of
+        /// printing the graph
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TopicGraphFormat {
+    Json,
+    Dot,
 }
 
 pub async fn analyze(arguments: Arguments, repository: Result<Repository>) -> Result<()> {
@@ -76,7 +100,41 @@ pub async fn analyze(arguments: Arguments, repository: Result<Repository>) -> Re
                 .print()
                 .wrap_err("failed to print file")?;
         }
+        Arguments::TopicGraph { format, check } => {
+            let repository = repository?;
+            let graph = TopicGraph::try_from_nodes_directory(repository.root.join("crates/nodes"))
+                .wrap_err("failed to analyze node crates")?;
+            if check {
+                return check_topic_graph(&graph, &repository);
+            }
+            match format {
+                TopicGraphFormat::Json => {
+                    let json = serde_json::to_string_pretty(&graph)
+                        .wrap_err("failed to serialize topic graph")?;
+                    println!("{json}");
+                }
+                TopicGraphFormat::Dot => print!("{}", graph.to_dot()),
+            }
+        }
     }
 
     Ok(())
 }
+
+fn check_topic_graph(graph: &TopicGraph, repository: &Repository) -> Result<()> {
+    let rules_path = repository.root.join("etc/topic_graph.json");
+    let rules: TopicGraphRules = serde_json::from_str(
+        &read_to_string(&rules_path)
+            .wrap_err_with(|| format!("failed to read {}", rules_path.display()))?,
+    )
+    .wrap_err_with(|| format!("failed to parse {}", rules_path.display()))?;
+
+    let violations = graph.check(&rules);
+    for violation in &violations {
+        eprintln!("{violation}");
+    }
+    if !violations.is_empty() {
+        bail!("topic graph has {} violation(s)", violations.len());
+    }
+    Ok(())
+}